@@ -9,6 +9,7 @@ use strum::Display;
 
 use crate::{
     entities::metadata,
+    exporter::ExporterService,
     fitness::resolver::ExerciseService,
     importer::{DeployImportJobInput, ImporterService},
     miscellaneous::resolver::MiscellaneousService,
@@ -39,6 +40,12 @@ pub async fn media_jobs(_information: ScheduledJob, ctx: JobContext) -> Result<(
         .invalidate_import_jobs()
         .await
         .unwrap();
+    tracing::trace!("Deleting import reports older than the retention period");
+    ctx.data::<Arc<ImporterService>>()
+        .unwrap()
+        .delete_old_import_reports()
+        .await
+        .unwrap();
     let service = ctx.data::<Arc<MiscellaneousService>>().unwrap();
     service
         .cleanup_data_without_associated_user_activities()
@@ -70,6 +77,12 @@ pub async fn user_jobs(_information: ScheduledJob, ctx: JobContext) -> Result<()
         .regenerate_user_summaries()
         .await
         .unwrap();
+    tracing::trace!("Removing personal bests that refer to deleted workouts");
+    ctx.data::<Arc<ExerciseService>>()
+        .unwrap()
+        .reconcile_personal_bests()
+        .await
+        .unwrap();
     Ok(())
 }
 
@@ -86,11 +99,42 @@ pub async fn yank_integrations_data(
     Ok(())
 }
 
+pub async fn scheduled_imports(
+    _information: ScheduledJob,
+    ctx: JobContext,
+) -> Result<(), JobError> {
+    tracing::trace!("Deploying the scheduled imports that are due");
+    if let Err(e) = ctx
+        .data::<Arc<ImporterService>>()
+        .unwrap()
+        .run_due_scheduled_imports()
+        .await
+    {
+        tracing::error!("Could not deploy the scheduled imports: {e:?}");
+    }
+    Ok(())
+}
+
+pub async fn scheduled_backups(
+    _information: ScheduledJob,
+    ctx: JobContext,
+) -> Result<(), JobError> {
+    tracing::trace!("Backing up the data of the users that are due");
+    ctx.data::<Arc<ExporterService>>()
+        .unwrap()
+        .perform_scheduled_backups()
+        .await
+        .unwrap();
+    Ok(())
+}
+
 // Application Jobs
 
 #[derive(Debug, Deserialize, Serialize, Display)]
 pub enum ApplicationJob {
-    ImportFromExternalSource(i32, DeployImportJobInput),
+    ImportFromExternalSource(i32, i32, DeployImportJobInput),
+    RetryImportFailedItems(i32, i32),
+    ResumeImport(i32, i32),
     UserCreated(i32),
     RecalculateUserSummary(i32),
     UpdateMetadata(metadata::Model),
@@ -99,6 +143,7 @@ pub enum ApplicationJob {
     RecalculateCalendarEvents,
     AssociatePersonWithMetadata(i32, PartialMetadataPerson, usize),
     AssociateGroupWithMetadata(MetadataLot, MetadataSource, String),
+    PerformExport(i32),
 }
 
 impl Job for ApplicationJob {
@@ -114,10 +159,19 @@ pub async fn perform_application_job(
     let importer_service = ctx.data::<Arc<ImporterService>>().unwrap();
     let misc_service = ctx.data::<Arc<MiscellaneousService>>().unwrap();
     let exercise_service = ctx.data::<Arc<ExerciseService>>().unwrap();
+    let exporter_service = ctx.data::<Arc<ExporterService>>().unwrap();
     let start = Instant::now();
     let status = match information {
-        ApplicationJob::ImportFromExternalSource(user_id, input) => importer_service
-            .start_importing(user_id, input)
+        ApplicationJob::ImportFromExternalSource(user_id, job_id, input) => importer_service
+            .start_importing(user_id, job_id, input)
+            .await
+            .is_ok(),
+        ApplicationJob::RetryImportFailedItems(user_id, job_id) => importer_service
+            .start_retrying(user_id, job_id)
+            .await
+            .is_ok(),
+        ApplicationJob::ResumeImport(user_id, job_id) => importer_service
+            .resume_importing(user_id, job_id)
             .await
             .is_ok(),
         ApplicationJob::UserCreated(user_id) => {
@@ -168,6 +222,9 @@ pub async fn perform_application_job(
             .associate_group_with_metadata(lot, source, group_identifier)
             .await
             .is_ok(),
+        ApplicationJob::PerformExport(user_id) => {
+            exporter_service.perform_export(user_id).await.is_ok()
+        }
     };
     tracing::trace!(
         "Job: {:#?}, Time Taken: {}ms, Successful = {}",
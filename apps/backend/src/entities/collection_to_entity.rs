@@ -19,6 +19,9 @@ pub struct Model {
     pub person_id: Option<i32>,
     pub metadata_group_id: Option<i32>,
     pub exercise_id: Option<i32>,
+    pub rank: Option<i32>,
+    /// The import which created it, if any.
+    pub import_report_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
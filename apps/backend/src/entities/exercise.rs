@@ -32,7 +32,6 @@ use crate::{
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
-    #[sea_orm(unique)]
     pub name: String,
     #[graphql(skip)]
     #[sea_orm(unique)]
@@ -46,6 +45,10 @@ pub struct Model {
     #[sea_orm(column_type = "Json")]
     pub muscles: Vec<ExerciseMuscle>,
     pub attributes: ExerciseAttributes,
+    /// The user who created the exercise, if it is a custom exercise.
+    #[graphql(skip_input)]
+    #[serde(default)]
+    pub created_by_user_id: Option<i32>,
 }
 
 #[async_trait]
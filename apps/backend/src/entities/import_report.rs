@@ -5,7 +5,7 @@ use database::ImportSource;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::importer::ImportResultResponse;
+use crate::importer::{ImportProgress, ImportResultResponse, ImportRetry};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize, SimpleObject)]
 #[sea_orm(table_name = "import_report")]
@@ -19,6 +19,12 @@ pub struct Model {
     pub finished_on: Option<DateTimeUtc>,
     pub details: Option<ImportResultResponse>,
     pub success: Option<bool>,
+    pub progress: Option<ImportProgress>,
+    #[graphql(skip)]
+    pub payload: Option<ImportRetry>,
+    /// When the job was started or resumed by the process that is running it.
+    #[graphql(skip)]
+    pub running_since: Option<DateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -9,7 +9,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     entities::{partial_metadata, prelude::PartialMetadata},
-    models::media::{MediaSpecifics, MetadataFreeCreator, MetadataImage, MetadataVideo},
+    models::media::{
+        MediaSpecifics, MetadataExternalIdentifier, MetadataFreeCreator, MetadataImage,
+        MetadataVideo,
+    },
 };
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize, Default)]
@@ -37,6 +40,8 @@ pub struct Model {
     pub last_processed_on_for_calendar: Option<DateTimeUtc>,
     #[sea_orm(column_type = "Json")]
     pub free_creators: Option<Vec<MetadataFreeCreator>>,
+    #[sea_orm(column_type = "Json")]
+    pub external_identifiers: Option<Vec<MetadataExternalIdentifier>>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
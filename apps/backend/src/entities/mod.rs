@@ -17,7 +17,9 @@ pub mod partial_metadata;
 pub mod partial_metadata_to_metadata_group;
 pub mod person;
 pub mod person_to_partial_metadata;
+pub mod reading_goal;
 pub mod review;
+pub mod scheduled_import;
 pub mod seen;
 pub mod user;
 pub mod user_measurement;
@@ -15,7 +15,9 @@ pub use super::partial_metadata::Entity as PartialMetadata;
 pub use super::partial_metadata_to_metadata_group::Entity as PartialMetadataToMetadataGroup;
 pub use super::person::Entity as Person;
 pub use super::person_to_partial_metadata::Entity as PersonToPartialMetadata;
+pub use super::reading_goal::Entity as ReadingGoal;
 pub use super::review::Entity as Review;
+pub use super::scheduled_import::Entity as ScheduledImport;
 pub use super::seen::Entity as Seen;
 pub use super::user::Entity as User;
 pub use super::user_measurement::Entity as UserMeasurement;
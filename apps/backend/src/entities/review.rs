@@ -29,6 +29,10 @@ pub struct Model {
     pub comments: Vec<ImportOrExportItemReviewComment>,
     pub metadata_group_id: Option<i32>,
     pub collection_id: Option<i32>,
+    /// The seen history item this review is for. For eg: a specific rewatch.
+    pub seen_id: Option<i32>,
+    /// The import which created it, if any.
+    pub import_report_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -11,8 +11,8 @@ use serde::{Deserialize, Serialize};
 use crate::{
     entities::{prelude::UserToEntity, user_to_entity},
     models::media::{
-        SeenOrReviewOrCalendarEventExtraInformation, SeenPodcastExtraInformation,
-        SeenShowExtraInformation,
+        SeenOrReviewOrCalendarEventExtraInformation, SeenPartExtraInformation,
+        SeenPodcastExtraInformation, SeenSession, SeenShowExtraInformation,
     },
     utils::associate_user_with_metadata,
 };
@@ -37,10 +37,23 @@ pub struct Model {
     #[graphql(skip)]
     #[serde(skip)]
     pub extra_information: Option<SeenOrReviewOrCalendarEventExtraInformation>,
+    #[sea_orm(column_type = "Json")]
+    pub sessions: Option<Vec<SeenSession>>,
+    pub dropped_reason: Option<String>,
+    /// The people the media was consumed with, if it was not consumed alone.
+    #[sea_orm(column_type = "Json")]
+    pub watched_with: Option<Vec<String>>,
+    /// Where the media was consumed. For eg: a streaming service or a theater.
+    pub watch_platform: Option<String>,
+    /// The import which created it, if any.
+    #[graphql(skip)]
+    pub import_report_id: Option<i32>,
     #[sea_orm(ignore)]
     pub show_information: Option<SeenShowExtraInformation>,
     #[sea_orm(ignore)]
     pub podcast_information: Option<SeenPodcastExtraInformation>,
+    #[sea_orm(ignore)]
+    pub part_information: Option<SeenPartExtraInformation>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -11,7 +11,7 @@ use sea_orm::{entity::prelude::*, ActiveValue, FromQueryResult};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    models::media::UserSummary,
+    models::{fitness::UserWorkoutInput, media::UserSummary},
     users::{UserNotification, UserPreferences, UserSinkIntegration, UserYankIntegration},
 };
 
@@ -45,6 +45,14 @@ pub struct UserWithOnlySummary {
     pub summary: Option<UserSummary>,
 }
 
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromQueryResult, DerivePartialModel,
+)]
+#[sea_orm(entity = "Entity")]
+pub struct UserWithOnlyWorkoutDraft {
+    pub workout_draft: Option<UserWorkoutInput>,
+}
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize, SimpleObject)]
 #[graphql(name = "User")]
 #[sea_orm(table_name = "user")]
@@ -69,6 +77,9 @@ pub struct Model {
     pub notifications: Vec<UserNotification>,
     #[graphql(skip)]
     pub summary: Option<UserSummary>,
+    #[sea_orm(column_type = "Json")]
+    #[graphql(skip)]
+    pub workout_draft: Option<UserWorkoutInput>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -77,8 +88,12 @@ pub enum Relation {
     Collection,
     #[sea_orm(has_many = "super::import_report::Entity")]
     ImportReport,
+    #[sea_orm(has_many = "super::reading_goal::Entity")]
+    ReadingGoal,
     #[sea_orm(has_many = "super::review::Entity")]
     Review,
+    #[sea_orm(has_many = "super::scheduled_import::Entity")]
+    ScheduledImport,
     #[sea_orm(has_many = "super::seen::Entity")]
     Seen,
     #[sea_orm(has_many = "super::user_measurement::Entity")]
@@ -101,12 +116,24 @@ impl Related<super::import_report::Entity> for Entity {
     }
 }
 
+impl Related<super::reading_goal::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ReadingGoal.def()
+    }
+}
+
 impl Related<super::review::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Review.def()
     }
 }
 
+impl Related<super::scheduled_import::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ScheduledImport.def()
+    }
+}
+
 impl Related<super::seen::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Seen.def()
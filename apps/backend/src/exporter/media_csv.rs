@@ -0,0 +1,350 @@
+use database::{MetadataLot, MetadataSource};
+use rust_decimal::{prelude::ToPrimitive, Decimal, RoundingStrategy};
+use rust_decimal_macros::dec;
+use sea_orm::prelude::DateTimeUtc;
+
+use crate::{
+    importer::convert_rating_to_scale, models::media::ImportOrExportMediaItem,
+    users::UserReviewScale,
+};
+
+/// A media item along with the details of its metadata that are not part of its
+/// export.
+#[derive(Debug)]
+pub struct MediaCsvItem {
+    pub title: String,
+    pub year: Option<i32>,
+    pub creators: Vec<String>,
+    pub item: ImportOrExportMediaItem,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Column {
+    Title,
+    Creators,
+    Year,
+    Source,
+    Identifier,
+    TmdbId,
+    Season,
+    Episode,
+    WatchedOn,
+    Rating,
+    Rating10,
+    Review,
+    Collections,
+}
+
+/// A line of the file. Every time the media was finished gets one, and the
+/// reviews are put on the line of the watch they are for.
+#[derive(Debug, Default, PartialEq)]
+struct Line {
+    season: Option<i32>,
+    episode: Option<i32>,
+    watched_on: Option<DateTimeUtc>,
+    rating: Option<Decimal>,
+    review: Option<String>,
+}
+
+pub fn get_file_name(lot: MetadataLot) -> &'static str {
+    match lot {
+        MetadataLot::AudioBook => "audio_books.csv",
+        MetadataLot::Anime => "anime.csv",
+        MetadataLot::Book => "books.csv",
+        MetadataLot::Podcast => "podcasts.csv",
+        MetadataLot::Manga => "manga.csv",
+        MetadataLot::Movie => "movies.csv",
+        MetadataLot::Show => "shows.csv",
+        MetadataLot::VideoGame => "video_games.csv",
+        MetadataLot::VisualNovel => "visual_novels.csv",
+    }
+}
+
+/// The role of the people that are put in the creators column.
+pub fn get_creator_role(lot: MetadataLot) -> Option<&'static str> {
+    match lot {
+        MetadataLot::AudioBook | MetadataLot::Book | MetadataLot::Manga => Some("Author"),
+        MetadataLot::Movie => Some("Director"),
+        _ => None,
+    }
+}
+
+fn get_columns(lot: MetadataLot) -> Vec<Column> {
+    let mut columns = vec![Column::Title];
+    if get_creator_role(lot).is_some() {
+        columns.push(Column::Creators);
+    }
+    columns.extend([Column::Year, Column::Source, Column::Identifier]);
+    if matches!(lot, MetadataLot::Movie | MetadataLot::Show) {
+        columns.push(Column::TmdbId);
+    }
+    match lot {
+        MetadataLot::Show => columns.extend([Column::Season, Column::Episode]),
+        MetadataLot::Podcast => columns.push(Column::Episode),
+        _ => {}
+    }
+    columns.extend([Column::WatchedOn, Column::Rating]);
+    if lot != MetadataLot::Book {
+        columns.push(Column::Rating10);
+    }
+    columns.extend([Column::Review, Column::Collections]);
+    columns
+}
+
+// DEV: Books use the names of the Goodreads import format and the rest the ones
+// of the Letterboxd import format, which most other services understand too.
+fn get_column_name(column: Column, lot: MetadataLot) -> &'static str {
+    let is_book = lot == MetadataLot::Book;
+    match column {
+        Column::Title => "Title",
+        Column::Creators if is_book => "Author",
+        Column::Creators if lot == MetadataLot::Movie => "Directors",
+        Column::Creators => "Authors",
+        Column::Year if is_book => "Year Published",
+        Column::Year => "Year",
+        Column::Source => "Source",
+        Column::Identifier => "Identifier",
+        Column::TmdbId => "tmdbID",
+        Column::Season => "Season",
+        Column::Episode => "Episode",
+        Column::WatchedOn if is_book => "Date Read",
+        Column::WatchedOn => "WatchedDate",
+        Column::Rating if is_book => "My Rating",
+        Column::Rating => "Rating",
+        Column::Rating10 => "Rating10",
+        Column::Review if is_book => "My Review",
+        Column::Review => "Review",
+        Column::Collections if is_book => "Bookshelves",
+        Column::Collections => "Tags",
+    }
+}
+
+fn get_tmdb_id(item: &ImportOrExportMediaItem) -> Option<String> {
+    match item.source {
+        MetadataSource::Tmdb => Some(item.identifier.clone()),
+        _ => item
+            .external_identifiers
+            .iter()
+            .flatten()
+            .find(|i| i.source == MetadataSource::Tmdb)
+            .map(|i| i.identifier.clone()),
+    }
+}
+
+fn get_lines(item: &ImportOrExportMediaItem) -> Vec<Line> {
+    let mut lines = item
+        .seen_history
+        .iter()
+        .filter(|s| s.dropped.is_none() && s.progress.map_or(true, |p| p == 100))
+        .map(|s| Line {
+            season: s.show_season_number,
+            episode: s.show_episode_number.or(s.podcast_episode_number),
+            watched_on: s.ended_on,
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    for review in item.reviews.iter() {
+        let episode = review.show_episode_number.or(review.podcast_episode_number);
+        let text = review.review.as_ref().and_then(|r| r.text.clone());
+        let position = lines.iter().position(|l| {
+            l.rating.is_none()
+                && l.review.is_none()
+                && l.season == review.show_season_number
+                && l.episode == episode
+                && review.seen_on.map_or(true, |on| l.watched_on == Some(on))
+        });
+        let line = match position {
+            Some(p) => &mut lines[p],
+            None => {
+                lines.push(Line {
+                    season: review.show_season_number,
+                    episode,
+                    watched_on: review.seen_on,
+                    ..Default::default()
+                });
+                lines.last_mut().unwrap()
+            }
+        };
+        line.rating = review.rating;
+        line.review = text;
+    }
+    // DEV: Media that was never finished or reviewed, for eg: the ones in a
+    // collection, still needs a line
+    if lines.is_empty() {
+        lines.push(Line::default());
+    }
+    lines
+}
+
+fn round(value: Decimal) -> Decimal {
+    value.round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
+}
+
+/// Get the value of a column for a line. Ratings are out of 100, so they are
+/// converted to the scales used by the other services.
+fn get_value(
+    column: Column,
+    lot: MetadataLot,
+    media: &MediaCsvItem,
+    tmdb_id: &Option<String>,
+    line: &Line,
+) -> String {
+    let to_string = |v: Option<i32>| v.map(|v| v.to_string()).unwrap_or_default();
+    let is_book = lot == MetadataLot::Book;
+    match column {
+        Column::Title => media.title.clone(),
+        Column::Creators => media.creators.join(", "),
+        Column::Year => to_string(media.year),
+        Column::Source => format!("{:?}", media.item.source),
+        Column::Identifier => media.item.identifier.clone(),
+        Column::TmdbId => tmdb_id.clone().unwrap_or_default(),
+        Column::Season => to_string(line.season),
+        Column::Episode => to_string(line.episode),
+        Column::WatchedOn => line
+            .watched_on
+            .map(|d| match is_book {
+                true => d.format("%Y/%m/%d").to_string(),
+                false => d.format("%Y-%m-%d").to_string(),
+            })
+            .unwrap_or_default(),
+        // DEV: Goodreads only has whole stars
+        Column::Rating if is_book => to_string(
+            line.rating
+                .and_then(|r| round(r / dec!(20)).to_i32())
+                .map(|r| r.max(1)),
+        ),
+        Column::Rating => line
+            .rating
+            .map(|r| convert_rating_to_scale(r, UserReviewScale::OutOfFive).max(dec!(0.5)))
+            .map(|r| r.normalize().to_string())
+            .unwrap_or_default(),
+        Column::Rating10 => to_string(
+            line.rating
+                .and_then(|r| round(r / dec!(10)).to_i32())
+                .map(|r| r.max(1)),
+        ),
+        Column::Review => line.review.clone().unwrap_or_default(),
+        Column::Collections => media.item.collections.join(", "),
+    }
+}
+
+pub fn export(lot: MetadataLot, items: Vec<MediaCsvItem>) -> Result<String, csv::Error> {
+    let columns = get_columns(lot);
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(columns.iter().map(|c| get_column_name(*c, lot)))?;
+    for media in items.iter() {
+        let tmdb_id = get_tmdb_id(&media.item);
+        for line in get_lines(&media.item) {
+            writer.write_record(
+                columns
+                    .iter()
+                    .map(|c| get_value(*c, lot, media, &tmdb_id, &line)),
+            )?;
+        }
+    }
+    let data = writer.into_inner().map_err(|e| e.into_error())?;
+    Ok(String::from_utf8_lossy(&data).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::models::media::{
+        ImportOrExportItemRating, ImportOrExportItemReview, ImportOrExportMediaItemSeen,
+    };
+
+    fn get_item(lot: MetadataLot) -> ImportOrExportMediaItem {
+        ImportOrExportMediaItem {
+            source_id: "1".to_owned(),
+            lot,
+            source: MetadataSource::Tmdb,
+            identifier: "95396".to_owned(),
+            internal_identifier: None,
+            seen_history: vec![],
+            reviews: vec![],
+            collections: vec![],
+            external_identifiers: None,
+            is_favorite: None,
+            next_episode: None,
+            display_title: None,
+        }
+    }
+
+    #[test]
+    fn reviews_are_put_on_the_line_of_their_watch() {
+        let first = Utc.with_ymd_and_hms(2022, 4, 8, 0, 0, 0).unwrap();
+        let second = Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap();
+        let mut item = get_item(MetadataLot::Movie);
+        item.seen_history = vec![
+            ImportOrExportMediaItemSeen {
+                ended_on: Some(first),
+                ..Default::default()
+            },
+            ImportOrExportMediaItemSeen {
+                ended_on: Some(second),
+                ..Default::default()
+            },
+            ImportOrExportMediaItemSeen {
+                progress: Some(40),
+                ..Default::default()
+            },
+        ];
+        item.reviews = vec![ImportOrExportItemRating {
+            rating: Some(dec!(80)),
+            review: Some(ImportOrExportItemReview {
+                text: Some("Better the second time".to_owned()),
+                ..Default::default()
+            }),
+            seen_on: Some(second),
+            ..Default::default()
+        }];
+        assert_eq!(
+            get_lines(&item),
+            vec![
+                Line {
+                    watched_on: Some(first),
+                    ..Default::default()
+                },
+                Line {
+                    watched_on: Some(second),
+                    rating: Some(dec!(80)),
+                    review: Some("Better the second time".to_owned()),
+                    ..Default::default()
+                },
+            ]
+        );
+        assert_eq!(
+            get_lines(&get_item(MetadataLot::Movie)),
+            vec![Line::default()]
+        );
+    }
+
+    #[test]
+    fn books_use_the_goodreads_columns() {
+        let mut item = get_item(MetadataLot::Book);
+        item.source = MetadataSource::Openlibrary;
+        item.identifier = "OL45804W".to_owned();
+        item.collections = vec!["Owned".to_owned()];
+        item.reviews = vec![ImportOrExportItemRating {
+            rating: Some(dec!(70)),
+            ..Default::default()
+        }];
+        let csv = export(
+            MetadataLot::Book,
+            vec![MediaCsvItem {
+                title: "Fantastic Mr Fox".to_owned(),
+                year: Some(1970),
+                creators: vec!["Roald Dahl".to_owned()],
+                item,
+            }],
+        )
+        .unwrap();
+        assert_eq!(
+            csv,
+            "Title,Author,Year Published,Source,Identifier,Date Read,My Rating,My Review,Bookshelves\n\
+            Fantastic Mr Fox,Roald Dahl,1970,Openlibrary,OL45804W,,4,,Owned\n"
+        );
+    }
+}
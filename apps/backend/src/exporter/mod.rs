@@ -0,0 +1,301 @@
+use std::{collections::HashMap, sync::Arc};
+
+use apalis::prelude::Storage;
+use async_graphql::{Context, Error, Object, Result, SimpleObject};
+use chrono::{Duration, TimeZone, Utc};
+use database::MetadataLot;
+use http_types::mime;
+use itertools::Itertools;
+use sea_orm::{
+    prelude::DateTimeUtc, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+
+use crate::{
+    background::ApplicationJob,
+    entities::{
+        metadata, metadata_to_person,
+        prelude::{Metadata, MetadataToPerson, Person, User},
+        user,
+    },
+    file_storage::FileStorageService,
+    fitness::resolver::ExerciseService,
+    miscellaneous::resolver::MiscellaneousService,
+    models::ImportOrExportRyotData,
+    traits::AuthProvider,
+};
+
+pub mod media_csv;
+pub mod strong_app;
+
+#[derive(Debug, SimpleObject)]
+pub struct ExportItem {
+    /// The key of the export in the file storage. It can be used as the
+    /// `exportPath` of the "Ryot" importer.
+    key: String,
+    /// When the export was created.
+    created_on: DateTimeUtc,
+    /// A link to download the export, which is valid for 90 minutes.
+    url: String,
+}
+
+#[derive(Default)]
+pub struct ExporterQuery;
+
+#[Object]
+impl ExporterQuery {
+    /// Get all the exports of the user that have been completed, newest first.
+    async fn user_exports(&self, gql_ctx: &Context<'_>) -> Result<Vec<ExportItem>> {
+        let service = gql_ctx.data_unchecked::<Arc<ExporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.user_exports(user_id).await
+    }
+}
+
+#[derive(Default)]
+pub struct ExporterMutation;
+
+#[Object]
+impl ExporterMutation {
+    /// Add job to export all the data of the user to a JSON file. It appears in
+    /// `userExports` once it is done. It has the format of the `ryot` export rather
+    /// than the one read by the "Media JSON" importer, which can not hold workouts
+    /// or measurements. Its `media` part is in that format though.
+    async fn deploy_export_job(&self, gql_ctx: &Context<'_>) -> Result<String> {
+        let service = gql_ctx.data_unchecked::<Arc<ExporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.deploy_export_job(user_id).await
+    }
+}
+
+pub struct ExporterService {
+    media_service: Arc<MiscellaneousService>,
+    exercise_service: Arc<ExerciseService>,
+    file_storage_service: Arc<FileStorageService>,
+    config: Arc<config::AppConfig>,
+}
+
+impl AuthProvider for ExporterService {}
+
+impl ExporterService {
+    pub fn new(
+        media_service: Arc<MiscellaneousService>,
+        exercise_service: Arc<ExerciseService>,
+        file_storage_service: Arc<FileStorageService>,
+        config: Arc<config::AppConfig>,
+    ) -> Self {
+        Self {
+            media_service,
+            exercise_service,
+            file_storage_service,
+            config,
+        }
+    }
+
+    pub async fn deploy_export_job(&self, user_id: i32) -> Result<String> {
+        if !self.file_storage_service.is_enabled().await {
+            return Err(Error::new(
+                "File storage must be enabled to export data".to_owned(),
+            ));
+        }
+        let job = self
+            .media_service
+            .perform_application_job
+            .clone()
+            .push(ApplicationJob::PerformExport(user_id))
+            .await
+            .map_err(|e| Error::new(e.to_string()))?;
+        Ok(job.to_string())
+    }
+
+    /// Get all the data of the user in the format that the "Ryot" importer reads.
+    pub async fn export_ryot_data(&self, user_id: i32) -> Result<ImportOrExportRyotData> {
+        Ok(ImportOrExportRyotData {
+            media: self.media_service.export_media(user_id).await?,
+            people: self.media_service.export_people(user_id).await?,
+            metadata_groups: self.media_service.export_metadata_groups(user_id).await?,
+            collections: self.media_service.export_collections(user_id).await?,
+            measurements: self.exercise_service.export_measurements(user_id).await?,
+            workouts: self.exercise_service.export_workouts(user_id).await?,
+            exercises: self.exercise_service.export_exercises(user_id).await?,
+            preferences: Some(self.media_service.export_preferences(user_id).await?),
+        })
+    }
+
+    /// Get the media of the user with the lot as a CSV file that other services
+    /// can import.
+    pub async fn export_media_csv(&self, user_id: i32, lot: MetadataLot) -> Result<String> {
+        let items = self
+            .media_service
+            .export_media(user_id)
+            .await?
+            .into_iter()
+            .filter(|m| m.lot == lot)
+            .collect_vec();
+        // DEV: The exported media is identified by its ID in the database
+        let ids = items
+            .iter()
+            .filter_map(|m| m.source_id.parse::<i32>().ok())
+            .collect_vec();
+        let metas = Metadata::find()
+            .filter(metadata::Column::Id.is_in(ids.clone()))
+            .all(&self.media_service.db)
+            .await?
+            .into_iter()
+            .map(|m| (m.id, m))
+            .collect::<HashMap<_, _>>();
+        let mut creators: HashMap<i32, Vec<String>> = HashMap::new();
+        if let Some(role) = media_csv::get_creator_role(lot) {
+            let people = MetadataToPerson::find()
+                .filter(metadata_to_person::Column::MetadataId.is_in(ids))
+                .filter(metadata_to_person::Column::Role.eq(role))
+                .order_by_asc(metadata_to_person::Column::Index)
+                .find_also_related(Person)
+                .all(&self.media_service.db)
+                .await?;
+            for (association, person) in people {
+                if let Some(person) = person {
+                    creators
+                        .entry(association.metadata_id)
+                        .or_default()
+                        .push(person.name);
+                }
+            }
+        }
+        let items = items
+            .into_iter()
+            .filter_map(|item| {
+                let id = item.source_id.parse::<i32>().ok()?;
+                let meta = metas.get(&id)?;
+                Some(media_csv::MediaCsvItem {
+                    title: meta.title.clone(),
+                    year: meta.publish_year,
+                    creators: creators.remove(&id).unwrap_or_default(),
+                    item,
+                })
+            })
+            .collect();
+        Ok(media_csv::export(lot, items)?)
+    }
+
+    /// Get the workouts of the user as a CSV file in the format of the Strong app.
+    pub async fn export_workouts_csv(&self, user_id: i32) -> Result<String> {
+        let workouts = self.exercise_service.export_workouts(user_id).await?;
+        Ok(strong_app::export(workouts)?)
+    }
+
+    pub async fn perform_export(&self, user_id: i32) -> Result<()> {
+        self.upload_export(user_id, get_exports_prefix(user_id))
+            .await?;
+        tracing::debug!("Exported the data of user {user_id}");
+        Ok(())
+    }
+
+    /// Back up the data of the users whose latest backup is older than the
+    /// configured interval, and delete the backups that are no longer kept.
+    pub async fn perform_scheduled_backups(&self) -> Result<()> {
+        let backup_every = self.config.file_storage.backup_every;
+        if backup_every == 0 || !self.file_storage_service.is_enabled().await {
+            return Ok(());
+        }
+        let user_ids: Vec<i32> = User::find()
+            .select_only()
+            .column(user::Column::Id)
+            .into_tuple()
+            .all(&self.media_service.db)
+            .await?;
+        for user_id in user_ids {
+            let prefix = get_backups_prefix(user_id);
+            // DEV: If the backups can not be listed, uploading another one would
+            // create a new backup every time this runs.
+            let backups = match self.list_exports(prefix.clone()).await {
+                Ok(backups) => backups,
+                Err(e) => {
+                    tracing::error!("Could not list the backups of user {user_id}: {e:?}");
+                    continue;
+                }
+            };
+            let is_due = backups.first().map_or(true, |(_, on)| {
+                Utc::now() - *on >= Duration::hours(backup_every)
+            });
+            if !is_due {
+                continue;
+            }
+            if let Err(e) = self.upload_export(user_id, prefix).await {
+                tracing::error!("Could not back up the data of user {user_id}: {e:?}");
+                continue;
+            }
+            tracing::debug!("Backed up the data of user {user_id}");
+            // DEV: The backup that was just created is not in the list
+            let to_keep = self.config.file_storage.backups_to_keep.saturating_sub(1);
+            for (key, _) in backups.into_iter().skip(to_keep) {
+                self.file_storage_service.delete_object(key).await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn upload_export(&self, user_id: i32, prefix: String) -> Result<()> {
+        let data = self.export_ryot_data(user_id).await?;
+        let data = serde_json::to_vec(&data)?;
+        // DEV: The time is part of the key so that the exports can be listed without
+        // reading their metadata.
+        let key = format!("{}{}.json", prefix, Utc::now().timestamp_millis());
+        if !self
+            .file_storage_service
+            .put_object(key, data, mime::JSON.essence())
+            .await
+        {
+            return Err(Error::new("The export could not be uploaded".to_owned()));
+        }
+        Ok(())
+    }
+
+    /// Get the keys of the exports with the prefix and when they were created,
+    /// newest first.
+    async fn list_exports(&self, prefix: String) -> Result<Vec<(String, DateTimeUtc)>> {
+        let exports = self
+            .file_storage_service
+            .list_objects(prefix)
+            .await?
+            .into_iter()
+            .filter_map(|key| get_export_time(&key).map(|on| (key, on)))
+            .sorted_by_key(|(_, on)| *on)
+            .rev()
+            .collect();
+        Ok(exports)
+    }
+
+    async fn user_exports(&self, user_id: i32) -> Result<Vec<ExportItem>> {
+        let mut exports = vec![];
+        for (key, created_on) in self.list_exports(get_exports_prefix(user_id)).await? {
+            let url = self
+                .file_storage_service
+                .get_presigned_url(key.clone())
+                .await;
+            exports.push(ExportItem {
+                key,
+                created_on,
+                url,
+            });
+        }
+        Ok(exports)
+    }
+}
+
+pub fn get_exports_prefix(user_id: i32) -> String {
+    format!("exports/{user_id}/")
+}
+
+fn get_backups_prefix(user_id: i32) -> String {
+    format!("backups/{user_id}/")
+}
+
+fn get_export_time(key: &str) -> Option<DateTimeUtc> {
+    let millis = key
+        .rsplit('/')
+        .next()?
+        .strip_suffix(".json")?
+        .parse()
+        .ok()?;
+    Utc.timestamp_millis_opt(millis).single()
+}
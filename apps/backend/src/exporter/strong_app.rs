@@ -0,0 +1,208 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+
+use crate::{entities::workout, models::fitness::SetLot};
+
+pub const FILE_NAME: &str = "workouts.csv";
+
+/// A row of the export, which is a set of an exercise. The columns are the ones
+/// that `importer::strong_app` reads.
+#[derive(Debug, Serialize)]
+struct Entry {
+    #[serde(rename = "Date")]
+    date: String,
+    #[serde(rename = "Workout Name")]
+    workout_name: String,
+    #[serde(rename = "Workout Duration")]
+    workout_duration: String,
+    #[serde(rename = "Exercise Name")]
+    exercise_name: String,
+    #[serde(rename = "Set Order")]
+    set_order: String,
+    #[serde(rename = "Weight")]
+    weight: Option<Decimal>,
+    #[serde(rename = "Reps")]
+    reps: Option<usize>,
+    #[serde(rename = "Distance")]
+    distance: Option<Decimal>,
+    #[serde(rename = "Seconds")]
+    seconds: Option<Decimal>,
+    #[serde(rename = "Notes")]
+    notes: Option<String>,
+    #[serde(rename = "Workout Notes")]
+    workout_notes: Option<String>,
+    #[serde(rename = "RPE")]
+    rpe: Option<Decimal>,
+    #[serde(rename = "Superset")]
+    superset: Option<String>,
+}
+
+/// Get the duration in the format Strong uses, for eg: `1h 5m`.
+fn get_workout_duration(workout: &workout::Model) -> String {
+    let minutes = (workout.end_time - workout.start_time).num_minutes().max(0);
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m}m"),
+        (h, 0) => format!("{h}h"),
+        (h, m) => format!("{h}h {m}m"),
+    }
+}
+
+/// Get the labels of the exercises of a workout that are in a superset. Each
+/// superset gets a letter and its exercises are numbered, for eg: `A1` and `A2`.
+fn get_superset_labels(workout: &workout::Model) -> Vec<Option<String>> {
+    let mut labels = vec![None; workout.information.exercises.len()];
+    for (group, members) in workout.information.supersets.iter().enumerate() {
+        let letter = char::from(b'A' + (group % 26) as u8);
+        for (position, idx) in members.iter().enumerate() {
+            if let Some(label) = labels.get_mut(usize::from(*idx)) {
+                *label = Some(format!("{letter}{}", position + 1));
+            }
+        }
+    }
+    labels
+}
+
+fn get_entries(workout: &workout::Model) -> Vec<Entry> {
+    let date = workout.start_time.format("%Y-%m-%d %H:%M:%S").to_string();
+    let workout_duration = get_workout_duration(workout);
+    let superset_labels = get_superset_labels(workout);
+    let mut entries = vec![];
+    for (exercise, superset) in workout.information.exercises.iter().zip(superset_labels) {
+        let mut order = 0;
+        let num_sets = exercise.sets.len();
+        for (idx, set) in exercise.sets.iter().enumerate() {
+            let set_order = match set.lot {
+                SetLot::Normal => {
+                    order += 1;
+                    order.to_string()
+                }
+                SetLot::WarmUp => "W".to_owned(),
+                SetLot::Drop => "D".to_owned(),
+                SetLot::Failure => "F".to_owned(),
+            };
+            // DEV: The importer reads the notes of every set as a separate note of
+            // the exercise, so the notes left over go on the last set
+            let notes = match idx + 1 == num_sets {
+                true => Some(exercise.notes.iter().skip(idx).cloned().collect::<Vec<_>>())
+                    .filter(|n| !n.is_empty())
+                    .map(|n| n.join("\n")),
+                false => exercise.notes.get(idx).cloned(),
+            };
+            let statistic = &set.statistic;
+            entries.push(Entry {
+                date: date.clone(),
+                workout_name: workout.name.clone(),
+                workout_duration: workout_duration.clone(),
+                exercise_name: exercise.name.clone(),
+                set_order,
+                weight: statistic.weight.map(|w| w.normalize()),
+                reps: statistic.reps,
+                distance: statistic.distance.map(|d| d.normalize()),
+                seconds: statistic
+                    .duration
+                    .map(|d| (d * dec!(60)).round_dp(0).normalize()),
+                notes,
+                workout_notes: workout.comment.clone(),
+                rpe: statistic.rpe.map(|r| r.normalize()),
+                superset: superset.clone(),
+            });
+        }
+    }
+    entries
+}
+
+pub fn export(workouts: Vec<workout::Model>) -> Result<String, csv::Error> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .from_writer(vec![]);
+    for workout in workouts.iter() {
+        for entry in get_entries(workout) {
+            writer.serialize(entry)?;
+        }
+    }
+    let data = writer.into_inner().map_err(|e| e.into_error())?;
+    Ok(String::from_utf8_lossy(&data).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use database::ExerciseLot;
+
+    use super::*;
+    use crate::models::fitness::{
+        ProcessedExercise, WorkoutBalance, WorkoutInformation, WorkoutSetRecord,
+        WorkoutSetStatistic, WorkoutSummary, WorkoutTotalMeasurement,
+    };
+
+    fn get_exercise(name: &str, sets: Vec<(SetLot, Decimal, usize)>) -> ProcessedExercise {
+        ProcessedExercise {
+            id: 1,
+            name: name.to_owned(),
+            lot: ExerciseLot::RepsAndWeight,
+            sets: sets
+                .into_iter()
+                .map(|(lot, weight, reps)| WorkoutSetRecord {
+                    statistic: WorkoutSetStatistic {
+                        weight: Some(weight),
+                        reps: Some(reps),
+                        ..Default::default()
+                    },
+                    lot,
+                    personal_bests: vec![],
+                    personal_best_improvements: vec![],
+                })
+                .collect(),
+            notes: vec![],
+            rest_time: None,
+            total: WorkoutTotalMeasurement::default(),
+            assets: Default::default(),
+        }
+    }
+
+    #[test]
+    fn workouts_are_exported_in_the_strong_layout() {
+        let start_time = Utc.with_ymd_and_hms(2023, 11, 20, 18, 30, 0).unwrap();
+        let mut squat = get_exercise(
+            "Squat (Barbell)",
+            vec![
+                (SetLot::WarmUp, dec!(60), 5),
+                (SetLot::Normal, dec!(100.00), 5),
+                (SetLot::Normal, dec!(100), 5),
+            ],
+        );
+        squat.notes = vec!["Belt on".to_owned()];
+        let workout = workout::Model {
+            id: "1".to_owned(),
+            start_time,
+            end_time: start_time + Duration::minutes(65),
+            user_id: 1,
+            summary: WorkoutSummary {
+                total: WorkoutTotalMeasurement::default(),
+                exercises: vec![],
+                balance: WorkoutBalance::default(),
+            },
+            information: WorkoutInformation {
+                supersets: vec![vec![1, 2]],
+                exercises: vec![
+                    squat,
+                    get_exercise("Pull Up", vec![(SetLot::Normal, dec!(0), 8)]),
+                    get_exercise("Dip", vec![(SetLot::Failure, dec!(0), 12)]),
+                ],
+                assets: Default::default(),
+            },
+            name: "Legs".to_owned(),
+            comment: None,
+        };
+        assert_eq!(
+            export(vec![workout]).unwrap(),
+            "Date;Workout Name;Workout Duration;Exercise Name;Set Order;Weight;Reps;Distance;Seconds;Notes;Workout Notes;RPE;Superset\n\
+            2023-11-20 18:30:00;Legs;1h 5m;Squat (Barbell);W;60;5;;;Belt on;;;\n\
+            2023-11-20 18:30:00;Legs;1h 5m;Squat (Barbell);1;100;5;;;;;;\n\
+            2023-11-20 18:30:00;Legs;1h 5m;Squat (Barbell);2;100;5;;;;;;\n\
+            2023-11-20 18:30:00;Legs;1h 5m;Pull Up;1;0;8;;;;;;A1\n\
+            2023-11-20 18:30:00;Legs;1h 5m;Dip;F;0;12;;;;;;A2\n"
+        );
+    }
+}
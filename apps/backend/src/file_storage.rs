@@ -1,5 +1,8 @@
-use aws_sdk_s3::presigning::PresigningConfig;
+use std::path::Path;
+
+use aws_sdk_s3::{presigning::PresigningConfig, primitives::ByteStream};
 use chrono::Duration;
+use tokio::io::AsyncWriteExt;
 use uuid::Uuid;
 
 #[derive(Debug)]
@@ -39,6 +42,73 @@ impl FileStorageService {
             .to_string()
     }
 
+    pub async fn object_exists(&self, key: String) -> bool {
+        self.s3_client
+            .head_object()
+            .bucket(&self.bucket_name)
+            .key(key)
+            .send()
+            .await
+            .is_ok()
+    }
+
+    /// Download an object to a file, writing its contents as they are received.
+    pub async fn download_object(&self, key: String, path: &Path) -> anyhow::Result<()> {
+        let mut object = self
+            .s3_client
+            .get_object()
+            .bucket(&self.bucket_name)
+            .key(key)
+            .send()
+            .await?;
+        let mut file = tokio::fs::File::create(path).await?;
+        while let Some(bytes) = object.body.try_next().await? {
+            file.write_all(&bytes).await?;
+        }
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// Upload an object, replacing the one with the same key if it exists.
+    pub async fn put_object(&self, key: String, data: Vec<u8>, content_type: &str) -> bool {
+        self.s3_client
+            .put_object()
+            .bucket(&self.bucket_name)
+            .key(key)
+            .content_type(content_type)
+            .body(ByteStream::from(data))
+            .send()
+            .await
+            .is_ok()
+    }
+
+    /// Get the keys of all the objects whose key starts with the prefix.
+    pub async fn list_objects(&self, prefix: String) -> anyhow::Result<Vec<String>> {
+        let mut keys = vec![];
+        let mut token = None;
+        loop {
+            let rsp = self
+                .s3_client
+                .list_objects_v2()
+                .bucket(&self.bucket_name)
+                .prefix(&prefix)
+                .set_continuation_token(token)
+                .send()
+                .await?;
+            keys.extend(
+                rsp.contents()
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|o| o.key().map(String::from)),
+            );
+            token = rsp.next_continuation_token().map(String::from);
+            if token.is_none() {
+                break;
+            }
+        }
+        Ok(keys)
+    }
+
     pub async fn delete_object(&self, key: String) -> bool {
         self.s3_client
             .delete_object()
@@ -49,8 +119,13 @@ impl FileStorageService {
             .is_ok()
     }
 
-    pub async fn get_presigned_put_url(&self, filename: String) -> (String, String) {
-        let key = format!("uploads/{}-{}", Uuid::new_v4(), filename);
+    pub async fn get_presigned_put_url(&self, user_id: i32, filename: String) -> (String, String) {
+        let key = format!(
+            "{}{}-{}",
+            get_user_uploads_prefix(user_id),
+            Uuid::new_v4(),
+            filename
+        );
         let url = self
             .s3_client
             .put_object()
@@ -66,3 +141,8 @@ impl FileStorageService {
         (key, url)
     }
 }
+
+/// The prefix of the keys of the objects uploaded by a user.
+pub fn get_user_uploads_prefix(user_id: i32) -> String {
+    format!("uploads/{user_id}/")
+}
@@ -1,43 +1,95 @@
-use std::cmp::Ordering;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
 
 use anyhow::{bail, Result};
 use chrono::Utc;
-use database::ExerciseLot;
+use database::{ExerciseForce, ExerciseLot, ExerciseMuscle};
 use rs_utils::LengthVec;
-use rust_decimal::{prelude::FromPrimitive, Decimal};
+use rust_decimal::{prelude::ToPrimitive, Decimal};
 use rust_decimal_macros::dec;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait,
-    QueryFilter,
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, ModelTrait, QueryFilter, QuerySelect, QueryTrait,
 };
 
 use crate::{
     entities::{
+        exercise,
         prelude::{Exercise, UserToEntity},
         user_to_entity, workout,
     },
     models::fitness::{
-        ExerciseBestSetRecord, ProcessedExercise, UserToExerciseBestSetExtraInformation,
-        UserToExerciseExtraInformation, UserToExerciseHistoryExtraInformation, UserWorkoutInput,
-        UserWorkoutSetRecord, WorkoutInformation, WorkoutSetPersonalBest, WorkoutSetRecord,
-        WorkoutSetStatistic, WorkoutSummary, WorkoutSummaryExercise, WorkoutTotalMeasurement,
+        ExerciseBestSetRecord, ExerciseProgressionSuggestion, PlateBreakdown, PlateCount,
+        ProcessedExercise, ProgressionScheme, SetLot, UserExerciseGoal, UserExerciseInput,
+        UserExerciseProgression, UserToExerciseBestSetExtraInformation,
+        UserToExerciseExtraInformation, UserToExerciseHistoryExtraInformation,
+        UserToExerciseRepMax, UserWorkoutInput, UserWorkoutSetRecord, WorkoutBalance,
+        WorkoutInformation, WorkoutSetPersonalBest, WorkoutSetPersonalBestImprovement,
+        WorkoutSetRecord, WorkoutSetStatistic, WorkoutSummary, WorkoutSummaryExercise,
+        WorkoutTotalMeasurement,
     },
-    users::{UserExercisePreferences, UserUnitSystem},
+    users::{UserExercisePreferences, UserSetVolumeFormula, UserUnitSystem},
 };
 
-fn get_best_set_index(records: &[WorkoutSetRecord]) -> Option<usize> {
+/// Key the ids of the exercises visible to a user by their names. Custom exercises
+/// are only visible to the user who created them, whereas exercises without an
+/// owner (built-in ones and custom ones created before owners were recorded) are
+/// visible to everyone. If names collide, the exercise of the user is preferred.
+pub fn get_exercise_ids_by_name(
+    mut exercises: Vec<(String, i32, Option<i32>)>,
+    user_id: i32,
+) -> HashMap<String, i32> {
+    exercises.retain(|(_, _, owner)| owner.map_or(true, |o| o == user_id));
+    exercises.sort_by_key(|(_, _, owner)| owner.is_some());
+    exercises
+        .into_iter()
+        .map(|(name, id, _)| (name, id))
+        .collect()
+}
+
+/// Get the ids of the exercises visible to a user, optionally only the ones with
+/// the given name.
+pub async fn find_exercise_ids_by_name(
+    db: &DatabaseConnection,
+    user_id: i32,
+    name: Option<&str>,
+) -> Result<HashMap<String, i32>, DbErr> {
+    let exercises = Exercise::find()
+        .select_only()
+        .column(exercise::Column::Name)
+        .column(exercise::Column::Id)
+        .column(exercise::Column::CreatedByUserId)
+        .apply_if(name, |q, n| q.filter(exercise::Column::Name.eq(n)))
+        .into_tuple::<(String, i32, Option<i32>)>()
+        .all(db)
+        .await?;
+    Ok(get_exercise_ids_by_name(exercises, user_id))
+}
+
+/// The set that best represents the effort put into an exercise. Sets are ranked
+/// using the statistics that are meaningful for the type of the exercise.
+fn get_best_set_index(
+    records: &[WorkoutSetRecord],
+    lot: ExerciseLot,
+    volume_formula: UserSetVolumeFormula,
+) -> Option<usize> {
     records
         .iter()
         .enumerate()
-        .max_by_key(|(_, record)| {
-            record.statistic.duration.unwrap_or(dec!(0))
-                + record.statistic.distance.unwrap_or(dec!(0))
-                + record
-                    .statistic
-                    .reps
-                    .map(|r| Decimal::from_usize(r).unwrap())
-                    .unwrap_or(dec!(0))
-                + record.statistic.weight.unwrap_or(dec!(0))
+        .max_by_key(|(_, record)| match lot {
+            ExerciseLot::RepsAndWeight => (
+                record.calculate_one_rm(),
+                record.calculate_volume(volume_formula),
+            ),
+            ExerciseLot::Duration => (record.statistic.duration, None),
+            ExerciseLot::DistanceAndDuration => {
+                (record.statistic.distance, record.calculate_pace())
+            }
+            ExerciseLot::WeightAndDistance => {
+                (record.calculate_loaded_distance(), record.statistic.weight)
+            }
         })
         .map(|(index, _)| index)
 }
@@ -45,12 +97,13 @@ fn get_best_set_index(records: &[WorkoutSetRecord]) -> Option<usize> {
 fn get_index_of_highest_pb(
     records: &[WorkoutSetRecord],
     pb_type: &WorkoutSetPersonalBest,
+    volume_formula: UserSetVolumeFormula,
 ) -> Option<usize> {
     let max_el = records
         .iter()
         .max_by(|record1, record2| {
-            let pb1 = record1.get_personal_best(pb_type);
-            let pb2 = record2.get_personal_best(pb_type);
+            let pb1 = record1.get_personal_best(pb_type, volume_formula);
+            let pb2 = record2.get_personal_best(pb_type, volume_formula);
             match (pb1, pb2) {
                 (Some(pb1), Some(pb2)) => pb1.cmp(&pb2),
                 (Some(_), None) => Ordering::Greater,
@@ -62,6 +115,182 @@ fn get_index_of_highest_pb(
     records.iter().position(|e| e == max_el)
 }
 
+/// The goals that were reached by at least one of the sets.
+fn get_goals_achieved(
+    records: &[WorkoutSetRecord],
+    goals: &[UserExerciseGoal],
+    volume_formula: UserSetVolumeFormula,
+) -> Vec<UserExerciseGoal> {
+    goals
+        .iter()
+        .filter(|goal| {
+            records.iter().any(|record| {
+                record
+                    .get_personal_best(&goal.lot, volume_formula)
+                    .is_some_and(|value| value >= goal.target)
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+/// Record the sets which beat the most reps performed at their load. Weights are
+/// rounded down to a multiple of `granularity`. Returns the number of rep maxes
+/// that were set.
+fn update_rep_maxes(
+    rep_maxes: &mut Vec<UserToExerciseRepMax>,
+    sets: &[WorkoutSetRecord],
+    granularity: Decimal,
+    workout_id: &str,
+) -> usize {
+    let mut achieved = 0;
+    for (set_idx, set) in sets.iter().enumerate() {
+        if set.lot == SetLot::WarmUp {
+            continue;
+        }
+        let (Some(weight), Some(reps)) = (set.statistic.weight, set.statistic.reps) else {
+            continue;
+        };
+        let Some(weight) = weight
+            .checked_div(granularity)
+            .map(|w| w.floor() * granularity)
+        else {
+            continue;
+        };
+        if reps == 0 {
+            continue;
+        }
+        let rep_max = UserToExerciseRepMax {
+            weight,
+            reps,
+            workout_id: workout_id.to_owned(),
+            set_idx,
+        };
+        match rep_maxes.iter_mut().find(|r| r.weight == weight) {
+            Some(existing) if existing.reps >= reps => continue,
+            Some(existing) => *existing = rep_max,
+            None => rep_maxes.push(rep_max),
+        }
+        achieved += 1;
+    }
+    rep_maxes.sort_by_key(|r| r.weight);
+    achieved
+}
+
+/// The number of recent sessions that are looked at when suggesting the target of
+/// the next session of an exercise.
+pub const PROGRESSION_SESSIONS: usize = 3;
+
+/// Suggest the target of the next session of an exercise using the sets of its
+/// most recent sessions (oldest first). For linear progression, the weight is
+/// reduced by 10% if the target reps were missed at the same weight in each of the
+/// last `PROGRESSION_SESSIONS` sessions.
+pub fn get_progression_suggestion(
+    progression: &UserExerciseProgression,
+    sessions: &[Vec<WorkoutSetRecord>],
+    volume_formula: UserSetVolumeFormula,
+) -> Option<ExerciseProgressionSuggestion> {
+    let best_sets = sessions
+        .iter()
+        .filter_map(|sets| {
+            let sets = sets
+                .iter()
+                .filter(|s| s.lot != SetLot::WarmUp)
+                .cloned()
+                .collect::<Vec<_>>();
+            let best =
+                &sets[get_best_set_index(&sets, ExerciseLot::RepsAndWeight, volume_formula)?];
+            Some((best.statistic.weight?, best.statistic.reps?))
+        })
+        .collect::<Vec<_>>();
+    let &(weight, reps) = best_sets.last()?;
+    let min_reps = progression.min_reps;
+    let increment = progression.weight_increment;
+    let (next_weight, next_reps) = match progression.scheme {
+        ProgressionScheme::Linear => {
+            let stalled = best_sets.len() >= PROGRESSION_SESSIONS
+                && best_sets
+                    .iter()
+                    .rev()
+                    .take(PROGRESSION_SESSIONS)
+                    .all(|&(w, r)| w == weight && r < min_reps);
+            if reps >= min_reps {
+                (weight + increment, min_reps)
+            } else if stalled {
+                let deload = weight * dec!(0.9);
+                match increment > dec!(0) {
+                    true => ((deload / increment).floor() * increment, min_reps),
+                    false => (deload, min_reps),
+                }
+            } else {
+                (weight, min_reps)
+            }
+        }
+        ProgressionScheme::DoubleProgression => {
+            let max_reps = progression.max_reps.unwrap_or(min_reps).max(min_reps);
+            if reps >= max_reps {
+                (weight + increment, min_reps)
+            } else {
+                (weight, (reps + 1).max(min_reps))
+            }
+        }
+    };
+    Some(ExerciseProgressionSuggestion {
+        weight: next_weight,
+        reps: next_reps,
+        weight_change: next_weight - weight,
+        reps_change: next_reps as i64 - reps as i64,
+    })
+}
+
+const LOWER_BODY_MUSCLES: [ExerciseMuscle; 6] = [
+    ExerciseMuscle::Abductors,
+    ExerciseMuscle::Adductors,
+    ExerciseMuscle::Calves,
+    ExerciseMuscle::Glutes,
+    ExerciseMuscle::Hamstrings,
+    ExerciseMuscle::Quadriceps,
+];
+
+impl WorkoutBalance {
+    /// Add the sets of an exercise to the movement pattern it belongs to. Exercises
+    /// that mostly target the lower body count as legs, the rest are classified
+    /// by their force.
+    fn add_exercise(&mut self, exercise: &exercise::Model, num_sets: usize) {
+        let lower_body = exercise
+            .muscles
+            .iter()
+            .filter(|m| LOWER_BODY_MUSCLES.contains(m))
+            .count();
+        if lower_body > 0 && lower_body * 2 >= exercise.muscles.len() {
+            self.legs += num_sets;
+            return;
+        }
+        match exercise.force {
+            Some(ExerciseForce::Push) => self.push += num_sets,
+            Some(ExerciseForce::Pull) => self.pull += num_sets,
+            _ => {}
+        }
+    }
+
+    /// Warn when both pushing and pulling were done, but one of them has at least
+    /// three times the sets of the other. Dedicated push or pull days are not
+    /// flagged.
+    fn calculate_warning(&mut self) {
+        let (more, less, label) = match self.push.cmp(&self.pull) {
+            Ordering::Greater => (self.push, self.pull, "push"),
+            Ordering::Less => (self.pull, self.push, "pull"),
+            Ordering::Equal => return,
+        };
+        if less > 0 && more >= less * 3 {
+            self.warning = Some(format!(
+                "This workout is heavily {label} dominant ({} push sets vs {} pull sets).",
+                self.push, self.pull
+            ));
+        }
+    }
+}
+
 impl UserWorkoutSetRecord {
     pub fn translate_units(&mut self, unit_type: UserUnitSystem) {
         match unit_type {
@@ -92,23 +321,33 @@ impl UserWorkoutSetRecord {
                 stats.reps = self.statistic.reps;
                 stats.weight = self.statistic.weight;
             }
+            ExerciseLot::WeightAndDistance => {
+                stats.weight = self.statistic.weight;
+                stats.distance = self.statistic.distance;
+                stats.duration = self.statistic.duration;
+            }
         }
+        stats.rpe = self.statistic.rpe;
         self.statistic = stats;
     }
 }
 
 impl UserWorkoutInput {
     /// Create a workout in the database and also update user and exercise associations.
-    pub async fn calculate_and_commit(
+    pub async fn calculate_and_commit<C>(
         self,
         user_id: i32,
-        db: &DatabaseConnection,
+        db: &C,
         id: String,
         preferences: UserExercisePreferences,
-    ) -> Result<String> {
+    ) -> Result<String>
+    where
+        C: ConnectionTrait,
+    {
         let mut input = self;
         let mut exercises = vec![];
         let mut workout_totals = vec![];
+        let mut balance = WorkoutBalance::default();
         if input.exercises.len() == 0 {
             bail!("This workout has no associated exercises")
         }
@@ -146,6 +385,9 @@ impl UserWorkoutInput {
                                 history: vec![history_item],
                                 lifetime_stats: WorkoutTotalMeasurement::default(),
                                 personal_bests: vec![],
+                                goals: vec![],
+                                progression: None,
+                                rep_maxes: vec![],
                             },
                         )),
                         ..Default::default()
@@ -166,11 +408,17 @@ impl UserWorkoutInput {
             for set in ex.sets.iter_mut() {
                 set.translate_units(preferences.unit_system);
                 set.remove_invalids(&db_ex.lot);
+                let record = WorkoutSetRecord {
+                    statistic: set.statistic.clone(),
+                    lot: set.lot,
+                    personal_bests: vec![],
+                    personal_best_improvements: vec![],
+                };
                 if let Some(r) = set.statistic.reps {
                     total.reps += r;
-                    if let Some(w) = set.statistic.weight {
-                        total.weight += w * Decimal::from_usize(r).unwrap();
-                    }
+                }
+                if let Some(v) = record.calculate_volume(preferences.volume_formula) {
+                    total.weight += v;
                 }
                 if let Some(d) = set.statistic.duration {
                     total.duration += d;
@@ -178,11 +426,7 @@ impl UserWorkoutInput {
                 if let Some(d) = set.statistic.distance {
                     total.distance += d;
                 }
-                sets.push(WorkoutSetRecord {
-                    statistic: set.statistic.clone(),
-                    lot: set.lot,
-                    personal_bests: vec![],
-                });
+                sets.push(record);
             }
             let mut personal_bests = association
                 .exercise_extra_information
@@ -200,25 +444,56 @@ impl UserWorkoutInput {
                     WorkoutSetPersonalBest::Volume,
                     WorkoutSetPersonalBest::Reps,
                 ],
+                ExerciseLot::WeightAndDistance => vec![
+                    WorkoutSetPersonalBest::Weight,
+                    WorkoutSetPersonalBest::LoadedDistance,
+                ],
             };
             for best_type in types_of_prs.iter() {
-                let set_idx = get_index_of_highest_pb(&sets, best_type).unwrap();
+                let set_idx =
+                    get_index_of_highest_pb(&sets, best_type, preferences.volume_formula).unwrap();
                 let possible_record = personal_bests
                     .iter()
                     .find(|pb| pb.lot == *best_type)
                     .and_then(|record| record.sets.first());
                 let set = sets.get_mut(set_idx).unwrap();
                 if let Some(r) = possible_record {
-                    if set.get_personal_best(best_type) > r.data.get_personal_best(best_type) {
+                    let current = set.get_personal_best(best_type, preferences.volume_formula);
+                    let previous = r
+                        .data
+                        .get_personal_best(best_type, preferences.volume_formula);
+                    if current > previous {
                         set.personal_bests.push(*best_type);
                         total.personal_bests_achieved += 1;
+                        if let (Some(current), Some(previous)) = (current, previous) {
+                            let delta = current - previous;
+                            set.personal_best_improvements.push(
+                                WorkoutSetPersonalBestImprovement {
+                                    lot: *best_type,
+                                    delta,
+                                    percentage: (delta * dec!(100))
+                                        .checked_div(previous)
+                                        .map(|p| p.round_dp(2)),
+                                },
+                            );
+                        }
                     }
                 } else {
                     set.personal_bests.push(*best_type);
                     total.personal_bests_achieved += 1;
                 }
             }
+            let goals_achieved = get_goals_achieved(
+                &sets,
+                &association
+                    .exercise_extra_information
+                    .clone()
+                    .unwrap()
+                    .goals,
+                preferences.volume_formula,
+            );
             workout_totals.push(total.clone());
+            balance.add_exercise(&db_ex, sets.len());
             for (set_idx, set) in sets.iter().enumerate() {
                 for best in set.personal_bests.iter() {
                     let to_insert_record = ExerciseBestSetRecord {
@@ -243,6 +518,20 @@ impl UserWorkoutInput {
             }
             let mut association_extra_information =
                 association.exercise_extra_information.clone().unwrap();
+            if let (ExerciseLot::RepsAndWeight, Some(granularity)) =
+                (db_ex.lot, preferences.rep_max_granularity)
+            {
+                let granularity = match preferences.unit_system {
+                    UserUnitSystem::Metric => granularity,
+                    UserUnitSystem::Imperial => granularity * dec!(0.45359),
+                };
+                update_rep_maxes(
+                    &mut association_extra_information.rep_maxes,
+                    &sets,
+                    granularity,
+                    &id,
+                );
+            }
             let mut association: user_to_entity::ActiveModel = association.into();
             association_extra_information.lifetime_stats += total.clone();
             association_extra_information.personal_bests = personal_bests;
@@ -251,6 +540,7 @@ impl UserWorkoutInput {
             association.update(db).await?;
             exercises.push((
                 db_ex.lot,
+                goals_achieved,
                 ProcessedExercise {
                     id: ex.exercise_id,
                     name: db_ex.name,
@@ -264,10 +554,11 @@ impl UserWorkoutInput {
             ));
         }
         let summary_total = workout_totals.into_iter().sum();
+        balance.calculate_warning();
         let model = workout::Model {
             id,
             start_time: input.start_time,
-            end_time: input.end_time,
+            end_time: input.end_time.unwrap_or_else(Utc::now),
             user_id,
             name: input.name,
             comment: input.comment,
@@ -275,18 +566,24 @@ impl UserWorkoutInput {
                 total: summary_total,
                 exercises: exercises
                     .iter()
-                    .map(|(lot, e)| WorkoutSummaryExercise {
-                        num_sets: e.sets.len(),
-                        name: e.name.clone(),
-                        lot: *lot,
-                        best_set: e.sets[get_best_set_index(&e.sets).unwrap()].clone(),
+                    .map(|(lot, goals_achieved, e)| {
+                        let best_set_idx =
+                            get_best_set_index(&e.sets, *lot, preferences.volume_formula).unwrap();
+                        WorkoutSummaryExercise {
+                            num_sets: e.sets.len(),
+                            name: e.name.clone(),
+                            lot: *lot,
+                            best_set: e.sets[best_set_idx].clone(),
+                            goals_achieved: goals_achieved.clone(),
+                        }
                     })
                     .collect(),
+                balance,
             },
             information: WorkoutInformation {
                 supersets: input.supersets,
                 assets: input.assets.clone(),
-                exercises: exercises.into_iter().map(|(_, ex)| ex).collect(),
+                exercises: exercises.into_iter().map(|(_, _, ex)| ex).collect(),
             },
         };
         let insert: workout::ActiveModel = model.into();
@@ -296,6 +593,38 @@ impl UserWorkoutInput {
 }
 
 impl workout::Model {
+    /// The input that creates this workout again. The statistics of the sets are
+    /// the ones that were stored, so they are in metric units.
+    pub fn into_input(self) -> UserWorkoutInput {
+        UserWorkoutInput {
+            name: self.name,
+            comment: self.comment,
+            start_time: self.start_time,
+            end_time: Some(self.end_time),
+            exercises: self
+                .information
+                .exercises
+                .into_iter()
+                .map(|e| UserExerciseInput {
+                    exercise_id: e.id,
+                    sets: e
+                        .sets
+                        .into_iter()
+                        .map(|s| UserWorkoutSetRecord {
+                            statistic: s.statistic,
+                            lot: s.lot,
+                        })
+                        .collect(),
+                    notes: e.notes,
+                    rest_time: e.rest_time,
+                    assets: e.assets,
+                })
+                .collect(),
+            supersets: self.information.supersets,
+            assets: self.information.assets,
+        }
+    }
+
     // DEV: For exercises, reduce count, remove from history if present. We will not
     // recalculate exercise associations totals or change personal bests.
     pub async fn delete_existing(self, db: &DatabaseConnection, user_id: i32) -> Result<()> {
@@ -324,3 +653,471 @@ impl workout::Model {
         Ok(())
     }
 }
+
+/// Drop the personal best records that belong to workouts which no longer exist
+/// and put the best surviving record first. Returns the number of records dropped.
+pub fn reconcile_personal_bests(
+    personal_bests: &mut Vec<UserToExerciseBestSetExtraInformation>,
+    workout_ids: &HashSet<String>,
+    volume_formula: UserSetVolumeFormula,
+) -> usize {
+    let mut dropped = 0;
+    for record in personal_bests.iter_mut() {
+        let total = record.sets.len();
+        record.sets.retain(|s| workout_ids.contains(&s.workout_id));
+        if record.sets.len() == total {
+            continue;
+        }
+        dropped += total - record.sets.len();
+        let lot = record.lot;
+        record.sets.sort_by(|a, b| {
+            b.data
+                .get_personal_best(&lot, volume_formula)
+                .cmp(&a.data.get_personal_best(&lot, volume_formula))
+        });
+    }
+    personal_bests.retain(|pb| !pb.sets.is_empty());
+    dropped
+}
+
+/// Get the plates that need to be loaded on each side of the bar to make up the
+/// weight, using the heaviest plates first. Returns `None` if the weight is less
+/// than that of the bar.
+pub fn calculate_plate_breakdown(
+    weight: Decimal,
+    bar_weight: Decimal,
+    available: &[Decimal],
+) -> Option<PlateBreakdown> {
+    if weight < bar_weight {
+        return None;
+    }
+    let mut plates = available
+        .iter()
+        .copied()
+        .filter(|p| *p > dec!(0))
+        .collect::<Vec<_>>();
+    plates.sort_unstable_by(|a, b| b.cmp(a));
+    plates.dedup();
+    let mut remaining = (weight - bar_weight) / dec!(2);
+    let mut per_side = vec![];
+    for plate in plates {
+        let count = (remaining / plate).floor();
+        if count > dec!(0) {
+            remaining -= count * plate;
+            per_side.push(PlateCount {
+                weight: plate,
+                count: count.to_usize().unwrap(),
+            });
+        }
+    }
+    Some(PlateBreakdown {
+        bar_weight,
+        per_side,
+        remainder: (remaining * dec!(2)).normalize(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_set(
+        lot: SetLot,
+        weight: Decimal,
+        reps: usize,
+        rpe: Option<Decimal>,
+    ) -> WorkoutSetRecord {
+        WorkoutSetRecord {
+            statistic: WorkoutSetStatistic {
+                weight: Some(weight),
+                reps: Some(reps),
+                rpe,
+                ..Default::default()
+            },
+            lot,
+            personal_bests: vec![],
+            personal_best_improvements: vec![],
+        }
+    }
+
+    fn get_total_volume(sets: &[WorkoutSetRecord], formula: UserSetVolumeFormula) -> Decimal {
+        sets.iter()
+            .filter_map(|s| s.calculate_volume(formula))
+            .sum()
+    }
+
+    #[test]
+    fn volume_formulas_diverge() {
+        let sets = vec![
+            get_set(SetLot::WarmUp, dec!(40), 10, Some(dec!(5))),
+            get_set(SetLot::Normal, dec!(100), 5, Some(dec!(8))),
+            get_set(SetLot::Normal, dec!(100), 5, None),
+        ];
+        assert_eq!(
+            get_total_volume(&sets, UserSetVolumeFormula::Simple),
+            dec!(1400)
+        );
+        assert_eq!(
+            get_total_volume(&sets, UserSetVolumeFormula::RpeAdjusted),
+            dec!(1100)
+        );
+        assert_eq!(
+            get_total_volume(&sets, UserSetVolumeFormula::WorkingSetsOnly),
+            dec!(1000)
+        );
+    }
+
+    #[test]
+    fn volume_pr_depends_on_formula() {
+        let sets = vec![
+            get_set(SetLot::WarmUp, dec!(60), 20, None),
+            get_set(SetLot::Normal, dec!(100), 8, Some(dec!(6))),
+            get_set(SetLot::Normal, dec!(90), 8, Some(dec!(10))),
+        ];
+        let pb = WorkoutSetPersonalBest::Volume;
+        let index = |f| get_index_of_highest_pb(&sets, &pb, f);
+        assert_eq!(index(UserSetVolumeFormula::Simple), Some(0));
+        assert_eq!(index(UserSetVolumeFormula::RpeAdjusted), Some(0));
+        assert_eq!(index(UserSetVolumeFormula::WorkingSetsOnly), Some(1));
+    }
+
+    #[test]
+    fn orphaned_personal_bests_are_dropped() {
+        let record = |workout_id: &str, weight| ExerciseBestSetRecord {
+            workout_id: workout_id.to_owned(),
+            set_idx: 0,
+            data: get_set(SetLot::Normal, weight, 5, None),
+        };
+        let mut personal_bests = vec![
+            UserToExerciseBestSetExtraInformation {
+                lot: WorkoutSetPersonalBest::Weight,
+                sets: vec![
+                    record("3", dec!(100)),
+                    record("1", dec!(80)),
+                    record("2", dec!(90)),
+                ],
+            },
+            UserToExerciseBestSetExtraInformation {
+                lot: WorkoutSetPersonalBest::Reps,
+                sets: vec![record("3", dec!(100))],
+            },
+        ];
+        let workout_ids = HashSet::from(["1".to_owned(), "2".to_owned()]);
+        let dropped = reconcile_personal_bests(
+            &mut personal_bests,
+            &workout_ids,
+            UserSetVolumeFormula::Simple,
+        );
+        assert_eq!(dropped, 2);
+        assert_eq!(personal_bests.len(), 1);
+        let ids = personal_bests[0]
+            .sets
+            .iter()
+            .map(|s| s.workout_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["2", "1"]);
+    }
+
+    #[test]
+    fn loaded_distance_pr_uses_weight_and_distance() {
+        let mut sets = [(dec!(100), dec!(40)), (dec!(80), dec!(60))]
+            .into_iter()
+            .map(|(weight, distance)| WorkoutSetRecord {
+                statistic: WorkoutSetStatistic {
+                    weight: Some(weight),
+                    distance: Some(distance),
+                    reps: Some(5),
+                    ..Default::default()
+                },
+                lot: SetLot::Normal,
+                personal_bests: vec![],
+                personal_best_improvements: vec![],
+            })
+            .collect::<Vec<_>>();
+        sets.iter_mut()
+            .for_each(|s| s.remove_invalids(&ExerciseLot::WeightAndDistance));
+        assert_eq!(sets[0].statistic.reps, None);
+        let formula = UserSetVolumeFormula::Simple;
+        let by_weight = get_index_of_highest_pb(&sets, &WorkoutSetPersonalBest::Weight, formula);
+        let by_loaded_distance =
+            get_index_of_highest_pb(&sets, &WorkoutSetPersonalBest::LoadedDistance, formula);
+        assert_eq!(by_weight, Some(0));
+        assert_eq!(by_loaded_distance, Some(1));
+    }
+
+    #[test]
+    fn weight_is_broken_down_into_plates() {
+        let available = [
+            dec!(25),
+            dec!(20),
+            dec!(15),
+            dec!(10),
+            dec!(5),
+            dec!(2.5),
+            dec!(1.25),
+        ];
+        let plates = |weight| {
+            calculate_plate_breakdown(weight, dec!(20), &available).map(|b| {
+                let per_side = b
+                    .per_side
+                    .into_iter()
+                    .map(|p| (p.weight, p.count))
+                    .collect::<Vec<_>>();
+                (per_side, b.remainder)
+            })
+        };
+        assert_eq!(
+            plates(dec!(100)),
+            Some((vec![(dec!(25), 1), (dec!(15), 1)], dec!(0)))
+        );
+        assert_eq!(plates(dec!(20)), Some((vec![], dec!(0))));
+        assert_eq!(
+            plates(dec!(140)),
+            Some((vec![(dec!(25), 2), (dec!(10), 1)], dec!(0)))
+        );
+        assert_eq!(
+            plates(dec!(102.5)),
+            Some((vec![(dec!(25), 1), (dec!(15), 1), (dec!(1.25), 1)], dec!(0)))
+        );
+        assert_eq!(
+            plates(dec!(101)),
+            Some((vec![(dec!(25), 1), (dec!(15), 1)], dec!(1)))
+        );
+        assert_eq!(plates(dec!(15)), None);
+    }
+
+    fn get_timed_set(distance: Option<Decimal>, duration: Decimal) -> WorkoutSetRecord {
+        WorkoutSetRecord {
+            statistic: WorkoutSetStatistic {
+                distance,
+                duration: Some(duration),
+                ..Default::default()
+            },
+            lot: SetLot::Normal,
+            personal_bests: vec![],
+            personal_best_improvements: vec![],
+        }
+    }
+
+    #[test]
+    fn best_set_for_reps_and_weight_uses_one_rm() {
+        let sets = vec![
+            get_set(SetLot::Normal, dec!(100), 12, None),
+            get_set(SetLot::Normal, dec!(140), 3, None),
+            get_set(SetLot::Normal, dec!(120), 5, None),
+        ];
+        let formula = UserSetVolumeFormula::Simple;
+        assert_eq!(
+            get_best_set_index(&sets, ExerciseLot::RepsAndWeight, formula),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn best_set_for_duration_uses_time() {
+        let sets = vec![
+            get_timed_set(None, dec!(45)),
+            get_timed_set(None, dec!(90)),
+            get_timed_set(None, dec!(60)),
+        ];
+        let formula = UserSetVolumeFormula::Simple;
+        assert_eq!(
+            get_best_set_index(&sets, ExerciseLot::Duration, formula),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn best_set_for_distance_and_duration_uses_distance() {
+        let sets = vec![
+            get_timed_set(Some(dec!(5)), dec!(25)),
+            get_timed_set(Some(dec!(10)), dec!(55)),
+            get_timed_set(Some(dec!(3)), dec!(60)),
+        ];
+        let formula = UserSetVolumeFormula::Simple;
+        assert_eq!(
+            get_best_set_index(&sets, ExerciseLot::DistanceAndDuration, formula),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn best_set_is_not_decided_by_magnitude_across_statistics() {
+        let mut plank = get_timed_set(None, dec!(3600));
+        plank.statistic.weight = Some(dec!(0));
+        let sets = vec![plank, get_set(SetLot::Normal, dec!(200), 1, None)];
+        let formula = UserSetVolumeFormula::Simple;
+        assert_eq!(
+            get_best_set_index(&sets, ExerciseLot::RepsAndWeight, formula),
+            Some(1)
+        );
+        assert_eq!(
+            get_best_set_index(&sets, ExerciseLot::Duration, formula),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn goals_are_achieved_when_target_is_reached() {
+        let sets = vec![
+            get_set(SetLot::Normal, dec!(95), 6, None),
+            get_set(SetLot::Normal, dec!(100), 1, None),
+        ];
+        let goal = |lot, target| UserExerciseGoal { lot, target };
+        let goals = vec![
+            goal(WorkoutSetPersonalBest::Weight, dec!(100)),
+            goal(WorkoutSetPersonalBest::Weight, dec!(110)),
+            goal(WorkoutSetPersonalBest::Volume, dec!(500)),
+        ];
+        let achieved = get_goals_achieved(&sets, &goals, UserSetVolumeFormula::Simple);
+        assert_eq!(
+            achieved,
+            vec![
+                goal(WorkoutSetPersonalBest::Weight, dec!(100)),
+                goal(WorkoutSetPersonalBest::Volume, dec!(500)),
+            ]
+        );
+    }
+
+    #[test]
+    fn linear_progression_adds_weight_once_reps_are_reached() {
+        let progression = UserExerciseProgression {
+            scheme: ProgressionScheme::Linear,
+            weight_increment: dec!(2.5),
+            min_reps: 5,
+            max_reps: None,
+        };
+        let session = |weight, reps| {
+            vec![
+                get_set(SetLot::WarmUp, dec!(40), 10, None),
+                get_set(SetLot::Normal, weight, reps, None),
+            ]
+        };
+        let suggest = |sessions: &[Vec<WorkoutSetRecord>]| {
+            get_progression_suggestion(&progression, sessions, UserSetVolumeFormula::Simple)
+                .map(|s| (s.weight, s.reps, s.weight_change))
+        };
+        assert_eq!(suggest(&[]), None);
+        assert_eq!(
+            suggest(&[session(dec!(100), 5)]),
+            Some((dec!(102.5), 5, dec!(2.5)))
+        );
+        assert_eq!(
+            suggest(&[session(dec!(100), 5), session(dec!(102.5), 4)]),
+            Some((dec!(102.5), 5, dec!(0)))
+        );
+        let stalled = [
+            session(dec!(102.5), 4),
+            session(dec!(102.5), 3),
+            session(dec!(102.5), 4),
+        ];
+        assert_eq!(suggest(&stalled), Some((dec!(90), 5, dec!(-12.5))));
+    }
+
+    #[test]
+    fn double_progression_adds_reps_until_the_top_of_the_range() {
+        let progression = UserExerciseProgression {
+            scheme: ProgressionScheme::DoubleProgression,
+            weight_increment: dec!(2),
+            min_reps: 8,
+            max_reps: Some(12),
+        };
+        let suggest = |weight, reps| {
+            let sessions = [vec![get_set(SetLot::Normal, weight, reps, None)]];
+            get_progression_suggestion(&progression, &sessions, UserSetVolumeFormula::Simple)
+                .map(|s| (s.weight, s.reps, s.reps_change))
+        };
+        assert_eq!(suggest(dec!(20), 9), Some((dec!(20), 10, 1)));
+        assert_eq!(suggest(dec!(20), 6), Some((dec!(20), 8, 2)));
+        assert_eq!(suggest(dec!(20), 12), Some((dec!(22), 8, -4)));
+    }
+
+    #[test]
+    fn rep_maxes_are_tracked_per_weight_bucket() {
+        let mut rep_maxes = vec![];
+        let sets = [
+            get_set(SetLot::WarmUp, dec!(60), 10, None),
+            get_set(SetLot::Normal, dec!(100), 5, None),
+            get_set(SetLot::Normal, dec!(101), 6, None),
+            get_set(SetLot::Normal, dec!(90), 8, None),
+        ];
+        let achieved = update_rep_maxes(&mut rep_maxes, &sets, dec!(2.5), "w1");
+        assert_eq!(achieved, 3);
+        assert_eq!(
+            rep_maxes
+                .iter()
+                .map(|r| (r.weight, r.reps, r.set_idx))
+                .collect::<Vec<_>>(),
+            vec![(dec!(90), 8, 3), (dec!(100), 6, 2)]
+        );
+        let sets = [
+            get_set(SetLot::Normal, dec!(102), 6, None),
+            get_set(SetLot::Normal, dec!(92.4), 9, None),
+        ];
+        let achieved = update_rep_maxes(&mut rep_maxes, &sets, dec!(2.5), "w2");
+        assert_eq!(achieved, 1);
+        assert_eq!(
+            rep_maxes
+                .iter()
+                .map(|r| (r.weight, r.reps, r.workout_id.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(dec!(90), 9, "w2"), (dec!(100), 6, "w1")]
+        );
+    }
+
+    #[test]
+    fn custom_exercise_shadows_built_in_with_same_name() {
+        let exercises = vec![
+            ("Bench Press".to_owned(), 1, None),
+            ("Squat".to_owned(), 2, None),
+            ("Bench Press".to_owned(), 3, Some(10)),
+            ("Squat".to_owned(), 4, Some(20)),
+            ("Zercher Carry".to_owned(), 5, Some(20)),
+        ];
+        let ids = get_exercise_ids_by_name(exercises, 10);
+        assert_eq!(
+            ids,
+            HashMap::from([("Bench Press".to_owned(), 3), ("Squat".to_owned(), 2)])
+        );
+    }
+
+    #[test]
+    fn workouts_are_converted_back_to_their_input() {
+        let set = get_set(SetLot::Normal, dec!(100), 5, None);
+        let workout = workout::Model {
+            id: "1".to_owned(),
+            start_time: Utc::now(),
+            end_time: Utc::now(),
+            user_id: 1,
+            summary: WorkoutSummary {
+                total: WorkoutTotalMeasurement::default(),
+                exercises: vec![],
+                balance: WorkoutBalance::default(),
+            },
+            information: WorkoutInformation {
+                supersets: vec![vec![0, 1]],
+                exercises: vec![ProcessedExercise {
+                    id: 3,
+                    name: "Squat".to_owned(),
+                    lot: ExerciseLot::RepsAndWeight,
+                    sets: vec![set.clone()],
+                    notes: vec!["Go deeper".to_owned()],
+                    rest_time: Some(90),
+                    total: WorkoutTotalMeasurement::default(),
+                    assets: Default::default(),
+                }],
+                assets: Default::default(),
+            },
+            name: "Legs".to_owned(),
+            comment: None,
+        };
+        let input = workout.clone().into_input();
+        assert_eq!(input.name, "Legs");
+        assert_eq!(input.end_time, Some(workout.end_time));
+        assert_eq!(input.supersets, vec![vec![0, 1]]);
+        assert_eq!(input.exercises[0].exercise_id, 3);
+        assert_eq!(input.exercises[0].rest_time, Some(90));
+        assert_eq!(input.exercises[0].sets[0].statistic, set.statistic);
+        assert_eq!(input.exercises[0].sets[0].lot, SetLot::Normal);
+    }
+}
@@ -1,3 +1,3 @@
 pub mod resolver;
 
-mod logic;
+pub mod logic;
@@ -1,16 +1,21 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use apalis::{prelude::Storage, sqlite::SqliteStorage};
 use async_graphql::{Context, Enum, Error, InputObject, Object, Result, SimpleObject};
+use chrono::{Duration, NaiveDate, Utc};
 use database::{
     ExerciseEquipment, ExerciseForce, ExerciseLevel, ExerciseLot, ExerciseMechanic, ExerciseMuscle,
     ExerciseSource,
 };
 use itertools::Itertools;
+use rust_decimal::Decimal;
 use sea_orm::{
-    prelude::DateTimeUtc, ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection,
-    EntityTrait, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, QueryTrait,
-    RelationTrait,
+    prelude::DateTimeUtc, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait,
+    DatabaseBackend, DatabaseConnection, EntityTrait, ModelTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, QueryTrait, RelationTrait, TransactionTrait,
 };
 use sea_query::{Alias, Condition, Expr, Func, JoinType};
 use serde::{Deserialize, Serialize};
@@ -25,21 +30,31 @@ use crate::{
         collection,
         exercise::{self, ExerciseListItem},
         prelude::{Exercise, UserMeasurement, UserToEntity, Workout},
-        user::UserWithOnlyPreferences,
+        user::{self, UserWithOnlyPreferences, UserWithOnlyWorkoutDraft},
         user_measurement, user_to_entity, workout,
     },
     file_storage::FileStorageService,
+    fitness::logic::{
+        calculate_plate_breakdown, find_exercise_ids_by_name, get_progression_suggestion,
+        reconcile_personal_bests, PROGRESSION_SESSIONS,
+    },
     miscellaneous::DefaultCollection,
     models::{
         fitness::{
             Exercise as GithubExercise, ExerciseAttributes, ExerciseCategory,
-            GithubExerciseAttributes, UserWorkoutInput, WorkoutListItem, WorkoutSetRecord,
+            ExerciseProgressionSuggestion, GithubExerciseAttributes, ImportOrExportExerciseItem,
+            PlateBreakdown, UserExerciseGoal, UserExerciseProgression,
+            UserToExerciseExtraInformation, UserWorkoutInput, WorkoutListItem, WorkoutSetRecord,
         },
         media::ChangeCollectionToEntityInput,
         EntityLot, IdObject, SearchDetails, SearchInput, SearchResults, StoredUrl,
     },
     traits::{AuthProvider, GraphqlRepresentation},
-    utils::{add_entity_to_collection, entity_in_collections, get_ilike_query, partial_user_by_id},
+    users::{UserExercisePreferences, UserUnitSystem},
+    utils::{
+        add_entity_to_collection, admin_account_guard, entity_in_collections, get_ilike_query,
+        partial_user_by_id,
+    },
 };
 
 static JSON_URL: &str =
@@ -119,6 +134,43 @@ struct UserExerciseDetailsInput {
     take_history: Option<usize>,
 }
 
+#[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
+struct NeglectedExercisesInput {
+    /// Exercises that have not been performed in these many days are returned.
+    /// Must be between 0 and 36500.
+    days: i64,
+    /// Whether exercises that have never been performed should also be returned.
+    include_never_performed: Option<bool>,
+    muscle: Option<ExerciseMuscle>,
+}
+
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
+struct NeglectedExercise {
+    exercise_id: i32,
+    name: String,
+    #[graphql(name = "type")]
+    lot: ExerciseLot,
+    muscles: Vec<ExerciseMuscle>,
+    num_times_performed: i32,
+    last_performed_on: Option<DateTimeUtc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
+struct WorkoutCalendarInput {
+    start_time: DateTimeUtc,
+    end_time: DateTimeUtc,
+    /// Only count workouts in which this exercise was performed.
+    exercise_id: Option<i32>,
+    /// Only count workouts in which an exercise targeting this muscle was performed.
+    muscle: Option<ExerciseMuscle>,
+}
+
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
+struct WorkoutCalendarDay {
+    date: NaiveDate,
+    num_workouts: usize,
+}
+
 #[derive(Default)]
 pub struct ExerciseQuery;
 
@@ -184,6 +236,64 @@ impl ExerciseQuery {
         service.user_exercise_details(user_id, input).await
     }
 
+    /// Suggest the target of the next session of an exercise using the progression
+    /// set for it. Returns nothing if no progression has been set or the exercise
+    /// has no sets with both weight and reps.
+    async fn exercise_progression_suggestion(
+        &self,
+        gql_ctx: &Context<'_>,
+        exercise_id: i32,
+    ) -> Result<Option<ExerciseProgressionSuggestion>> {
+        let service = gql_ctx.data_unchecked::<Arc<ExerciseService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service
+            .exercise_progression_suggestion(user_id, exercise_id)
+            .await
+    }
+
+    /// Get the exercises that the user has not performed recently, ordered by the
+    /// time they were last performed.
+    async fn neglected_exercises(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: NeglectedExercisesInput,
+    ) -> Result<Vec<NeglectedExercise>> {
+        let service = gql_ctx.data_unchecked::<Arc<ExerciseService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.neglected_exercises(user_id, input).await
+    }
+
+    /// Get the number of workouts done by the user on each day in a time range. Days
+    /// without any workouts are not returned.
+    async fn workout_calendar(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: WorkoutCalendarInput,
+    ) -> Result<Vec<WorkoutCalendarDay>> {
+        let service = gql_ctx.data_unchecked::<Arc<ExerciseService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.workout_calendar(user_id, input).await
+    }
+
+    /// Get the workout that the user has not finished logging yet.
+    async fn workout_draft(&self, gql_ctx: &Context<'_>) -> Result<Option<UserWorkoutInput>> {
+        let service = gql_ctx.data_unchecked::<Arc<ExerciseService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.workout_draft(user_id).await
+    }
+
+    /// Get the plates that need to be loaded on each side of the bar to lift a
+    /// weight (in kg). Returns nothing if the user has not enabled plate display.
+    async fn plate_breakdown(
+        &self,
+        gql_ctx: &Context<'_>,
+        weight: Decimal,
+    ) -> Result<Option<PlateBreakdown>> {
+        let service = gql_ctx.data_unchecked::<Arc<ExerciseService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.plate_breakdown(user_id, weight).await
+    }
+
     /// Get all the measurements for a user.
     async fn user_measurements_list(
         &self,
@@ -202,7 +312,7 @@ pub struct ExerciseMutation;
 #[Object]
 impl ExerciseMutation {
     /// Create a user measurement.
-    async fn create_user_measurement(
+    pub async fn create_user_measurement(
         &self,
         gql_ctx: &Context<'_>,
         input: user_measurement::Model,
@@ -234,6 +344,31 @@ impl ExerciseMutation {
         service.create_user_workout(user_id, input).await
     }
 
+    /// Save a workout that is still in progress. It replaces any existing draft.
+    async fn save_workout_draft(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: UserWorkoutInput,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<ExerciseService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.save_workout_draft(user_id, input).await
+    }
+
+    /// Delete the workout draft of the user without committing it.
+    async fn delete_workout_draft(&self, gql_ctx: &Context<'_>) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<ExerciseService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.delete_workout_draft(user_id).await
+    }
+
+    /// Process the workout draft of the user and commit it to database.
+    async fn finish_workout_draft(&self, gql_ctx: &Context<'_>) -> Result<String> {
+        let service = gql_ctx.data_unchecked::<Arc<ExerciseService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.finish_workout_draft(user_id).await
+    }
+
     /// Delete a workout and remove all exercise associations.
     async fn delete_user_workout(&self, gql_ctx: &Context<'_>, workout_id: String) -> Result<bool> {
         let service = gql_ctx.data_unchecked::<Arc<ExerciseService>>();
@@ -251,6 +386,78 @@ impl ExerciseMutation {
         let user_id = service.user_id_from_ctx(gql_ctx).await?;
         service.create_custom_exercise(user_id, input).await
     }
+
+    /// Set the goals of the user for an exercise that they have performed. Pass an
+    /// empty list to clear them.
+    async fn set_exercise_goals(
+        &self,
+        gql_ctx: &Context<'_>,
+        exercise_id: i32,
+        goals: Vec<UserExerciseGoal>,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<ExerciseService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service
+            .set_exercise_goals(user_id, exercise_id, goals)
+            .await
+    }
+
+    /// Set how an exercise should be progressed from one session to the next. Pass
+    /// nothing to remove it.
+    async fn set_exercise_progression(
+        &self,
+        gql_ctx: &Context<'_>,
+        exercise_id: i32,
+        progression: Option<UserExerciseProgression>,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<ExerciseService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service
+            .set_exercise_progression(user_id, exercise_id, progression)
+            .await
+    }
+
+    /// Recalculate all the exercise associations of a user by replaying their
+    /// workouts in the order in which they were performed. Goals and progression
+    /// settings are kept. The account performing this must be an `Admin`. Returns
+    /// the number of workouts that were replayed.
+    async fn rebuild_all_exercise_associations(
+        &self,
+        gql_ctx: &Context<'_>,
+        user_id: i32,
+    ) -> Result<usize> {
+        let service = gql_ctx.data_unchecked::<Arc<ExerciseService>>();
+        let admin_id = service.user_id_from_ctx(gql_ctx).await?;
+        admin_account_guard(&service.db, admin_id).await?;
+        service.rebuild_all_exercise_associations(user_id).await
+    }
+
+    /// Remove personal best records that refer to deleted workouts for all users.
+    /// The account performing this must be an `Admin`. Returns the number of
+    /// records that were removed.
+    async fn reconcile_personal_bests(&self, gql_ctx: &Context<'_>) -> Result<usize> {
+        let service = gql_ctx.data_unchecked::<Arc<ExerciseService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        admin_account_guard(&service.db, user_id).await?;
+        service.reconcile_personal_bests().await
+    }
+
+    /// Change the type of an exercise and normalize all the sets that were logged
+    /// for it, recalculating the personal bests of the users who performed it. Only
+    /// an `Admin` can change exercises that they did not create. Returns the number
+    /// of workouts that contained the exercise.
+    async fn convert_exercise_lot(
+        &self,
+        gql_ctx: &Context<'_>,
+        exercise_id: i32,
+        lot: ExerciseLot,
+    ) -> Result<usize> {
+        let service = gql_ctx.data_unchecked::<Arc<ExerciseService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service
+            .convert_exercise_lot(user_id, exercise_id, lot)
+            .await
+    }
 }
 
 pub struct ExerciseService {
@@ -398,6 +605,131 @@ impl ExerciseService {
         Ok(resp)
     }
 
+    async fn neglected_exercises(
+        &self,
+        user_id: i32,
+        input: NeglectedExercisesInput,
+    ) -> Result<Vec<NeglectedExercise>> {
+        // DEV: Larger values make `Duration::days` panic.
+        if !(0..=36500).contains(&input.days) {
+            return Err(Error::new("The number of days must be between 0 and 36500"));
+        }
+        let associations = UserToEntity::find()
+            .filter(user_to_entity::Column::UserId.eq(user_id))
+            .filter(user_to_entity::Column::ExerciseId.is_not_null())
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|a| (a.exercise_id.unwrap(), a))
+            .collect::<HashMap<_, _>>();
+        let include_never_performed = input.include_never_performed.unwrap_or(false);
+        let exercises = Exercise::find()
+            .apply_if(
+                match include_never_performed {
+                    true => None,
+                    false => Some(associations.keys().copied().collect_vec()),
+                },
+                |query, v| query.filter(exercise::Column::Id.is_in(v)),
+            )
+            .filter(
+                Condition::any()
+                    .add(exercise::Column::CreatedByUserId.is_null())
+                    .add(exercise::Column::CreatedByUserId.eq(user_id)),
+            )
+            .order_by_asc(exercise::Column::Name)
+            .all(&self.db)
+            .await?;
+        let threshold = Utc::now() - Duration::days(input.days);
+        let mut neglected = vec![];
+        for exercise in exercises {
+            if let Some(muscle) = input.muscle {
+                if !exercise.muscles.contains(&muscle) {
+                    continue;
+                }
+            }
+            let (num_times_performed, last_performed_on) = match associations.get(&exercise.id) {
+                Some(a) if a.num_times_interacted > 0 => {
+                    (a.num_times_interacted, Some(a.last_updated_on))
+                }
+                _ => (0, None),
+            };
+            let is_neglected = match last_performed_on {
+                Some(on) => on < threshold,
+                None => include_never_performed,
+            };
+            if is_neglected {
+                neglected.push(NeglectedExercise {
+                    exercise_id: exercise.id,
+                    name: exercise.name,
+                    lot: exercise.lot,
+                    muscles: exercise.muscles,
+                    num_times_performed,
+                    last_performed_on,
+                });
+            }
+        }
+        neglected.sort_by_key(|e| e.last_performed_on);
+        Ok(neglected)
+    }
+
+    async fn workout_calendar(
+        &self,
+        user_id: i32,
+        input: WorkoutCalendarInput,
+    ) -> Result<Vec<WorkoutCalendarDay>> {
+        let mut exercise_ids = input.exercise_id.map(|e| vec![e]);
+        if let Some(muscle) = input.muscle {
+            let with_muscle = Exercise::find()
+                .all(&self.db)
+                .await?
+                .into_iter()
+                .filter(|e| e.muscles.contains(&muscle))
+                .map(|e| e.id)
+                .filter(|id| exercise_ids.as_ref().map_or(true, |ids| ids.contains(id)))
+                .collect_vec();
+            exercise_ids = Some(with_muscle);
+        }
+        let workouts = Workout::find()
+            .filter(workout::Column::UserId.eq(user_id))
+            .filter(workout::Column::StartTime.gte(input.start_time))
+            .filter(workout::Column::StartTime.lte(input.end_time))
+            .order_by_asc(workout::Column::StartTime)
+            .all(&self.db)
+            .await?;
+        let days = workouts
+            .into_iter()
+            .filter(|w| match &exercise_ids {
+                Some(ids) => w.information.exercises.iter().any(|e| ids.contains(&e.id)),
+                None => true,
+            })
+            .map(|w| w.start_time.date_naive())
+            .dedup_with_count()
+            .map(|(num_workouts, date)| WorkoutCalendarDay { date, num_workouts })
+            .collect();
+        Ok(days)
+    }
+
+    async fn plate_breakdown(
+        &self,
+        user_id: i32,
+        weight: Decimal,
+    ) -> Result<Option<PlateBreakdown>> {
+        let plates = partial_user_by_id::<UserWithOnlyPreferences>(&self.db, user_id)
+            .await?
+            .preferences
+            .fitness
+            .exercises
+            .plates;
+        if !plates.enabled {
+            return Ok(None);
+        }
+        Ok(calculate_plate_breakdown(
+            weight,
+            plates.bar_weight,
+            &plates.available,
+        ))
+    }
+
     async fn user_workout_list(
         &self,
         user_id: i32,
@@ -469,6 +801,11 @@ impl ExerciseService {
                 Expr::col((etu, user_to_entity::Column::NumTimesInteracted)),
                 "num_times_interacted",
             )
+            .filter(
+                Condition::any()
+                    .add(exercise::Column::CreatedByUserId.is_null())
+                    .add(exercise::Column::CreatedByUserId.eq(user_id)),
+            )
             .apply_if(input.filter, |query, q| {
                 query
                     .apply_if(q.lot, |q, v| q.filter(exercise::Column::Lot.eq(v)))
@@ -638,7 +975,7 @@ impl ExerciseService {
         Ok(resp)
     }
 
-    async fn create_user_measurement(
+    pub async fn create_user_measurement(
         &self,
         user_id: i32,
         mut input: user_measurement::Model,
@@ -676,13 +1013,65 @@ impl ExerciseService {
         Ok(identifier)
     }
 
-    async fn create_custom_exercise(
+    async fn workout_draft(&self, user_id: i32) -> Result<Option<UserWorkoutInput>> {
+        let user = partial_user_by_id::<UserWithOnlyWorkoutDraft>(&self.db, user_id).await?;
+        Ok(user.workout_draft)
+    }
+
+    async fn save_workout_draft(&self, user_id: i32, mut input: UserWorkoutInput) -> Result<bool> {
+        input.end_time = None;
+        self.set_workout_draft(user_id, Some(input)).await?;
+        Ok(true)
+    }
+
+    async fn delete_workout_draft(&self, user_id: i32) -> Result<bool> {
+        self.set_workout_draft(user_id, None).await?;
+        Ok(true)
+    }
+
+    async fn finish_workout_draft(&self, user_id: i32) -> Result<String> {
+        let Some(draft) = self.workout_draft(user_id).await? else {
+            return Err(Error::new("There is no workout draft for this user"));
+        };
+        let identifier = self.create_user_workout(user_id, draft).await?;
+        self.set_workout_draft(user_id, None).await?;
+        Ok(identifier)
+    }
+
+    async fn set_workout_draft(&self, user_id: i32, draft: Option<UserWorkoutInput>) -> Result<()> {
+        let user = user::ActiveModel {
+            id: ActiveValue::Unchanged(user_id),
+            workout_draft: ActiveValue::Set(draft),
+            ..Default::default()
+        };
+        user.update(&self.db).await?;
+        Ok(())
+    }
+
+    pub async fn create_custom_exercise(
         &self,
         user_id: i32,
         input: exercise::Model,
     ) -> Result<IdObject> {
+        let existing = Exercise::find()
+            .filter(exercise::Column::Name.eq(&input.name))
+            .all(&self.db)
+            .await?;
+        if existing
+            .iter()
+            .any(|e| e.created_by_user_id == Some(user_id))
+        {
+            return Err(Error::new("You already have an exercise with this name"));
+        }
+        if !existing.is_empty()
+            && (!self.config.exercise.allow_shadowing_names
+                || self.db.get_database_backend() == DatabaseBackend::Sqlite)
+        {
+            return Err(Error::new("An exercise with this name already exists"));
+        }
         let mut input = input;
         input.source = ExerciseSource::Custom;
+        input.created_by_user_id = Some(user_id);
         input.attributes.internal_images = input
             .attributes
             .images
@@ -702,6 +1091,7 @@ impl ExerciseService {
                 collection_name: DefaultCollection::Custom.to_string(),
                 entity_id: exercise.id,
                 entity_lot: EntityLot::Exercise,
+                ..Default::default()
             },
         )
         .await?;
@@ -724,6 +1114,181 @@ impl ExerciseService {
         Ok(workouts)
     }
 
+    pub async fn export_exercises(&self, user_id: i32) -> Result<Vec<ImportOrExportExerciseItem>> {
+        let associations = UserToEntity::find()
+            .filter(user_to_entity::Column::UserId.eq(user_id))
+            .filter(user_to_entity::Column::ExerciseId.is_not_null())
+            .find_also_related(Exercise)
+            .order_by_asc(user_to_entity::Column::Id)
+            .all(&self.db)
+            .await?;
+        let mut exercises = vec![];
+        for (association, exercise) in associations {
+            let exercise = exercise.unwrap();
+            let collections =
+                entity_in_collections(&self.db, user_id, exercise.id, EntityLot::Exercise)
+                    .await?
+                    .into_iter()
+                    .map(|c| c.name)
+                    .collect();
+            exercises.push(ImportOrExportExerciseItem {
+                name: exercise.name.clone(),
+                num_times_interacted: association.num_times_interacted,
+                extra_information: association.exercise_extra_information.unwrap_or_default(),
+                collections,
+                details: match exercise.source {
+                    ExerciseSource::Custom => Some(exercise),
+                    ExerciseSource::Github => None,
+                },
+            });
+        }
+        Ok(exercises)
+    }
+
+    /// Restore exercise associations and workouts from an export. The history,
+    /// lifetime statistics and personal bests are rebuilt by replaying every
+    /// workout of the user, so that they also account for the existing ones.
+    /// Returns the workouts that could not be restored.
+    #[instrument(skip(self, exercises, workouts))]
+    pub async fn restore_exercises_and_workouts(
+        &self,
+        user_id: i32,
+        exercises: Vec<ImportOrExportExerciseItem>,
+        workouts: Vec<workout::Model>,
+    ) -> Result<Vec<String>> {
+        let mut exercise_ids = HashMap::new();
+        for item in exercises {
+            let exercise_id = match find_exercise_ids_by_name(&self.db, user_id, Some(&item.name))
+                .await?
+                .remove(&item.name)
+            {
+                Some(id) => id,
+                None => match item.details {
+                    Some(details) => {
+                        let mut details: exercise::ActiveModel = details.into();
+                        details.id = ActiveValue::NotSet;
+                        details.source = ActiveValue::Set(ExerciseSource::Custom);
+                        details.created_by_user_id = ActiveValue::Set(Some(user_id));
+                        let created = details.insert(&self.db).await?;
+                        add_entity_to_collection(
+                            &self.db,
+                            user_id,
+                            ChangeCollectionToEntityInput {
+                                collection_name: DefaultCollection::Custom.to_string(),
+                                entity_id: created.id,
+                                entity_lot: EntityLot::Exercise,
+                                ..Default::default()
+                            },
+                        )
+                        .await?;
+                        created.id
+                    }
+                    None => {
+                        tracing::error!("Exercise with name = {} not found", item.name);
+                        continue;
+                    }
+                },
+            };
+            exercise_ids.insert(item.name.clone(), exercise_id);
+            let association = UserToEntity::find()
+                .filter(user_to_entity::Column::UserId.eq(user_id))
+                .filter(user_to_entity::Column::ExerciseId.eq(exercise_id))
+                .one(&self.db)
+                .await?;
+            // DEV: Only the settings are taken from the export. Everything else is
+            // calculated again once the workouts have been restored.
+            match association {
+                Some(a) => {
+                    let mut information = a.exercise_extra_information.clone().unwrap_or_default();
+                    if information.goals.is_empty() {
+                        information.goals = item.extra_information.goals;
+                    }
+                    if information.progression.is_none() {
+                        information.progression = item.extra_information.progression;
+                    }
+                    let mut association: user_to_entity::ActiveModel = a.into();
+                    association.exercise_extra_information = ActiveValue::Set(Some(information));
+                    association.update(&self.db).await?;
+                }
+                None => {
+                    let association = user_to_entity::ActiveModel {
+                        user_id: ActiveValue::Set(user_id),
+                        exercise_id: ActiveValue::Set(Some(exercise_id)),
+                        exercise_extra_information: ActiveValue::Set(Some(
+                            UserToExerciseExtraInformation {
+                                goals: item.extra_information.goals,
+                                progression: item.extra_information.progression,
+                                ..Default::default()
+                            },
+                        )),
+                        ..Default::default()
+                    };
+                    association.insert(&self.db).await?;
+                }
+            }
+            for collection_name in item.collections {
+                add_entity_to_collection(
+                    &self.db,
+                    user_id,
+                    ChangeCollectionToEntityInput {
+                        collection_name,
+                        entity_id: exercise_id,
+                        entity_lot: EntityLot::Exercise,
+                        ..Default::default()
+                    },
+                )
+                .await
+                .ok();
+            }
+        }
+        let mut failed = vec![];
+        let mut restored = vec![];
+        'workouts: for mut workout in workouts {
+            for exercise in workout.information.exercises.iter_mut() {
+                let id = match exercise_ids.get(&exercise.name) {
+                    Some(id) => *id,
+                    None => {
+                        let Some(id) =
+                            find_exercise_ids_by_name(&self.db, user_id, Some(&exercise.name))
+                                .await?
+                                .remove(&exercise.name)
+                        else {
+                            failed.push(format!(
+                                "The workout {} was not restored because the exercise {} does not exist",
+                                workout.name, exercise.name
+                            ));
+                            continue 'workouts;
+                        };
+                        exercise_ids.insert(exercise.name.clone(), id);
+                        id
+                    }
+                };
+                exercise.id = id;
+            }
+            restored.push(workout);
+        }
+        let user = partial_user_by_id::<UserWithOnlyPreferences>(&self.db, user_id).await?;
+        let txn = self.db.begin().await?;
+        for mut workout in restored {
+            match Workout::find_by_id(workout.id.clone()).one(&txn).await? {
+                Some(existing) if existing.user_id == user_id => {
+                    tracing::debug!("Workout with id = {} already exists", workout.id);
+                    continue;
+                }
+                // DEV: The export was made on this instance by another user.
+                Some(_) => workout.id = Sonyflake::new().unwrap().next_id().unwrap().to_string(),
+                None => {}
+            }
+            workout.user_id = user_id;
+            let workout: workout::ActiveModel = workout.into();
+            workout.insert(&txn).await?;
+        }
+        self.replay_workouts(&txn, user_id, user.preferences.fitness.exercises)
+            .await?;
+        txn.commit().await?;
+        Ok(failed)
+    }
+
     pub async fn delete_user_workout(&self, user_id: i32, workout_id: String) -> Result<bool> {
         if let Some(wkt) = Workout::find()
             .filter(workout::Column::UserId.eq(user_id))
@@ -737,4 +1302,293 @@ impl ExerciseService {
             Err(Error::new("Workout does not exist for user"))
         }
     }
+
+    async fn performed_exercise_association(
+        &self,
+        user_id: i32,
+        exercise_id: i32,
+    ) -> Result<user_to_entity::Model> {
+        UserToEntity::find()
+            .filter(user_to_entity::Column::UserId.eq(user_id))
+            .filter(user_to_entity::Column::ExerciseId.eq(exercise_id))
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| Error::new("The exercise has not been performed by the user"))
+    }
+
+    async fn set_exercise_goals(
+        &self,
+        user_id: i32,
+        exercise_id: i32,
+        goals: Vec<UserExerciseGoal>,
+    ) -> Result<bool> {
+        let association = self
+            .performed_exercise_association(user_id, exercise_id)
+            .await?;
+        let mut extra_information = association.exercise_extra_information.clone().unwrap();
+        extra_information.goals = goals;
+        let mut association: user_to_entity::ActiveModel = association.into();
+        association.exercise_extra_information = ActiveValue::Set(Some(extra_information));
+        association.update(&self.db).await?;
+        Ok(true)
+    }
+
+    async fn set_exercise_progression(
+        &self,
+        user_id: i32,
+        exercise_id: i32,
+        progression: Option<UserExerciseProgression>,
+    ) -> Result<bool> {
+        if let Some(p) = progression.as_ref() {
+            if p.min_reps == 0 || p.max_reps.is_some_and(|max| max < p.min_reps) {
+                return Err(Error::new("The rep range of the progression is invalid"));
+            }
+        }
+        let association = self
+            .performed_exercise_association(user_id, exercise_id)
+            .await?;
+        let mut extra_information = association.exercise_extra_information.clone().unwrap();
+        extra_information.progression = progression;
+        let mut association: user_to_entity::ActiveModel = association.into();
+        association.exercise_extra_information = ActiveValue::Set(Some(extra_information));
+        association.update(&self.db).await?;
+        Ok(true)
+    }
+
+    async fn exercise_progression_suggestion(
+        &self,
+        user_id: i32,
+        exercise_id: i32,
+    ) -> Result<Option<ExerciseProgressionSuggestion>> {
+        let association = self
+            .performed_exercise_association(user_id, exercise_id)
+            .await?;
+        let extra_information = association.exercise_extra_information.unwrap();
+        let Some(progression) = extra_information.progression else {
+            return Ok(None);
+        };
+        let recent = extra_information
+            .history
+            .iter()
+            .rev()
+            .take(PROGRESSION_SESSIONS)
+            .collect_vec();
+        let workouts = Workout::find()
+            .filter(workout::Column::Id.is_in(recent.iter().map(|h| h.workout_id.clone())))
+            .order_by_asc(workout::Column::StartTime)
+            .all(&self.db)
+            .await?;
+        let sessions = workouts
+            .into_iter()
+            .filter_map(|w| {
+                let element = recent.iter().find(|h| h.workout_id == w.id)?;
+                w.information
+                    .exercises
+                    .get(element.idx)
+                    .map(|e| e.sets.clone())
+            })
+            .collect_vec();
+        let volume_formula = partial_user_by_id::<UserWithOnlyPreferences>(&self.db, user_id)
+            .await?
+            .preferences
+            .fitness
+            .exercises
+            .volume_formula;
+        Ok(get_progression_suggestion(
+            &progression,
+            &sessions,
+            volume_formula,
+        ))
+    }
+
+    #[instrument(skip(self))]
+    pub async fn rebuild_all_exercise_associations(&self, user_id: i32) -> Result<usize> {
+        let preferences = partial_user_by_id::<UserWithOnlyPreferences>(&self.db, user_id)
+            .await?
+            .preferences
+            .fitness
+            .exercises;
+        // DEV: Every workout is deleted and committed again, so a failure part way
+        // through must not lose the ones that were not committed yet.
+        let txn = self.db.begin().await?;
+        let replayed = self.replay_workouts(&txn, user_id, preferences).await?;
+        txn.commit().await?;
+        Ok(replayed)
+    }
+
+    async fn replay_workouts<C>(
+        &self,
+        db: &C,
+        user_id: i32,
+        preferences: UserExercisePreferences,
+    ) -> Result<usize>
+    where
+        C: ConnectionTrait,
+    {
+        let associations = UserToEntity::find()
+            .filter(user_to_entity::Column::UserId.eq(user_id))
+            .filter(user_to_entity::Column::ExerciseId.is_not_null())
+            .all(db)
+            .await?;
+        let settings = associations
+            .into_iter()
+            .filter_map(|a| {
+                let info = a.exercise_extra_information?;
+                Some((a.exercise_id?, (info.goals, info.progression)))
+            })
+            .collect::<HashMap<_, _>>();
+        UserToEntity::delete_many()
+            .filter(user_to_entity::Column::UserId.eq(user_id))
+            .filter(user_to_entity::Column::ExerciseId.is_not_null())
+            .exec(db)
+            .await?;
+        let workouts = Workout::find()
+            .filter(workout::Column::UserId.eq(user_id))
+            .order_by_asc(workout::Column::StartTime)
+            .order_by_asc(workout::Column::Id)
+            .all(db)
+            .await?;
+        // DEV: The stored statistics have already been converted to metric units.
+        let replay_preferences = UserExercisePreferences {
+            unit_system: UserUnitSystem::Metric,
+            ..preferences
+        };
+        let mut replayed = 0;
+        for workout in workouts {
+            if workout.information.exercises.is_empty() {
+                continue;
+            }
+            let id = workout.id.clone();
+            let input = workout.clone().into_input();
+            workout.delete(db).await?;
+            input
+                .calculate_and_commit(user_id, db, id, replay_preferences.clone())
+                .await?;
+            replayed += 1;
+        }
+        for (exercise_id, (goals, progression)) in settings {
+            let Some(association) = UserToEntity::find()
+                .filter(user_to_entity::Column::UserId.eq(user_id))
+                .filter(user_to_entity::Column::ExerciseId.eq(exercise_id))
+                .one(db)
+                .await?
+            else {
+                continue;
+            };
+            let mut extra_information = association.exercise_extra_information.clone().unwrap();
+            extra_information.goals = goals;
+            extra_information.progression = progression;
+            let mut association: user_to_entity::ActiveModel = association.into();
+            association.exercise_extra_information = ActiveValue::Set(Some(extra_information));
+            association.update(db).await?;
+        }
+        tracing::debug!("Replayed {replayed} workouts for user {user_id}");
+        Ok(replayed)
+    }
+
+    #[instrument(skip(self))]
+    async fn convert_exercise_lot(
+        &self,
+        user_id: i32,
+        exercise_id: i32,
+        lot: ExerciseLot,
+    ) -> Result<usize> {
+        let exercise = Exercise::find_by_id(exercise_id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| Error::new("Exercise does not exist"))?;
+        if exercise.created_by_user_id != Some(user_id) {
+            admin_account_guard(&self.db, user_id).await?;
+        }
+        if exercise.lot == lot {
+            return Ok(0);
+        }
+        let associations = UserToEntity::find()
+            .filter(user_to_entity::Column::ExerciseId.eq(exercise_id))
+            .all(&self.db)
+            .await?;
+        let mut affected = vec![];
+        let mut touched = 0;
+        for association in associations {
+            if let Some(info) = association.exercise_extra_information {
+                touched += info.history.iter().map(|h| &h.workout_id).unique().count();
+            }
+            let preferences =
+                partial_user_by_id::<UserWithOnlyPreferences>(&self.db, association.user_id)
+                    .await?
+                    .preferences
+                    .fitness
+                    .exercises;
+            affected.push((association.user_id, preferences));
+        }
+        // DEV: The sets are normalized against the lot that is stored in the database
+        // when the workouts are replayed, so the exercise is changed first.
+        let txn = self.db.begin().await?;
+        let mut exercise: exercise::ActiveModel = exercise.into();
+        exercise.lot = ActiveValue::Set(lot);
+        exercise.update(&txn).await?;
+        for (affected_user_id, preferences) in affected {
+            self.replay_workouts(&txn, affected_user_id, preferences)
+                .await?;
+        }
+        txn.commit().await?;
+        tracing::debug!("Converted exercise {exercise_id} to {lot:?} in {touched} workouts");
+        Ok(touched)
+    }
+
+    pub async fn reconcile_personal_bests(&self) -> Result<usize> {
+        let workout_ids = Workout::find()
+            .select_only()
+            .column(workout::Column::Id)
+            .into_tuple::<String>()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .collect::<HashSet<_>>();
+        let associations = UserToEntity::find()
+            .filter(user_to_entity::Column::ExerciseId.is_not_null())
+            .all(&self.db)
+            .await?;
+        let mut volume_formulas = HashMap::new();
+        let mut dropped = 0;
+        for association in associations {
+            let Some(mut extra_information) = association.exercise_extra_information.clone() else {
+                continue;
+            };
+            let volume_formula = match volume_formulas.get(&association.user_id) {
+                Some(formula) => *formula,
+                None => {
+                    let formula = partial_user_by_id::<UserWithOnlyPreferences>(
+                        &self.db,
+                        association.user_id,
+                    )
+                    .await?
+                    .preferences
+                    .fitness
+                    .exercises
+                    .volume_formula;
+                    volume_formulas.insert(association.user_id, formula);
+                    formula
+                }
+            };
+            let count = reconcile_personal_bests(
+                &mut extra_information.personal_bests,
+                &workout_ids,
+                volume_formula,
+            );
+            if count == 0 {
+                continue;
+            }
+            tracing::debug!(
+                "Dropped {} orphaned personal bests for exercise {:?}",
+                count,
+                association.exercise_id
+            );
+            dropped += count;
+            let mut association: user_to_entity::ActiveModel = association.into();
+            association.exercise_extra_information = ActiveValue::Set(Some(extra_information));
+            association.update(&self.db).await?;
+        }
+        Ok(dropped)
+    }
 }
@@ -1,6 +1,7 @@
 use async_graphql::{EmptySubscription, MergedObject, Schema};
 
 use crate::{
+    exporter::{ExporterMutation, ExporterQuery},
     fitness::resolver::{ExerciseMutation, ExerciseQuery},
     importer::{ImporterMutation, ImporterQuery},
     miscellaneous::resolver::{MiscellaneousMutation, MiscellaneousQuery},
@@ -8,10 +9,20 @@ use crate::{
 };
 
 #[derive(MergedObject, Default)]
-pub struct QueryRoot(MiscellaneousQuery, ImporterQuery, ExerciseQuery);
+pub struct QueryRoot(
+    MiscellaneousQuery,
+    ImporterQuery,
+    ExerciseQuery,
+    ExporterQuery,
+);
 
 #[derive(MergedObject, Default)]
-pub struct MutationRoot(MiscellaneousMutation, ImporterMutation, ExerciseMutation);
+pub struct MutationRoot(
+    MiscellaneousMutation,
+    ImporterMutation,
+    ExerciseMutation,
+    ExporterMutation,
+);
 
 pub type GraphqlSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
 
@@ -24,5 +35,6 @@ pub async fn get_schema(app_services: &AppServices) -> GraphqlSchema {
     .data(app_services.media_service.clone())
     .data(app_services.importer_service.clone())
     .data(app_services.exercise_service.clone())
+    .data(app_services.exporter_service.clone())
     .finish()
 }
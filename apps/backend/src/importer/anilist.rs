@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use database::{MetadataLot, MetadataSource};
+use graphql_client::{GraphQLQuery, Response};
+use http_types::mime;
+use rs_utils::convert_naive_to_utc;
+use rust_decimal::{prelude::FromPrimitive, Decimal};
+use sea_orm::prelude::DateTimeUtc;
+use serde::{de::DeserializeOwned, Serialize};
+use surf::http::headers::{ACCEPT, AUTHORIZATION};
+
+use crate::{
+    importer::{
+        get_source_json, DeployAnilistImportInput, ImportResult, ImporterError, ImporterResult,
+    },
+    miscellaneous::DefaultCollection,
+    models::media::{
+        ImportOrExportItemIdentifier, ImportOrExportItemRating, ImportOrExportMediaItem,
+        ImportOrExportMediaItemSeen, ImportOrExportMediaItemSeenDropped,
+    },
+    utils::get_base_http_client,
+};
+
+static URL: &str = "https://graphql.anilist.co";
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "src/providers/anilist/schema.json",
+    query_path = "src/providers/anilist/viewer.graphql",
+    response_derives = "Debug",
+    variables_derives = "Debug"
+)]
+struct ViewerQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "src/providers/anilist/schema.json",
+    query_path = "src/providers/anilist/media_list.graphql",
+    response_derives = "Debug",
+    variables_derives = "Debug"
+)]
+struct MediaListQuery;
+
+type ListGroup = media_list_query::MediaListQueryMediaListCollectionLists;
+type ListEntry = media_list_query::MediaListQueryMediaListCollectionListsEntries;
+
+async fn send_query<V, T>(client: &surf::Client, body: V, context: &str) -> ImporterResult<T>
+where
+    V: Serialize,
+    T: DeserializeOwned,
+{
+    let request = client
+        .post("")
+        .body_json(&body)
+        .map_err(|e| ImporterError::parse(context, e))?;
+    let response: Response<T> = get_source_json(request, context).await?;
+    match (response.data, response.errors) {
+        (Some(data), _) => Ok(data),
+        (None, errors) => Err(ImporterError::provider(
+            context,
+            errors
+                .unwrap_or_default()
+                .into_iter()
+                .map(|e| e.message)
+                .collect::<Vec<_>>()
+                .join(", "),
+        )),
+    }
+}
+
+pub async fn import(input: DeployAnilistImportInput) -> ImporterResult<ImportResult> {
+    let client = get_base_http_client(
+        URL,
+        vec![
+            (ACCEPT, mime::JSON.to_string()),
+            (AUTHORIZATION, format!("Bearer {}", input.access_token)),
+        ],
+    );
+    let viewer: viewer_query::ResponseData = send_query(
+        &client,
+        ViewerQuery::build_query(viewer_query::Variables {}),
+        "the account",
+    )
+    .await?;
+    let user_id = viewer
+        .viewer
+        .ok_or_else(|| ImporterError::auth("the account", "No user for the access token"))?
+        .id;
+    let mut media = vec![];
+    for (lot, type_) in [
+        (MetadataLot::Anime, media_list_query::MediaType::ANIME),
+        (MetadataLot::Manga, media_list_query::MediaType::MANGA),
+    ] {
+        let context = format!("the {} list", lot.to_string().to_lowercase());
+        let lists: media_list_query::ResponseData = send_query(
+            &client,
+            MediaListQuery::build_query(media_list_query::Variables { user_id, type_ }),
+            &context,
+        )
+        .await?;
+        let groups = lists
+            .media_list_collection
+            .and_then(|c| c.lists)
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .collect();
+        media.extend(convert_lists(groups, lot));
+    }
+    Ok(ImportResult {
+        collections: vec![],
+        failed_items: vec![],
+        media,
+        workouts: vec![],
+        ..Default::default()
+    })
+}
+
+/// Merge the entries of all the lists into one item per media. An entry is
+/// present in the list of its status as well as in every custom list it was
+/// added to, which become collections.
+fn convert_lists(groups: Vec<ListGroup>, lot: MetadataLot) -> Vec<ImportOrExportMediaItem> {
+    let mut media: Vec<ImportOrExportMediaItem> = vec![];
+    let mut positions = HashMap::new();
+    for group in groups {
+        let custom_list = group
+            .is_custom_list
+            .unwrap_or_default()
+            .then_some(group.name)
+            .flatten();
+        for entry in group.entries.unwrap_or_default().into_iter().flatten() {
+            let position = *positions.entry(entry.media_id).or_insert_with(|| {
+                media.push(convert_entry(&entry, lot));
+                media.len() - 1
+            });
+            if let Some(name) = custom_list.as_ref() {
+                media[position].collections.push(name.clone());
+            }
+        }
+    }
+    media
+}
+
+fn get_date(date: Option<(Option<i64>, Option<i64>, Option<i64>)>) -> Option<DateTimeUtc> {
+    let (year, month, day) = date?;
+    NaiveDate::from_ymd_opt(
+        year?.try_into().ok()?,
+        month.unwrap_or(1).try_into().ok()?,
+        day.unwrap_or(1).try_into().ok()?,
+    )
+    .map(convert_naive_to_utc)
+}
+
+fn convert_entry(entry: &ListEntry, lot: MetadataLot) -> ImportOrExportMediaItem {
+    use media_list_query::MediaListStatus;
+
+    let title = entry
+        .media
+        .as_ref()
+        .and_then(|m| m.title.as_ref())
+        .and_then(|t| t.user_preferred.clone());
+    let total = entry.media.as_ref().and_then(|m| match lot {
+        MetadataLot::Anime => m.episodes,
+        _ => m.chapters,
+    });
+    // DEV: The progress on AniList is the number of episodes or chapters consumed
+    let progress = match (entry.progress, total) {
+        (Some(done), Some(total)) if total > 0 => i32::try_from((done * 100 / total).min(99)).ok(),
+        _ => Some(0),
+    };
+    let started_on = get_date(entry.started_at.as_ref().map(|d| (d.year, d.month, d.day)));
+    let ended_on = get_date(
+        entry
+            .completed_at
+            .as_ref()
+            .map(|d| (d.year, d.month, d.day)),
+    );
+    let completed = |times: i64| {
+        let mut seen_history = vec![
+            ImportOrExportMediaItemSeen::default();
+            usize::try_from(times.max(1)).unwrap_or(1)
+        ];
+        seen_history[0].started_on = started_on;
+        seen_history[0].ended_on = ended_on;
+        seen_history
+    };
+    let in_progress = ImportOrExportMediaItemSeen {
+        progress,
+        started_on,
+        ..Default::default()
+    };
+    let repeat = entry.repeat.unwrap_or_default();
+    let mut collections = vec![];
+    let seen_history = match entry.status {
+        Some(MediaListStatus::COMPLETED) => completed(repeat + 1),
+        Some(MediaListStatus::REPEATING) => {
+            collections.push(DefaultCollection::InProgress.to_string());
+            let mut history = completed(repeat.max(1));
+            history.push(in_progress);
+            history
+        }
+        Some(MediaListStatus::CURRENT) => {
+            collections.push(DefaultCollection::InProgress.to_string());
+            vec![in_progress]
+        }
+        Some(MediaListStatus::DROPPED) => vec![ImportOrExportMediaItemSeen {
+            dropped: Some(ImportOrExportMediaItemSeenDropped::default()),
+            ..in_progress
+        }],
+        Some(MediaListStatus::PAUSED) => {
+            collections.push("Paused".to_owned());
+            vec![in_progress]
+        }
+        Some(MediaListStatus::PLANNING) => {
+            collections.push(DefaultCollection::Watchlist.to_string());
+            vec![]
+        }
+        _ => vec![],
+    };
+    let reviews = entry
+        .score
+        .filter(|s| *s > 0.0)
+        .and_then(Decimal::from_f64)
+        .map(|rating| ImportOrExportItemRating {
+            rating: Some(rating.round_dp(2)),
+            ..Default::default()
+        })
+        .into_iter()
+        .collect();
+    ImportOrExportMediaItem {
+        source_id: title.clone().unwrap_or_else(|| entry.media_id.to_string()),
+        lot,
+        source: MetadataSource::Anilist,
+        identifier: "".to_string(),
+        internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails(
+            entry.media_id.to_string(),
+        )),
+        seen_history,
+        reviews,
+        collections,
+        external_identifiers: None,
+        is_favorite: None,
+        next_episode: None,
+        display_title: title,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn entries_are_merged_across_lists() {
+        let data: media_list_query::ResponseData = serde_json::from_str(
+            r#"{"MediaListCollection": {"lists": [
+                {"name": "Completed", "isCustomList": false, "entries": [{
+                    "mediaId": 1, "status": "COMPLETED", "score": 85.0, "progress": 26,
+                    "repeat": 2, "startedAt": {"year": 2020, "month": 3, "day": null},
+                    "completedAt": {"year": null, "month": null, "day": null},
+                    "media": {"episodes": 26, "chapters": null,
+                        "title": {"userPreferred": "Cowboy Bebop"}}
+                }]},
+                {"name": "Watching", "isCustomList": false, "entries": [{
+                    "mediaId": 2, "status": "CURRENT", "score": 0.0, "progress": 6,
+                    "repeat": 0, "startedAt": null, "completedAt": null,
+                    "media": {"episodes": 12, "chapters": null,
+                        "title": {"userPreferred": "Mushishi"}}
+                }]},
+                {"name": "Classics", "isCustomList": true, "entries": [{
+                    "mediaId": 1, "status": "COMPLETED", "score": 85.0, "progress": 26,
+                    "repeat": 2, "startedAt": null, "completedAt": null,
+                    "media": {"episodes": 26, "chapters": null,
+                        "title": {"userPreferred": "Cowboy Bebop"}}
+                }]}
+            ]}}"#,
+        )
+        .unwrap();
+        let groups = data
+            .media_list_collection
+            .and_then(|c| c.lists)
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect();
+        let media = convert_lists(groups, MetadataLot::Anime);
+        assert_eq!(media.len(), 2);
+        let bebop = &media[0];
+        assert_eq!(bebop.collections, vec!["Classics".to_owned()]);
+        assert_eq!(bebop.seen_history.len(), 3);
+        assert_eq!(
+            bebop.seen_history[0].started_on,
+            Some(convert_naive_to_utc(
+                NaiveDate::from_ymd_opt(2020, 3, 1).unwrap()
+            ))
+        );
+        assert_eq!(bebop.seen_history[0].ended_on, None);
+        assert_eq!(bebop.reviews[0].rating, Some(dec!(85)));
+        let mushishi = &media[1];
+        assert!(mushishi.reviews.is_empty());
+        assert_eq!(mushishi.seen_history[0].progress, Some(50));
+        assert_eq!(
+            mushishi.collections,
+            vec![DefaultCollection::InProgress.to_string()]
+        );
+    }
+}
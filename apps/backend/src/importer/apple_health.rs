@@ -0,0 +1,423 @@
+use std::{collections::HashMap, io::BufRead};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use convert_case::{Case, Casing};
+use csv::StringRecord;
+use database::ExerciseLot;
+use itertools::Itertools;
+use quick_xml::events::{BytesStart, Event};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    file_storage::FileStorageService,
+    fitness::{logic::find_exercise_ids_by_name, resolver::ExerciseService},
+    models::fitness::{
+        EntityAssets, SetLot, UserExerciseInput, UserWorkoutInput, UserWorkoutSetRecord,
+        WorkoutSetStatistic,
+    },
+};
+
+use super::{
+    create_imported_exercise, get_csv_reader, DeployAppleHealthImportInput, ImportResult,
+    ImporterError, ImporterResult, UploadedFile, UTF8_BOM,
+};
+
+/// The prefix of the types of workouts in the Health export.
+const ACTIVITY_TYPE_PREFIX: &str = "HKWorkoutActivityType";
+
+/// The prefix of the types of workout statistics which hold the distance covered.
+const DISTANCE_TYPE_PREFIX: &str = "HKQuantityTypeIdentifierDistance";
+
+/// A workout recorded in Apple Health.
+#[derive(Debug, Clone, PartialEq)]
+struct HealthWorkout {
+    /// The type of the workout, for eg: `Running` or `Traditional Strength Training`.
+    activity: String,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    /// The time spent working out in minutes, which does not include pauses.
+    duration: Option<Decimal>,
+    /// The distance covered in kilometers.
+    distance: Option<Decimal>,
+}
+
+impl HealthWorkout {
+    fn is_strength_training(&self) -> bool {
+        let activity = self.activity.to_lowercase();
+        activity.contains("strength") || activity == "core training"
+    }
+
+    /// Strength sessions only record their duration, whereas the distance of
+    /// cardio workouts is kept if it is known.
+    fn get_lot(&self) -> ExerciseLot {
+        match self.distance {
+            Some(_) if !self.is_strength_training() => ExerciseLot::DistanceAndDuration,
+            _ => ExerciseLot::Duration,
+        }
+    }
+
+    fn into_workout(self, exercise_id: i32) -> UserWorkoutInput {
+        let duration = self.duration.or_else(|| {
+            let seconds = (self.end_time - self.start_time).num_seconds();
+            Some((Decimal::from(seconds) / dec!(60)).round_dp(2))
+        });
+        let distance = match self.is_strength_training() {
+            true => None,
+            false => self.distance,
+        };
+        UserWorkoutInput {
+            name: self.activity,
+            comment: None,
+            start_time: self.start_time,
+            end_time: Some(self.end_time),
+            exercises: vec![UserExerciseInput {
+                exercise_id,
+                sets: vec![UserWorkoutSetRecord {
+                    statistic: WorkoutSetStatistic {
+                        duration,
+                        distance,
+                        ..Default::default()
+                    },
+                    lot: SetLot::Normal,
+                }],
+                notes: vec![],
+                rest_time: None,
+                assets: EntityAssets::default(),
+            }],
+            supersets: vec![],
+            assets: EntityAssets::default(),
+        }
+    }
+}
+
+fn get_activity_name(activity_type: &str) -> String {
+    activity_type
+        .strip_prefix(ACTIVITY_TYPE_PREFIX)
+        .unwrap_or(activity_type)
+        .to_case(Case::Title)
+}
+
+fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S %z")
+        .map(|d| d.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(|d| DateTime::from_naive_utc_and_offset(d, Utc))
+        })
+}
+
+fn to_kilometers(value: Decimal, unit: &str) -> Option<Decimal> {
+    let kilometers = match unit {
+        "km" => value,
+        "m" => value / dec!(1000),
+        "mi" => value * dec!(1.609344),
+        "yd" => value * dec!(0.0009144),
+        _ => return None,
+    };
+    Some(kilometers.round_dp(3)).filter(|d| !d.is_zero())
+}
+
+fn to_minutes(value: Decimal, unit: &str) -> Option<Decimal> {
+    let minutes = match unit {
+        "min" => value,
+        "s" => value / dec!(60),
+        "hr" => value * dec!(60),
+        _ => return None,
+    };
+    Some(minutes.round_dp(2))
+}
+
+fn get_attributes(element: &BytesStart) -> ImporterResult<HashMap<String, String>> {
+    let error = |e: &dyn std::fmt::Display| ImporterError::parse("the export file", e);
+    element
+        .attributes()
+        .map(|attribute| {
+            let attribute = attribute.map_err(|e| error(&e))?;
+            let value = attribute.unescape_value().map_err(|e| error(&e))?;
+            let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+            Ok((key, value.into_owned()))
+        })
+        .collect()
+}
+
+fn get_quantity<'a>(
+    attributes: &'a HashMap<String, String>,
+    value: &str,
+    unit: &str,
+) -> Option<(Decimal, &'a str)> {
+    let value = attributes.get(value)?.parse().ok()?;
+    Some((
+        value,
+        attributes.get(unit).map(String::as_str).unwrap_or_default(),
+    ))
+}
+
+fn parse_xml_workout(attributes: &HashMap<String, String>) -> Option<HealthWorkout> {
+    Some(HealthWorkout {
+        activity: get_activity_name(attributes.get("workoutActivityType")?),
+        start_time: parse_date(attributes.get("startDate")?)?,
+        end_time: parse_date(attributes.get("endDate")?)?,
+        duration: get_quantity(attributes, "duration", "durationUnit")
+            .and_then(|(v, u)| to_minutes(v, u)),
+        distance: get_quantity(attributes, "totalDistance", "totalDistanceUnit")
+            .and_then(|(v, u)| to_kilometers(v, u)),
+    })
+}
+
+/// Exports from iOS 16 onwards have the distance covered in the statistics of the
+/// workout instead of its attributes.
+fn add_distance_statistic(
+    workout: &mut HashMap<String, String>,
+    statistic: HashMap<String, String>,
+) {
+    let is_distance = statistic
+        .get("type")
+        .map_or(false, |t| t.starts_with(DISTANCE_TYPE_PREFIX));
+    if !is_distance || workout.contains_key("totalDistance") {
+        return;
+    }
+    if let Some(sum) = statistic.get("sum") {
+        workout.insert("totalDistance".to_owned(), sum.to_owned());
+        let unit = statistic.get("unit").cloned().unwrap_or_default();
+        workout.insert("totalDistanceUnit".to_owned(), unit);
+    }
+}
+
+/// Read the workouts of the `export.xml` file one element at a time, since it also
+/// holds every other sample recorded by the device and can be very large.
+fn read_xml_workouts<R: BufRead>(
+    data: R,
+    warnings: &mut Vec<String>,
+) -> ImporterResult<Vec<HealthWorkout>> {
+    let mut reader = quick_xml::Reader::from_reader(data);
+    let mut buf = vec![];
+    let mut elements = vec![];
+    let mut current: Option<HashMap<String, String>> = None;
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| ImporterError::parse("the export file", e))?;
+        match event {
+            Event::Start(e) if e.name().as_ref() == b"Workout" => {
+                current = Some(get_attributes(&e)?);
+            }
+            Event::Empty(e) if e.name().as_ref() == b"Workout" => {
+                elements.push(get_attributes(&e)?);
+            }
+            Event::End(e) if e.name().as_ref() == b"Workout" => {
+                elements.extend(current.take());
+            }
+            Event::Empty(e) if e.name().as_ref() == b"WorkoutStatistics" => {
+                if let Some(workout) = current.as_mut() {
+                    add_distance_statistic(workout, get_attributes(&e)?);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(elements
+        .iter()
+        .filter_map(|attributes| {
+            let workout = parse_xml_workout(attributes);
+            if workout.is_none() {
+                warnings.push(format!(
+                    "Workout starting on {:?} could not be read",
+                    attributes.get("startDate")
+                ));
+            }
+            workout
+        })
+        .collect())
+}
+
+/// Read the workouts of a CSV file in the format used by Health Auto Export, which
+/// has the columns `Workout Type`, `Start`, `End` and optionally `Distance (km)`
+/// (or `mi`).
+fn read_csv_workouts<R: BufRead>(
+    data: R,
+    trim_csv: bool,
+    warnings: &mut Vec<String>,
+) -> ImporterResult<Vec<HealthWorkout>> {
+    let mut reader = get_csv_reader(data, b',', trim_csv)?;
+    let headers = reader
+        .headers()
+        .map_err(|e| ImporterError::parse("the workouts file", e))?
+        .clone();
+    let get_column = |name: &str| {
+        headers.iter().position(|h| h == name).ok_or_else(|| {
+            ImporterError::parse("the workouts file", format!("No column named {:?}", name))
+        })
+    };
+    let activity_column = get_column("Workout Type")?;
+    let start_column = get_column("Start")?;
+    let end_column = get_column("End")?;
+    let distance_column = headers.iter().enumerate().find_map(|(idx, header)| {
+        let unit = header.strip_prefix("Distance (")?.strip_suffix(')')?;
+        Some((idx, unit.to_owned()))
+    });
+    let mut workouts = vec![];
+    for (idx, result) in reader.records().enumerate() {
+        let workout = result.ok().and_then(|record: StringRecord| {
+            let field = |column: usize| record.get(column).filter(|v| !v.is_empty());
+            Some(HealthWorkout {
+                activity: get_activity_name(field(activity_column)?),
+                start_time: parse_date(field(start_column)?)?,
+                end_time: parse_date(field(end_column)?)?,
+                duration: None,
+                distance: distance_column
+                    .as_ref()
+                    .and_then(|(column, unit)| to_kilometers(field(*column)?.parse().ok()?, unit)),
+            })
+        });
+        match workout {
+            Some(w) => workouts.push(w),
+            None => warnings.push(format!("Row {idx} could not be read")),
+        }
+    }
+    Ok(workouts)
+}
+
+/// Get the type of the exercise that the workouts of each activity are imported
+/// as. The distance is kept if any of the workouts has one.
+fn get_exercise_lots(workouts: &[HealthWorkout]) -> HashMap<String, ExerciseLot> {
+    let mut lots = HashMap::new();
+    for workout in workouts {
+        let lot = lots
+            .entry(workout.activity.clone())
+            .or_insert(ExerciseLot::Duration);
+        if workout.get_lot() == ExerciseLot::DistanceAndDuration {
+            *lot = ExerciseLot::DistanceAndDuration;
+        }
+    }
+    lots
+}
+
+pub async fn import(
+    user_id: i32,
+    input: DeployAppleHealthImportInput,
+    trim_csv: bool,
+    db: &DatabaseConnection,
+    file_storage_service: &FileStorageService,
+    exercise_service: &ExerciseService,
+) -> ImporterResult<ImportResult> {
+    let export = UploadedFile::find(file_storage_service, user_id, &input.export_path).await?;
+    let mut warnings = vec![];
+    let workouts = {
+        let mut data = export.open()?;
+        let start = data
+            .fill_buf()
+            .map_err(|e| ImporterError::parse("the export file", e))?;
+        let has_bom = start.starts_with(UTF8_BOM);
+        let is_xml = start
+            .iter()
+            .skip(if has_bom { UTF8_BOM.len() } else { 0 })
+            .find(|b| !b.is_ascii_whitespace())
+            == Some(&b'<');
+        match is_xml {
+            true => {
+                if has_bom {
+                    data.consume(UTF8_BOM.len());
+                }
+                read_xml_workouts(data, &mut warnings)?
+            }
+            false => read_csv_workouts(data, trim_csv, &mut warnings)?,
+        }
+    };
+    let existing = find_exercise_ids_by_name(db, user_id, None)
+        .await
+        .map_err(|e| ImporterError::database("the exercises", e))?;
+    let mut exercise_ids = HashMap::new();
+    for (name, lot) in get_exercise_lots(&workouts)
+        .into_iter()
+        .sorted_by(|(a, _), (b, _)| a.cmp(b))
+    {
+        if let Some(id) = existing.get(&name) {
+            exercise_ids.insert(name, *id);
+            continue;
+        }
+        match create_imported_exercise(exercise_service, user_id, &name, lot).await {
+            Ok(id) => {
+                exercise_ids.insert(name, id);
+            }
+            Err(e) => warnings.push(format!(
+                "Exercise {name:?} could not be created and its workouts were not imported: {}",
+                e.message
+            )),
+        }
+    }
+    let workouts = workouts
+        .into_iter()
+        .filter_map(|w| {
+            let exercise_id = *exercise_ids.get(&w.activity)?;
+            Some(w.into_workout(exercise_id))
+        })
+        .collect();
+    Ok(ImportResult {
+        workouts,
+        warnings,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_workouts_from_the_health_export() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE HealthData [
+<!ELEMENT HealthData (ExportDate,Me,(Record|Workout)*)>
+]>
+<HealthData locale="en_US">
+ <Record type="HKQuantityTypeIdentifierStepCount" value="120" startDate="2023-10-01 06:00:00 +0200" endDate="2023-10-01 06:05:00 +0200"/>
+ <Workout workoutActivityType="HKWorkoutActivityTypeTraditionalStrengthTraining" duration="45.5" durationUnit="min" startDate="2023-10-01 07:00:00 +0200" endDate="2023-10-01 07:50:00 +0200"/>
+ <Workout workoutActivityType="HKWorkoutActivityTypeRunning" duration="1800" durationUnit="s" startDate="2023-10-02 07:00:00 +0200" endDate="2023-10-02 07:31:00 +0200">
+  <MetadataEntry key="HKIndoorWorkout" value="0"/>
+  <WorkoutStatistics type="HKQuantityTypeIdentifierActiveEnergyBurned" sum="320" unit="kcal"/>
+  <WorkoutStatistics type="HKQuantityTypeIdentifierDistanceWalkingRunning" sum="3.1" unit="mi"/>
+ </Workout>
+ <Workout workoutActivityType="HKWorkoutActivityTypeYoga" startDate="yesterday" endDate="today"/>
+</HealthData>
+"#;
+        let mut warnings = vec![];
+        let workouts = read_xml_workouts(xml.as_bytes(), &mut warnings).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(workouts.len(), 2);
+        assert_eq!(workouts[0].activity, "Traditional Strength Training");
+        assert_eq!(workouts[0].duration, Some(dec!(45.5)));
+        assert_eq!(workouts[0].get_lot(), ExerciseLot::Duration);
+        assert_eq!(
+            workouts[1].start_time,
+            DateTime::parse_from_rfc3339("2023-10-02T05:00:00Z").unwrap()
+        );
+        assert_eq!(workouts[1].duration, Some(dec!(30)));
+        assert_eq!(workouts[1].distance, Some(dec!(4.989)));
+        assert_eq!(workouts[1].get_lot(), ExerciseLot::DistanceAndDuration);
+    }
+
+    #[test]
+    fn reads_workouts_from_a_converted_csv() {
+        let csv = "Workout Type,Start,End,Duration,Distance (km)\n\
+            Outdoor Walk,2023-10-01 07:00:00,2023-10-01 07:40:00,00:40:00,3.2\n\
+            Functional Strength Training,2023-10-02 18:00:00,2023-10-02 18:45:00,00:45:00,\n";
+        let mut warnings = vec![];
+        let workouts = read_csv_workouts(csv.as_bytes(), true, &mut warnings).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(workouts[0].distance, Some(dec!(3.2)));
+        assert!(workouts[1].is_strength_training());
+        let lots = get_exercise_lots(&workouts);
+        assert_eq!(lots["Outdoor Walk"], ExerciseLot::DistanceAndDuration);
+        assert_eq!(lots["Functional Strength Training"], ExerciseLot::Duration);
+        let workout = workouts[1].clone().into_workout(7);
+        let statistic = &workout.exercises[0].sets[0].statistic;
+        assert_eq!(statistic.duration, Some(dec!(45)));
+        assert!(statistic.distance.is_none());
+    }
+}
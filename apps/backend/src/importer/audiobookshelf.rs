@@ -0,0 +1,429 @@
+use std::collections::HashMap;
+
+use chrono::{TimeZone, Utc};
+use database::{MetadataLot, MetadataSource};
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use rust_decimal_macros::dec;
+use sea_orm::prelude::DateTimeUtc;
+use serde::{Deserialize, Serialize};
+use surf::http::headers::AUTHORIZATION;
+
+use crate::{
+    importer::{
+        get_source_json, DeployAudiobookshelfImportInput, ImportErrorCode, ImportFailStep,
+        ImportFailedItem, ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportResult,
+        ImportedSourceEvents, ImporterResult,
+    },
+    miscellaneous::DefaultCollection,
+    models::media::{ImportOrExportMediaItemSeen, SeenSession},
+    providers::openlibrary::OpenlibraryService,
+    utils::get_base_http_client,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MediaProgress {
+    library_item_id: String,
+    /// The fraction of the item that was listened to.
+    #[serde(default)]
+    progress: Decimal,
+    #[serde(default)]
+    is_finished: bool,
+    /// The timestamps are in milliseconds.
+    started_at: Option<i64>,
+    finished_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct User {
+    #[serde(default)]
+    media_progress: Vec<MediaProgress>,
+}
+
+/// A session in which the user listened to an item.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListeningSession {
+    id: String,
+    library_item_id: String,
+    media_type: String,
+    media_metadata: ItemMetadata,
+    /// The length of the item in seconds.
+    #[serde(default)]
+    duration: Decimal,
+    /// The number of seconds listened during the session.
+    #[serde(default)]
+    time_listening: Decimal,
+    /// The position in the item at the end of the session, in seconds.
+    #[serde(default)]
+    current_time: Decimal,
+    /// The timestamps are in milliseconds.
+    started_at: i64,
+    updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListeningSessions {
+    sessions: Vec<ListeningSession>,
+    num_pages: usize,
+}
+
+/// The number of sessions requested at a time.
+const SESSIONS_PER_PAGE: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ItemMetadata {
+    title: Option<String>,
+    asin: Option<String>,
+    isbn: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ItemMedia {
+    metadata: ItemMetadata,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+    id: String,
+    media_type: String,
+    media: ItemMedia,
+}
+
+/// Import the progress of every book. If `previous` is given, only the listening
+/// sessions since the last import are imported instead.
+pub async fn import(
+    input: DeployAudiobookshelfImportInput,
+    previous: Option<ImportedSourceEvents>,
+    openlibrary_service: &OpenlibraryService,
+) -> ImporterResult<ImportResult> {
+    let client = get_base_http_client(
+        &format!("{}/api/", input.url.trim_end_matches('/')),
+        vec![(AUTHORIZATION, format!("Bearer {}", input.api_key))],
+    );
+    if let Some(previous) = previous {
+        return import_sessions(&client, previous, openlibrary_service).await;
+    }
+    let user: User = get_source_json(client.get("me"), "the user").await?;
+    let total = user.media_progress.len();
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    for (idx, progress) in user.media_progress.into_iter().enumerate() {
+        let context = format!("the library item {}", progress.library_item_id);
+        let item: Item = match get_source_json(
+            client.get(format!("items/{}", progress.library_item_id)),
+            &context,
+        )
+        .await
+        {
+            Ok(item) => item,
+            Err(e) => {
+                failed_items.push(ImportFailedItem {
+                    lot: MetadataLot::AudioBook,
+                    step: ImportFailStep::ItemDetailsFromSource,
+                    source_row: serde_json::to_string(&progress).ok(),
+                    identifier: progress.library_item_id,
+                    error: Some(e.to_string()),
+                    code: e.code(),
+                });
+                continue;
+            }
+        };
+        // DEV: The progress of podcasts is recorded per episode
+        if item.media_type != "book" {
+            continue;
+        }
+        let metadata = &item.media.metadata;
+        let title = metadata.title.clone().unwrap_or_else(|| item.id.clone());
+        tracing::debug!("Getting details for {title:?} ({idx}/{total})");
+        let Some(resolved) = resolve_book(metadata, openlibrary_service).await else {
+            failed_items.push(get_unresolved_item(
+                title,
+                metadata,
+                serde_json::to_string(&item).ok(),
+            ));
+            continue;
+        };
+        let (seen, collections) = convert_progress(&progress);
+        media.push(get_media_item(title, resolved, seen, collections));
+    }
+    Ok(ImportResult {
+        collections: vec![],
+        media,
+        failed_items,
+        workouts: vec![],
+        ..Default::default()
+    })
+}
+
+/// Import the listening sessions that were updated since the last import and
+/// were not imported by it, as the progress of their books.
+async fn import_sessions(
+    client: &surf::Client,
+    previous: ImportedSourceEvents,
+    openlibrary_service: &OpenlibraryService,
+) -> ImporterResult<ImportResult> {
+    let since = previous.since.map(|s| s.timestamp_millis());
+    let mut seen_ids = previous.ids;
+    let mut sessions = vec![];
+    let mut page = 0;
+    // DEV: The sessions are sorted by when they were last updated, newest first.
+    'pages: loop {
+        let response: ListeningSessions = get_source_json(
+            client.get(format!(
+                "me/listening-sessions?itemsPerPage={SESSIONS_PER_PAGE}&page={page}"
+            )),
+            "the listening sessions",
+        )
+        .await?;
+        for session in response.sessions {
+            if since.map_or(false, |s| session.updated_at <= s) {
+                break 'pages;
+            }
+            // DEV: Sessions that were imported before are skipped, as are the ones
+            // which moved to the next page while it was being requested.
+            if seen_ids.insert(session.id.clone()) {
+                sessions.push(session);
+            }
+        }
+        page += 1;
+        if page >= response.num_pages {
+            break;
+        }
+    }
+    let mut books: HashMap<String, Vec<ListeningSession>> = HashMap::new();
+    for session in sessions {
+        // DEV: The progress of podcasts is recorded per episode
+        if session.media_type == "book" {
+            books
+                .entry(session.library_item_id.clone())
+                .or_default()
+                .push(session);
+        }
+    }
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    let mut source_event_ids = HashMap::new();
+    for (library_item_id, mut sessions) in books {
+        sessions.sort_by_key(|s| s.updated_at);
+        let metadata = &sessions[0].media_metadata;
+        let title = metadata.title.clone().unwrap_or(library_item_id);
+        let Some(resolved) = resolve_book(metadata, openlibrary_service).await else {
+            failed_items.push(get_unresolved_item(
+                title,
+                metadata,
+                serde_json::to_string(&sessions).ok(),
+            ));
+            continue;
+        };
+        let (seen, collections) = convert_sessions(&sessions);
+        source_event_ids.insert(title.clone(), sessions.into_iter().map(|s| s.id).collect());
+        media.push(get_media_item(title, resolved, seen, collections));
+    }
+    Ok(ImportResult {
+        media,
+        failed_items,
+        source_event_ids,
+        ..Default::default()
+    })
+}
+
+/// Find the book on the providers using its ASIN, or otherwise its ISBN.
+async fn resolve_book(
+    metadata: &ItemMetadata,
+    openlibrary_service: &OpenlibraryService,
+) -> Option<(MetadataLot, MetadataSource, String)> {
+    match (metadata.asin.as_ref(), metadata.isbn.as_ref()) {
+        (Some(asin), _) => Some((
+            MetadataLot::AudioBook,
+            MetadataSource::Audible,
+            asin.clone(),
+        )),
+        (None, Some(isbn)) => openlibrary_service
+            .id_from_isbn(isbn)
+            .await
+            .map(|id| (MetadataLot::Book, MetadataSource::Openlibrary, id)),
+        (None, None) => None,
+    }
+}
+
+fn get_unresolved_item(
+    title: String,
+    metadata: &ItemMetadata,
+    source_row: Option<String>,
+) -> ImportFailedItem {
+    ImportFailedItem {
+        lot: MetadataLot::AudioBook,
+        step: ImportFailStep::InputTransformation,
+        identifier: title,
+        error: Some("No ASIN or ISBN that could be resolved".to_owned()),
+        code: match metadata.isbn {
+            Some(_) => ImportErrorCode::NotFound,
+            None => ImportErrorCode::MissingIdentifier,
+        },
+        source_row,
+    }
+}
+
+fn get_media_item(
+    title: String,
+    (lot, source, identifier): (MetadataLot, MetadataSource, String),
+    seen: ImportOrExportMediaItemSeen,
+    collections: Vec<String>,
+) -> ImportOrExportMediaItem {
+    ImportOrExportMediaItem {
+        source_id: title,
+        lot,
+        source,
+        identifier: "".to_string(),
+        internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails(identifier)),
+        seen_history: vec![seen],
+        reviews: vec![],
+        collections,
+        external_identifiers: None,
+        is_favorite: None,
+        next_episode: None,
+        display_title: None,
+    }
+}
+
+fn get_date(timestamp: Option<i64>) -> Option<DateTimeUtc> {
+    Utc.timestamp_millis_opt(timestamp?).single()
+}
+
+/// The seen item for the progress of a book, along with the collections it
+/// should be added to.
+fn convert_progress(progress: &MediaProgress) -> (ImportOrExportMediaItemSeen, Vec<String>) {
+    let started_on = get_date(progress.started_at);
+    if progress.is_finished {
+        let seen = ImportOrExportMediaItemSeen {
+            started_on,
+            ended_on: get_date(progress.finished_at),
+            ..Default::default()
+        };
+        return (seen, vec![]);
+    }
+    let seen = ImportOrExportMediaItemSeen {
+        progress: (progress.progress * dec!(100))
+            .floor()
+            .to_i32()
+            .map(|p| p.min(99)),
+        started_on,
+        ..Default::default()
+    };
+    (seen, vec![DefaultCollection::InProgress.to_string()])
+}
+
+/// The seen item for the listening sessions of a book, which are sorted by when
+/// they were last updated, along with the collections it should be added to. The
+/// progress is where the last session ended.
+fn convert_sessions(sessions: &[ListeningSession]) -> (ImportOrExportMediaItemSeen, Vec<String>) {
+    let last = sessions.last().unwrap();
+    let progress = match last.duration.is_zero() {
+        true => Decimal::ZERO,
+        false => last.current_time / last.duration * dec!(100),
+    };
+    let seen_sessions = sessions
+        .iter()
+        .map(|s| SeenSession {
+            started_on: get_date(Some(s.started_at)),
+            duration: (s.time_listening / dec!(60))
+                .round()
+                .to_i32()
+                .unwrap_or_default(),
+            progress: None,
+        })
+        .collect();
+    let started_on = get_date(Some(sessions[0].started_at));
+    if progress >= dec!(100) {
+        let seen = ImportOrExportMediaItemSeen {
+            started_on,
+            ended_on: get_date(Some(last.updated_at)),
+            sessions: Some(seen_sessions),
+            ..Default::default()
+        };
+        return (seen, vec![]);
+    }
+    let seen = ImportOrExportMediaItemSeen {
+        progress: progress.floor().to_i32().map(|p| p.min(99)),
+        started_on,
+        sessions: Some(seen_sessions),
+        ..Default::default()
+    };
+    (seen, vec![DefaultCollection::InProgress.to_string()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_is_converted_to_seen_items() {
+        let progress: Vec<MediaProgress> = serde_json::from_str(
+            r#"[
+                {"libraryItemId": "a", "progress": 1, "isFinished": true,
+                    "startedAt": 1672531200000, "finishedAt": 1672617600000},
+                {"libraryItemId": "b", "progress": 0.4567, "isFinished": false,
+                    "startedAt": 1672531200000, "finishedAt": null}
+            ]"#,
+        )
+        .unwrap();
+        let (finished, collections) = convert_progress(&progress[0]);
+        assert!(collections.is_empty());
+        assert_eq!(finished.progress, None);
+        assert_eq!(
+            finished.ended_on,
+            Some(Utc.with_ymd_and_hms(2023, 1, 2, 0, 0, 0).unwrap())
+        );
+        let (listening, collections) = convert_progress(&progress[1]);
+        assert_eq!(listening.progress, Some(45));
+        assert_eq!(
+            listening.started_on,
+            Some(Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(collections, vec![DefaultCollection::InProgress.to_string()]);
+    }
+
+    #[test]
+    fn sessions_are_converted_to_progress() {
+        let sessions: Vec<ListeningSession> = serde_json::from_str(
+            r#"[
+                {"id": "s1", "libraryItemId": "a", "mediaType": "book",
+                    "mediaMetadata": {"title": "Dune", "asin": "B002V1OF70", "isbn": null},
+                    "duration": 1000, "timeListening": 1800, "currentTime": 300,
+                    "startedAt": 1672531200000, "updatedAt": 1672534800000},
+                {"id": "s2", "libraryItemId": "a", "mediaType": "book",
+                    "mediaMetadata": {"title": "Dune", "asin": "B002V1OF70", "isbn": null},
+                    "duration": 1000, "timeListening": 600, "currentTime": 456.7,
+                    "startedAt": 1672617600000, "updatedAt": 1672618200000}
+            ]"#,
+        )
+        .unwrap();
+        let (listening, collections) = convert_sessions(&sessions);
+        assert_eq!(listening.progress, Some(45));
+        assert_eq!(
+            listening.started_on,
+            Some(Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap())
+        );
+        let durations = listening
+            .sessions
+            .unwrap()
+            .into_iter()
+            .map(|s| s.duration)
+            .collect::<Vec<_>>();
+        assert_eq!(durations, vec![30, 10]);
+        assert_eq!(collections, vec![DefaultCollection::InProgress.to_string()]);
+        let mut sessions = sessions;
+        sessions[1].current_time = dec!(1000);
+        let (finished, collections) = convert_sessions(&sessions[1..]);
+        assert_eq!(finished.progress, None);
+        assert_eq!(
+            finished.ended_on,
+            Some(Utc.with_ymd_and_hms(2023, 1, 2, 0, 10, 0).unwrap())
+        );
+        assert!(collections.is_empty());
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use csv::StringRecord;
+use rs_utils::convert_naive_to_utc;
+use rust_decimal::{Decimal, RoundingStrategy};
+use rust_decimal_macros::dec;
+
+use crate::{entities::user_measurement, models::fitness::UserMeasurementStats};
+
+use super::{
+    get_csv_reader, BodyMeasurementsFormat, DeployBodyMeasurementsImportInput, ImportResult,
+    ImporterError, ImporterResult,
+};
+
+/// The columns of the Withings export and the statistics they hold. The columns
+/// are matched without their unit, since it depends on the settings of the user.
+const WITHINGS_COLUMNS: [(&str, &str); 5] = [
+    ("Weight", "weight"),
+    ("Fat mass", FAT_MASS),
+    ("Bone mass", "bone_mass"),
+    ("Muscle mass", "muscle"),
+    ("Hydration", "total_body_water"),
+];
+
+/// Withings exports the mass of the body fat, whereas Ryot records its percentage.
+const FAT_MASS: &str = "fat_mass";
+
+/// The layout of the file after it has been matched to its headers.
+struct Columns {
+    date: usize,
+    comment: Option<usize>,
+    statistics: Vec<(usize, String)>,
+}
+
+fn get_column(headers: &StringRecord, name: &str) -> ImporterResult<usize> {
+    headers
+        .iter()
+        .position(|h| h == name)
+        .ok_or_else(|| ImporterError::parse("the file", format!("No column named {:?}", name)))
+}
+
+fn get_columns(
+    headers: &StringRecord,
+    input: &DeployBodyMeasurementsImportInput,
+) -> ImporterResult<Columns> {
+    match input.format.unwrap_or_default() {
+        BodyMeasurementsFormat::Generic => {
+            let statistics = input
+                .columns
+                .iter()
+                .flatten()
+                .map(|(column, statistic)| Ok((get_column(headers, column)?, statistic.clone())))
+                .collect::<ImporterResult<Vec<_>>>()?;
+            if statistics.is_empty() {
+                return Err(ImporterError::parse(
+                    "the input",
+                    "At least one column with a statistic is required",
+                ));
+            }
+            Ok(Columns {
+                date: get_column(headers, input.date_column.as_deref().unwrap_or("Date"))?,
+                comment: input
+                    .comment_column
+                    .as_deref()
+                    .map(|c| get_column(headers, c))
+                    .transpose()?,
+                statistics,
+            })
+        }
+        BodyMeasurementsFormat::Withings => {
+            let without_unit = |h: &str| h.split(" (").next().unwrap_or_default().trim().to_owned();
+            let statistics = headers
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, header)| {
+                    let header = without_unit(header);
+                    WITHINGS_COLUMNS
+                        .iter()
+                        .find(|(column, _)| *column == header)
+                        .map(|(_, statistic)| (idx, statistic.to_string()))
+                })
+                .collect();
+            Ok(Columns {
+                date: get_column(headers, "Date")?,
+                comment: headers.iter().position(|h| h == "Comments"),
+                statistics,
+            })
+        }
+    }
+}
+
+/// Parse the date of a measurement using the given format, or as an ISO 8601 date
+/// with an optional time if there is none.
+fn parse_timestamp(value: &str, format: Option<&str>) -> Option<DateTime<Utc>> {
+    let from_naive = |ndt: NaiveDateTime| DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc);
+    if let Some(format) = format {
+        return match NaiveDateTime::parse_from_str(value, format) {
+            Ok(ndt) => Some(from_naive(ndt)),
+            Err(_) => NaiveDate::parse_from_str(value, format)
+                .ok()
+                .map(convert_naive_to_utc),
+        };
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(value, f).ok())
+        .map(from_naive)
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .map(convert_naive_to_utc)
+        })
+}
+
+/// Set the statistic with the given name, which is the name of the field in
+/// snake case. Unknown statistics are saved as custom ones.
+fn set_statistic(stats: &mut UserMeasurementStats, name: &str, value: Decimal) {
+    let field = match name {
+        "weight" => &mut stats.weight,
+        "body_mass_index" => &mut stats.body_mass_index,
+        "total_body_water" => &mut stats.total_body_water,
+        "muscle" => &mut stats.muscle,
+        "lean_body_mass" => &mut stats.lean_body_mass,
+        "body_fat" => &mut stats.body_fat,
+        "bone_mass" => &mut stats.bone_mass,
+        "visceral_fat" => &mut stats.visceral_fat,
+        "waist_circumference" => &mut stats.waist_circumference,
+        "waist_to_height_ratio" => &mut stats.waist_to_height_ratio,
+        "hip_circumference" => &mut stats.hip_circumference,
+        "waist_to_hip_ratio" => &mut stats.waist_to_hip_ratio,
+        "chest_circumference" => &mut stats.chest_circumference,
+        "thigh_circumference" => &mut stats.thigh_circumference,
+        "biceps_circumference" => &mut stats.biceps_circumference,
+        "neck_circumference" => &mut stats.neck_circumference,
+        "body_fat_caliper" => &mut stats.body_fat_caliper,
+        "chest_skinfold" => &mut stats.chest_skinfold,
+        "abdominal_skinfold" => &mut stats.abdominal_skinfold,
+        "thigh_skinfold" => &mut stats.thigh_skinfold,
+        "basal_metabolic_rate" => &mut stats.basal_metabolic_rate,
+        "total_daily_energy_expenditure" => &mut stats.total_daily_energy_expenditure,
+        "calories" => &mut stats.calories,
+        _ => {
+            stats
+                .custom
+                .get_or_insert_with(HashMap::new)
+                .insert(name.to_owned(), value);
+            return;
+        }
+    };
+    *field = Some(value);
+}
+
+pub fn import(
+    input: DeployBodyMeasurementsImportInput,
+    trim_csv: bool,
+) -> ImporterResult<ImportResult> {
+    let mut reader = get_csv_reader(input.csv.as_bytes(), b',', trim_csv)?;
+    let headers = reader
+        .headers()
+        .map_err(|e| ImporterError::parse("the file", e))?
+        .clone();
+    let columns = get_columns(&headers, &input)?;
+    let mut measurements = vec![];
+    let mut warnings = vec![];
+    for (idx, result) in reader.records().enumerate() {
+        let record = match result {
+            Ok(r) => r,
+            Err(e) => {
+                warnings.push(format!("Row {idx} could not be read: {e}"));
+                continue;
+            }
+        };
+        let field = |column: usize| record.get(column).map(str::trim).filter(|v| !v.is_empty());
+        let date = field(columns.date).unwrap_or_default();
+        let Some(timestamp) = parse_timestamp(date, input.date_format.as_deref()) else {
+            warnings.push(format!("Row {idx} has an invalid date: {date:?}"));
+            continue;
+        };
+        let mut stats = UserMeasurementStats::default();
+        let mut fat_mass = None;
+        for (column, statistic) in columns.statistics.iter() {
+            let Some(value) = field(*column) else {
+                continue;
+            };
+            match value.parse::<Decimal>() {
+                Ok(v) if statistic == FAT_MASS => fat_mass = Some(v),
+                Ok(v) => set_statistic(&mut stats, statistic, v),
+                Err(_) => warnings.push(format!(
+                    "Row {idx} has an invalid value for {statistic:?}: {value:?}"
+                )),
+            }
+        }
+        if let (Some(fat_mass), Some(weight)) = (fat_mass, stats.weight) {
+            if !weight.is_zero() {
+                let body_fat = fat_mass * dec!(100) / weight;
+                stats.body_fat = Some(
+                    body_fat.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero),
+                );
+            }
+        }
+        if stats == UserMeasurementStats::default() {
+            continue;
+        }
+        measurements.push(user_measurement::Model {
+            timestamp,
+            user_id: 0,
+            name: None,
+            comment: columns.comment.and_then(field).map(String::from),
+            stats,
+        });
+    }
+    Ok(ImportResult {
+        measurements,
+        warnings,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_input(csv: &str, format: BodyMeasurementsFormat) -> DeployBodyMeasurementsImportInput {
+        DeployBodyMeasurementsImportInput {
+            csv: csv.to_owned(),
+            format: Some(format),
+            date_column: None,
+            date_format: None,
+            columns: None,
+            comment_column: None,
+        }
+    }
+
+    #[test]
+    fn reads_mapped_columns_of_a_generic_file() {
+        let csv = "Time,Weight (kg),Waist (cm),Glucose,Note\n\
+            10-01-2023 07:30,72.5,81,5.4,after run\n\
+            10-02-2023 07:30,,,,\n\
+            yesterday,72.1,,,\n";
+        let mut input = get_input(csv, BodyMeasurementsFormat::Generic);
+        input.date_column = Some("Time".to_owned());
+        input.date_format = Some("%m-%d-%Y %H:%M".to_owned());
+        input.comment_column = Some("Note".to_owned());
+        input.columns = Some(HashMap::from([
+            ("Weight (kg)".to_owned(), "weight".to_owned()),
+            ("Waist (cm)".to_owned(), "waist_circumference".to_owned()),
+            ("Glucose".to_owned(), "glucose".to_owned()),
+        ]));
+        let import = import(input, true).unwrap();
+        assert_eq!(import.measurements.len(), 1);
+        let measurement = &import.measurements[0];
+        assert_eq!(
+            measurement.timestamp,
+            DateTime::parse_from_rfc3339("2023-10-01T07:30:00Z").unwrap()
+        );
+        assert_eq!(measurement.stats.weight, Some(dec!(72.5)));
+        assert_eq!(measurement.stats.waist_circumference, Some(dec!(81)));
+        assert_eq!(
+            measurement.stats.custom,
+            Some(HashMap::from([("glucose".to_owned(), dec!(5.4))]))
+        );
+        assert_eq!(measurement.comment.as_deref(), Some("after run"));
+        assert_eq!(import.warnings.len(), 1);
+    }
+
+    #[test]
+    fn converts_the_fat_mass_of_withings_exports() {
+        let csv = "Date,\"Weight (kg)\",\"Fat mass (kg)\",\"Bone mass (kg)\",\"Muscle mass (kg)\",\"Hydration (kg)\",Comments\n\
+            \"2023-10-01 07:32:10\",80,16,3.2,,,\n";
+        let import = import(get_input(csv, BodyMeasurementsFormat::Withings), true).unwrap();
+        let stats = &import.measurements[0].stats;
+        assert_eq!(stats.weight, Some(dec!(80)));
+        assert_eq!(stats.body_fat, Some(dec!(20)));
+        assert_eq!(stats.bone_mass, Some(dec!(3.2)));
+        assert!(stats.muscle.is_none());
+        assert!(stats.custom.is_none());
+    }
+
+    #[test]
+    fn generic_files_need_a_statistic() {
+        let input = get_input("Date,Weight\n", BodyMeasurementsFormat::Generic);
+        assert!(import(input, true).is_err());
+    }
+}
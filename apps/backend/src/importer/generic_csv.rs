@@ -0,0 +1,290 @@
+use std::fmt::Debug;
+
+use csv::StringRecord;
+use database::{MetadataLot, MetadataSource};
+use rs_utils::{convert_naive_to_utc, convert_string_to_date};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use strum::IntoEnumIterator;
+
+use crate::{
+    importer::{
+        get_csv_reader, get_raw_csv_row, DeployGenericCsvImportInput, ImportErrorCode,
+        ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier, ImportOrExportMediaItem,
+        ImportResult, ImporterError, ImporterResult,
+    },
+    models::media::{ImportOrExportItemRating, ImportOrExportMediaItemSeen},
+};
+
+/// Parse the variant of an enum ignoring case, spaces and punctuation, so that
+/// `Audio Book`, `audio_book` and `AudioBook` are all accepted.
+fn parse_variant<T: IntoEnumIterator + Debug>(value: &str) -> Option<T> {
+    let normalize = |s: &str| {
+        s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect::<String>()
+            .to_lowercase()
+    };
+    let value = normalize(value);
+    T::iter().find(|v| normalize(&format!("{:?}", v)) == value)
+}
+
+fn get_column(headers: &StringRecord, name: &str) -> ImporterResult<usize> {
+    headers
+        .iter()
+        .position(|h| h == name)
+        .ok_or_else(|| ImporterError::parse("the file", format!("No column named {:?}", name)))
+}
+
+fn get_optional_column(
+    headers: &StringRecord,
+    name: Option<&String>,
+) -> ImporterResult<Option<usize>> {
+    name.map(|n| get_column(headers, n)).transpose()
+}
+
+/// Convert a rating out of the scale to one out of 100, limited to `0..=100`.
+fn convert_rating(rating: Decimal, rating_scale: Decimal) -> Option<Decimal> {
+    let rating = rating.checked_mul(dec!(100))?.checked_div(rating_scale)?;
+    Some(rating.clamp(Decimal::ZERO, dec!(100)))
+}
+
+pub fn import(input: DeployGenericCsvImportInput, trim_csv: bool) -> ImporterResult<ImportResult> {
+    let mut reader = get_csv_reader(input.csv.as_bytes(), b',', trim_csv)?;
+    let headers = reader
+        .headers()
+        .map_err(|e| ImporterError::parse("the file", e))?
+        .clone();
+    let mapping = &input.mapping;
+    let title_column = get_column(&headers, &mapping.title)?;
+    let id_column = get_column(&headers, &mapping.provider_id)?;
+    let lot_column = get_optional_column(&headers, mapping.lot.as_ref())?;
+    let source_column = get_optional_column(&headers, mapping.source.as_ref())?;
+    let rating_column = get_optional_column(&headers, mapping.rating.as_ref())?;
+    let watched_on_column = get_optional_column(&headers, mapping.watched_on.as_ref())?;
+    let collection_column = get_optional_column(&headers, mapping.collection.as_ref())?;
+    let rating_scale = input
+        .rating_scale
+        .filter(|s| s.is_sign_positive() && !s.is_zero())
+        .unwrap_or(dec!(100));
+    let mut media: Vec<ImportOrExportMediaItem> = vec![];
+    let mut failed_items = vec![];
+    for (idx, result) in reader.records().enumerate() {
+        let default_lot = input.lot.unwrap_or_default();
+        let record = match result {
+            Ok(r) => r,
+            Err(e) => {
+                failed_items.push(ImportFailedItem {
+                    lot: default_lot,
+                    step: ImportFailStep::InputTransformation,
+                    identifier: idx.to_string(),
+                    error: Some(ImporterError::parse("the file", e).to_string()),
+                    code: ImportErrorCode::ParseError,
+                    source_row: None,
+                });
+                continue;
+            }
+        };
+        let field =
+            |column: Option<usize>| column.and_then(|c| record.get(c)).filter(|v| !v.is_empty());
+        let title = field(Some(title_column)).unwrap_or_default().to_owned();
+        let source_row = get_raw_csv_row(&record);
+        let mut fail = |error: String, code: ImportErrorCode| {
+            failed_items.push(ImportFailedItem {
+                lot: default_lot,
+                step: ImportFailStep::InputTransformation,
+                identifier: if title.is_empty() {
+                    idx.to_string()
+                } else {
+                    title.clone()
+                },
+                error: Some(error),
+                code,
+                source_row: source_row.clone(),
+            })
+        };
+        let lot = match field(lot_column) {
+            Some(l) => parse_variant::<MetadataLot>(l),
+            None => input.lot,
+        };
+        let Some(lot) = lot else {
+            fail(
+                "The type of the media is missing or invalid".to_owned(),
+                ImportErrorCode::ParseError,
+            );
+            continue;
+        };
+        let source = match field(source_column) {
+            Some(s) => parse_variant::<MetadataSource>(s),
+            None => input.source,
+        };
+        let Some(source) = source else {
+            fail(
+                "The provider of the media is missing or invalid".to_owned(),
+                ImportErrorCode::ParseError,
+            );
+            continue;
+        };
+        let Some(identifier) = field(Some(id_column)).map(String::from) else {
+            fail(
+                "The identifier of the media is missing".to_owned(),
+                ImportErrorCode::MissingIdentifier,
+            );
+            continue;
+        };
+        let rating = match field(rating_column).map(|r| r.parse::<Decimal>()) {
+            Some(Ok(r)) => match convert_rating(r, rating_scale) {
+                Some(r) => Some(r),
+                None => {
+                    fail(
+                        format!("The rating {} is too large to be converted", r),
+                        ImportErrorCode::ParseError,
+                    );
+                    continue;
+                }
+            },
+            Some(Err(e)) => {
+                fail(
+                    format!("The rating is invalid: {}", e),
+                    ImportErrorCode::ParseError,
+                );
+                continue;
+            }
+            None => None,
+        };
+        let watched_on = match field(watched_on_column).map(convert_string_to_date) {
+            Some(Some(d)) => Some(convert_naive_to_utc(d)),
+            Some(None) => {
+                fail(
+                    "The date is not in the YYYY-MM-DD format".to_owned(),
+                    ImportErrorCode::ParseError,
+                );
+                continue;
+            }
+            None => None,
+        };
+        let collection = field(collection_column).map(String::from);
+        let position = media
+            .iter()
+            .position(|m| m.lot == lot && m.source == source && m.identifier == identifier);
+        let item = match position {
+            Some(p) => &mut media[p],
+            None => {
+                media.push(ImportOrExportMediaItem {
+                    source_id: if title.is_empty() {
+                        identifier.clone()
+                    } else {
+                        title.clone()
+                    },
+                    lot,
+                    source,
+                    internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails(
+                        identifier.clone(),
+                    )),
+                    identifier,
+                    seen_history: vec![],
+                    reviews: vec![],
+                    collections: vec![],
+                    external_identifiers: None,
+                    is_favorite: None,
+                    next_episode: None,
+                    display_title: None,
+                });
+                media.last_mut().unwrap()
+            }
+        };
+        if watched_on.is_some() || collection.is_none() {
+            item.seen_history.push(ImportOrExportMediaItemSeen {
+                ended_on: watched_on,
+                ..Default::default()
+            });
+        }
+        if rating.is_some() {
+            item.reviews.push(ImportOrExportItemRating {
+                rating,
+                ..Default::default()
+            });
+        }
+        if let Some(collection) = collection {
+            if !item.collections.contains(&collection) {
+                item.collections.push(collection);
+            }
+        }
+    }
+    Ok(ImportResult {
+        collections: vec![],
+        media,
+        failed_items,
+        workouts: vec![],
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::importer::GenericCsvColumnMapping;
+
+    use super::*;
+
+    fn get_input(csv: &str) -> DeployGenericCsvImportInput {
+        DeployGenericCsvImportInput {
+            csv: csv.to_owned(),
+            mapping: GenericCsvColumnMapping {
+                title: "Name".to_owned(),
+                provider_id: "TMDB".to_owned(),
+                lot: Some("Kind".to_owned()),
+                source: None,
+                rating: Some("Stars".to_owned()),
+                watched_on: Some("Watched".to_owned()),
+                collection: Some("List".to_owned()),
+            },
+            lot: None,
+            source: Some(MetadataSource::Tmdb),
+            rating_scale: Some(dec!(5)),
+        }
+    }
+
+    #[test]
+    fn rows_are_read_using_the_mapping() {
+        let input = get_input(
+            "Name,Kind,TMDB,Stars,Watched,List\n\
+            Heat,movie,949,4.5,2023-01-02,\n\
+            Heat,Movie,949,,2023-02-03,\n\
+            The Wire,show,1438,,,Watchlist\n\
+            Dune,tape,438631,,,\n\
+            Alien,movie,348,,02/01/2023,\n",
+        );
+        let result = import(input, true).unwrap();
+        assert_eq!(result.failed_items.len(), 2);
+        assert_eq!(result.failed_items[0].identifier, "Dune");
+        assert_eq!(result.media.len(), 2);
+        let heat = &result.media[0];
+        assert_eq!(heat.seen_history.len(), 2);
+        assert_eq!(heat.reviews[0].rating, Some(dec!(90)));
+        let wire = &result.media[1];
+        assert_eq!(wire.lot, MetadataLot::Show);
+        assert!(wire.seen_history.is_empty());
+        assert_eq!(wire.collections, vec!["Watchlist".to_owned()]);
+    }
+
+    #[test]
+    fn ratings_are_limited_to_the_scale() {
+        let input = get_input(
+            "Name,Kind,TMDB,Stars,Watched,List\n\
+            Heat,movie,949,7,,\n\
+            Alien,movie,348,-1,,\n\
+            Dune,movie,438631,79228162514264337593543950335,,\n",
+        );
+        let result = import(input, true).unwrap();
+        assert_eq!(result.media[0].reviews[0].rating, Some(dec!(100)));
+        assert_eq!(result.media[1].reviews[0].rating, Some(dec!(0)));
+        assert_eq!(result.failed_items.len(), 1);
+        assert_eq!(result.failed_items[0].identifier, "Dune");
+    }
+
+    #[test]
+    fn missing_columns_fail_the_import() {
+        let input = get_input("Name,Kind,Stars,Watched,List\nHeat,movie,4.5,,\n");
+        assert!(import(input, true).is_err());
+    }
+}
@@ -1,15 +1,19 @@
-use async_graphql::Result;
-use chrono::{DateTime, Utc};
-use database::{MetadataLot, MetadataSource};
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use database::{MetadataLot, MetadataSource, Visibility};
 use itertools::Itertools;
+use rs_utils::convert_naive_to_utc;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     importer::{
-        DeployGoodreadsImportInput, ImportOrExportItemIdentifier, ImportOrExportMediaItem,
-        ImportResult,
+        get_csv_reader, get_reading_goals, read_csv_rows, send_source_request,
+        DeployGoodreadsImportInput, ImportErrorCode, ImportFailStep, ImportFailedItem,
+        ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportResult, ImporterError,
+        ImporterResult,
     },
     miscellaneous::DefaultCollection,
     models::media::{
@@ -35,6 +39,8 @@ struct RssItem {
     book: RssBookDetails,
     book_published: String,
     user_shelves: String,
+    #[serde(default)]
+    user_date_added: String,
     user_read_at: String,
     user_review: String,
     user_rating: String,
@@ -51,15 +57,263 @@ struct RssDetail {
     channel: RssChannel,
 }
 
-pub async fn import(input: DeployGoodreadsImportInput) -> Result<ImportResult> {
-    let content = surf::get(input.rss_url)
-        .await
-        .unwrap()
+/// A row of `goodreads_library_export.csv`.
+#[derive(Debug, Serialize, Deserialize)]
+struct CsvBook {
+    #[serde(rename = "Book Id")]
+    book_id: String,
+    #[serde(rename = "Title")]
+    title: String,
+    #[serde(rename = "Author", default)]
+    author: String,
+    #[serde(rename = "Additional Authors", default)]
+    additional_authors: String,
+    #[serde(rename = "My Rating", default)]
+    rating: Option<Decimal>,
+    #[serde(rename = "Number of Pages", default)]
+    pages: Option<i32>,
+    #[serde(rename = "Original Publication Year", default)]
+    original_publication_year: Option<i32>,
+    #[serde(rename = "Year Published", default)]
+    year_published: Option<i32>,
+    /// The dates are in the `YYYY/MM/DD` format.
+    #[serde(rename = "Date Read", default)]
+    date_read: String,
+    #[serde(rename = "Date Added", default)]
+    date_added: String,
+    /// The shelves other than the exclusive one, separated by commas.
+    #[serde(rename = "Bookshelves", default)]
+    bookshelves: String,
+    #[serde(rename = "Exclusive Shelf", default)]
+    exclusive_shelf: String,
+    #[serde(rename = "My Review", default)]
+    review: String,
+    #[serde(rename = "Spoiler", default)]
+    spoiler: String,
+    #[serde(rename = "Private Notes", default)]
+    private_notes: String,
+    #[serde(rename = "Read Count", default)]
+    read_count: Option<i32>,
+}
+
+const READ_SHELF: &str = "read";
+const CURRENTLY_READING_SHELF: &str = "currently-reading";
+const TO_READ_SHELF: &str = "to-read";
+
+pub async fn import(
+    input: DeployGoodreadsImportInput,
+    trim_csv: bool,
+) -> ImporterResult<ImportResult> {
+    let reading_goals = get_reading_goals(input.reading_challenges.as_deref(), trim_csv)?;
+    let mut import = match (input.csv, input.rss_url) {
+        (Some(csv), _) => import_csv(&csv, trim_csv)?,
+        (None, Some(rss_url)) => import_rss(rss_url).await?,
+        (None, None) => {
+            return Err(ImporterError::parse(
+                "the input",
+                "Either the RSS url or the CSV export is required",
+            ))
+        }
+    };
+    import.reading_goals = reading_goals;
+    Ok(import)
+}
+
+fn get_book_details(
+    book_id: String,
+    title: String,
+    description: Option<String>,
+    authors: Vec<String>,
+    image: Option<String>,
+    pages: Option<i32>,
+    publish_year: Option<i32>,
+) -> ImportOrExportItemIdentifier {
+    ImportOrExportItemIdentifier::AlreadyFilled(Box::new(MediaDetails {
+        identifier: book_id,
+        title,
+        description,
+        production_status: "Released".to_owned(),
+        lot: MetadataLot::Book,
+        source: MetadataSource::Custom,
+        creators: authors
+            .into_iter()
+            .map(|name| MetadataFreeCreator {
+                name,
+                role: "Author".to_owned(),
+                image: None,
+            })
+            .collect(),
+        url_images: image
+            .into_iter()
+            .map(|image| MetadataImageForMediaDetails {
+                image,
+                lot: MetadataImageLot::Poster,
+            })
+            .collect(),
+        specifics: MediaSpecifics::Book(BookSpecifics { pages }),
+        publish_year,
+        videos: vec![],
+        provider_rating: None,
+        publish_date: None,
+        genres: vec![],
+        suggestions: vec![],
+        group_identifiers: vec![],
+        is_nsfw: None,
+        people: vec![],
+        s3_images: vec![],
+    }))
+}
+
+fn get_csv_date(date: &str) -> Option<DateTime<Utc>> {
+    NaiveDate::parse_from_str(date, "%Y/%m/%d")
+        .ok()
+        .map(convert_naive_to_utc)
+}
+
+fn import_csv(data: &str, trim_csv: bool) -> ImporterResult<ImportResult> {
+    let reader = get_csv_reader(data.as_bytes(), b',', trim_csv)?;
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    let mut collection_added_on = HashMap::new();
+    for (idx, row) in read_csv_rows::<CsvBook, _>(reader).enumerate() {
+        let book = match row.record {
+            Ok(b) => b,
+            Err(e) => {
+                failed_items.push(ImportFailedItem {
+                    lot: MetadataLot::Book,
+                    step: ImportFailStep::InputTransformation,
+                    identifier: idx.to_string(),
+                    error: Some(ImporterError::parse("the library export", e).to_string()),
+                    code: ImportErrorCode::ParseError,
+                    source_row: row.raw,
+                });
+                continue;
+            }
+        };
+        let shelf = book.exclusive_shelf.as_str();
+        let times = match shelf {
+            READ_SHELF => book.read_count.unwrap_or_default().max(1),
+            _ => book.read_count.unwrap_or_default().max(0),
+        };
+        let mut seen_history =
+            vec![ImportOrExportMediaItemSeen::default(); usize::try_from(times).unwrap_or(0)];
+        if let Some(seen) = seen_history.first_mut() {
+            seen.ended_on = get_csv_date(&book.date_read);
+        }
+        let mut collections = book
+            .bookshelves
+            .split(',')
+            .map(str::trim)
+            .filter(|s| ![READ_SHELF, CURRENTLY_READING_SHELF, TO_READ_SHELF, ""].contains(s))
+            .map(String::from)
+            .collect_vec();
+        match shelf {
+            CURRENTLY_READING_SHELF => collections.push(DefaultCollection::InProgress.to_string()),
+            TO_READ_SHELF => {
+                collections.push(DefaultCollection::Watchlist.to_string());
+                if let Some(added_on) = get_csv_date(&book.date_added) {
+                    collection_added_on.insert(
+                        (
+                            DefaultCollection::Watchlist.to_string(),
+                            book.book_id.clone(),
+                        ),
+                        added_on,
+                    );
+                }
+            }
+            _ => {}
+        }
+        let mut reviews = vec![];
+        // DEV: Rates items out of 5, and unrated items have a rating of 0
+        let rating = book
+            .rating
+            .filter(|r| !r.is_zero())
+            .map(|r| r.saturating_mul(dec!(20)));
+        if rating.is_some() || !book.review.is_empty() {
+            reviews.push(ImportOrExportItemRating {
+                rating,
+                review: (!book.review.is_empty()).then(|| ImportOrExportItemReview {
+                    date: None,
+                    spoiler: Some(book.spoiler.eq_ignore_ascii_case("true")),
+                    text: Some(book.review),
+                    visibility: None,
+                }),
+                ..Default::default()
+            });
+        }
+        if !book.private_notes.is_empty() {
+            reviews.push(ImportOrExportItemRating {
+                review: Some(ImportOrExportItemReview {
+                    date: None,
+                    spoiler: Some(false),
+                    text: Some(book.private_notes),
+                    visibility: Some(Visibility::Private),
+                }),
+                ..Default::default()
+            });
+        }
+        let authors = [book.author.as_str()]
+            .into_iter()
+            .chain(book.additional_authors.split(','))
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .map(String::from)
+            .collect();
+        media.push(ImportOrExportMediaItem {
+            source_id: book.book_id.clone(),
+            source: MetadataSource::Custom,
+            lot: MetadataLot::Book,
+            internal_identifier: Some(get_book_details(
+                book.book_id,
+                book.title,
+                None,
+                authors,
+                None,
+                book.pages,
+                book.original_publication_year.or(book.year_published),
+            )),
+            identifier: "".to_string(),
+            seen_history,
+            collections,
+            reviews,
+            external_identifiers: None,
+            is_favorite: None,
+            next_episode: None,
+            display_title: None,
+        });
+    }
+    Ok(ImportResult {
+        media,
+        failed_items,
+        collection_added_on,
+        ..Default::default()
+    })
+}
+
+async fn import_rss(rss_url: String) -> ImporterResult<ImportResult> {
+    let context = "the RSS feed";
+    let content = send_source_request(surf::get(rss_url), context)
+        .await?
         .body_string()
         .await
-        .unwrap();
-    let books: RssDetail = quick_xml::de::from_str(&content).unwrap();
+        .map_err(|e| ImporterError::parse(context, e))?;
+    let books: RssDetail =
+        quick_xml::de::from_str(&content).map_err(|e| ImporterError::parse(context, e))?;
     let books = books.channel.item.into_iter().collect_vec();
+    let collection_added_on = books
+        .iter()
+        .filter(|d| d.user_shelves == TO_READ_SHELF)
+        .filter_map(|d| {
+            let added_on = DateTime::parse_from_rfc2822(&d.user_date_added).ok()?;
+            Some((
+                (
+                    DefaultCollection::Watchlist.to_string(),
+                    d.book_id.to_string(),
+                ),
+                added_on.with_timezone(&Utc),
+            ))
+        })
+        .collect();
     Ok(ImportResult {
         media: books
             .into_iter()
@@ -73,6 +327,7 @@ pub async fn import(input: DeployGoodreadsImportInput) -> Result<ImportResult> {
                         date: None,
                         spoiler: Some(false),
                         text: Some(d.user_review),
+                        visibility: None,
                     });
                 };
                 if !d.user_rating.is_empty() {
@@ -97,7 +352,7 @@ pub async fn import(input: DeployGoodreadsImportInput) -> Result<ImportResult> {
                 }
 
                 let mut default_collections = vec![];
-                if d.user_shelves == "to-read" {
+                if d.user_shelves == TO_READ_SHELF {
                     default_collections.push(DefaultCollection::Watchlist.to_string());
                 }
 
@@ -105,47 +360,65 @@ pub async fn import(input: DeployGoodreadsImportInput) -> Result<ImportResult> {
                     source_id: d.book_id.to_string(),
                     source: MetadataSource::Custom,
                     lot: MetadataLot::Book,
-                    internal_identifier: Some(ImportOrExportItemIdentifier::AlreadyFilled(
-                        Box::new(MediaDetails {
-                            identifier: d.book_id.to_string(),
-                            title: d.title,
-                            description: Some(d.book_description),
-                            production_status: "Released".to_owned(),
-                            lot: MetadataLot::Book,
-                            source: MetadataSource::Custom,
-                            creators: vec![MetadataFreeCreator {
-                                name: d.author_name,
-                                role: "Author".to_owned(),
-                                image: None,
-                            }],
-                            url_images: vec![MetadataImageForMediaDetails {
-                                image: d.book_large_image_url,
-                                lot: MetadataImageLot::Poster,
-                            }],
-                            specifics: MediaSpecifics::Book(BookSpecifics {
-                                pages: d.book.num_pages.parse().ok(),
-                            }),
-                            publish_year: d.book_published.parse().ok(),
-                            videos: vec![],
-                            provider_rating: None,
-                            publish_date: None,
-                            genres: vec![],
-                            suggestions: vec![],
-                            group_identifiers: vec![],
-                            is_nsfw: None,
-                            people: vec![],
-                            s3_images: vec![],
-                        }),
+                    internal_identifier: Some(get_book_details(
+                        d.book_id.to_string(),
+                        d.title,
+                        Some(d.book_description),
+                        vec![d.author_name],
+                        Some(d.book_large_image_url),
+                        d.book.num_pages.parse().ok(),
+                        d.book_published.parse().ok(),
                     )),
                     identifier: "".to_string(),
                     seen_history,
                     collections: default_collections,
                     reviews,
+                    external_identifiers: None,
+                    is_favorite: None,
+                    next_episode: None,
+                    display_title: None,
                 }
             })
             .collect(),
         failed_items: vec![],
         collections: vec![],
         workouts: vec![],
+        collection_added_on,
+        ..Default::default()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn library_export_is_read() {
+        let data = "Book Id,Title,Author,Additional Authors,My Rating,Number of Pages,\
+            Year Published,Original Publication Year,Date Read,Date Added,Bookshelves,\
+            Exclusive Shelf,My Review,Spoiler,Private Notes,Read Count\n\
+            1,Dune,Frank Herbert,,5,412,2005,1965,2023/01/15,2022/12/01,\
+            \"favorites, sci-fi\",read,Great,,Reread the appendix,2\n\
+            2,Emma,Jane Austen,,0,,,1815,,2023/02/01,to-read,to-read,,,,0\n";
+        let import = import_csv(data, true).unwrap();
+        assert!(import.failed_items.is_empty());
+        let dune = &import.media[0];
+        assert_eq!(dune.seen_history.len(), 2);
+        assert!(dune.seen_history[0].ended_on.is_some());
+        assert_eq!(dune.collections, vec!["favorites", "sci-fi"]);
+        assert_eq!(dune.reviews.len(), 2);
+        assert_eq!(dune.reviews[0].rating, Some(dec!(100)));
+        let notes = dune.reviews[1].review.as_ref().unwrap();
+        assert_eq!(notes.visibility, Some(Visibility::Private));
+        let emma = &import.media[1];
+        assert!(emma.seen_history.is_empty());
+        assert!(emma.reviews.is_empty());
+        assert_eq!(
+            emma.collections,
+            vec![DefaultCollection::Watchlist.to_string()]
+        );
+        assert!(import
+            .collection_added_on
+            .contains_key(&(DefaultCollection::Watchlist.to_string(), "2".to_owned())));
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use database::{MetadataLot, MetadataSource};
+use rs_utils::convert_naive_to_utc;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    importer::{
+        get_csv_reader, read_csv_rows, send_provider_request, DeployImdbImportInput,
+        ImportErrorCode, ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier,
+        ImportOrExportMediaItem, ImportResult, ImporterError, ImporterResult,
+    },
+    miscellaneous::DefaultCollection,
+    models::media::ImportOrExportItemRating,
+    providers::tmdb::{TmdbMovieService, TmdbShowService},
+};
+
+/// A row of the ratings or the watchlist file. They have the same columns except
+/// for the date, which is `Date Rated` in the former and `Created` in the latter.
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    #[serde(rename = "Const")]
+    imdb_id: String,
+    #[serde(rename = "Title")]
+    title: String,
+    #[serde(rename = "Title Type")]
+    title_type: String,
+    #[serde(rename = "Your Rating", default)]
+    rating: Option<Decimal>,
+    #[serde(rename = "Created", default)]
+    created: Option<NaiveDate>,
+}
+
+#[derive(Debug)]
+struct Title {
+    imdb_id: String,
+    item: ImportOrExportMediaItem,
+    added_to_watchlist_on: Option<NaiveDate>,
+}
+
+fn get_lot(title_type: &str) -> Option<MetadataLot> {
+    match title_type {
+        "movie" | "tvMovie" | "tvSpecial" | "short" | "tvShort" | "video" => {
+            Some(MetadataLot::Movie)
+        }
+        "tvSeries" | "tvMiniSeries" => Some(MetadataLot::Show),
+        _ => None,
+    }
+}
+
+/// Read the files of the export and merge the rows that refer to the same title.
+/// The titles still need to be resolved to a TMDB identifier.
+fn collect_titles(
+    input: DeployImdbImportInput,
+    trim_csv: bool,
+) -> ImporterResult<(Vec<Title>, Vec<ImportFailedItem>)> {
+    let mut titles: Vec<Title> = vec![];
+    let mut failed_items = vec![];
+    let files = [
+        ("the ratings file", input.ratings, false),
+        ("the watchlist file", input.watchlist, true),
+    ];
+    for (context, data, is_watchlist) in files {
+        let Some(data) = data else {
+            continue;
+        };
+        let reader = get_csv_reader(data.as_bytes(), b',', trim_csv)?;
+        for (idx, row) in read_csv_rows::<Entry, _>(reader).enumerate() {
+            let record = match row.record {
+                Ok(r) => r,
+                Err(e) => {
+                    failed_items.push(ImportFailedItem {
+                        lot: MetadataLot::Movie,
+                        step: ImportFailStep::InputTransformation,
+                        identifier: idx.to_string(),
+                        error: Some(ImporterError::parse(context, e).to_string()),
+                        code: ImportErrorCode::ParseError,
+                        source_row: row.raw,
+                    });
+                    continue;
+                }
+            };
+            let Some(lot) = get_lot(&record.title_type) else {
+                failed_items.push(ImportFailedItem {
+                    lot: MetadataLot::Movie,
+                    step: ImportFailStep::InputTransformation,
+                    identifier: record.title,
+                    error: Some(format!(
+                        "Titles of type {:?} can not be imported",
+                        record.title_type
+                    )),
+                    code: ImportErrorCode::Unsupported,
+                    source_row: row.raw,
+                });
+                continue;
+            };
+            let position = titles.iter().position(|t| t.imdb_id == record.imdb_id);
+            let title = match position {
+                Some(p) => &mut titles[p],
+                None => {
+                    titles.push(Title {
+                        imdb_id: record.imdb_id.clone(),
+                        item: ImportOrExportMediaItem {
+                            source_id: record.title.clone(),
+                            lot,
+                            source: MetadataSource::Tmdb,
+                            identifier: "".to_string(),
+                            internal_identifier: None,
+                            seen_history: vec![],
+                            reviews: vec![],
+                            collections: vec![],
+                            external_identifiers: None,
+                            is_favorite: None,
+                            next_episode: None,
+                            display_title: None,
+                        },
+                        added_to_watchlist_on: None,
+                    });
+                    titles.last_mut().unwrap()
+                }
+            };
+            if is_watchlist {
+                title
+                    .item
+                    .collections
+                    .push(DefaultCollection::Watchlist.to_string());
+                title.added_to_watchlist_on = record.created;
+            }
+            // DEV: The watchlist also has the rating of titles that were rated
+            if let (Some(rating), true) = (record.rating, title.item.reviews.is_empty()) {
+                title.item.reviews.push(ImportOrExportItemRating {
+                    // DEV: Rates items out of 10
+                    rating: Some(rating.saturating_mul(dec!(10))),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+    Ok((titles, failed_items))
+}
+
+pub async fn import(
+    input: DeployImdbImportInput,
+    trim_csv: bool,
+    movie_service: &TmdbMovieService,
+    show_service: &TmdbShowService,
+) -> ImporterResult<ImportResult> {
+    let (titles, mut failed_items) = collect_titles(input, trim_csv)?;
+    let total = titles.len();
+    let mut media = vec![];
+    let mut collection_added_on = HashMap::new();
+    for (idx, mut title) in titles.into_iter().enumerate() {
+        tracing::debug!(
+            "Getting details for {title:?} ({idx}/{total})",
+            title = title.item.source_id
+        );
+        let identifier = send_provider_request(MetadataSource::Tmdb, || async {
+            match title.item.lot {
+                MetadataLot::Show => show_service.id_from_imdb_id(&title.imdb_id).await,
+                _ => movie_service.id_from_imdb_id(&title.imdb_id).await,
+            }
+        })
+        .await;
+        let identifier = match identifier {
+            Ok(identifier) => identifier,
+            Err(e) => {
+                let error = e.to_string();
+                failed_items.push(ImportFailedItem {
+                    lot: title.item.lot,
+                    step: ImportFailStep::MediaDetailsFromProvider,
+                    identifier: title.item.source_id,
+                    code: ImportErrorCode::from_provider_message(&error),
+                    error: Some(error),
+                    source_row: None,
+                });
+                continue;
+            }
+        };
+        let Some(identifier) = identifier else {
+            failed_items.push(ImportFailedItem {
+                lot: title.item.lot,
+                step: ImportFailStep::InputTransformation,
+                identifier: title.item.source_id,
+                error: Some(format!(
+                    "Could not convert IMDb ID: {} to TMDB ID",
+                    title.imdb_id
+                )),
+                code: ImportErrorCode::NotFound,
+                source_row: None,
+            });
+            continue;
+        };
+        if let Some(added_on) = title.added_to_watchlist_on {
+            collection_added_on.insert(
+                (
+                    DefaultCollection::Watchlist.to_string(),
+                    title.item.source_id.clone(),
+                ),
+                convert_naive_to_utc(added_on),
+            );
+        }
+        title.item.internal_identifier =
+            Some(ImportOrExportItemIdentifier::NeedsDetails(identifier));
+        media.push(title.item);
+    }
+    Ok(ImportResult {
+        collections: vec![],
+        media,
+        failed_items,
+        workouts: vec![],
+        collection_added_on,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ratings_and_watchlist_are_merged() {
+        let input = DeployImdbImportInput {
+            ratings: Some(
+                "Const,Your Rating,Date Rated,Title,URL,Title Type,IMDb Rating,Year\n\
+                tt0133093,9,2023-01-02,The Matrix,https://www.imdb.com/title/tt0133093/,movie,8.7,1999\n\
+                tt0903747,10,2023-01-03,Breaking Bad,https://www.imdb.com/title/tt0903747/,tvSeries,9.5,2008\n\
+                tt0959621,8,2023-01-04,Pilot,https://www.imdb.com/title/tt0959621/,tvEpisode,8.2,2008\n"
+                    .to_owned(),
+            ),
+            watchlist: Some(
+                "Position,Const,Created,Modified,Description,Title,URL,Title Type,Your Rating\n\
+                1,tt0133093,2022-12-01,2022-12-01,,The Matrix,https://www.imdb.com/title/tt0133093/,movie,9\n\
+                2,tt0113277,2022-12-02,2022-12-02,,Heat,https://www.imdb.com/title/tt0113277/,movie,\n"
+                    .to_owned(),
+            ),
+        };
+        let (titles, failed_items) = collect_titles(input, true).unwrap();
+        assert_eq!(failed_items.len(), 1);
+        assert_eq!(failed_items[0].identifier, "Pilot");
+        let ids = titles.iter().map(|t| t.imdb_id.as_str());
+        assert_eq!(
+            ids.collect::<Vec<_>>(),
+            vec!["tt0133093", "tt0903747", "tt0113277"]
+        );
+        let matrix = &titles[0];
+        assert_eq!(matrix.item.reviews.len(), 1);
+        assert_eq!(matrix.item.reviews[0].rating, Some(dec!(90)));
+        assert_eq!(
+            matrix.item.collections,
+            vec![DefaultCollection::Watchlist.to_string()]
+        );
+        assert_eq!(
+            matrix.added_to_watchlist_on,
+            NaiveDate::from_ymd_opt(2022, 12, 1)
+        );
+        assert_eq!(titles[1].item.lot, MetadataLot::Show);
+        assert!(titles[2].item.reviews.is_empty());
+    }
+}
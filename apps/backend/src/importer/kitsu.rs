@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+
+use database::{MetadataLot, MetadataSource};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use sea_orm::prelude::DateTimeUtc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use surf::http::headers::ACCEPT;
+
+use crate::{
+    importer::{
+        get_source_json, DeployKitsuImportInput, ImportErrorCode, ImportFailStep, ImportFailedItem,
+        ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportResult, ImporterError,
+        ImporterResult,
+    },
+    miscellaneous::DefaultCollection,
+    models::media::{
+        ImportOrExportItemRating, ImportOrExportMediaItemSeen, ImportOrExportMediaItemSeenDropped,
+        MetadataExternalIdentifier,
+    },
+    utils::get_base_http_client,
+};
+
+static API_URL: &str = "https://kitsu.io/api/edge/";
+// DEV: The maximum allowed by the API for library entries
+static PAGE_LIMIT: i32 = 500;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+struct ResourceIdentifier {
+    #[serde(rename = "type")]
+    kind: String,
+    id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum RelationshipData {
+    One(ResourceIdentifier),
+    Many(Vec<ResourceIdentifier>),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Relationship {
+    data: Option<RelationshipData>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Resource<A> {
+    #[serde(flatten)]
+    identifier: ResourceIdentifier,
+    attributes: A,
+    #[serde(default)]
+    relationships: HashMap<String, Relationship>,
+}
+
+impl<A> Resource<A> {
+    fn related(&self, name: &str) -> Vec<&ResourceIdentifier> {
+        match self.relationships.get(name).and_then(|r| r.data.as_ref()) {
+            Some(RelationshipData::One(i)) => vec![i],
+            Some(RelationshipData::Many(i)) => i.iter().collect(),
+            None => vec![],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LibraryEntry {
+    status: String,
+    #[serde(default)]
+    progress: i32,
+    #[serde(default)]
+    reconsume_count: i32,
+    /// The rating out of 20, in steps of 1.
+    rating_twenty: Option<i32>,
+    started_at: Option<DateTimeUtc>,
+    finished_at: Option<DateTimeUtc>,
+}
+
+/// The attributes of the media and the mappings that are included with the
+/// library entries.
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct Included {
+    canonical_title: Option<String>,
+    episode_count: Option<i32>,
+    chapter_count: Option<i32>,
+    external_site: Option<String>,
+    external_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct Links {
+    next: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LibraryResponse {
+    data: Vec<Resource<LibraryEntry>>,
+    #[serde(default)]
+    included: Vec<Resource<Included>>,
+    #[serde(default)]
+    links: Links,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct UserResponse {
+    data: Vec<ResourceIdentifier>,
+}
+
+pub async fn import(input: DeployKitsuImportInput) -> ImporterResult<ImportResult> {
+    let client = get_base_http_client(API_URL, vec![(ACCEPT, "application/vnd.api+json")]);
+    let users: UserResponse = get_source_json(
+        client
+            .get("users")
+            .query(&json!({ "filter[name]": input.username }))
+            .map_err(|e| ImporterError::parse("the user", e))?,
+        "the user",
+    )
+    .await?;
+    let user_id = users
+        .data
+        .first()
+        .ok_or_else(|| ImporterError::provider("the user", "No user with this name"))?
+        .id
+        .clone();
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    for (lot, kind) in [(MetadataLot::Anime, "anime"), (MetadataLot::Manga, "manga")] {
+        let context = format!("the {kind} library");
+        let mut request = client
+            .get("library-entries")
+            .query(&json!({
+                "filter[userId]": user_id,
+                "filter[kind]": kind,
+                "include": format!("{kind},{kind}.mappings"),
+                "page[limit]": PAGE_LIMIT,
+            }))
+            .map_err(|e| ImporterError::parse(&context, e))?;
+        loop {
+            let page: LibraryResponse = get_source_json(request, &context).await?;
+            let (items, failed) = convert_page(&page, lot, kind);
+            media.extend(items);
+            failed_items.extend(failed);
+            match page.links.next {
+                Some(next) => request = client.get(next),
+                None => break,
+            }
+        }
+    }
+    Ok(ImportResult {
+        collections: vec![],
+        media,
+        failed_items,
+        workouts: vec![],
+        ..Default::default()
+    })
+}
+
+/// The identifiers of the media on the providers that Ryot supports, in the
+/// order in which they should be tried.
+fn get_external_identifiers(
+    mappings: &[&Resource<Included>],
+    kind: &str,
+) -> Vec<MetadataExternalIdentifier> {
+    [
+        (format!("anilist/{kind}"), MetadataSource::Anilist),
+        (format!("myanimelist/{kind}"), MetadataSource::Mal),
+    ]
+    .into_iter()
+    .filter_map(|(site, source)| {
+        let mapping = mappings
+            .iter()
+            .find(|m| m.attributes.external_site.as_ref() == Some(&site))?;
+        Some(MetadataExternalIdentifier {
+            source,
+            identifier: mapping.attributes.external_id.clone()?,
+        })
+    })
+    .collect()
+}
+
+fn convert_page(
+    page: &LibraryResponse,
+    lot: MetadataLot,
+    kind: &str,
+) -> (Vec<ImportOrExportMediaItem>, Vec<ImportFailedItem>) {
+    let included = page
+        .included
+        .iter()
+        .map(|i| (&i.identifier, i))
+        .collect::<HashMap<_, _>>();
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    for entry in page.data.iter() {
+        let Some(details) = entry
+            .related(kind)
+            .first()
+            .and_then(|i| included.get(i).copied())
+        else {
+            failed_items.push(ImportFailedItem {
+                lot,
+                step: ImportFailStep::ItemDetailsFromSource,
+                identifier: entry.identifier.id.clone(),
+                error: Some("The library entry has no media".to_owned()),
+                code: ImportErrorCode::ParseError,
+                source_row: serde_json::to_string(entry).ok(),
+            });
+            continue;
+        };
+        let title = details
+            .attributes
+            .canonical_title
+            .clone()
+            .unwrap_or_else(|| details.identifier.id.clone());
+        let mappings = details
+            .related("mappings")
+            .into_iter()
+            .filter_map(|i| included.get(i).copied())
+            .collect::<Vec<_>>();
+        let external_identifiers = get_external_identifiers(&mappings, kind);
+        let Some(first) = external_identifiers.first() else {
+            failed_items.push(ImportFailedItem {
+                lot,
+                step: ImportFailStep::ItemDetailsFromSource,
+                identifier: title,
+                error: Some("The media is not mapped to AniList or MyAnimeList".to_owned()),
+                code: ImportErrorCode::MissingIdentifier,
+                source_row: serde_json::to_string(entry).ok(),
+            });
+            continue;
+        };
+        let attributes = &entry.attributes;
+        let total = match lot {
+            MetadataLot::Anime => details.attributes.episode_count,
+            _ => details.attributes.chapter_count,
+        };
+        let progress = match total {
+            Some(total) if total > 0 => Some((attributes.progress * 100 / total).min(99)),
+            _ => Some(0),
+        };
+        let in_progress = ImportOrExportMediaItemSeen {
+            progress,
+            started_on: attributes.started_at,
+            ..Default::default()
+        };
+        let mut collections = vec![];
+        let seen_history = match attributes.status.as_str() {
+            "completed" => {
+                let times = usize::try_from(attributes.reconsume_count + 1).unwrap_or(1);
+                let mut history = vec![ImportOrExportMediaItemSeen::default(); times.max(1)];
+                history[0].started_on = attributes.started_at;
+                history[0].ended_on = attributes.finished_at;
+                history
+            }
+            "current" => {
+                collections.push(DefaultCollection::InProgress.to_string());
+                vec![in_progress]
+            }
+            "on_hold" => {
+                collections.push("On Hold".to_owned());
+                vec![in_progress]
+            }
+            "dropped" => vec![ImportOrExportMediaItemSeen {
+                dropped: Some(ImportOrExportMediaItemSeenDropped::default()),
+                ..in_progress
+            }],
+            "planned" => {
+                collections.push(DefaultCollection::Watchlist.to_string());
+                vec![]
+            }
+            _ => vec![],
+        };
+        let reviews = attributes
+            .rating_twenty
+            .map(|r| ImportOrExportItemRating {
+                // DEV: Rates items out of 20
+                rating: Some(Decimal::from(r).saturating_mul(dec!(5))),
+                ..Default::default()
+            })
+            .into_iter()
+            .collect();
+        media.push(ImportOrExportMediaItem {
+            source_id: title.clone(),
+            lot,
+            source: first.source,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetailsFromAny(
+                external_identifiers,
+            )),
+            seen_history,
+            reviews,
+            collections,
+            external_identifiers: None,
+            is_favorite: None,
+            next_episode: None,
+            display_title: Some(title),
+        });
+    }
+    (media, failed_items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn library_entries_are_mapped_to_supported_providers() {
+        let page: LibraryResponse = serde_json::from_str(
+            r#"{
+                "data": [
+                    {"id": "1", "type": "libraryEntries",
+                        "attributes": {"status": "completed", "progress": 26,
+                            "reconsumeCount": 1, "ratingTwenty": 17,
+                            "startedAt": null, "finishedAt": "2020-01-02T00:00:00.000Z"},
+                        "relationships": {"anime": {"data": {"type": "anime", "id": "10"}}}},
+                    {"id": "2", "type": "libraryEntries",
+                        "attributes": {"status": "current", "progress": 3,
+                            "reconsumeCount": 0, "ratingTwenty": null,
+                            "startedAt": null, "finishedAt": null},
+                        "relationships": {"anime": {"data": {"type": "anime", "id": "11"}}}}
+                ],
+                "included": [
+                    {"id": "10", "type": "anime",
+                        "attributes": {"canonicalTitle": "Cowboy Bebop", "episodeCount": 26},
+                        "relationships": {"mappings": {"data": [
+                            {"type": "mappings", "id": "100"},
+                            {"type": "mappings", "id": "101"}
+                        ]}}},
+                    {"id": "11", "type": "anime",
+                        "attributes": {"canonicalTitle": "Unmapped", "episodeCount": 12},
+                        "relationships": {"mappings": {"data": []}}},
+                    {"id": "100", "type": "mappings",
+                        "attributes": {"externalSite": "myanimelist/anime", "externalId": "1"}},
+                    {"id": "101", "type": "mappings",
+                        "attributes": {"externalSite": "anilist/anime", "externalId": "2"}}
+                ],
+                "links": {}
+            }"#,
+        )
+        .unwrap();
+        let (media, failed_items) = convert_page(&page, MetadataLot::Anime, "anime");
+        assert_eq!(failed_items.len(), 1);
+        assert_eq!(failed_items[0].identifier, "Unmapped");
+        assert_eq!(media.len(), 1);
+        let bebop = &media[0];
+        assert_eq!(bebop.source, MetadataSource::Anilist);
+        match bebop.internal_identifier.as_ref().unwrap() {
+            ImportOrExportItemIdentifier::NeedsDetailsFromAny(ids) => {
+                let ids = ids.iter().map(|i| (i.source, i.identifier.as_str()));
+                assert_eq!(
+                    ids.collect::<Vec<_>>(),
+                    vec![(MetadataSource::Anilist, "2"), (MetadataSource::Mal, "1")]
+                );
+            }
+            i => panic!("Unexpected identifier {:?}", i),
+        }
+        assert_eq!(bebop.seen_history.len(), 2);
+        assert!(bebop.seen_history[0].ended_on.is_some());
+        assert_eq!(bebop.reviews[0].rating, Some(dec!(85)));
+    }
+}
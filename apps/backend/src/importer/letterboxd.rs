@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use database::{MetadataLot, MetadataSource};
+use rs_utils::convert_naive_to_utc;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    importer::{
+        get_csv_reader, read_csv_rows, send_provider_request, DeployLetterboxdImportInput,
+        ImportErrorCode, ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier,
+        ImportOrExportMediaItem, ImportResult, ImporterError, ImporterResult,
+    },
+    miscellaneous::DefaultCollection,
+    models::media::{
+        ImportOrExportItemRating, ImportOrExportItemReview, ImportOrExportMediaItemSeen,
+    },
+    providers::tmdb::TmdbMovieService,
+};
+
+/// A row of any of the files in the export. They all share the first four
+/// columns, the rest are only present in some of them.
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    #[serde(rename = "Date")]
+    date: NaiveDate,
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Year")]
+    year: Option<i32>,
+    #[serde(rename = "Rating", default)]
+    rating: Option<Decimal>,
+    #[serde(rename = "Review", default)]
+    review: Option<String>,
+    #[serde(rename = "Watched Date", default)]
+    watched_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ExportFile {
+    Watched,
+    Reviews,
+    Ratings,
+    Watchlist,
+}
+
+#[derive(Debug)]
+struct Film {
+    name: String,
+    year: Option<i32>,
+    item: ImportOrExportMediaItem,
+}
+
+// DEV: Letterboxd rates films out of 5, in steps of half a star
+fn convert_rating(rating: Decimal) -> Decimal {
+    rating.saturating_mul(dec!(20))
+}
+
+/// Read all the files of the export and merge the rows that refer to the same
+/// film. The films still need to be resolved to a TMDB identifier.
+fn collect_films(
+    input: DeployLetterboxdImportInput,
+    trim_csv: bool,
+) -> ImporterResult<(Vec<Film>, Vec<ImportFailedItem>)> {
+    let lot = MetadataLot::Movie;
+    let mut films: Vec<Film> = vec![];
+    let mut failed_items = vec![];
+    // DEV: Reviews are read before ratings so that a rating which also has a review
+    // is not imported twice.
+    let files = [
+        (ExportFile::Watched, Some(input.watched)),
+        (ExportFile::Reviews, input.reviews),
+        (ExportFile::Ratings, input.ratings),
+        (ExportFile::Watchlist, input.watchlist),
+    ];
+    for (file, data) in files {
+        let Some(data) = data else {
+            continue;
+        };
+        let reader = get_csv_reader(data.as_bytes(), b',', trim_csv)?;
+        let context = match file {
+            ExportFile::Watched => "the watched file",
+            ExportFile::Reviews => "the reviews file",
+            ExportFile::Ratings => "the ratings file",
+            ExportFile::Watchlist => "the watchlist file",
+        };
+        for (idx, row) in read_csv_rows::<Entry, _>(reader).enumerate() {
+            let record = match row.record {
+                Ok(r) => r,
+                Err(e) => {
+                    failed_items.push(ImportFailedItem {
+                        lot,
+                        step: ImportFailStep::InputTransformation,
+                        identifier: idx.to_string(),
+                        error: Some(ImporterError::parse(context, e).to_string()),
+                        code: ImportErrorCode::ParseError,
+                        source_row: row.raw,
+                    });
+                    continue;
+                }
+            };
+            let position = films
+                .iter()
+                .position(|f| f.name == record.name && f.year == record.year);
+            let film = match position {
+                Some(p) => &mut films[p],
+                None => {
+                    let source_id = match record.year {
+                        Some(year) => format!("{} ({})", record.name, year),
+                        None => record.name.clone(),
+                    };
+                    films.push(Film {
+                        name: record.name.clone(),
+                        year: record.year,
+                        item: ImportOrExportMediaItem {
+                            source_id,
+                            lot,
+                            source: MetadataSource::Tmdb,
+                            identifier: "".to_string(),
+                            internal_identifier: None,
+                            seen_history: vec![],
+                            reviews: vec![],
+                            collections: vec![],
+                            external_identifiers: None,
+                            is_favorite: None,
+                            next_episode: None,
+                            display_title: None,
+                        },
+                    });
+                    films.last_mut().unwrap()
+                }
+            };
+            let date = Some(convert_naive_to_utc(record.date));
+            match file {
+                ExportFile::Watched => film.item.seen_history.push(ImportOrExportMediaItemSeen {
+                    ended_on: date,
+                    ..Default::default()
+                }),
+                ExportFile::Reviews => film.item.reviews.push(ImportOrExportItemRating {
+                    rating: record.rating.map(convert_rating),
+                    review: record.review.map(|text| ImportOrExportItemReview {
+                        date: record.watched_date.map(convert_naive_to_utc).or(date),
+                        spoiler: Some(false),
+                        text: Some(text),
+                        visibility: None,
+                    }),
+                    ..Default::default()
+                }),
+                ExportFile::Ratings => {
+                    if film.item.reviews.iter().all(|r| r.rating.is_none()) {
+                        film.item.reviews.push(ImportOrExportItemRating {
+                            rating: record.rating.map(convert_rating),
+                            ..Default::default()
+                        });
+                    }
+                }
+                ExportFile::Watchlist => film
+                    .item
+                    .collections
+                    .push(DefaultCollection::Watchlist.to_string()),
+            }
+        }
+    }
+    Ok((films, failed_items))
+}
+
+pub async fn import(
+    input: DeployLetterboxdImportInput,
+    trim_csv: bool,
+    tmdb_service: &TmdbMovieService,
+) -> ImporterResult<ImportResult> {
+    let watchlist_added_on = input
+        .watchlist
+        .as_deref()
+        .map(|w| get_watchlist_added_on(w, trim_csv))
+        .transpose()?
+        .unwrap_or_default();
+    let (films, mut failed_items) = collect_films(input, trim_csv)?;
+    let total = films.len();
+    let mut media = vec![];
+    let mut collection_added_on = HashMap::new();
+    for (idx, mut film) in films.into_iter().enumerate() {
+        tracing::debug!(
+            "Getting details for {title:?} ({idx}/{total})",
+            title = film.item.source_id
+        );
+        let ids = send_provider_request(MetadataSource::Tmdb, || {
+            tmdb_service.ids_from_title(&film.name, film.year)
+        })
+        .await;
+        let ids = match ids {
+            Ok(ids) => ids,
+            Err(e) => {
+                let error = e.to_string();
+                failed_items.push(ImportFailedItem {
+                    lot: film.item.lot,
+                    step: ImportFailStep::MediaDetailsFromProvider,
+                    identifier: film.item.source_id,
+                    code: ImportErrorCode::from_provider_message(&error),
+                    error: Some(error),
+                    source_row: None,
+                });
+                continue;
+            }
+        };
+        let identifier = match ids.as_slice() {
+            [id] => id.clone(),
+            ids => {
+                let (error, code) = match ids {
+                    [] => ("Could not find the film on TMDB", ImportErrorCode::NotFound),
+                    _ => (
+                        "More than one film on TMDB has this title",
+                        ImportErrorCode::Ambiguous,
+                    ),
+                };
+                failed_items.push(ImportFailedItem {
+                    lot: film.item.lot,
+                    step: ImportFailStep::InputTransformation,
+                    identifier: film.item.source_id,
+                    error: Some(error.to_owned()),
+                    code,
+                    source_row: None,
+                });
+                continue;
+            }
+        };
+        if let Some(added_on) = watchlist_added_on.get(&(film.name, film.year)) {
+            collection_added_on.insert(
+                (
+                    DefaultCollection::Watchlist.to_string(),
+                    film.item.source_id.clone(),
+                ),
+                convert_naive_to_utc(*added_on),
+            );
+        }
+        film.item.internal_identifier =
+            Some(ImportOrExportItemIdentifier::NeedsDetails(identifier));
+        media.push(film.item);
+    }
+    Ok(ImportResult {
+        collections: vec![],
+        media,
+        failed_items,
+        workouts: vec![],
+        collection_added_on,
+        ..Default::default()
+    })
+}
+
+fn get_watchlist_added_on(
+    watchlist: &str,
+    trim_csv: bool,
+) -> ImporterResult<HashMap<(String, Option<i32>), NaiveDate>> {
+    Ok(get_csv_reader(watchlist.as_bytes(), b',', trim_csv)?
+        .into_deserialize::<Entry>()
+        .flatten()
+        .map(|e| ((e.name, e.year), e.date))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_of_the_same_film_are_merged() {
+        let input = DeployLetterboxdImportInput {
+            watched: "Date,Name,Year,Letterboxd URI\n\
+                2023-01-02,Dune,2021,https://boxd.it/1\n\
+                2023-01-03,Dune,1984,https://boxd.it/2\n"
+                .to_owned(),
+            ratings: Some(
+                "Date,Name,Year,Letterboxd URI,Rating\n\
+                2023-01-02,Dune,2021,https://boxd.it/1,4.5\n\
+                2023-01-03,Dune,1984,https://boxd.it/2,2\n"
+                    .to_owned(),
+            ),
+            reviews: Some(
+                "Date,Name,Year,Letterboxd URI,Rating,Rewatch,Review,Tags,Watched Date\n\
+                2023-01-05,Dune,2021,https://boxd.it/3,4.5,,Loud,,2023-01-02\n"
+                    .to_owned(),
+            ),
+            watchlist: Some(
+                "Date,Name,Year,Letterboxd URI\n\
+                2023-01-04,Heat,1995,https://boxd.it/4\n\
+                not a date,Alien,1979,https://boxd.it/5\n"
+                    .to_owned(),
+            ),
+        };
+        let (films, failed_items) = collect_films(input, true).unwrap();
+        assert_eq!(failed_items.len(), 1);
+        let ids = films.iter().map(|f| f.item.source_id.as_str());
+        assert_eq!(
+            ids.collect::<Vec<_>>(),
+            vec!["Dune (2021)", "Dune (1984)", "Heat (1995)"]
+        );
+        let dune = &films[0].item;
+        assert_eq!(dune.seen_history.len(), 1);
+        assert_eq!(dune.reviews.len(), 1);
+        assert_eq!(dune.reviews[0].rating, Some(dec!(90)));
+        let review = dune.reviews[0].review.as_ref().unwrap();
+        assert_eq!(review.text.as_deref(), Some("Loud"));
+        assert_eq!(
+            review.date,
+            Some(convert_naive_to_utc(
+                NaiveDate::from_ymd_opt(2023, 1, 2).unwrap()
+            ))
+        );
+        assert_eq!(films[1].item.reviews[0].rating, Some(dec!(40)));
+        assert_eq!(
+            films[2].item.collections,
+            vec![DefaultCollection::Watchlist.to_string()]
+        );
+    }
+}
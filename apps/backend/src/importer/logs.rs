@@ -0,0 +1,183 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{Mutex, OnceLock},
+};
+
+use async_graphql::SimpleObject;
+use chrono::Utc;
+use sea_orm::prelude::DateTimeUtc;
+use serde::{Deserialize, Serialize};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+/// The name of the span that the logs of an import job are captured in. It
+/// must have a `job_id` field.
+pub const IMPORT_JOB_SPAN: &str = "import_job";
+
+/// An event that was logged while an import job was running.
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct ImportJobLog {
+    pub logged_on: DateTimeUtc,
+    pub level: String,
+    /// The module that emitted the event.
+    pub target: String,
+    pub message: String,
+}
+
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Default, Eq, PartialEq, Clone)]
+pub struct ImportJobLogs {
+    pub entries: Vec<ImportJobLog>,
+    /// The number of events that were not retained in `entries`.
+    pub overflow: usize,
+}
+
+#[derive(Debug)]
+struct CapturedLogs {
+    max_entries: usize,
+    logs: ImportJobLogs,
+}
+
+fn captured_logs() -> &'static Mutex<HashMap<i32, CapturedLogs>> {
+    static LOGS: OnceLock<Mutex<HashMap<i32, CapturedLogs>>> = OnceLock::new();
+    LOGS.get_or_init(Default::default)
+}
+
+/// Start capturing the logs of the import job with the given id. Only the
+/// first `max_entries` events are retained, the rest are only counted.
+pub fn start_capturing(job_id: i32, max_entries: usize) {
+    captured_logs().lock().unwrap().insert(
+        job_id,
+        CapturedLogs {
+            max_entries,
+            logs: ImportJobLogs::default(),
+        },
+    );
+}
+
+/// The logs captured so far for an import job that is still running.
+pub fn get_captured(job_id: i32) -> Option<ImportJobLogs> {
+    let logs = captured_logs().lock().unwrap();
+    logs.get(&job_id).map(|c| c.logs.clone())
+}
+
+/// Stop capturing the logs of an import job and return the ones that were
+/// captured.
+pub fn finish_capturing(job_id: i32) -> ImportJobLogs {
+    captured_logs()
+        .lock()
+        .unwrap()
+        .remove(&job_id)
+        .map(|c| c.logs)
+        .unwrap_or_default()
+}
+
+struct JobId(i32);
+
+#[derive(Default)]
+struct JobIdVisitor(Option<i32>);
+
+impl Visit for JobIdVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        if field.name() == "job_id" {
+            self.0 = i32::try_from(value).ok();
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn Debug) {}
+}
+
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.0 = value.to_owned();
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+/// Captures the events that are emitted inside an import job span so that they
+/// can be stored in the report of the job.
+pub struct ImportJobLogLayer;
+
+impl<S> Layer<S> for ImportJobLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != IMPORT_JOB_SPAN {
+            return;
+        }
+        let mut visitor = JobIdVisitor::default();
+        attrs.record(&mut visitor);
+        if let (Some(job_id), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(JobId(job_id));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(job_id) = ctx.event_scope(event).and_then(|mut scope| {
+            scope.find_map(|span| span.extensions().get::<JobId>().map(|j| j.0))
+        }) else {
+            return;
+        };
+        let mut logs = captured_logs().lock().unwrap();
+        let Some(captured) = logs.get_mut(&job_id) else {
+            return;
+        };
+        if captured.logs.entries.len() >= captured.max_entries {
+            captured.logs.overflow += 1;
+            return;
+        }
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        captured.logs.entries.push(ImportJobLog {
+            logged_on: Utc::now(),
+            level: event.metadata().level().to_string(),
+            target: event.metadata().target().to_owned(),
+            message: visitor.0,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn only_events_inside_the_job_span_are_captured() {
+        let subscriber = tracing_subscriber::registry().with(ImportJobLogLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            start_capturing(1, 2);
+            tracing::warn!("Before the job");
+            tracing::info_span!(IMPORT_JOB_SPAN, job_id = 1).in_scope(|| {
+                tracing::warn!("Could not find item {}", 10);
+                tracing::debug_span!("nested").in_scope(|| tracing::error!("Failed"));
+                tracing::info!("Dropped");
+            });
+            tracing::warn!("After the job");
+        });
+        let logs = finish_capturing(1);
+        let messages = logs.entries.iter().map(|l| l.message.as_str());
+        assert_eq!(
+            messages.collect::<Vec<_>>(),
+            vec!["Could not find item 10", "Failed"]
+        );
+        assert_eq!(logs.entries[0].level, "WARN");
+        assert_eq!(logs.overflow, 1);
+        assert_eq!(finish_capturing(1), ImportJobLogs::default());
+    }
+}
@@ -1,9 +1,8 @@
 use std::{
-    fs::File,
-    io::{BufReader, Read},
+    collections::{HashMap, HashSet},
+    io::Read,
 };
 
-use async_graphql::Result;
 use database::{MetadataLot, MetadataSource};
 use flate2::bufread::GzDecoder;
 use rs_utils::{convert_naive_to_utc, convert_string_to_date};
@@ -13,22 +12,47 @@ use sea_orm::prelude::DateTimeUtc;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{
-    importer::{DeployMalImportInput, ImportResult},
+    file_storage::FileStorageService,
+    importer::{
+        read_uploaded_file, send_provider_request, DeployMalImportInput, ImportResult,
+        ImporterError, ImporterResult,
+    },
+    miscellaneous::DefaultCollection,
     models::media::{
         ImportOrExportItemIdentifier, ImportOrExportItemRating, ImportOrExportMediaItem,
         ImportOrExportMediaItemSeen,
     },
+    providers::mal::MalAnimeService,
 };
 
-fn decode_data<T>(path: &str) -> Result<T>
+/// The first bytes of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Read the contents of an export, which is gzip compressed when downloaded from
+/// MyAnimeList but might have been decompressed by the user.
+fn decompress(data: &[u8]) -> std::io::Result<String> {
+    let mut string_data = String::new();
+    let mut reader = data;
+    if data.starts_with(&GZIP_MAGIC) {
+        GzDecoder::new(data).read_to_string(&mut string_data)?;
+    } else {
+        reader.read_to_string(&mut string_data)?;
+    }
+    Ok(string_data)
+}
+
+async fn decode_data<T>(
+    file_storage_service: &FileStorageService,
+    user_id: i32,
+    path: &str,
+) -> ImporterResult<T>
 where
     T: DeserializeOwned,
 {
-    let data = BufReader::new(File::open(path)?);
-    let mut decoder = GzDecoder::new(data);
-    let mut string_data = String::new();
-    decoder.read_to_string(&mut string_data)?;
-    let deserialized = serde_xml_rs::from_str::<T>(&string_data)?;
+    let error = |e: &dyn std::fmt::Display| ImporterError::parse(format!("{path:?}"), e);
+    let data = read_uploaded_file(file_storage_service, user_id, path).await?;
+    let string_data = decompress(&data).map_err(|e| error(&e))?;
+    let deserialized = serde_xml_rs::from_str::<T>(&string_data).map_err(|e| error(&e))?;
     Ok(deserialized)
 }
 
@@ -42,15 +66,23 @@ fn get_date(date: String) -> Option<DateTimeUtc> {
 
 fn convert_to_format(item: Item, lot: MetadataLot) -> ImportOrExportMediaItem {
     let progress = if item.done != 0 && item.total != 0 {
-        Some(item.done / item.total)
+        Some(item.done * 100 / item.total)
     } else {
         None
     };
-    let seen_item = ImportOrExportMediaItemSeen {
-        started_on: get_date(item.my_start_date),
-        ended_on: get_date(item.my_finish_date),
-        progress,
-        ..Default::default()
+    // DEV: Items that are planned have not been started, so they only need to be
+    // added to the watchlist
+    let is_planned = ["Plan to Watch", "Plan to Read"].contains(&item.my_status.as_str());
+    let (seen_history, collections) = if is_planned {
+        (vec![], vec![DefaultCollection::Watchlist.to_string()])
+    } else {
+        let seen_item = ImportOrExportMediaItemSeen {
+            started_on: get_date(item.my_start_date),
+            ended_on: get_date(item.my_finish_date),
+            progress,
+            ..Default::default()
+        };
+        (vec![seen_item], vec![])
     };
     let review_item = ImportOrExportItemRating {
         review: None,
@@ -62,24 +94,85 @@ fn convert_to_format(item: Item, lot: MetadataLot) -> ImportOrExportMediaItem {
         ..Default::default()
     };
     ImportOrExportMediaItem {
-        source_id: item.title,
+        source_id: item.title.clone(),
         lot,
         source: MetadataSource::Mal,
         identifier: "".to_string(),
         internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails(
             item.identifier.to_string(),
         )),
-        seen_history: vec![seen_item],
+        seen_history,
         reviews: vec![review_item],
-        collections: vec![],
+        collections,
+        external_identifiers: None,
+        is_favorite: None,
+        next_episode: None,
+        display_title: Some(item.title),
+    }
+}
+
+/// Get the identifier of the first entry of the franchise that this anime belongs
+/// to by following its prequels. The franchise of every anime that is visited is
+/// cached, so that the other anime of the same franchise need fewer requests.
+async fn get_franchise_identifier(
+    anime_service: &MalAnimeService,
+    franchises: &mut HashMap<String, String>,
+    identifier: String,
+) -> anyhow::Result<String> {
+    let mut visited = vec![];
+    let mut current = identifier;
+    let franchise = loop {
+        if let Some(franchise) = franchises.get(&current) {
+            break franchise.clone();
+        }
+        if visited.contains(&current) {
+            break current;
+        }
+        visited.push(current.clone());
+        let prequel = send_provider_request(MetadataSource::Mal, || {
+            anime_service.franchise_prequel(&current)
+        })
+        .await?;
+        match prequel {
+            Some(prequel) => current = prequel,
+            None => break current,
+        }
+    };
+    for identifier in visited {
+        franchises.insert(identifier, franchise.clone());
     }
+    Ok(franchise)
 }
 
-pub async fn import(input: DeployMalImportInput) -> Result<ImportResult> {
-    let anime_data = decode_data::<DataRoot>(&input.anime_path)?;
-    let manga_data = decode_data::<DataRoot>(&input.manga_path)?;
+pub async fn import(
+    user_id: i32,
+    input: DeployMalImportInput,
+    file_storage_service: &FileStorageService,
+    anime_service: &MalAnimeService,
+) -> ImporterResult<ImportResult> {
+    let anime_data =
+        decode_data::<DataRoot>(file_storage_service, user_id, &input.anime_path).await?;
+    let manga_data =
+        decode_data::<DataRoot>(file_storage_service, user_id, &input.manga_path).await?;
+    let build_franchise_groups = input.build_franchise_groups.unwrap_or_default();
     let mut media = vec![];
+    let mut franchises = HashSet::new();
+    let mut franchise_cache = HashMap::new();
     for item in anime_data.items.into_iter() {
+        if build_franchise_groups {
+            match get_franchise_identifier(
+                anime_service,
+                &mut franchise_cache,
+                item.identifier.to_string(),
+            )
+            .await
+            {
+                Ok(franchise) => {
+                    franchises.insert(franchise);
+                }
+                Err(e) => tracing::debug!("Could not resolve franchise: {:?}", e),
+            }
+        }
         media.push(convert_to_format(item, MetadataLot::Anime));
     }
     for item in manga_data.items.into_iter() {
@@ -90,6 +183,11 @@ pub async fn import(input: DeployMalImportInput) -> Result<ImportResult> {
         failed_items: vec![],
         media,
         workouts: vec![],
+        metadata_groups: franchises
+            .into_iter()
+            .map(|f| (MetadataLot::Anime, MetadataSource::Mal, f))
+            .collect(),
+        ..Default::default()
     })
 }
 
@@ -112,4 +210,48 @@ struct Item {
     my_start_date: String,
     my_finish_date: String,
     my_score: u32,
+    #[serde(default)]
+    my_status: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    #[test]
+    fn exports_are_read_with_or_without_compression() {
+        let xml = "<myanimelist></myanimelist>";
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(xml.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert_eq!(decompress(&compressed).unwrap(), xml);
+        assert_eq!(decompress(xml.as_bytes()).unwrap(), xml);
+    }
+
+    #[test]
+    fn planned_items_are_added_to_the_watchlist() {
+        let item = |status: &str| Item {
+            identifier: 1,
+            title: "Cowboy Bebop".to_owned(),
+            total: 26,
+            done: 13,
+            my_start_date: "0000-00-00".to_owned(),
+            my_finish_date: "0000-00-00".to_owned(),
+            my_score: 0,
+            my_status: status.to_owned(),
+        };
+        let planned = convert_to_format(item("Plan to Watch"), MetadataLot::Anime);
+        assert!(planned.seen_history.is_empty());
+        assert_eq!(
+            planned.collections,
+            vec![DefaultCollection::Watchlist.to_string()]
+        );
+        let watching = convert_to_format(item("Watching"), MetadataLot::Anime);
+        assert_eq!(watching.seen_history[0].progress, Some(50));
+        assert!(watching.collections.is_empty());
+    }
 }
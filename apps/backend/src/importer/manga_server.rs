@@ -0,0 +1,355 @@
+use chrono::{Datelike, NaiveDate};
+use database::{MetadataLot, MetadataSource};
+use http_types::mime;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use rust_decimal_macros::dec;
+use sea_orm::prelude::DateTimeUtc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use surf::http::headers::{ACCEPT, AUTHORIZATION};
+
+use crate::{
+    importer::{
+        get_source_json, DeployMangaServerImportInput, ImportErrorCode, ImportFailStep,
+        ImportFailedItem, ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportResult,
+        ImporterError, ImporterResult,
+    },
+    models::media::{ImportOrExportMediaItemSeen, MediaSearchItem},
+    traits::MediaProvider,
+    utils::get_base_http_client,
+};
+
+/// The number of series requested from the server at once.
+const PAGE_SIZE: usize = 100;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Server {
+    Komga,
+    Kavita,
+}
+
+/// A series on the server along with how much of it has been read. The parts are
+/// books for Komga and pages for Kavita.
+#[derive(Debug)]
+struct Series {
+    title: String,
+    year: Option<i32>,
+    read_parts: i32,
+    total_parts: i32,
+    last_read_on: Option<DateTimeUtc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KomgaSeriesMetadata {
+    title: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KomgaBooksMetadata {
+    release_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KomgaSeries {
+    metadata: KomgaSeriesMetadata,
+    books_metadata: KomgaBooksMetadata,
+    books_count: i32,
+    books_read_count: i32,
+    books_in_progress_count: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct KomgaPage {
+    content: Vec<KomgaSeries>,
+    last: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct KavitaUser {
+    token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KavitaSeries {
+    id: i32,
+    name: String,
+    localized_name: Option<String>,
+    pages: i32,
+    pages_read: i32,
+    latest_read_date: Option<DateTimeUtc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KavitaSeriesMetadata {
+    release_year: Option<i32>,
+}
+
+async fn get_komga_series(input: &DeployMangaServerImportInput) -> ImporterResult<Vec<Series>> {
+    let client = get_base_http_client(
+        &format!("{}/api/v1/", input.url.trim_end_matches('/')),
+        vec![("X-API-Key", input.api_key.clone())],
+    );
+    let mut series = vec![];
+    for page in 0.. {
+        let rsp: KomgaPage = get_source_json(
+            client.get(format!(
+                "series?read_status=READ&read_status=IN_PROGRESS&page={page}&size={PAGE_SIZE}"
+            )),
+            "the series",
+        )
+        .await?;
+        series.extend(rsp.content.into_iter().map(|s| Series {
+            title: s.metadata.title,
+            year: s.books_metadata.release_date.map(|d| d.year()),
+            // DEV: A book that has been started counts as half read, so that the
+            // series is in progress even if no book was finished.
+            read_parts: s.books_read_count * 2 + s.books_in_progress_count,
+            total_parts: s.books_count * 2,
+            last_read_on: None,
+        }));
+        if rsp.last {
+            break;
+        }
+    }
+    Ok(series)
+}
+
+async fn get_kavita_series(input: &DeployMangaServerImportInput) -> ImporterResult<Vec<Series>> {
+    let url = format!("{}/api/", input.url.trim_end_matches('/'));
+    let user: KavitaUser = get_source_json(
+        get_base_http_client(&url, vec![(ACCEPT, mime::JSON)]).post(format!(
+            "Plugin/authenticate?apiKey={}&pluginName=Ryot",
+            input.api_key
+        )),
+        "the user",
+    )
+    .await?;
+    let client = get_base_http_client(
+        &url,
+        vec![(AUTHORIZATION, format!("Bearer {}", user.token))],
+    );
+    let mut series = vec![];
+    for page in 1.. {
+        let request = client
+            .post(format!(
+                "Series/all-v2?PageNumber={page}&PageSize={PAGE_SIZE}"
+            ))
+            .body_json(&json!({ "statements": [], "combination": 1, "limitTo": 0 }))
+            .map_err(|e| ImporterError::parse("the series", e))?;
+        let rsp: Vec<KavitaSeries> = get_source_json(request, "the series").await?;
+        let is_last = rsp.len() < PAGE_SIZE;
+        for s in rsp.into_iter().filter(|s| s.pages_read > 0) {
+            let context = format!("the metadata of the series {}", s.id);
+            let metadata: KavitaSeriesMetadata = get_source_json(
+                client.get(format!("Series/metadata?seriesId={}", s.id)),
+                &context,
+            )
+            .await?;
+            series.push(Series {
+                title: s.localized_name.filter(|n| !n.is_empty()).unwrap_or(s.name),
+                // DEV: Kavita uses 0 when the year is not known
+                year: metadata.release_year.filter(|y| *y > 0),
+                read_parts: s.pages_read,
+                total_parts: s.pages,
+                last_read_on: s.latest_read_date,
+            });
+        }
+        if is_last {
+            break;
+        }
+    }
+    Ok(series)
+}
+
+fn normalize_title(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Find the search result with the same title as the series. The year is only
+/// used to pick between results with the same title.
+fn match_series(
+    series: &Series,
+    results: &[MediaSearchItem],
+) -> Result<String, (&'static str, ImportErrorCode)> {
+    let title = normalize_title(&series.title);
+    let matching = results
+        .iter()
+        .filter(|r| normalize_title(&r.title) == title)
+        .collect::<Vec<_>>();
+    let same_year = matching
+        .iter()
+        .filter(|r| series.year.is_some() && r.publish_year == series.year)
+        .collect::<Vec<_>>();
+    let candidates = match same_year.is_empty() {
+        true => matching.iter().collect(),
+        false => same_year,
+    };
+    match candidates.as_slice() {
+        [result] => Ok(result.identifier.clone()),
+        [] => Err((
+            "Could not find the series on the provider",
+            ImportErrorCode::NotFound,
+        )),
+        _ => Err((
+            "More than one series on the provider has this title",
+            ImportErrorCode::Ambiguous,
+        )),
+    }
+}
+
+/// Series that have been read completely are finished, the rest are in progress.
+fn get_seen(series: &Series) -> ImportOrExportMediaItemSeen {
+    let progress = match series.read_parts >= series.total_parts {
+        true => None,
+        false => {
+            let percent = Decimal::from(series.read_parts) * dec!(100)
+                / Decimal::from(series.total_parts.max(1));
+            Some(percent.floor().to_i32().unwrap_or_default().clamp(1, 99))
+        }
+    };
+    ImportOrExportMediaItemSeen {
+        progress,
+        ended_on: series.last_read_on.filter(|_| progress.is_none()),
+        ..Default::default()
+    }
+}
+
+pub async fn import(
+    server: Server,
+    input: DeployMangaServerImportInput,
+    provider: &(dyn MediaProvider + Send + Sync),
+) -> ImporterResult<ImportResult> {
+    let lot = MetadataLot::Manga;
+    let source = input.source.unwrap_or(MetadataSource::Anilist);
+    let series = match server {
+        Server::Komga => get_komga_series(&input).await?,
+        Server::Kavita => get_kavita_series(&input).await?,
+    };
+    let total = series.len();
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    for (idx, series) in series.into_iter().enumerate() {
+        tracing::debug!(
+            "Getting details for {title:?} ({idx}/{total})",
+            title = series.title
+        );
+        let mapping = input
+            .mappings
+            .iter()
+            .flatten()
+            .find(|m| normalize_title(&m.series) == normalize_title(&series.title));
+        let identifier = match mapping {
+            Some(m) => Ok(m.identifier.clone()),
+            None => match provider.search(&series.title, None, true).await {
+                Ok(results) => match_series(&series, &results.items)
+                    .map_err(|(error, code)| (error.to_owned(), code)),
+                Err(e) => Err((e.to_string(), ImportErrorCode::ProviderError)),
+            },
+        };
+        let identifier = match identifier {
+            Ok(i) => i,
+            Err((error, code)) => {
+                failed_items.push(ImportFailedItem {
+                    lot,
+                    step: ImportFailStep::InputTransformation,
+                    identifier: series.title,
+                    error: Some(error),
+                    code,
+                    source_row: None,
+                });
+                continue;
+            }
+        };
+        media.push(ImportOrExportMediaItem {
+            source_id: series.title.clone(),
+            lot,
+            source,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails(identifier)),
+            seen_history: vec![get_seen(&series)],
+            reviews: vec![],
+            collections: vec![],
+            external_identifiers: None,
+            is_favorite: None,
+            next_episode: None,
+            display_title: None,
+        });
+    }
+    Ok(ImportResult {
+        collections: vec![],
+        media,
+        failed_items,
+        workouts: vec![],
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_series(title: &str, year: Option<i32>, read_parts: i32) -> Series {
+        Series {
+            title: title.to_owned(),
+            year,
+            read_parts,
+            total_parts: 20,
+            last_read_on: None,
+        }
+    }
+
+    fn get_result(identifier: &str, title: &str, year: i32) -> MediaSearchItem {
+        MediaSearchItem {
+            identifier: identifier.to_owned(),
+            title: title.to_owned(),
+            image: None,
+            publish_year: Some(year),
+        }
+    }
+
+    #[test]
+    fn series_are_matched_by_title_and_year() {
+        let results = vec![
+            get_result("1", "Berserk", 1989),
+            get_result("2", "Berserk: The Prototype", 1988),
+            get_result("3", "Hunter x Hunter", 1998),
+            get_result("4", "Hunter x Hunter", 2011),
+        ];
+        assert_eq!(
+            match_series(&get_series("berserk", None, 1), &results),
+            Ok("1".to_owned())
+        );
+        assert_eq!(
+            match_series(&get_series("Hunter x Hunter", Some(1998), 1), &results),
+            Ok("3".to_owned())
+        );
+        assert_eq!(
+            match_series(&get_series("Hunter x Hunter", None, 1), &results)
+                .unwrap_err()
+                .1,
+            ImportErrorCode::Ambiguous
+        );
+        assert_eq!(
+            match_series(&get_series("Vagabond", None, 1), &results)
+                .unwrap_err()
+                .1,
+            ImportErrorCode::NotFound
+        );
+    }
+
+    #[test]
+    fn partly_read_series_are_in_progress() {
+        assert_eq!(get_seen(&get_series("Monster", None, 20)).progress, None);
+        assert_eq!(get_seen(&get_series("Monster", None, 5)).progress, Some(25));
+        assert_eq!(get_seen(&get_series("Monster", None, 0)).progress, Some(1));
+    }
+}
@@ -1,21 +1,22 @@
-use async_graphql::Result;
-
 use crate::{
-    importer::{DeployMediaJsonImportInput, ImportResult},
-    models::media::{ImportOrExportItemIdentifier, ImportOrExportMediaItem},
+    importer::{
+        get_exported_item_identifier, DeployMediaJsonImportInput, ImportResult, ImporterError,
+        ImporterResult,
+    },
+    models::media::ImportOrExportMediaItem,
 };
 
-pub async fn import(input: DeployMediaJsonImportInput) -> Result<ImportResult> {
-    let mut media = serde_json::from_str::<Vec<ImportOrExportMediaItem>>(&input.export).unwrap();
-    media.iter_mut().for_each(|m| {
-        m.internal_identifier = Some(ImportOrExportItemIdentifier::NeedsDetails(
-            m.identifier.clone(),
-        ))
-    });
+pub async fn import(input: DeployMediaJsonImportInput) -> ImporterResult<ImportResult> {
+    let mut media = serde_json::from_str::<Vec<ImportOrExportMediaItem>>(&input.export)
+        .map_err(|e| ImporterError::parse("the export", e))?;
+    media
+        .iter_mut()
+        .for_each(|m| m.internal_identifier = Some(get_exported_item_identifier(m)));
     Ok(ImportResult {
         collections: vec![],
         media,
         failed_items: vec![],
         workouts: vec![],
+        ..Default::default()
     })
 }
@@ -1,8 +1,7 @@
 // Responsible for importing from https://github.com/bonukai/MediaTracker.
 
-use async_graphql::Result;
 use database::{MetadataLot, MetadataSource, Visibility};
-use rust_decimal::Decimal;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
 use rust_decimal_macros::dec;
 use sea_orm::prelude::DateTimeUtc;
 use serde::{Deserialize, Serialize};
@@ -12,14 +11,15 @@ use uuid::Uuid;
 
 use crate::{
     importer::{
-        DeployMediaTrackerImportInput, ImportFailStep, ImportFailedItem, ImportOrExportMediaItem,
-        ImportResult,
+        get_source_json, DeployMediaTrackerImportInput, ImportFailStep, ImportFailedItem,
+        ImportOrExportMediaItem, ImportResult, ImporterError, ImporterResult,
     },
+    miscellaneous::DefaultCollection,
     models::{
         media::{
             BookSpecifics, CreateOrUpdateCollectionInput, ImportOrExportItemIdentifier,
             ImportOrExportItemRating, ImportOrExportItemReview, ImportOrExportMediaItemSeen,
-            MediaDetails, MediaSpecifics, MetadataFreeCreator,
+            MediaDetails, MediaSpecifics, MetadataFreeCreator, SeenShowExtraInformation,
         },
         IdObject,
     },
@@ -65,6 +65,19 @@ struct ListResponse {
     items: Vec<ListItemResponse>,
     description: Option<String>,
     privacy: ListPrivacy,
+    #[serde(default)]
+    is_watchlist: bool,
+}
+
+impl ListResponse {
+    /// The watchlist of MediaTracker is imported into the one of Ryot, and the
+    /// custom lists into collections with the same name.
+    fn collection_name(&self) -> String {
+        match self.is_watchlist {
+            true => DefaultCollection::Watchlist.to_string(),
+            false => self.name.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -134,35 +147,55 @@ struct ItemDetails {
     overview: Option<String>,
     authors: Option<Vec<String>>,
     number_of_pages: Option<ItemNumberOfPages>,
+    #[serde(default)]
+    first_unwatched_episode: Option<ItemEpisode>,
+    /// The fraction of the item that was consumed, if it is in progress.
+    #[serde(default)]
+    progress: Option<Decimal>,
 }
 
-pub async fn import(input: DeployMediaTrackerImportInput) -> Result<ImportResult> {
+/// The seen item for an item that is in progress, if it is.
+fn get_in_progress_seen(progress: Option<Decimal>) -> Option<ImportOrExportMediaItemSeen> {
+    let progress = (progress? * dec!(100)).floor().to_i32()?;
+    (1..100)
+        .contains(&progress)
+        .then(|| ImportOrExportMediaItemSeen {
+            progress: Some(progress),
+            ..Default::default()
+        })
+}
+
+pub async fn import(input: DeployMediaTrackerImportInput) -> ImporterResult<ImportResult> {
     let client: Client = Config::new()
         .add_header(USER_AGENT, USER_AGENT_STR)
         .unwrap()
         .add_header("Access-Token", input.api_key)
-        .unwrap()
-        .set_base_url(Url::parse(&format!("{}/api/", input.api_url)).unwrap())
+        .map_err(|e| ImporterError::auth("the user", e))?
+        .set_base_url(
+            Url::parse(&format!("{}/api/", input.api_url))
+                .map_err(|e| ImporterError::parse("the API URL", e))?,
+        )
         .try_into()
         .unwrap();
 
-    let mut rsp = client.get("user").await.unwrap();
-    let data: IdObject = rsp.body_json().await.unwrap();
+    let data: IdObject = get_source_json(client.get("user"), "the user").await?;
 
     let user_id: i32 = data.id;
 
-    let mut rsp = client
-        .get("lists")
-        .query(&serde_json::json!({ "userId": user_id }))
-        .unwrap()
-        .await
-        .unwrap();
-    let mut lists: Vec<ListResponse> = rsp.body_json().await.unwrap();
+    let mut lists: Vec<ListResponse> = get_source_json(
+        client
+            .get("lists")
+            .query(&serde_json::json!({ "userId": user_id }))
+            .unwrap(),
+        "the lists",
+    )
+    .await?;
 
     let all_collections = lists
         .iter()
+        .filter(|l| !l.is_watchlist)
         .map(|l| CreateOrUpdateCollectionInput {
-            name: l.name.clone(),
+            name: l.collection_name(),
             description: l.description.as_ref().and_then(|s| match s.as_str() {
                 "" => None,
                 x => Some(x.to_owned()),
@@ -175,21 +208,21 @@ pub async fn import(input: DeployMediaTrackerImportInput) -> Result<ImportResult
         })
         .collect();
     for list in lists.iter_mut() {
-        let mut rsp = client
-            .get("list/items")
-            .query(&serde_json::json!({ "listId": list.id }))
-            .unwrap()
-            .await
-            .unwrap();
-        let items: Vec<ListItemResponse> = rsp.body_json().await.unwrap();
+        let items: Vec<ListItemResponse> = get_source_json(
+            client
+                .get("list/items")
+                .query(&serde_json::json!({ "listId": list.id }))
+                .unwrap(),
+            &format!("the items of list {:?}", list.name),
+        )
+        .await?;
         list.items = items;
     }
 
     let mut failed_items = vec![];
 
     // all items returned here are seen atleast once
-    let mut rsp = client.get("items").await.unwrap();
-    let mut data: Vec<Item> = rsp.body_json().await.unwrap();
+    let mut data: Vec<Item> = get_source_json(client.get("items"), "the items").await?;
 
     // There are a few items that are added to lists but have not been seen, so will
     // add them manually.
@@ -211,8 +244,12 @@ pub async fn import(input: DeployMediaTrackerImportInput) -> Result<ImportResult
     // batches. Example: https://users.rust-lang.org/t/can-tokio-semaphore-be-used-to-limit-spawned-tasks/59899.
     for (idx, d) in data.into_iter().enumerate() {
         let lot = MetadataLot::from(d.media_type.clone());
-        let mut rsp = client.get(format!("details/{}", d.id)).await.unwrap();
-        let details: ItemDetails = match rsp.body_json().await {
+        let details: ItemDetails = match get_source_json(
+            client.get(format!("details/{}", d.id)),
+            &format!("the details of item {}", d.id),
+        )
+        .await
+        {
             Ok(s) => s,
             Err(e) => {
                 tracing::error!("Encountered error for id = {id:?}: {e:?}", id = d.id);
@@ -221,6 +258,8 @@ pub async fn import(input: DeployMediaTrackerImportInput) -> Result<ImportResult
                     step: ImportFailStep::ItemDetailsFromSource,
                     identifier: d.id.to_string(),
                     error: Some(e.to_string()),
+                    code: e.code(),
+                    source_row: serde_json::to_string(&d).ok(),
                 });
                 continue;
             }
@@ -254,11 +293,16 @@ pub async fn import(input: DeployMediaTrackerImportInput) -> Result<ImportResult
         for list in lists.iter() {
             for item in list.items.iter() {
                 if item.media_item.id == d.id {
-                    collections.push(list.name.clone());
+                    collections.push(list.collection_name());
                 }
             }
         }
 
+        let in_progress = get_in_progress_seen(details.progress);
+        if in_progress.is_some() {
+            collections.push(DefaultCollection::InProgress.to_string());
+        }
+
         let num_pages = details.number_of_pages.and_then(|d| match d {
             ItemNumberOfPages::Nothing(_) => None,
             ItemNumberOfPages::Something(s) => Some(s),
@@ -309,6 +353,7 @@ pub async fn import(input: DeployMediaTrackerImportInput) -> Result<ImportResult
                         date: None,
                         spoiler: Some(false),
                         text: r.review,
+                        visibility: None,
                     })
                 } else {
                     None
@@ -341,7 +386,17 @@ pub async fn import(input: DeployMediaTrackerImportInput) -> Result<ImportResult
                         ..Default::default()
                     }
                 })
+                .chain(in_progress)
                 .collect(),
+            external_identifiers: None,
+            is_favorite: None,
+            next_episode: details
+                .first_unwatched_episode
+                .map(|e| SeenShowExtraInformation {
+                    season: e.season_number,
+                    episode: e.episode_number,
+                }),
+            display_title: None,
         };
         final_data.push(item);
     }
@@ -350,5 +405,22 @@ pub async fn import(input: DeployMediaTrackerImportInput) -> Result<ImportResult
         failed_items,
         collections: all_collections,
         workouts: vec![],
+        ..Default::default()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_progress_is_imported() {
+        assert_eq!(
+            get_in_progress_seen(Some(dec!(0.456))).and_then(|s| s.progress),
+            Some(45)
+        );
+        assert!(get_in_progress_seen(Some(dec!(1))).is_none());
+        assert!(get_in_progress_seen(Some(dec!(0))).is_none());
+        assert!(get_in_progress_seen(None).is_none());
+    }
+}
@@ -1,42 +1,93 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    io::{BufRead, BufReader, Read},
+    mem,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration as StdDuration,
+};
 
-use apalis::prelude::Storage;
-use async_graphql::{Context, Enum, InputObject, Object, Result, SimpleObject};
-use chrono::{Duration, Utc};
-use database::{ImportSource, MetadataLot};
+use apalis::{cron::Schedule, prelude::Storage};
+use async_graphql::{Context, Enum, Error, InputObject, Object, Result, SimpleObject};
+use chrono::{Duration, NaiveDate, Utc};
+use database::{
+    ExerciseLevel, ExerciseLot, ExerciseSource, ImportSource, MetadataLot, MetadataSource,
+    SeenState, Visibility,
+};
+use futures::{stream, StreamExt};
 use itertools::Itertools;
+use rust_decimal::{Decimal, RoundingStrategy};
 use rust_decimal_macros::dec;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, FromJsonQueryResult, QueryFilter,
-    QueryOrder,
+    prelude::DateTimeUtc, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DbErr,
+    EntityTrait, FromJsonQueryResult, QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
 };
-use serde::{Deserialize, Serialize};
-use tracing::instrument;
+use sea_query::Query;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use surf::{RequestBuilder, Response, StatusCode};
+use tokio::{
+    sync::mpsc,
+    time::{sleep, timeout},
+};
+use tracing::{instrument, Instrument};
+use uuid::Uuid;
 
 use crate::{
     background::ApplicationJob,
-    entities::{import_report, prelude::ImportReport, user::UserWithOnlyPreferences},
+    entities::{
+        collection, collection_to_entity, exercise, import_report, metadata, metadata_group,
+        person,
+        prelude::{
+            Collection, CollectionToEntity, ImportReport, Metadata, MetadataGroup, Person, Review,
+            ScheduledImport, Seen, Workout,
+        },
+        reading_goal, review, scheduled_import, seen,
+        user::{self, UserWithOnlyPreferences},
+        user_measurement, workout,
+    },
+    exporter::get_exports_prefix,
+    file_storage::{get_user_uploads_prefix, FileStorageService},
     fitness::resolver::ExerciseService,
-    miscellaneous::resolver::MiscellaneousService,
+    miscellaneous::{resolver::MiscellaneousService, DefaultCollection},
     models::{
-        fitness::UserWorkoutInput,
+        fitness::{ExerciseAttributes, ImportOrExportExerciseItem, UserWorkoutInput},
         media::{
             ChangeCollectionToEntityInput, CreateOrUpdateCollectionInput,
-            ImportOrExportItemIdentifier, ImportOrExportMediaItem, PostReviewInput,
-            ProgressUpdateInput,
+            ImportOrExportItemIdentifier, ImportOrExportItemRating, ImportOrExportMediaItem,
+            ImportOrExportMediaItemSeen, ImportOrExportMetadataGroupItem, ImportOrExportPersonItem,
+            MediaSpecifics, MetadataExternalIdentifier, PostReviewInput, ProgressUpdateInput,
+            ProgressUpdateResultUnion, SeenOrReviewOrCalendarEventExtraInformation,
         },
-        EntityLot,
+        EntityLot, IdObject,
     },
     traits::AuthProvider,
-    users::UserReviewScale,
-    utils::partial_user_by_id,
+    users::{UserPreferences, UserReviewScale},
+    utils::{partial_user_by_id, user_by_id},
 };
 
+use self::logs::{ImportJobLogs, IMPORT_JOB_SPAN};
+
+mod anilist;
+mod apple_health;
+mod audiobookshelf;
+mod body_measurements;
+mod generic_csv;
 mod goodreads;
+mod imdb;
+mod kitsu;
+mod letterboxd;
+pub mod logs;
 mod mal;
+mod manga_server;
 mod media_json;
 mod media_tracker;
 mod movary;
+mod rate_limit;
+mod ryot;
+mod serializd;
+mod steam;
 mod story_graph;
 mod strong_app;
 mod trakt;
@@ -49,40 +100,132 @@ pub struct DeployMediaTrackerImportInput {
     api_key: String,
 }
 
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct DeployAudiobookshelfImportInput {
+    // The URL of the Audiobookshelf server.
+    url: String,
+    // The API token of the user on the server.
+    api_key: String,
+    /// Only import the listening sessions since the last successful import from
+    /// the server, instead of the progress of every book.
+    incremental: Option<bool>,
+}
+
 #[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
 pub struct DeployGoodreadsImportInput {
     // The RSS url that can be found from the user's profile
-    rss_url: String,
+    rss_url: Option<String>,
+    /// The contents of `goodreads_library_export.csv`. It has all the books of the
+    /// user, so it is used instead of the RSS feed if both are given.
+    csv: Option<String>,
+    /// The CSV contents of the yearly reading goals of the user.
+    reading_challenges: Option<String>,
 }
 
 #[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
 pub struct DeployTraktImportInput {
-    // The public username in Trakt.
-    username: String,
+    // The public username in Trakt. Not needed if an access token is given.
+    username: Option<String>,
+    /// An OAuth access token of the user, to import private accounts and the
+    /// items hidden from their progress.
+    access_token: Option<String>,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct DeployAnilistImportInput {
+    // An access token of the AniList account, which can be used to read its lists.
+    access_token: String,
 }
 
 #[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
 pub struct DeployMovaryImportInput {
     // The CSV contents of the history file.
-    history: String,
+    history: Option<String>,
     // The CSV contents of the ratings file.
-    ratings: String,
+    ratings: Option<String>,
     // The CSV contents of the watchlist file.
-    watchlist: String,
+    watchlist: Option<String>,
+    /// The url of the Movary instance, to import using its API instead of the
+    /// exported files.
+    api_url: Option<String>,
+    /// The name of the user on the instance. Required when using the API.
+    username: Option<String>,
+    /// An API token of the user, needed if their profile is not public.
+    api_token: Option<String>,
 }
 
 #[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
 pub struct DeployMalImportInput {
-    /// The anime export file path (uploaded via temporary upload).
+    /// The path of the anime export returned by the `/upload` endpoint, or its key
+    /// if it was uploaded using `presignedPutS3Url`.
     anime_path: String,
-    /// The manga export file path (uploaded via temporary upload).
+    /// The path (or key) of the manga export, uploaded the same way.
     manga_path: String,
+    /// Whether to group the imported anime by franchise using their related
+    /// anime on MyAnimeList.
+    build_franchise_groups: Option<bool>,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct DeployImdbImportInput {
+    // The CSV contents of the ratings file.
+    ratings: Option<String>,
+    // The CSV contents of the watchlist file.
+    watchlist: Option<String>,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct DeployKitsuImportInput {
+    // The public username in Kitsu.
+    username: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct DeployLetterboxdImportInput {
+    // The CSV contents of the watched file.
+    watched: String,
+    // The CSV contents of the ratings file.
+    ratings: Option<String>,
+    // The CSV contents of the reviews file.
+    reviews: Option<String>,
+    // The CSV contents of the watchlist file.
+    watchlist: Option<String>,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct MangaServerImportMapping {
+    /// The title of the series on the server.
+    series: String,
+    /// The identifier of the manga on the provider.
+    identifier: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct DeployMangaServerImportInput {
+    /// The URL of the Komga or Kavita server.
+    url: String,
+    /// An API key of the user on the server.
+    api_key: String,
+    /// The provider used to find the series. Defaults to Anilist.
+    source: Option<MetadataSource>,
+    /// The series which can not be found on the provider using their title.
+    mappings: Option<Vec<MangaServerImportMapping>>,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct DeploySerializdImportInput {
+    // The CSV contents of the diary file.
+    diary: String,
+    // The CSV contents of the watchlist file.
+    watchlist: Option<String>,
 }
 
 #[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
 pub struct DeployStoryGraphImportInput {
     // The CSV contents of the export file.
     export: String,
+    /// The CSV contents of the yearly reading goals of the user.
+    reading_challenges: Option<String>,
 }
 
 #[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
@@ -91,11 +234,149 @@ pub struct StrongAppImportMapping {
     target_name: String,
 }
 
+/// An exercise present in a Strong export along with the exercise that it most
+/// likely corresponds to.
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct StrongAppExportExercise {
+    source_name: String,
+    target_name: Option<String>,
+}
+
+/// How many of the exercise rows in a Strong export would be mapped to an
+/// exercise using a given mapping.
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct StrongAppMappingCoverage {
+    total_rows: usize,
+    mapped_rows: usize,
+    unmapped_rows: usize,
+    /// The percentage of rows that would be mapped, rounded to two decimals.
+    mapped_percentage: Decimal,
+    /// The distinct names of the exercises that would not be mapped, in the
+    /// order in which they first appear.
+    unmapped_names: Vec<String>,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct DeployAppleHealthImportInput {
+    /// The path of the `export.xml` file of a Health export, or of a CSV file of
+    /// workouts from Health Auto Export, returned by the `/upload` endpoint. It can
+    /// also be its key if it was uploaded using `presignedPutS3Url`.
+    export_path: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct DeploySteamImportInput {
+    /// The Steam ID of the user, or the custom name in the URL of their profile.
+    steam_id: String,
+    /// A Steam Web API key from https://steamcommunity.com/dev/apikey.
+    api_key: String,
+}
+
 #[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
 pub struct DeployStrongAppImportInput {
-    // The path to the CSV file in the local file system.
+    /// The path of the CSV export returned by the `/upload` endpoint, or its key
+    /// if it was uploaded using `presignedPutS3Url`.
     export_path: String,
-    mapping: Vec<StrongAppImportMapping>,
+    /// The exercises that the exercises in the export correspond to. Exercises
+    /// that are not mapped are matched to the one with the same (or the most
+    /// similar) name.
+    mapping: Option<Vec<StrongAppImportMapping>>,
+    /// Create a custom exercise for every exercise in the export that could not
+    /// be matched. Its type is inferred from the statistics of its sets. Defaults
+    /// to `false`, in which case the sets of those exercises are not imported.
+    create_missing_exercises: Option<bool>,
+    /// Whether supersets should be detected from the labels in the export.
+    /// Defaults to `true`.
+    detect_supersets: Option<bool>,
+    /// Prepended to the photo filenames in the export to get the keys of the
+    /// uploaded files. For eg: `uploads/strong/`.
+    assets_prefix: Option<String>,
+    /// The names of the exercises in the export that are used to log bodyweight.
+    /// Their rows are imported as measurements instead of workout sets.
+    bodyweight_exercise_names: Option<Vec<String>>,
+    /// What to do with workouts that have no duration or are longer than
+    /// `max_workout_duration`. Defaults to `Clamp`.
+    duration_handling: Option<WorkoutDurationHandling>,
+    /// The longest plausible duration of a workout in hours, between 1 and 48.
+    /// Defaults to 6.
+    max_workout_duration: Option<i64>,
+}
+
+/// What to do with an imported workout whose duration is implausible.
+#[derive(Debug, Enum, PartialEq, Eq, Copy, Clone, Serialize, Deserialize, Default)]
+pub enum WorkoutDurationHandling {
+    /// The workout is not imported.
+    Reject,
+    /// Workouts without a duration are assumed to take two minutes per set and
+    /// longer ones are shortened to the maximum duration.
+    #[default]
+    Clamp,
+    /// The workout is imported as it is.
+    Accept,
+}
+
+/// The names of the columns of a CSV file which hold the fields of the media.
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct GenericCsvColumnMapping {
+    /// The title of the media. Used to identify the rows in the report.
+    title: String,
+    /// The identifier of the media on its provider.
+    provider_id: String,
+    /// The type of the media, for eg: `Movie` or `Audio Book`.
+    lot: Option<String>,
+    /// The provider of the media, for eg: `Tmdb` or `Openlibrary`.
+    source: Option<String>,
+    /// The rating of the media, on the scale given in the input.
+    rating: Option<String>,
+    /// The date on which the media was consumed, in the `YYYY-MM-DD` format.
+    watched_on: Option<String>,
+    /// The collection that the media should be added to.
+    collection: Option<String>,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct DeployGenericCsvImportInput {
+    // The CSV contents of the file.
+    csv: String,
+    mapping: GenericCsvColumnMapping,
+    /// The type of the media in rows which do not have a column for it.
+    lot: Option<MetadataLot>,
+    /// The provider of the media in rows which do not have a column for it.
+    source: Option<MetadataSource>,
+    /// The highest possible rating in the file, for eg: `5` or `10`. Defaults to
+    /// `100`.
+    rating_scale: Option<Decimal>,
+}
+
+/// The layout of a CSV file of body measurements.
+#[derive(Debug, Enum, PartialEq, Eq, Copy, Clone, Serialize, Deserialize, Default)]
+pub enum BodyMeasurementsFormat {
+    /// Any CSV file, read using the columns given in the input.
+    #[default]
+    Generic,
+    /// The `weight.csv` file of a Withings data export.
+    Withings,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct DeployBodyMeasurementsImportInput {
+    // The CSV contents of the file.
+    csv: String,
+    /// Defaults to `Generic`.
+    format: Option<BodyMeasurementsFormat>,
+    /// The column which holds the date (and time) of the measurements in a generic
+    /// file. Defaults to `Date`.
+    date_column: Option<String>,
+    /// The `chrono` format of the dates in a generic file, for eg: `%m-%d-%Y %H:%M`.
+    /// By default ISO 8601 dates with an optional time are accepted. Dates are in
+    /// UTC unless they have an offset.
+    date_format: Option<String>,
+    /// The statistic held by each column of a generic file, for eg:
+    /// `{"Weight (kg)": "weight", "Fat %": "body_fat"}`. Statistics which are not
+    /// known to Ryot are saved as custom statistics.
+    columns: Option<HashMap<String, String>>,
+    /// The column which holds a comment about the measurements in a generic file.
+    comment_column: Option<String>,
 }
 
 #[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
@@ -104,6 +385,16 @@ pub struct DeployMediaJsonImportInput {
     export: String,
 }
 
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+pub struct DeployRyotImportInput {
+    /// The path of the JSON export returned by the `/upload` endpoint, or its key
+    /// if it was uploaded using `presignedPutS3Url`.
+    export_path: String,
+    /// Do not apply the preferences from the export if the user has already changed
+    /// their own. Defaults to `false`.
+    keep_customized_preferences: Option<bool>,
+}
+
 #[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
 pub struct DeployImportJobInput {
     pub source: ImportSource,
@@ -111,10 +402,105 @@ pub struct DeployImportJobInput {
     pub goodreads: Option<DeployGoodreadsImportInput>,
     pub trakt: Option<DeployTraktImportInput>,
     pub movary: Option<DeployMovaryImportInput>,
+    pub letterboxd: Option<DeployLetterboxdImportInput>,
+    pub imdb: Option<DeployImdbImportInput>,
     pub mal: Option<DeployMalImportInput>,
+    pub anilist: Option<DeployAnilistImportInput>,
+    pub kitsu: Option<DeployKitsuImportInput>,
+    pub audiobookshelf: Option<DeployAudiobookshelfImportInput>,
+    pub generic_csv: Option<DeployGenericCsvImportInput>,
+    pub body_measurements: Option<DeployBodyMeasurementsImportInput>,
     pub story_graph: Option<DeployStoryGraphImportInput>,
+    pub serializd: Option<DeploySerializdImportInput>,
     pub strong_app: Option<DeployStrongAppImportInput>,
+    pub apple_health: Option<DeployAppleHealthImportInput>,
+    pub steam: Option<DeploySteamImportInput>,
+    pub manga_server: Option<DeployMangaServerImportInput>,
     pub media_json: Option<DeployMediaJsonImportInput>,
+    pub ryot: Option<DeployRyotImportInput>,
+    /// The visibility of the collections created during the import. Collections
+    /// whose visibility is provided by the source are not affected. Defaults to
+    /// private.
+    pub collection_visibility: Option<Visibility>,
+    /// The number of seconds after which fetching the details of a single item is
+    /// abandoned and it is marked as failed. Defaults to the server configuration.
+    pub item_timeout: Option<u64>,
+    /// Rename collections (or statuses) from the source to the given names. For
+    /// eg: `{"on-hold": "Paused"}`. Unmapped collections keep their original names.
+    pub status_collection_map: Option<HashMap<String, String>>,
+    /// Use the air date of the episode as the date of seen history items which do
+    /// not have one. Defaults to `false`, in which case the time of import is used.
+    pub use_air_date_for_undated: Option<bool>,
+    /// Whether a rating of exactly zero in the source means that the item was not
+    /// rated. Defaults to `true`, in which case such ratings are not imported.
+    pub zero_rating_is_unrated: Option<bool>,
+    /// Recalculate the summary of the user before the import is marked as finished
+    /// instead of doing it in the background. Defaults to `false`.
+    pub recalculate_summary_synchronously: Option<bool>,
+    /// The people to record as having consumed the media along with the user, for
+    /// seen history items whose source does not record it.
+    pub watched_with: Option<Vec<String>>,
+    /// The identity used to detect items which have already been imported. Defaults
+    /// to `ProviderId`.
+    pub dedup_key: Option<ImportDedupKey>,
+    /// How seen history items are matched against the existing ones of the user to
+    /// skip duplicates. Defaults to `STRICT`. Not used if the `dedupKey` is
+    /// `SOURCE_EVENT_ID`.
+    pub seen_match: Option<ImportSeenMatch>,
+    /// Only import media items of these types. Defaults to importing all of them.
+    pub lots: Option<Vec<MetadataLot>>,
+    /// Remove the whitespace around the headers and fields of CSV files before
+    /// they are read. Defaults to `true`. A leading byte order mark is always removed.
+    pub trim_csv_whitespace: Option<bool>,
+}
+
+impl DeployImportJobInput {
+    /// Whether the data is fetched from a remote source every time the job runs,
+    /// instead of being read from the contents of files in the input.
+    fn is_remote(&self) -> bool {
+        match self.source {
+            ImportSource::MediaTracker => self.media_tracker.is_some(),
+            ImportSource::Audiobookshelf => self.audiobookshelf.is_some(),
+            ImportSource::Trakt => self.trakt.is_some(),
+            ImportSource::Anilist => self.anilist.is_some(),
+            ImportSource::Kitsu => self.kitsu.is_some(),
+            ImportSource::Steam => self.steam.is_some(),
+            ImportSource::Komga | ImportSource::Kavita => self.manga_server.is_some(),
+            ImportSource::Goodreads => self
+                .goodreads
+                .as_ref()
+                .map_or(false, |g| g.rss_url.is_some() && g.csv.is_none()),
+            ImportSource::Movary => self.movary.as_ref().map_or(false, |m| m.api_url.is_some()),
+            _ => false,
+        }
+    }
+}
+
+/// The identity used to detect items which have already been imported.
+#[derive(Debug, Enum, PartialEq, Eq, Copy, Clone, Serialize, Deserialize, Default)]
+pub enum ImportDedupKey {
+    /// A completed seen history item is skipped if the user has already seen the
+    /// same media from the same provider on the same date (and episode or part).
+    #[default]
+    ProviderId,
+    /// Like `ProviderId`, but media is identified by its type, title and publish
+    /// year, so media that was seen using another provider also matches.
+    TitleAndYear,
+    /// A media item is skipped entirely if it was committed by an earlier
+    /// successful import from the same source.
+    SourceEventId,
+}
+
+/// How a completed seen history item is matched against the existing ones of the
+/// user to detect duplicates.
+#[derive(Debug, Enum, PartialEq, Eq, Copy, Clone, Serialize, Deserialize, Default)]
+pub enum ImportSeenMatch {
+    /// The dates on which the items were finished must be the same.
+    #[default]
+    Strict,
+    /// The dates are only compared if both items have one. An item without a date
+    /// matches any finished item of the same media (and episode or part).
+    Lenient,
 }
 
 /// The various steps in which media importing can fail
@@ -140,19 +526,445 @@ pub struct ImportFailedItem {
     step: ImportFailStep,
     identifier: String,
     error: Option<String>,
+    /// The category of the error, to decide how it can be remedied.
+    #[serde(default)]
+    code: ImportErrorCode,
+    /// The row (or object) of the source the item was read from, if available.
+    #[serde(default)]
+    source_row: Option<String>,
+}
+
+/// The categories of errors which can stop an item from being imported.
+#[derive(Debug, Enum, PartialEq, Eq, Copy, Clone, Serialize, Deserialize, Default)]
+pub enum ImportErrorCode {
+    /// The category is not known. Used for items of older imports.
+    #[default]
+    Unknown,
+    /// The data of the item in the source could not be read.
+    ParseError,
+    /// Items of this type can not be imported.
+    Unsupported,
+    /// The item has no identifier that can be used to find it on a provider.
+    MissingIdentifier,
+    /// The item could not be found on the provider.
+    NotFound,
+    /// More than one media on the provider matched the item.
+    Ambiguous,
+    /// The provider rejected the request because too many were made.
+    ProviderRateLimited,
+    /// The provider could not be reached or returned an error.
+    ProviderError,
+    /// Fetching the details of the item took too long.
+    Timeout,
+    /// The item could not be saved to the database.
+    SaveError,
+}
+
+impl ImportErrorCode {
+    /// Guess the category of an error returned by a provider from its message.
+    fn from_provider_message(message: &str) -> Self {
+        let message = message.to_lowercase();
+        if ["429", "too many requests", "rate limit"]
+            .iter()
+            .any(|m| message.contains(m))
+        {
+            Self::ProviderRateLimited
+        } else if ["404", "not found"].iter().any(|m| message.contains(m)) {
+            Self::NotFound
+        } else {
+            Self::ProviderError
+        }
+    }
 }
 
 #[derive(Debug, SimpleObject, Serialize, Deserialize, Eq, PartialEq, Clone)]
 pub struct ImportDetails {
     pub total: usize,
+    /// The number of items that were skipped because they already existed.
+    #[serde(default)]
+    pub skipped: usize,
+    /// The number of seen history items that were skipped because they already
+    /// existed.
+    #[serde(default)]
+    pub skipped_seen_history: usize,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ImportResult {
     collections: Vec<CreateOrUpdateCollectionInput>,
     media: Vec<ImportOrExportMediaItem>,
     failed_items: Vec<ImportFailedItem>,
     workouts: Vec<UserWorkoutInput>,
+    people: Vec<ImportOrExportPersonItem>,
+    measurements: Vec<user_measurement::Model>,
+    // DEV: Workouts that have already been processed and need to be committed as is.
+    completed_workouts: Vec<workout::Model>,
+    exercises: Vec<ImportOrExportExerciseItem>,
+    preferences: Option<UserPreferences>,
+    reading_goals: Vec<reading_goal::Model>,
+    // DEV: Groups that need to be associated once the media has been committed.
+    metadata_groups: Vec<(MetadataLot, MetadataSource, String)>,
+    metadata_groups_reviewed: Vec<ImportOrExportMetadataGroupItem>,
+    // DEV: The position of media in the collections, keyed by the name of the
+    // collection and the source id of the media.
+    collection_ranks: HashMap<(String, String), i32>,
+    // DEV: When media was added to the collections in the source, keyed like
+    // `collection_ranks`.
+    collection_added_on: HashMap<(String, String), DateTimeUtc>,
+    warnings: Vec<String>,
+    // DEV: The identifiers of the events in the source (for eg: listening sessions)
+    // that a media item was made from, keyed by the source id of the media.
+    source_event_ids: HashMap<String, Vec<String>>,
+}
+
+/// The events of a source that were committed by earlier imports, and when the
+/// latest of those imports was started.
+#[derive(Debug, Default)]
+pub struct ImportedSourceEvents {
+    pub since: Option<DateTimeUtc>,
+    pub ids: HashSet<String>,
+}
+
+/// The errors that can occur while getting the data to import from a source.
+#[derive(Debug)]
+pub enum ImporterError {
+    /// The data from the source could not be read or understood.
+    ParseError { context: String, message: String },
+    /// The source could not be reached or is temporarily unavailable.
+    NetworkError { context: String, message: String },
+    /// The source rejected the credentials that were provided.
+    AuthError { context: String, message: String },
+    /// The source or a metadata provider (for eg: Openlibrary, MAL) rejected the
+    /// request.
+    ProviderError { context: String, message: String },
+    /// The database could not be queried.
+    DatabaseError { context: String, message: String },
+}
+
+pub type ImporterResult<T> = std::result::Result<T, ImporterError>;
+
+impl ImporterError {
+    pub fn parse(context: impl Into<String>, message: impl fmt::Display) -> Self {
+        Self::ParseError {
+            context: context.into(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn network(context: impl Into<String>, message: impl fmt::Display) -> Self {
+        Self::NetworkError {
+            context: context.into(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn auth(context: impl Into<String>, message: impl fmt::Display) -> Self {
+        Self::AuthError {
+            context: context.into(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn provider(context: impl Into<String>, message: impl fmt::Display) -> Self {
+        Self::ProviderError {
+            context: context.into(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn database(context: impl Into<String>, message: impl fmt::Display) -> Self {
+        Self::DatabaseError {
+            context: context.into(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Whether the error is likely to go away if the import is attempted again.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::NetworkError { .. } | Self::DatabaseError { .. })
+    }
+
+    /// The code to record for an item that failed because of this error.
+    pub fn code(&self) -> ImportErrorCode {
+        match self {
+            Self::ParseError { .. } => ImportErrorCode::ParseError,
+            Self::DatabaseError { .. } => ImportErrorCode::SaveError,
+            Self::NetworkError { message, .. }
+            | Self::AuthError { message, .. }
+            | Self::ProviderError { message, .. } => {
+                ImportErrorCode::from_provider_message(message)
+            }
+        }
+    }
+}
+
+// DEV: This also converts it to a GraphQL error at the boundary, since
+// `async_graphql::Error` can be created from anything that implements `Display`.
+impl fmt::Display for ImporterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ParseError { context, message } => {
+                write!(f, "Could not parse {context}: {message}")
+            }
+            Self::NetworkError { context, message } => {
+                write!(f, "Could not fetch {context}: {message}")
+            }
+            Self::AuthError { context, message } => {
+                write!(f, "Not authorized to fetch {context}: {message}")
+            }
+            Self::ProviderError { context, message } => {
+                write!(f, "Request for {context} was rejected: {message}")
+            }
+            Self::DatabaseError { context, message } => {
+                write!(f, "Could not query {context}: {message}")
+            }
+        }
+    }
+}
+
+/// The directory that the `/upload` endpoint writes the uploaded files to.
+const TEMPORARY_UPLOADS_DIR: &str = "tmp";
+
+/// Get the directory of the temporary uploads that a user can import from.
+pub fn get_user_temporary_uploads_dir(user_id: i32) -> PathBuf {
+    PathBuf::from(TEMPORARY_UPLOADS_DIR).join(user_id.to_string())
+}
+
+/// The minimum time between two runs of a scheduled import, in minutes.
+const MIN_SCHEDULED_IMPORT_INTERVAL: i64 = 60;
+
+/// A file that was uploaded for an import. The ones in the file storage are
+/// downloaded to the temporary uploads, and removed once they have been read.
+enum UploadedFile {
+    Local(PathBuf),
+    Downloaded(PathBuf),
+}
+
+impl UploadedFile {
+    /// Find a file that was uploaded for an import. It can be the path returned
+    /// by the `/upload` endpoint, or the key of an object uploaded by the user to
+    /// the file storage using the URL from `presignedPutS3Url` (or one of their
+    /// exports). Paths outside the temporary uploads of the user and keys of other
+    /// users are rejected.
+    async fn find(
+        file_storage_service: &FileStorageService,
+        user_id: i32,
+        path: &str,
+    ) -> ImporterResult<Self> {
+        let context = format!("the uploaded file {path:?}");
+        if let Some(path) = get_temporary_upload_path(user_id, path).await {
+            return Ok(Self::Local(path));
+        }
+        if !is_user_upload_key(user_id, path) {
+            return Err(ImporterError::auth(
+                context,
+                "It is not in the temporary uploads or the uploads of the user",
+            ));
+        }
+        tokio::fs::create_dir_all(TEMPORARY_UPLOADS_DIR)
+            .await
+            .map_err(|e| ImporterError::parse(context.clone(), e))?;
+        // DEV: The value is created before downloading so that a partially written
+        // file is removed when the download fails.
+        let downloaded =
+            Self::Downloaded(PathBuf::from(TEMPORARY_UPLOADS_DIR).join(Uuid::new_v4().to_string()));
+        file_storage_service
+            .download_object(path.to_owned(), downloaded.path())
+            .await
+            .map_err(|e| ImporterError::network(context, e))?;
+        Ok(downloaded)
+    }
+
+    fn path(&self) -> &Path {
+        match self {
+            Self::Local(path) | Self::Downloaded(path) => path,
+        }
+    }
+
+    fn open(&self) -> ImporterResult<Box<dyn BufRead + Send + '_>> {
+        let path = self.path();
+        let file = std::fs::File::open(path)
+            .map_err(|e| ImporterError::parse(format!("the uploaded file {path:?}"), e))?;
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+impl Drop for UploadedFile {
+    fn drop(&mut self) {
+        if let Self::Downloaded(path) = self {
+            std::fs::remove_file(path).ok();
+        }
+    }
+}
+
+/// Get the canonical path of a file in the temporary uploads of a user. Paths
+/// which lead outside of them (for eg: using `..` or symbolic links) are not
+/// returned.
+async fn get_temporary_upload_path(user_id: i32, path: &str) -> Option<PathBuf> {
+    let uploads_dir = tokio::fs::canonicalize(get_user_temporary_uploads_dir(user_id))
+        .await
+        .ok()?;
+    let path = tokio::fs::canonicalize(path).await.ok()?;
+    let is_file = tokio::fs::metadata(&path).await.ok()?.is_file();
+    (is_file && path.starts_with(&uploads_dir)).then_some(path)
+}
+
+/// Whether the key is of an object in the file storage that the user uploaded or
+/// exported.
+fn is_user_upload_key(user_id: i32, key: &str) -> bool {
+    let prefixes = [
+        get_user_uploads_prefix(user_id),
+        get_exports_prefix(user_id),
+    ];
+    prefixes.iter().any(|p| key.starts_with(p.as_str()))
+        && !key.split('/').any(|s| s == ".." || s == ".")
+}
+
+/// Create a custom exercise for an exercise of the source which the user does not
+/// have yet.
+async fn create_imported_exercise(
+    exercise_service: &ExerciseService,
+    user_id: i32,
+    name: &str,
+    lot: ExerciseLot,
+) -> Result<i32> {
+    let exercise = exercise::Model {
+        id: 0,
+        name: name.to_owned(),
+        identifier: None,
+        lot,
+        level: ExerciseLevel::Beginner,
+        force: None,
+        mechanic: None,
+        equipment: None,
+        source: ExerciseSource::Custom,
+        muscles: vec![],
+        attributes: ExerciseAttributes {
+            instructions: vec![],
+            internal_images: vec![],
+            images: vec![],
+        },
+        created_by_user_id: None,
+    };
+    let created = exercise_service
+        .create_custom_exercise(user_id, exercise)
+        .await?;
+    tracing::debug!("Created exercise {name:?} with id = {}", created.id);
+    Ok(created.id)
+}
+
+/// Read the complete contents of a file that was uploaded for an import.
+async fn read_uploaded_file(
+    file_storage_service: &FileStorageService,
+    user_id: i32,
+    path: &str,
+) -> ImporterResult<Vec<u8>> {
+    let file = UploadedFile::find(file_storage_service, user_id, path).await?;
+    let path = file.path();
+    tokio::fs::read(path)
+        .await
+        .map_err(|e| ImporterError::parse(format!("the uploaded file {path:?}"), e))
+}
+
+/// Send a request to a provider while the items of a source are being read. The
+/// requests share the rate limit of the provider with the rest of the import,
+/// and are retried if the provider rejects them for being too frequent.
+async fn send_provider_request<T, F, Fut>(
+    provider: MetadataSource,
+    mut request: F,
+) -> anyhow::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<T>>,
+{
+    let limits = rate_limit::get_limits();
+    let mut attempt = 0;
+    loop {
+        rate_limit::acquire(&provider.to_string()).await;
+        match request().await {
+            Err(e)
+                if attempt < limits.max_retries
+                    && ImportErrorCode::from_provider_message(&e.to_string())
+                        == ImportErrorCode::ProviderRateLimited =>
+            {
+                let wait = rate_limit::get_backoff(limits.backoff, attempt);
+                tracing::debug!("Provider {provider} rate limited a request, retrying in {wait:?}");
+                sleep(wait).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Send a request to the source and make sure that it succeeded. Requests to
+/// the same host are rate limited, and retried if the source rejects them for
+/// being too frequent.
+async fn send_source_request(request: RequestBuilder, context: &str) -> ImporterResult<Response> {
+    let rsp = request
+        .middleware(rate_limit::SourceRateLimit)
+        .await
+        .map_err(|e| ImporterError::network(context, e))?;
+    let status = rsp.status();
+    match status {
+        StatusCode::Unauthorized | StatusCode::Forbidden => {
+            Err(ImporterError::auth(context, status))
+        }
+        s if s.is_server_error() || s == StatusCode::TooManyRequests => {
+            Err(ImporterError::network(context, status))
+        }
+        s if !s.is_success() => Err(ImporterError::provider(context, status)),
+        _ => Ok(rsp),
+    }
+}
+
+/// Send a request to the source and deserialize its JSON response.
+async fn get_source_json<T: DeserializeOwned>(
+    request: RequestBuilder,
+    context: &str,
+) -> ImporterResult<T> {
+    send_source_request(request, context)
+        .await?
+        .body_json()
+        .await
+        .map_err(|e| ImporterError::parse(context, e))
+}
+
+impl ImportResult {
+    fn map_collection_names(&mut self, map: &HashMap<String, String>) {
+        let rename = |name: &mut String| {
+            if let Some(mapped) = map.get(name) {
+                *name = mapped.to_owned();
+            }
+        };
+        self.collections
+            .iter_mut()
+            .for_each(|c| rename(&mut c.name));
+        self.media
+            .iter_mut()
+            .flat_map(|m| m.collections.iter_mut())
+            .for_each(rename);
+        self.people
+            .iter_mut()
+            .flat_map(|p| p.collections.iter_mut())
+            .for_each(rename);
+        self.collection_ranks = self
+            .collection_ranks
+            .drain()
+            .map(|((mut name, source_id), rank)| {
+                rename(&mut name);
+                ((name, source_id), rank)
+            })
+            .collect();
+        self.collection_added_on = self
+            .collection_added_on
+            .drain()
+            .map(|((mut name, source_id), added_on)| {
+                rename(&mut name);
+                ((name, source_id), added_on)
+            })
+            .collect();
+    }
 }
 
 #[derive(
@@ -161,6 +973,255 @@ pub struct ImportResult {
 pub struct ImportResultResponse {
     pub import: ImportDetails,
     pub failed_items: Vec<ImportFailedItem>,
+    /// The number of failed items that were not retained in `failed_items`.
+    #[serde(default)]
+    pub failed_items_overflow: usize,
+    /// The reason the import could not be completed, if it failed.
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Problems that did not stop the import but might need attention.
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// The media items that were present in the source.
+    #[serde(default)]
+    #[graphql(skip)]
+    pub items: Vec<ImportedItem>,
+    /// The number of media items that were not retained in `items`.
+    #[serde(default)]
+    #[graphql(skip)]
+    pub items_overflow: usize,
+    /// The type and identifier in the source of the media items that were
+    /// committed. These are skipped by later imports deduplicated by
+    /// `SourceEventId`, so unlike `items` they are never truncated.
+    #[serde(default)]
+    #[graphql(skip)]
+    pub committed_items: Vec<(MetadataLot, String)>,
+    /// The identifiers of the events in the source, for eg: listening sessions,
+    /// that the committed items were made from. Later imports skip these.
+    #[serde(default)]
+    #[graphql(skip)]
+    pub source_event_ids: Vec<String>,
+    /// When the changes made by the import were reverted.
+    #[serde(default)]
+    pub reverted_on: Option<DateTimeUtc>,
+    /// The media items whose details could not be fetched from the provider.
+    #[serde(default)]
+    #[graphql(skip)]
+    pub retry: Option<ImportRetry>,
+    /// The events that were logged while the import was running.
+    #[serde(default)]
+    #[graphql(skip)]
+    pub logs: ImportJobLogs,
+    /// The changes to existing data that reverting the import has to undo.
+    #[serde(default)]
+    #[graphql(skip)]
+    pub undo: ImportUndo,
+}
+
+/// How far along the media items of a running import are.
+#[derive(
+    Debug, SimpleObject, Serialize, Deserialize, FromJsonQueryResult, Eq, PartialEq, Clone,
+)]
+pub struct ImportProgress {
+    /// The number of media items that have been processed.
+    pub processed: usize,
+    /// The number of media items that will be processed.
+    pub total: usize,
+    /// The identifier in the source of the media item being processed.
+    pub current_item: Option<String>,
+    /// When the import is expected to finish, based on the time taken by the
+    /// items processed so far.
+    pub estimated_finish_on: Option<DateTimeUtc>,
+    /// When the progress was recorded.
+    #[serde(default)]
+    pub updated_on: Option<DateTimeUtc>,
+}
+
+impl ImportProgress {
+    fn new(
+        processed: usize,
+        total: usize,
+        current_item: Option<String>,
+        started_on: DateTimeUtc,
+        now: DateTimeUtc,
+    ) -> Self {
+        let estimated_finish_on = (processed > 0).then(|| {
+            let elapsed = (now - started_on).num_milliseconds();
+            let remaining =
+                (total.saturating_sub(processed) as i64).saturating_mul(elapsed / processed as i64);
+            now + Duration::milliseconds(remaining)
+        });
+        Self {
+            processed,
+            total,
+            current_item,
+            estimated_finish_on,
+            updated_on: Some(now),
+        }
+    }
+}
+
+/// The state of a media item in the source at the time it was imported.
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct ImportedItem {
+    pub lot: MetadataLot,
+    /// The identifier of the item in the source.
+    pub identifier: String,
+    /// The highest progress in the seen history of the item.
+    pub progress: Option<i32>,
+    /// The latest rating given to the item.
+    pub rating: Option<Decimal>,
+}
+
+impl From<&ImportOrExportMediaItem> for ImportedItem {
+    fn from(item: &ImportOrExportMediaItem) -> Self {
+        Self {
+            lot: item.lot,
+            identifier: item.source_id.clone(),
+            progress: item
+                .seen_history
+                .iter()
+                .map(|s| s.progress.unwrap_or(100))
+                .max(),
+            rating: item.reviews.iter().rev().find_map(|r| r.rating),
+        }
+    }
+}
+
+/// A media item of an import, stored so that it can be imported again without
+/// reading the source.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct ImportRetryableItem {
+    /// The identifiers to fetch the details with, in the order they are tried.
+    identifiers: Vec<MetadataExternalIdentifier>,
+    // DEV: Only set for items whose details were provided by the source.
+    #[serde(default)]
+    details: Option<serde_json::Value>,
+    // DEV: `ImportOrExportMediaItem` does not implement `Eq`, so it is stored as
+    // JSON. This drops the internal identifier, hence `identifiers` and `details`.
+    item: serde_json::Value,
+}
+
+impl ImportRetryableItem {
+    fn new(item: &ImportOrExportMediaItem) -> Option<Self> {
+        let (identifiers, details) = match item.internal_identifier.as_ref()? {
+            ImportOrExportItemIdentifier::NeedsDetails(i) => {
+                let identifier = MetadataExternalIdentifier {
+                    source: item.source,
+                    identifier: i.clone(),
+                };
+                (vec![identifier], None)
+            }
+            ImportOrExportItemIdentifier::NeedsDetailsFromAny(ids) => (ids.clone(), None),
+            ImportOrExportItemIdentifier::AlreadyFilled(d) => {
+                (vec![], Some(serde_json::to_value(d).ok()?))
+            }
+        };
+        Some(Self {
+            identifiers,
+            details,
+            item: serde_json::to_value(item).ok()?,
+        })
+    }
+
+    fn into_item(self) -> Option<ImportOrExportMediaItem> {
+        let mut item: ImportOrExportMediaItem = serde_json::from_value(self.item).ok()?;
+        item.internal_identifier = Some(match (self.details, self.identifiers.as_slice()) {
+            (Some(details), _) => {
+                ImportOrExportItemIdentifier::AlreadyFilled(serde_json::from_value(details).ok()?)
+            }
+            (None, [i]) if i.source == item.source => {
+                ImportOrExportItemIdentifier::NeedsDetails(i.identifier.clone())
+            }
+            (None, _) => ImportOrExportItemIdentifier::NeedsDetailsFromAny(self.identifiers),
+        });
+        Some(item)
+    }
+}
+
+/// The data that an import changed or removed without creating it, as it was
+/// before the import.
+#[derive(Debug, Default, Serialize, Deserialize, FromJsonQueryResult, Eq, PartialEq, Clone)]
+pub struct ImportUndo {
+    /// The entries removed from the "Watchlist" and "In Progress" collections.
+    removed_collection_entries: Vec<collection_to_entity::Model>,
+    /// The seen items that were in progress and were updated.
+    updated_seen: Vec<seen::Model>,
+}
+
+/// What is needed to import media items again, without the data from the source.
+#[derive(Debug, Serialize, Deserialize, FromJsonQueryResult, Eq, PartialEq, Clone)]
+pub struct ImportRetry {
+    // DEV: Stored as JSON for the same reason as `ImportRetryableItem::item`.
+    input: serde_json::Value,
+    items: Vec<ImportRetryableItem>,
+}
+
+impl ImportRetry {
+    fn new(input: &DeployImportJobInput, items: Vec<ImportRetryableItem>) -> Option<Self> {
+        if items.is_empty() {
+            return None;
+        }
+        // DEV: The source specific inputs have credentials and file contents which
+        // should not be persisted. The items have already been extracted from them.
+        let input = DeployImportJobInput {
+            media_tracker: None,
+            goodreads: None,
+            trakt: None,
+            movary: None,
+            letterboxd: None,
+            imdb: None,
+            mal: None,
+            anilist: None,
+            kitsu: None,
+            audiobookshelf: None,
+            generic_csv: None,
+            body_measurements: None,
+            story_graph: None,
+            serializd: None,
+            strong_app: None,
+            apple_health: None,
+            steam: None,
+            manga_server: None,
+            media_json: None,
+            ryot: None,
+            ..input.clone()
+        };
+        Some(Self {
+            input: serde_json::to_value(input).ok()?,
+            items,
+        })
+    }
+
+    /// Take the media item with the given identifier in the source out of the
+    /// items, so that it can be imported using other details.
+    fn take_item(&mut self, identifier: &str) -> Option<ImportOrExportMediaItem> {
+        let position = self.items.iter().position(|i| {
+            i.clone()
+                .into_item()
+                .map_or(false, |m| m.source_id == identifier)
+        })?;
+        self.items.remove(position).into_item()
+    }
+}
+
+#[derive(Debug, SimpleObject, PartialEq, Clone)]
+pub struct ImportedItemChange {
+    pub before: ImportedItem,
+    pub after: ImportedItem,
+}
+
+#[derive(Debug, SimpleObject, Default, PartialEq, Clone)]
+pub struct ImportReportsDiff {
+    /// Items that are only present in the second report.
+    pub added: Vec<ImportedItem>,
+    /// Items that are only present in the first report.
+    pub removed: Vec<ImportedItem>,
+    /// Items whose progress or rating changed between the reports.
+    pub changed: Vec<ImportedItemChange>,
+    /// Whether one of the reports did not record all of its items, in which case
+    /// only the recorded ones are compared.
+    pub incomplete: bool,
 }
 
 #[derive(Default)]
@@ -174,6 +1235,86 @@ impl ImporterQuery {
         let user_id = service.user_id_from_ctx(gql_ctx).await?;
         service.import_reports(user_id).await
     }
+
+    /// Get the events that were logged while an import job was running. Only
+    /// the ones captured so far are returned if the job has not finished yet.
+    async fn import_job_logs(&self, gql_ctx: &Context<'_>, job_id: i32) -> Result<ImportJobLogs> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.import_job_logs(user_id, job_id).await
+    }
+
+    /// Get how far along the media items of an import are. Returns `null` if the
+    /// import has not started processing them yet.
+    async fn import_report_progress(
+        &self,
+        gql_ctx: &Context<'_>,
+        report_id: i32,
+    ) -> Result<Option<ImportProgress>> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.import_report_progress(user_id, report_id).await
+    }
+
+    /// Get the names of the exercises in a Strong export along with a guess of the
+    /// exercises they correspond to. Useful to build the mapping for the import.
+    async fn inspect_strong_app_export(
+        &self,
+        gql_ctx: &Context<'_>,
+        export_path: String,
+    ) -> Result<Vec<StrongAppExportExercise>> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        Ok(strong_app::inspect(
+            user_id,
+            &export_path,
+            &service.media_service.db,
+            &service.file_storage_service,
+        )
+        .await?)
+    }
+
+    /// Check how many of the exercise rows in a Strong export would be mapped
+    /// using the mapping in the input, without importing anything.
+    async fn preview_strong_app_mapping(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: DeployStrongAppImportInput,
+    ) -> Result<StrongAppMappingCoverage> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        Ok(strong_app::preview_mapping(
+            user_id,
+            &input,
+            &service.media_service.db,
+            &service.file_storage_service,
+        )
+        .await?)
+    }
+
+    /// Compare the media items of two imports from the same source. Only imports
+    /// that were performed after item level details started being recorded can
+    /// be compared.
+    async fn diff_imports(
+        &self,
+        gql_ctx: &Context<'_>,
+        report_a: i32,
+        report_b: i32,
+    ) -> Result<ImportReportsDiff> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.diff_imports(user_id, report_a, report_b).await
+    }
+
+    /// Get all the imports that the user has scheduled to run again.
+    async fn scheduled_imports(
+        &self,
+        gql_ctx: &Context<'_>,
+    ) -> Result<Vec<scheduled_import::Model>> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.scheduled_imports(user_id).await
+    }
 }
 
 #[derive(Default)]
@@ -191,11 +1332,96 @@ impl ImporterMutation {
         let user_id = service.user_id_from_ctx(gql_ctx).await?;
         service.deploy_import_job(user_id, input).await
     }
-}
 
+    /// Add job to import the media items of an earlier import whose details could
+    /// not be fetched from the provider again. The identifiers found during that
+    /// import are reused, so the data is not read from the source again.
+    async fn retry_import_failed_items(
+        &self,
+        gql_ctx: &Context<'_>,
+        report_id: i32,
+    ) -> Result<String> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.retry_import_failed_items(user_id, report_id).await
+    }
+
+    /// Import the seen history, reviews and collections of a media item whose
+    /// details could not be fetched from the provider during an import, using
+    /// metadata picked by the user instead. The item is identified by the
+    /// `identifier` it has in the failed items of the report.
+    async fn resolve_import_failed_item(
+        &self,
+        gql_ctx: &Context<'_>,
+        report_id: i32,
+        item_identifier: String,
+        metadata_id: i32,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service
+            .resolve_import_failed_item(user_id, report_id, item_identifier, metadata_id)
+            .await
+    }
+
+    /// Deploy an import job with the given input whenever the schedule is due. The
+    /// schedule is a cron expression with seconds, evaluated in UTC. Only sources
+    /// whose data is fetched from a remote server can be scheduled.
+    async fn create_scheduled_import(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: DeployImportJobInput,
+        schedule: String,
+    ) -> Result<IdObject> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service
+            .create_scheduled_import(user_id, input, schedule)
+            .await
+    }
+
+    /// Stop running a scheduled import. The reports of the imports that it ran
+    /// are kept.
+    async fn delete_scheduled_import(
+        &self,
+        gql_ctx: &Context<'_>,
+        scheduled_import_id: i32,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service
+            .delete_scheduled_import(user_id, scheduled_import_id)
+            .await
+    }
+
+    /// Delete the report of an import that has finished, along with its details.
+    async fn delete_import_report(
+        &self,
+        gql_ctx: &Context<'_>,
+        import_report_id: i32,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service
+            .delete_import_report(user_id, import_report_id)
+            .await
+    }
+
+    /// Delete the seen history, reviews and collection entries that were created
+    /// by an import that has finished.
+    async fn revert_import(&self, gql_ctx: &Context<'_>, report_id: i32) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.revert_import(user_id, report_id).await
+    }
+}
+
+#[derive(Clone)]
 pub struct ImporterService {
     media_service: Arc<MiscellaneousService>,
     exercise_service: Arc<ExerciseService>,
+    file_storage_service: Arc<FileStorageService>,
+    config: Arc<config::AppConfig>,
 }
 
 impl AuthProvider for ImporterService {}
@@ -204,10 +1430,19 @@ impl ImporterService {
     pub fn new(
         media_service: Arc<MiscellaneousService>,
         exercise_service: Arc<ExerciseService>,
+        file_storage_service: Arc<FileStorageService>,
+        config: Arc<config::AppConfig>,
     ) -> Self {
+        rate_limit::configure(rate_limit::RateLimits {
+            requests_per_second: config.scheduler.import_requests_per_second,
+            max_retries: config.scheduler.import_rate_limit_retries,
+            backoff: StdDuration::from_secs(config.scheduler.import_rate_limit_backoff),
+        });
         Self {
             media_service,
             exercise_service,
+            file_storage_service,
+            config,
         }
     }
 
@@ -219,27 +1454,517 @@ impl ImporterService {
         if let Some(s) = input.media_tracker.as_mut() {
             s.api_url = s.api_url.trim_end_matches('/').to_owned()
         }
+        // DEV: The report is created here so that the job can only ever write to the
+        // library of the user who deployed it.
+        let db_import_job = self.start_import_job(user_id, input.source).await?;
         let job = self
             .media_service
             .perform_application_job
             .clone()
-            .push(ApplicationJob::ImportFromExternalSource(user_id, input))
+            .push(ApplicationJob::ImportFromExternalSource(
+                user_id,
+                db_import_job.id,
+                input,
+            ))
             .await
-            .unwrap();
+            .map_err(|e| Error::new(e.to_string()))?;
+        Ok(job.to_string())
+    }
+
+    async fn scheduled_imports(&self, user_id: i32) -> Result<Vec<scheduled_import::Model>> {
+        let scheduled = ScheduledImport::find()
+            .filter(scheduled_import::Column::UserId.eq(user_id))
+            .order_by_asc(scheduled_import::Column::CreatedOn)
+            .all(&self.media_service.db)
+            .await?;
+        Ok(scheduled)
+    }
+
+    async fn create_scheduled_import(
+        &self,
+        user_id: i32,
+        input: DeployImportJobInput,
+        schedule: String,
+    ) -> Result<IdObject> {
+        if !input.is_remote() {
+            return Err(Error::new(format!(
+                "Imports from {} using files can not be scheduled",
+                input.source
+            )));
+        }
+        let Some(next_run_on) = get_next_scheduled_run(&schedule, Utc::now()) else {
+            return Err(Error::new(format!(
+                "The schedule {schedule:?} is not a valid cron expression"
+            )));
+        };
+        let following_run_on = get_next_scheduled_run(&schedule, next_run_on);
+        if following_run_on
+            .is_some_and(|f| f - next_run_on < Duration::minutes(MIN_SCHEDULED_IMPORT_INTERVAL))
+        {
+            return Err(Error::new(format!(
+                "Scheduled imports can run at most once every {MIN_SCHEDULED_IMPORT_INTERVAL} minutes"
+            )));
+        }
+        let scheduled = scheduled_import::ActiveModel {
+            user_id: ActiveValue::Set(user_id),
+            source: ActiveValue::Set(input.source),
+            input: ActiveValue::Set(serde_json::to_value(input)?),
+            schedule: ActiveValue::Set(schedule),
+            next_run_on: ActiveValue::Set(next_run_on),
+            ..Default::default()
+        };
+        let scheduled = scheduled.insert(&self.media_service.db).await?;
+        Ok(IdObject { id: scheduled.id })
+    }
+
+    async fn delete_scheduled_import(
+        &self,
+        user_id: i32,
+        scheduled_import_id: i32,
+    ) -> Result<bool> {
+        let deleted = ScheduledImport::delete_many()
+            .filter(scheduled_import::Column::Id.eq(scheduled_import_id))
+            .filter(scheduled_import::Column::UserId.eq(user_id))
+            .exec(&self.media_service.db)
+            .await?;
+        Ok(deleted.rows_affected > 0)
+    }
+
+    async fn delete_import_report(&self, user_id: i32, import_report_id: i32) -> Result<bool> {
+        let Some(report) = ImportReport::find_by_id(import_report_id)
+            .filter(import_report::Column::UserId.eq(user_id))
+            .one(&self.media_service.db)
+            .await?
+        else {
+            return Ok(false);
+        };
+        if report.success.is_none() {
+            return Err(Error::new(format!(
+                "Import report with id = {import_report_id} is still running"
+            )));
+        }
+        let deleted = ImportReport::delete_by_id(report.id)
+            .exec(&self.media_service.db)
+            .await?;
+        Ok(deleted.rows_affected > 0)
+    }
+
+    async fn revert_import(&self, user_id: i32, report_id: i32) -> Result<bool> {
+        let Some(report) = ImportReport::find_by_id(report_id)
+            .filter(import_report::Column::UserId.eq(user_id))
+            .one(&self.media_service.db)
+            .await?
+        else {
+            return Ok(false);
+        };
+        if report.success.is_none() {
+            return Err(Error::new(format!(
+                "Import report with id = {report_id} is still running"
+            )));
+        }
+        let txn = self.media_service.db.begin().await?;
+        let reviews = Review::delete_many()
+            .filter(review::Column::ImportReportId.eq(report_id))
+            .filter(review::Column::UserId.eq(user_id))
+            .exec(&txn)
+            .await?;
+        // DEV: Reviews posted since the import can be for the seen items it created.
+        Review::update_many()
+            .filter(
+                review::Column::SeenId.in_subquery(
+                    Query::select()
+                        .column(seen::Column::Id)
+                        .from(Seen)
+                        .and_where(seen::Column::ImportReportId.eq(report_id))
+                        .to_owned(),
+                ),
+            )
+            .set(review::ActiveModel {
+                seen_id: ActiveValue::Set(None),
+                ..Default::default()
+            })
+            .exec(&txn)
+            .await?;
+        let seen = Seen::delete_many()
+            .filter(seen::Column::ImportReportId.eq(report_id))
+            .filter(seen::Column::UserId.eq(user_id))
+            .exec(&txn)
+            .await?;
+        let collection_entries = CollectionToEntity::delete_many()
+            .filter(collection_to_entity::Column::ImportReportId.eq(report_id))
+            .exec(&txn)
+            .await?;
+        let mut details = report.details.clone();
+        let undo = details
+            .as_mut()
+            .map(|d| mem::take(&mut d.undo))
+            .unwrap_or_default();
+        for seen in undo.updated_seen {
+            Seen::update_many()
+                .filter(seen::Column::Id.eq(seen.id))
+                .filter(seen::Column::UserId.eq(user_id))
+                .set(seen::ActiveModel {
+                    progress: ActiveValue::Set(seen.progress),
+                    state: ActiveValue::Set(seen.state),
+                    finished_on: ActiveValue::Set(seen.finished_on),
+                    last_updated_on: ActiveValue::Set(seen.last_updated_on),
+                    num_times_updated: ActiveValue::Set(seen.num_times_updated),
+                    sessions: ActiveValue::Set(seen.sessions),
+                    dropped_reason: ActiveValue::Set(seen.dropped_reason),
+                    watched_with: ActiveValue::Set(seen.watched_with),
+                    watch_platform: ActiveValue::Set(seen.watch_platform),
+                    ..Default::default()
+                })
+                .exec(&txn)
+                .await?;
+        }
+        for entry in undo.removed_collection_entries {
+            let is_present = CollectionToEntity::find()
+                .filter(collection_to_entity::Column::CollectionId.eq(entry.collection_id))
+                .filter(collection_to_entity::Column::MetadataId.eq(entry.metadata_id))
+                .one(&txn)
+                .await?
+                .is_some();
+            let collection_exists = Collection::find_by_id(entry.collection_id)
+                .one(&txn)
+                .await?
+                .is_some();
+            if is_present || !collection_exists {
+                continue;
+            }
+            let mut restored: collection_to_entity::ActiveModel = entry.into();
+            restored.id = ActiveValue::NotSet;
+            restored.insert(&txn).await?;
+        }
+        // DEV: The items are not in the library anymore, so later imports that are
+        // deduplicated by `SourceEventId` must not skip them.
+        if let Some(d) = details.as_mut() {
+            d.committed_items.clear();
+            d.source_event_ids.clear();
+            d.reverted_on = Some(Utc::now());
+        }
+        let mut report: import_report::ActiveModel = report.into();
+        report.details = ActiveValue::Set(details);
+        report.update(&txn).await?;
+        txn.commit().await?;
+        tracing::debug!(
+            "Reverted import {report_id}: deleted {seen} seen items, {reviews} reviews and {collection_entries} collection entries",
+            seen = seen.rows_affected,
+            reviews = reviews.rows_affected,
+            collection_entries = collection_entries.rows_affected,
+        );
+        Ok(true)
+    }
+
+    /// The seen items of a media that are in progress, and its entries in the
+    /// "Watchlist" and "In Progress" collections of the user.
+    async fn get_undoable_state(
+        &self,
+        user_id: i32,
+        metadata_id: i32,
+    ) -> Result<(Vec<seen::Model>, Vec<collection_to_entity::Model>)> {
+        let seen = Seen::find()
+            .filter(seen::Column::UserId.eq(user_id))
+            .filter(seen::Column::MetadataId.eq(metadata_id))
+            .filter(seen::Column::Progress.lt(100))
+            .filter(seen::Column::State.ne(SeenState::Dropped))
+            .all(&self.media_service.db)
+            .await?;
+        let collections = Collection::find()
+            .filter(collection::Column::UserId.eq(user_id))
+            .filter(collection::Column::Name.is_in([
+                DefaultCollection::Watchlist.to_string(),
+                DefaultCollection::InProgress.to_string(),
+            ]))
+            .all(&self.media_service.db)
+            .await?;
+        let entries = CollectionToEntity::find()
+            .filter(
+                collection_to_entity::Column::CollectionId
+                    .is_in(collections.into_iter().map(|c| c.id)),
+            )
+            .filter(collection_to_entity::Column::MetadataId.eq(metadata_id))
+            .all(&self.media_service.db)
+            .await?;
+        Ok((seen, entries))
+    }
+
+    /// Delete the reports of the imports that finished before the retention
+    /// period. Reports of imports that are still running are always kept.
+    /// Successful imports that committed items only have their details pruned, since
+    /// later imports deduplicated by `SourceEventId` skip those items.
+    pub async fn delete_old_import_reports(&self) -> Result<()> {
+        let Some(cutoff) = get_report_retention_cutoff(
+            self.config.scheduler.import_report_retention_days,
+            Utc::now(),
+        ) else {
+            return Ok(());
+        };
+        let (kept, expired): (Vec<_>, Vec<_>) = ImportReport::find()
+            .filter(import_report::Column::Success.is_not_null())
+            .filter(import_report::Column::StartedOn.lt(cutoff))
+            .all(&self.media_service.db)
+            .await?
+            .into_iter()
+            .partition(|r| {
+                r.success == Some(true)
+                    && r.details
+                        .as_ref()
+                        .map_or(false, |d| !d.committed_items.is_empty())
+            });
+        let deleted = ImportReport::delete_many()
+            .filter(import_report::Column::Id.is_in(expired.into_iter().map(|r| r.id)))
+            .exec(&self.media_service.db)
+            .await?;
+        tracing::trace!("Deleted {} old import reports", deleted.rows_affected);
+        for report in kept {
+            let Some(details) = report.details.clone() else {
+                continue;
+            };
+            let pruned = prune_import_details(details);
+            if report.details.as_ref() == Some(&pruned) {
+                continue;
+            }
+            let mut report: import_report::ActiveModel = report.into();
+            report.details = ActiveValue::Set(Some(pruned));
+            report.update(&self.media_service.db).await?;
+        }
+        Ok(())
+    }
+
+    /// Deploy the imports whose schedule is due and move them to their next run.
+    pub async fn run_due_scheduled_imports(&self) -> Result<()> {
+        let now = Utc::now();
+        let due = ScheduledImport::find()
+            .filter(scheduled_import::Column::NextRunOn.lte(now))
+            .all(&self.media_service.db)
+            .await?;
+        for scheduled in due {
+            let id = scheduled.id;
+            let input = serde_json::from_value::<DeployImportJobInput>(scheduled.input.clone());
+            let next_run_on =
+                get_next_scheduled_run_after(&scheduled.schedule, scheduled.next_run_on, now);
+            let (Ok(input), Some(next_run_on)) = (input, next_run_on) else {
+                // DEV: These are validated when the import is scheduled, so they can
+                // only be invalid if their format changed in a later version.
+                tracing::error!("Deleting scheduled import with id = {id} since it is invalid");
+                ScheduledImport::delete_by_id(id)
+                    .exec(&self.media_service.db)
+                    .await?;
+                continue;
+            };
+            tracing::trace!("Deploying scheduled import with id = {id}");
+            // DEV: The import is still moved to its next run if it could not be
+            // deployed, so that it is not attempted again every minute.
+            if let Err(e) = self.deploy_import_job(scheduled.user_id, input).await {
+                tracing::error!("Could not deploy scheduled import with id = {id}: {e:?}");
+            }
+            let mut scheduled: scheduled_import::ActiveModel = scheduled.into();
+            scheduled.last_run_on = ActiveValue::Set(Some(now));
+            scheduled.next_run_on = ActiveValue::Set(next_run_on);
+            scheduled.update(&self.media_service.db).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn retry_import_failed_items(&self, user_id: i32, report_id: i32) -> Result<String> {
+        // DEV: The items are moved from the report to the job that retries them in
+        // a single transaction, so that they can not be retried twice.
+        let txn = self.media_service.db.begin().await?;
+        let (report, retry) = self.get_import_retry(&txn, user_id, report_id).await?;
+        let source = report.source;
+        let details = report.details.clone();
+        let mut report: import_report::ActiveModel = report.into();
+        report.details = ActiveValue::Set(details);
+        report.update(&txn).await?;
+        let db_import_job = import_report::ActiveModel {
+            user_id: ActiveValue::Set(user_id),
+            source: ActiveValue::Set(source),
+            payload: ActiveValue::Set(Some(retry)),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+        let job = self
+            .media_service
+            .perform_application_job
+            .clone()
+            .push(ApplicationJob::RetryImportFailedItems(
+                user_id,
+                db_import_job.id,
+            ))
+            .await
+            .map_err(|e| Error::new(e.to_string()))?;
         Ok(job.to_string())
     }
 
+    pub async fn resolve_import_failed_item(
+        &self,
+        user_id: i32,
+        report_id: i32,
+        item_identifier: String,
+        metadata_id: i32,
+    ) -> Result<bool> {
+        // DEV: The item is taken out of the report in a single transaction before it
+        // is imported, so that it can not also be retried by another job.
+        let txn = self.media_service.db.begin().await?;
+        let (report, mut retry) = self.get_import_retry(&txn, user_id, report_id).await?;
+        let Some(metadata) = Metadata::find_by_id(metadata_id).one(&txn).await? else {
+            return Err(Error::new(format!(
+                "Metadata with id = {metadata_id} not found"
+            )));
+        };
+        let Some(mut item) = retry.take_item(&item_identifier) else {
+            return Err(Error::new(format!(
+                "Import report with id = {report_id} has no failed item {item_identifier:?}"
+            )));
+        };
+        item.lot = metadata.lot;
+        item.source = metadata.source;
+        item.internal_identifier = Some(ImportOrExportItemIdentifier::NeedsDetails(
+            metadata.identifier,
+        ));
+        let stored = ImportRetry {
+            input: retry.input.clone(),
+            items: ImportRetryableItem::new(&item).into_iter().collect(),
+        };
+        let source = report.source;
+        let mut details = report.details.clone();
+        if let Some(d) = details.as_mut() {
+            d.failed_items.retain(|i| {
+                i.step != ImportFailStep::MediaDetailsFromProvider
+                    || i.identifier != item_identifier
+            });
+            d.retry = (!retry.items.is_empty()).then_some(retry);
+        }
+        let mut report: import_report::ActiveModel = report.into();
+        report.details = ActiveValue::Set(details);
+        report.update(&txn).await?;
+        txn.commit().await?;
+        let db_import_job = self.start_import_job(user_id, source).await?;
+        self.run_stored_import_job(user_id, db_import_job, stored, 0)
+            .await?;
+        Ok(true)
+    }
+
+    /// Get the report along with the items that can be retried, which are taken out
+    /// of its details.
+    async fn get_import_retry<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        user_id: i32,
+        report_id: i32,
+    ) -> Result<(import_report::Model, ImportRetry)> {
+        let Some(mut report) = ImportReport::find_by_id(report_id)
+            .filter(import_report::Column::UserId.eq(user_id))
+            .lock_exclusive()
+            .one(db)
+            .await?
+        else {
+            return Err(Error::new(format!(
+                "Import report with id = {report_id} not found"
+            )));
+        };
+        if report.success.is_none() {
+            return Err(Error::new(format!(
+                "Import report with id = {report_id} is still running"
+            )));
+        }
+        match report.details.as_mut().and_then(|d| d.retry.take()) {
+            Some(retry) => Ok((report, retry)),
+            None => Err(Error::new(format!(
+                "Import report with id = {report_id} has no items that can be retried"
+            ))),
+        }
+    }
+
+    /// Get the events of a source that were committed by the successful imports of
+    /// the user which have not been reverted.
+    async fn get_imported_source_events(
+        &self,
+        user_id: i32,
+        source: ImportSource,
+    ) -> std::result::Result<ImportedSourceEvents, DbErr> {
+        let reports = ImportReport::find()
+            .filter(import_report::Column::UserId.eq(user_id))
+            .filter(import_report::Column::Source.eq(source))
+            .filter(import_report::Column::Success.eq(true))
+            .all(&self.media_service.db)
+            .await?;
+        let mut events = ImportedSourceEvents::default();
+        for report in reports {
+            let Some(details) = report.details else {
+                continue;
+            };
+            if details.reverted_on.is_some() {
+                continue;
+            }
+            events.since = events.since.max(Some(report.started_on));
+            events.ids.extend(details.source_event_ids);
+        }
+        Ok(events)
+    }
+
+    /// Resume the import jobs that were interrupted when the server stopped. They are
+    /// still marked as running by the previous process, so the marks are removed
+    /// first.
+    pub async fn resume_interrupted_import_jobs(&self) -> Result<()> {
+        ImportReport::update_many()
+            .filter(import_report::Column::Success.is_null())
+            .set(import_report::ActiveModel {
+                running_since: ActiveValue::Set(None),
+                ..Default::default()
+            })
+            .exec(&self.media_service.db)
+            .await?;
+        self.invalidate_import_jobs().await
+    }
+
+    /// Mark an import job as running, unless it has finished or is already running.
+    /// Only the caller that marked it may run it, so that a job which was deployed
+    /// more than once is not run concurrently.
+    async fn claim_import_job(&self, job_id: i32) -> Result<bool> {
+        let claimed = ImportReport::update_many()
+            .filter(import_report::Column::Id.eq(job_id))
+            .filter(import_report::Column::Success.is_null())
+            .filter(import_report::Column::RunningSince.is_null())
+            .set(import_report::ActiveModel {
+                running_since: ActiveValue::Set(Some(Utc::now())),
+                ..Default::default()
+            })
+            .exec(&self.media_service.db)
+            .await?;
+        Ok(claimed.rows_affected > 0)
+    }
+
     pub async fn invalidate_import_jobs(&self) -> Result<()> {
         let all_jobs = ImportReport::find()
             .filter(import_report::Column::Success.is_null())
             .all(&self.media_service.db)
             .await?;
         for job in all_jobs {
-            if Utc::now() - job.started_on > Duration::hours(24) {
+            if is_import_job_stale(&job, Utc::now()) {
                 tracing::trace!("Invalidating job with id = {id}", id = job.id);
+                // DEV: A job that panicked is never removed from the captured logs.
+                logs::finish_capturing(job.id);
                 let mut job: import_report::ActiveModel = job.into();
                 job.success = ActiveValue::Set(Some(false));
+                job.payload = ActiveValue::Set(None);
                 job.save(&self.media_service.db).await?;
+                continue;
+            }
+            if job.running_since.is_some() {
+                continue;
+            }
+            if job.payload.is_some() {
+                tracing::trace!("Resuming job with id = {id}", id = job.id);
+                self.media_service
+                    .perform_application_job
+                    .clone()
+                    .push(ApplicationJob::ResumeImport(job.user_id, job.id))
+                    .await
+                    .unwrap();
             }
         }
         Ok(())
@@ -255,172 +1980,963 @@ impl ImporterService {
         Ok(reports)
     }
 
-    pub async fn start_importing(&self, user_id: i32, input: DeployImportJobInput) -> Result<()> {
-        match input.source {
-            ImportSource::StrongApp => self.import_exercises(user_id, input).await,
-            _ => self.import_media(user_id, input).await,
+    async fn import_job_logs(&self, user_id: i32, job_id: i32) -> Result<ImportJobLogs> {
+        let Some(report) = ImportReport::find_by_id(job_id)
+            .filter(import_report::Column::UserId.eq(user_id))
+            .one(&self.media_service.db)
+            .await?
+        else {
+            return Err(Error::new(format!(
+                "Import report with id = {job_id} not found"
+            )));
+        };
+        if report.finished_on.is_none() {
+            return Ok(logs::get_captured(job_id).unwrap_or_default());
         }
+        Ok(report.details.map(|d| d.logs).unwrap_or_default())
     }
 
-    #[instrument(skip(self, input))]
-    async fn import_exercises(&self, user_id: i32, input: DeployImportJobInput) -> Result<()> {
-        let db_import_job = self.start_import_job(user_id, input.source).await?;
-        let import = match input.source {
-            ImportSource::StrongApp => {
-                strong_app::import(input.strong_app.unwrap(), &self.media_service.db).await?
-            }
-            _ => unreachable!(),
+    async fn import_report_progress(
+        &self,
+        user_id: i32,
+        report_id: i32,
+    ) -> Result<Option<ImportProgress>> {
+        let Some(report) = ImportReport::find_by_id(report_id)
+            .filter(import_report::Column::UserId.eq(user_id))
+            .one(&self.media_service.db)
+            .await?
+        else {
+            return Err(Error::new(format!(
+                "Import report with id = {report_id} not found"
+            )));
         };
-        let details = ImportResultResponse {
-            import: ImportDetails {
-                total: import.workouts.len(),
-            },
-            failed_items: vec![],
+        Ok(report.progress)
+    }
+
+    async fn diff_imports(
+        &self,
+        user_id: i32,
+        report_a: i32,
+        report_b: i32,
+    ) -> Result<ImportReportsDiff> {
+        let reports = ImportReport::find()
+            .filter(import_report::Column::UserId.eq(user_id))
+            .filter(import_report::Column::Id.is_in([report_a, report_b]))
+            .all(&self.media_service.db)
+            .await?;
+        let get_report = |id: i32| {
+            reports
+                .iter()
+                .find(|r| r.id == id)
+                .ok_or_else(|| Error::new(format!("Import report with id = {id} not found")))
         };
-        for workout in import.workouts {
-            self.exercise_service
-                .create_user_workout(user_id, workout)
-                .await
-                .ok();
+        let (first, second) = (get_report(report_a)?, get_report(report_b)?);
+        if first.source != second.source {
+            return Err(Error::new(
+                "Only imports from the same source can be compared",
+            ));
         }
-        self.finish_import_job(db_import_job, details).await?;
-        Ok(())
+        let get_items = |r: &import_report::Model| {
+            r.details
+                .as_ref()
+                .map(|d| d.items.clone())
+                .unwrap_or_default()
+        };
+        let mut diff = diff_imported_items(get_items(first), get_items(second));
+        diff.incomplete = [first, second]
+            .iter()
+            .any(|r| r.details.as_ref().map_or(false, |d| d.items_overflow > 0));
+        Ok(diff)
     }
 
-    #[instrument(skip(self, input))]
-    async fn import_media(&self, user_id: i32, input: DeployImportJobInput) -> Result<()> {
-        let db_import_job = self.start_import_job(user_id, input.source).await?;
-        let mut import = match input.source {
-            ImportSource::MediaTracker => {
-                media_tracker::import(input.media_tracker.unwrap()).await?
-            }
-            ImportSource::MediaJson => media_json::import(input.media_json.unwrap()).await?,
-            ImportSource::Mal => mal::import(input.mal.unwrap()).await?,
-            ImportSource::Goodreads => goodreads::import(input.goodreads.unwrap()).await?,
-            ImportSource::Trakt => trakt::import(input.trakt.unwrap()).await?,
-            ImportSource::Movary => movary::import(input.movary.unwrap()).await?,
-            ImportSource::StoryGraph => {
-                story_graph::import(
-                    input.story_graph.unwrap(),
-                    &self.media_service.get_openlibrary_service().await?,
-                )
-                .await?
-            }
-            _ => unreachable!(),
+    pub async fn start_importing(
+        &self,
+        user_id: i32,
+        job_id: i32,
+        input: DeployImportJobInput,
+    ) -> Result<()> {
+        let db_import_job = self.get_import_job(job_id).await?;
+        check_import_job_can_start(&db_import_job, user_id)?;
+        if !self.claim_import_job(job_id).await? {
+            tracing::debug!("Import job with id = {job_id} is already running");
+            return Ok(());
+        }
+        logs::start_capturing(job_id, self.config.scheduler.import_max_log_entries);
+        let result = self
+            .run_import_job(user_id, input, db_import_job)
+            .instrument(tracing::info_span!(IMPORT_JOB_SPAN, job_id))
+            .await;
+        // DEV: The logs are taken when the report is saved. This only drops them
+        // if the job stopped before that.
+        logs::finish_capturing(job_id);
+        result
+    }
+
+    /// Import the failed items of a report again. They are stored as the payload of
+    /// the job that was created for them, so this is the same as resuming it. The
+    /// items that still fail can be retried from its report.
+    pub async fn start_retrying(&self, user_id: i32, job_id: i32) -> Result<()> {
+        self.resume_importing(user_id, job_id).await
+    }
+
+    /// Continue an import job that was interrupted while its media items were
+    /// being imported, starting from the last item that was recorded as processed.
+    pub async fn resume_importing(&self, user_id: i32, job_id: i32) -> Result<()> {
+        let Some(job) = ImportReport::find_by_id(job_id)
+            .filter(import_report::Column::UserId.eq(user_id))
+            .one(&self.media_service.db)
+            .await?
+        else {
+            return Err(Error::new(format!(
+                "Import job with id = {job_id} not found"
+            )));
         };
-        let preferences =
-            partial_user_by_id::<UserWithOnlyPreferences>(&self.media_service.db, user_id)
-                .await?
-                .preferences;
-        import.media = import
-            .media
-            .into_iter()
-            .sorted_unstable_by_key(|m| {
-                m.seen_history.len() + m.reviews.len() + m.collections.len()
-            })
-            .rev()
-            .collect_vec();
-        for col_details in import.collections.into_iter() {
-            self.media_service
-                .create_or_update_collection(user_id, col_details)
-                .await?;
+        if job.success.is_some() {
+            return Ok(());
         }
-        for (idx, item) in import.media.iter().enumerate() {
+        let Some(payload) = job.payload.clone() else {
+            return Ok(());
+        };
+        let processed = job.progress.as_ref().map_or(0, |p| p.processed);
+        tracing::debug!("Resuming import job with id = {job_id} from item {processed}");
+        self.run_stored_import_job(user_id, job, payload, processed)
+            .await
+    }
+
+    /// Import the media items stored for a job, skipping the first `skip` of them.
+    async fn run_stored_import_job(
+        &self,
+        user_id: i32,
+        db_import_job: import_report::Model,
+        payload: ImportRetry,
+        skip: usize,
+    ) -> Result<()> {
+        // DEV: The job can be deployed again by `invalidate_import_jobs` while it is
+        // waiting to be run or is running.
+        if !self.claim_import_job(db_import_job.id).await? {
             tracing::debug!(
-                "Importing media with identifier = {iden}",
-                iden = item.source_id
+                "Import job with id = {id} is already running",
+                id = db_import_job.id
             );
-            let identifier = item.internal_identifier.clone().unwrap();
-            let data = match identifier {
-                ImportOrExportItemIdentifier::NeedsDetails(i) => {
-                    self.media_service
-                        .commit_media(item.lot, item.source, &i)
-                        .await
-                }
-                ImportOrExportItemIdentifier::AlreadyFilled(a) => {
-                    self.media_service.commit_media_internal(*a.clone()).await
+            return Ok(());
+        }
+        let input: DeployImportJobInput =
+            serde_json::from_value(payload.input).map_err(|e| Error::new(e.to_string()))?;
+        let mut items = payload.items.into_iter();
+        // DEV: The items before the progress were processed before the job was
+        // interrupted. The ones among them that failed are not known anymore, so
+        // they are all treated as committed rather than imported a second time.
+        let processed_items = items
+            .by_ref()
+            .take(skip)
+            .filter_map(ImportRetryableItem::into_item)
+            .map(|m| (m.lot, m.source_id))
+            .collect_vec();
+        let import = ImportResult {
+            media: items.filter_map(ImportRetryableItem::into_item).collect(),
+            ..Default::default()
+        };
+        let job_id = db_import_job.id;
+        logs::start_capturing(job_id, self.config.scheduler.import_max_log_entries);
+        let result = async {
+            match self.import_media(user_id, job_id, &input, import).await {
+                Ok(mut details) => {
+                    details.committed_items.splice(0..0, processed_items);
+                    self.finish_import_job(db_import_job, details).await?;
+                    Ok(())
                 }
-            };
-            let metadata = match data {
-                Ok(r) => r,
                 Err(e) => {
-                    tracing::error!("{e:?}");
-                    import.failed_items.push(ImportFailedItem {
-                        lot: item.lot,
-                        step: ImportFailStep::MediaDetailsFromProvider,
+                    self.fail_import_job(db_import_job, e.message.clone())
+                        .await?;
+                    Err(e)
+                }
+            }
+        }
+        .instrument(tracing::info_span!(IMPORT_JOB_SPAN, job_id))
+        .await;
+        logs::finish_capturing(job_id);
+        result
+    }
+
+    async fn run_import_job(
+        &self,
+        user_id: i32,
+        input: DeployImportJobInput,
+        db_import_job: import_report::Model,
+    ) -> Result<()> {
+        let max_attempts = self.config.scheduler.import_max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            let (error, is_transient) = match self.get_import_result(user_id, input.clone()).await {
+                Ok(import) => {
+                    if let Err(e) = self
+                        .verify_import_job_owner(db_import_job.id, user_id)
+                        .await
+                    {
+                        (e, false)
+                    } else {
+                        let details = match input.source {
+                            ImportSource::StrongApp
+                            | ImportSource::AppleHealth
+                            | ImportSource::BodyMeasurements => {
+                                self.import_exercises(user_id, import).await
+                            }
+                            _ => {
+                                self.import_media(user_id, db_import_job.id, &input, import)
+                                    .await
+                            }
+                        };
+                        match details {
+                            Ok(details) => {
+                                self.finish_import_job(db_import_job, details).await?;
+                                return Ok(());
+                            }
+                            // DEV: Part of the import might have been committed by
+                            // now, so attempting it again would duplicate it.
+                            Err(e) => (e, false),
+                        }
+                    }
+                }
+                Err(e) => (Error::new(e.to_string()), e.is_transient()),
+            };
+            if !is_transient || attempt >= max_attempts {
+                tracing::error!(
+                    "Import job with id = {id} failed after {attempt} attempt(s): {error:?}",
+                    id = db_import_job.id
+                );
+                self.fail_import_job(db_import_job, error.message.clone())
+                    .await?;
+                return Err(error);
+            }
+            let backoff = self
+                .config
+                .scheduler
+                .import_retry_backoff
+                .saturating_mul(2_u64.saturating_pow(attempt - 1));
+            tracing::warn!(
+                "Import job with id = {id} failed on attempt {attempt}, retrying in {backoff}s: {error:?}",
+                id = db_import_job.id
+            );
+            sleep(StdDuration::from_secs(backoff)).await;
+            attempt += 1;
+        }
+    }
+
+    /// Load the import job again and make sure that it was deployed by the user
+    /// whose library is about to be written to.
+    async fn verify_import_job_owner(&self, job_id: i32, user_id: i32) -> Result<()> {
+        let job = self.get_import_job(job_id).await?;
+        check_import_job_owner(&job, user_id)
+    }
+
+    async fn get_import_job(&self, job_id: i32) -> Result<import_report::Model> {
+        let Some(job) = ImportReport::find_by_id(job_id)
+            .one(&self.media_service.db)
+            .await?
+        else {
+            return Err(Error::new(format!(
+                "Import job with id = {job_id} not found"
+            )));
+        };
+        Ok(job)
+    }
+
+    async fn get_import_result(
+        &self,
+        user_id: i32,
+        input: DeployImportJobInput,
+    ) -> ImporterResult<ImportResult> {
+        let trim_csv = input.trim_csv_whitespace.unwrap_or(true);
+        let import = match input.source {
+            ImportSource::AppleHealth => {
+                apple_health::import(
+                    user_id,
+                    input.apple_health.unwrap(),
+                    trim_csv,
+                    &self.media_service.db,
+                    &self.file_storage_service,
+                    &self.exercise_service,
+                )
+                .await?
+            }
+            ImportSource::StrongApp => {
+                strong_app::import(
+                    user_id,
+                    input.strong_app.unwrap(),
+                    trim_csv,
+                    &self.media_service.db,
+                    &self.file_storage_service,
+                    &self.exercise_service,
+                )
+                .await?
+            }
+            ImportSource::MediaTracker => {
+                media_tracker::import(input.media_tracker.unwrap()).await?
+            }
+            ImportSource::MediaJson => media_json::import(input.media_json.unwrap()).await?,
+            ImportSource::Mal => {
+                mal::import(
+                    user_id,
+                    input.mal.unwrap(),
+                    &self.file_storage_service,
+                    &self
+                        .media_service
+                        .get_mal_anime_service()
+                        .await
+                        .map_err(|e| ImporterError::provider("MAL", e.message))?,
+                )
+                .await?
+            }
+            ImportSource::Anilist => anilist::import(input.anilist.unwrap()).await?,
+            ImportSource::Audiobookshelf => {
+                let input = input.audiobookshelf.unwrap();
+                let previous = match input.incremental.unwrap_or_default() {
+                    true => Some(
+                        self.get_imported_source_events(user_id, ImportSource::Audiobookshelf)
+                            .await
+                            .map_err(|e| ImporterError::database("the earlier imports", e))?,
+                    ),
+                    false => None,
+                };
+                audiobookshelf::import(
+                    input,
+                    previous,
+                    &self
+                        .media_service
+                        .get_openlibrary_service()
+                        .await
+                        .map_err(|e| ImporterError::provider("Openlibrary", e.message))?,
+                )
+                .await?
+            }
+            ImportSource::Serializd => serializd::import(input.serializd.unwrap(), trim_csv)?,
+            ImportSource::Komga | ImportSource::Kavita => {
+                let server = match input.source {
+                    ImportSource::Komga => manga_server::Server::Komga,
+                    _ => manga_server::Server::Kavita,
+                };
+                let input = input.manga_server.unwrap();
+                let provider = self
+                    .media_service
+                    .get_media_provider(
+                        MetadataLot::Manga,
+                        input.source.unwrap_or(MetadataSource::Anilist),
+                    )
+                    .await
+                    .map_err(|e| ImporterError::provider("the manga provider", e.message))?;
+                manga_server::import(server, input, provider.as_ref()).await?
+            }
+            ImportSource::Steam => {
+                steam::import(
+                    input.steam.unwrap(),
+                    &self
+                        .media_service
+                        .get_igdb_service()
+                        .await
+                        .map_err(|e| ImporterError::provider("IGDB", e.message))?,
+                )
+                .await?
+            }
+            ImportSource::GenericCsv => generic_csv::import(input.generic_csv.unwrap(), trim_csv)?,
+            ImportSource::BodyMeasurements => {
+                body_measurements::import(input.body_measurements.unwrap(), trim_csv)?
+            }
+            ImportSource::Goodreads => {
+                goodreads::import(input.goodreads.unwrap(), trim_csv).await?
+            }
+            ImportSource::Kitsu => kitsu::import(input.kitsu.unwrap()).await?,
+            ImportSource::Trakt => trakt::import(input.trakt.unwrap()).await?,
+            ImportSource::Movary => movary::import(input.movary.unwrap(), trim_csv).await?,
+            ImportSource::Imdb => {
+                imdb::import(
+                    input.imdb.unwrap(),
+                    trim_csv,
+                    &self
+                        .media_service
+                        .get_tmdb_movie_service()
+                        .await
+                        .map_err(|e| ImporterError::provider("TMDB", e.message))?,
+                    &self
+                        .media_service
+                        .get_tmdb_show_service()
+                        .await
+                        .map_err(|e| ImporterError::provider("TMDB", e.message))?,
+                )
+                .await?
+            }
+            ImportSource::Letterboxd => {
+                letterboxd::import(
+                    input.letterboxd.unwrap(),
+                    trim_csv,
+                    &self
+                        .media_service
+                        .get_tmdb_movie_service()
+                        .await
+                        .map_err(|e| ImporterError::provider("TMDB", e.message))?,
+                )
+                .await?
+            }
+            ImportSource::Ryot => {
+                ryot::import(user_id, input.ryot.unwrap(), &self.file_storage_service).await?
+            }
+            ImportSource::StoryGraph => {
+                story_graph::import(
+                    input.story_graph.unwrap(),
+                    trim_csv,
+                    &self
+                        .media_service
+                        .get_openlibrary_service()
+                        .await
+                        .map_err(|e| ImporterError::provider("Openlibrary", e.message))?,
+                )
+                .await?
+            }
+        };
+        Ok(import)
+    }
+
+    #[instrument(skip(self, import))]
+    async fn import_exercises(
+        &self,
+        user_id: i32,
+        import: ImportResult,
+    ) -> Result<ImportResultResponse> {
+        let total = import.workouts.len() + import.measurements.len();
+        let start_times = import.workouts.iter().map(|w| w.start_time).collect_vec();
+        let mut existing = Workout::find()
+            .filter(workout::Column::UserId.eq(user_id))
+            .filter(workout::Column::StartTime.is_in(start_times))
+            .all(&self.media_service.db)
+            .await?
+            .into_iter()
+            .map(|w| get_workout_identity(&w))
+            .collect::<HashSet<_>>();
+        let (workouts, mut skipped) = filter_duplicate_workouts(import.workouts, &mut existing);
+        if skipped > 0 {
+            tracing::debug!("Skipping {skipped} workouts that have already been imported");
+        }
+        for workout in workouts {
+            self.exercise_service
+                .create_user_workout(user_id, workout)
+                .await
+                .ok();
+        }
+        for measurement in import.measurements {
+            // DEV: Measurements are identified by their timestamp, so this only fails
+            // if one was already recorded at the same time.
+            if self
+                .exercise_service
+                .create_user_measurement(user_id, measurement)
+                .await
+                .is_err()
+            {
+                skipped += 1;
+            }
+        }
+        let details = ImportResultResponse {
+            import: ImportDetails {
+                total,
+                skipped,
+                skipped_seen_history: 0,
+            },
+            failed_items: vec![],
+            failed_items_overflow: 0,
+            error: None,
+            warnings: import.warnings,
+            items: vec![],
+            items_overflow: 0,
+            committed_items: vec![],
+            source_event_ids: vec![],
+            reverted_on: None,
+            retry: None,
+            logs: ImportJobLogs::default(),
+            undo: ImportUndo::default(),
+        };
+        Ok(details)
+    }
+
+    #[instrument(skip(self, input, import))]
+    async fn import_media(
+        &self,
+        user_id: i32,
+        job_id: i32,
+        input: &DeployImportJobInput,
+        mut import: ImportResult,
+    ) -> Result<ImportResultResponse> {
+        let preferences =
+            partial_user_by_id::<UserWithOnlyPreferences>(&self.media_service.db, user_id)
+                .await?
+                .preferences;
+        if let Some(lots) = input.lots.as_ref() {
+            let total = import.media.len();
+            import.media = filter_media_by_lots(import.media, lots);
+            tracing::debug!(
+                "Skipping {} media items whose type was not selected",
+                total - import.media.len()
+            );
+        }
+        import.media = import
+            .media
+            .into_iter()
+            .sorted_unstable_by_key(|m| {
+                m.seen_history.len() + m.reviews.len() + m.collections.len()
+            })
+            .rev()
+            .collect_vec();
+        if let Some(map) = input.status_collection_map.as_ref() {
+            import.map_collection_names(map);
+        }
+        // DEV: The media is sorted by its activity, so the items that are not
+        // recorded are the least active ones.
+        let max_items = self.config.scheduler.import_max_recorded_items;
+        let mut items = import.media.iter().map(ImportedItem::from).collect_vec();
+        let items_overflow = items.len().saturating_sub(max_items);
+        items.truncate(max_items);
+        let total = import.media.len();
+        let dedup_key = input.dedup_key.unwrap_or_default();
+        let mut skipped = 0;
+        let mut skipped_seen_history = 0;
+        let seen_match = input.seen_match.unwrap_or_default();
+        let mut existing_seen = ExistingSeen::default();
+        let mut existing_reviews = ExistingReviews::default();
+        if dedup_key == ImportDedupKey::SourceEventId {
+            let previous = ImportReport::find()
+                .filter(import_report::Column::UserId.eq(user_id))
+                .filter(import_report::Column::Source.eq(input.source))
+                .filter(import_report::Column::Success.eq(true))
+                .all(&self.media_service.db)
+                .await?
+                .into_iter()
+                .filter_map(|r| r.details)
+                .filter(|d| d.reverted_on.is_none())
+                .flat_map(|d| d.committed_items)
+                .collect::<HashSet<_>>();
+            (import.media, skipped) = filter_previously_imported(import.media, &previous);
+            if skipped > 0 {
+                tracing::debug!("Skipping {skipped} media items that have already been imported");
+            }
+        } else {
+            let seen_history = Seen::find()
+                .filter(seen::Column::UserId.eq(user_id))
+                .filter(seen::Column::State.is_in([SeenState::Completed, SeenState::InProgress]))
+                .find_also_related(Metadata)
+                .all(&self.media_service.db)
+                .await?;
+            for (seen, metadata) in seen_history {
+                if seen.state == SeenState::InProgress {
+                    existing_seen.insert_in_progress(seen.metadata_id, seen.progress);
+                } else if let Some(metadata) = metadata {
+                    existing_seen.insert(SeenIdentity::from_seen(
+                        get_media_identity(dedup_key, &metadata),
+                        &seen,
+                    ));
+                }
+            }
+            let reviews = Review::find()
+                .filter(review::Column::UserId.eq(user_id))
+                .all(&self.media_service.db)
+                .await?;
+            for review in reviews.iter() {
+                existing_reviews.insert(ReviewIdentity::from_review(review));
+            }
+        }
+        for mut col_details in import.collections.into_iter() {
+            if col_details.visibility.is_none() {
+                col_details.visibility = input.collection_visibility;
+            }
+            self.media_service
+                .create_or_update_collection(user_id, col_details)
+                .await?;
+        }
+        // DEV: The position of the items in the payload matches the progress, so
+        // that an interrupted job can be resumed.
+        let payload = ImportRetry::new(
+            input,
+            import
+                .media
+                .iter()
+                .filter_map(ImportRetryableItem::new)
+                .collect(),
+        );
+        let model = import_report::ActiveModel {
+            id: ActiveValue::Unchanged(job_id),
+            payload: ActiveValue::Set(payload),
+            ..Default::default()
+        };
+        model.update(&self.media_service.db).await?;
+        let zero_rating_is_unrated = input.zero_rating_is_unrated.unwrap_or(true);
+        let mut retryable_items = vec![];
+        let mut committed_items = vec![];
+        let mut source_event_ids = vec![];
+        let mut undo = ImportUndo::default();
+        let item_timeout = StdDuration::from_secs(
+            input
+                .item_timeout
+                .unwrap_or(self.config.scheduler.import_item_timeout),
+        );
+        // DEV: The lookups run ahead of the item being imported, but their results
+        // are used in the order of the items (`buffered` rather than
+        // `buffer_unordered`), so that the writes of an item are not interleaved
+        // with those of others and the progress can still be used to resume the job.
+        // Items for the same media are only looked up once, since committing the
+        // same media concurrently would race. The lookups are driven by their own
+        // task, since they would otherwise not make progress (while their timeout
+        // runs) during the writes of the item being imported.
+        let media = Arc::new(mem::take(&mut import.media));
+        let lookup_keys = media.iter().map(get_lookup_key).collect_vec();
+        let concurrency = self.config.scheduler.import_lookup_concurrency.max(1);
+        let (lookups_tx, mut lookups) = mpsc::channel(concurrency);
+        tokio::spawn({
+            let service = self.clone();
+            let media = media.clone();
+            let lookup_keys = lookup_keys.clone();
+            async move {
+                let mut looked_up = HashSet::new();
+                let mut results =
+                    stream::iter(media.iter().zip(lookup_keys.iter()).map(|(item, key)| {
+                        let is_first = key.as_ref().map_or(true, |k| looked_up.insert(k.clone()));
+                        let service = &service;
+                        async move {
+                            match is_first {
+                                true => {
+                                    Some(service.lookup_imported_media(item, item_timeout).await)
+                                }
+                                false => None,
+                            }
+                        }
+                    }))
+                    .buffered(concurrency);
+                while let Some(result) = results.next().await {
+                    // DEV: The import stopped, so the remaining items are not needed.
+                    if lookups_tx.send(result).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            .instrument(tracing::Span::current())
+        });
+        let mut lookup_results = HashMap::new();
+        let media_started_on = Utc::now();
+        for (idx, item) in media.iter().enumerate() {
+            tracing::debug!(
+                "Importing media with identifier = {iden}",
+                iden = item.source_id
+            );
+            // DEV: The items before this one have been committed completely, so the
+            // progress is recorded before every item for the job to be resumed from
+            // the one that was interrupted.
+            let progress = ImportProgress::new(
+                idx,
+                media.len(),
+                Some(item.source_id.clone()),
+                media_started_on,
+                Utc::now(),
+            );
+            self.update_import_progress(job_id, progress).await;
+            let external_identifiers = match item.internal_identifier.as_ref() {
+                Some(ImportOrExportItemIdentifier::NeedsDetailsFromAny(ids)) => ids.clone(),
+                _ => vec![],
+            };
+            let data = match (lookups.recv().await.unwrap(), lookup_keys[idx].as_ref()) {
+                (Some(data), Some(key)) => {
+                    lookup_results.insert(key.clone(), data.clone());
+                    data
+                }
+                (Some(data), None) => data,
+                (None, key) => key.and_then(|k| lookup_results.get(k).cloned()).unwrap(),
+            };
+            let metadata = match data {
+                Ok(r) => r,
+                Err((e, code)) => {
+                    tracing::error!("{e:?}");
+                    import.failed_items.push(ImportFailedItem {
+                        lot: item.lot,
+                        step: ImportFailStep::MediaDetailsFromProvider,
                         identifier: item.source_id.to_owned(),
                         error: Some(e.message),
+                        code,
+                        source_row: None,
                     });
+                    retryable_items.extend(ImportRetryableItem::new(item));
                     continue;
                 }
             };
-            for seen in item.seen_history.iter() {
+            committed_items.push((item.lot, item.source_id.clone()));
+            source_event_ids.extend(
+                import
+                    .source_event_ids
+                    .remove(&item.source_id)
+                    .unwrap_or_default(),
+            );
+            if !external_identifiers.is_empty() {
+                if let Err(e) = self
+                    .media_service
+                    .add_external_identifiers(metadata.id, external_identifiers)
+                    .await
+                {
+                    tracing::debug!("Could not record external identifiers: {:?}", e);
+                }
+            }
+            let needs_air_dates = input.use_air_date_for_undated.unwrap_or_default()
+                && item.seen_history.iter().any(|s| s.ended_on.is_none());
+            let needs_total_pages = item
+                .seen_history
+                .iter()
+                .any(|s| s.progress.is_none() && s.pages_read.is_some());
+            let needs_seasons = item.lot == MetadataLot::Show
+                && (item.seen_history.iter().any(is_season_seen)
+                    || item.reviews.iter().any(|r| {
+                        r.show_season_number.is_some() && r.show_episode_number.is_none()
+                    }));
+            let details = if needs_air_dates
+                || needs_total_pages
+                || needs_seasons
+                || dedup_key == ImportDedupKey::TitleAndYear
+            {
+                Metadata::find_by_id(metadata.id)
+                    .one(&self.media_service.db)
+                    .await?
+            } else {
+                None
+            };
+            let media_identity = match details.as_ref() {
+                Some(m) => get_media_identity(dedup_key, m),
+                None => metadata.id.to_string(),
+            };
+            let specifics = details.map(|m| m.specifics);
+            let (seen_history, unknown_seasons) =
+                expand_season_seen(specifics.as_ref(), &item.seen_history);
+            for season in unknown_seasons {
+                import.failed_items.push(ImportFailedItem {
+                    lot: item.lot,
+                    step: ImportFailStep::SeenHistoryConversion,
+                    identifier: item.source_id.to_owned(),
+                    error: Some(format!("The show has no season {season}")),
+                    code: ImportErrorCode::NotFound,
+                    source_row: None,
+                });
+            }
+            // DEV: Progress updates change the seen items that are in progress instead
+            // of creating new ones, and move the media out of the "Watchlist" and
+            // "In Progress" collections, so their state is kept for reverting.
+            let (mut updatable_seen, collection_entries) = match seen_history.is_empty() {
+                true => (vec![], vec![]),
+                false => self.get_undoable_state(user_id, metadata.id).await?,
+            };
+            let mut seen_ids = HashMap::new();
+            for seen in seen_history.iter() {
                 let progress = if seen.progress.is_some() {
                     seen.progress
+                } else if let Some(p) = specifics
+                    .as_ref()
+                    .and_then(|s| get_progress_from_pages_read(s, seen))
+                {
+                    Some(p)
+                } else if seen.dropped.is_some() || seen.pages_read.is_some() {
+                    // DEV: The pages read can not be converted if the book has no
+                    // page count, but the book is still not finished.
+                    Some(0)
                 } else {
                     Some(100)
                 };
+                let date = seen.ended_on.map(|d| d.date_naive()).or_else(|| {
+                    specifics
+                        .as_ref()
+                        .and_then(|s| get_episode_air_date(s, seen))
+                });
+                let is_completed = progress == Some(100) && seen.dropped.is_none();
+                let identity = SeenIdentity::from_imported(media_identity.clone(), date, seen);
+                if is_completed && existing_seen.contains(&identity, seen_match) {
+                    skipped_seen_history += 1;
+                    continue;
+                }
+                // DEV: Updating an item that is in progress adds the sessions again, so
+                // the same progress is not imported twice.
+                if let (false, None, Some(p)) = (is_completed, &seen.dropped, progress) {
+                    if existing_seen.contains_in_progress(metadata.id, p) {
+                        skipped_seen_history += 1;
+                        continue;
+                    }
+                }
                 match self
                     .media_service
                     .progress_update(
                         ProgressUpdateInput {
                             metadata_id: metadata.id,
                             progress,
-                            date: seen.ended_on.map(|d| d.date_naive()),
+                            date,
                             show_season_number: seen.show_season_number,
                             show_episode_number: seen.show_episode_number,
                             podcast_episode_number: seen.podcast_episode_number,
+                            part_number: seen.part_number,
+                            total_parts: seen.total_parts,
+                            sessions: seen.sessions.clone(),
+                            watched_with: seen
+                                .watched_with
+                                .clone()
+                                .or_else(|| input.watched_with.clone()),
+                            watch_platform: seen.watch_platform.clone(),
                             change_state: None,
+                            import_report_id: Some(job_id),
                         },
                         user_id,
                     )
                     .await
                 {
+                    Ok(ProgressUpdateResultUnion::Ok(seen_id)) => {
+                        if let Some(idx) = updatable_seen.iter().position(|s| s.id == seen_id.id) {
+                            undo.updated_seen.push(updatable_seen.swap_remove(idx));
+                        }
+                        if is_completed && dedup_key != ImportDedupKey::SourceEventId {
+                            existing_seen.insert_imported(identity);
+                        }
+                        if let (true, Some(date)) = (is_completed, date) {
+                            seen_ids.insert(date, seen_id.id);
+                        }
+                        if let Some(dropped) = seen.dropped.clone() {
+                            if let Err(e) = self
+                                .media_service
+                                .mark_seen_as_dropped(
+                                    seen_id.id,
+                                    dropped.dropped_on,
+                                    dropped.reason,
+                                )
+                                .await
+                            {
+                                import.failed_items.push(ImportFailedItem {
+                                    lot: item.lot,
+                                    step: ImportFailStep::SeenHistoryConversion,
+                                    identifier: item.source_id.to_owned(),
+                                    error: Some(e.message),
+                                    code: ImportErrorCode::SaveError,
+                                    source_row: None,
+                                });
+                            }
+                        }
+                    }
                     Ok(_) => {}
                     Err(e) => import.failed_items.push(ImportFailedItem {
                         lot: item.lot,
                         step: ImportFailStep::SeenHistoryConversion,
                         identifier: item.source_id.to_owned(),
                         error: Some(e.message),
+                        code: ImportErrorCode::SaveError,
+                        source_row: None,
                     }),
                 };
             }
+            for entry in collection_entries {
+                if CollectionToEntity::find_by_id(entry.id)
+                    .one(&self.media_service.db)
+                    .await?
+                    .is_none()
+                {
+                    undo.removed_collection_entries.push(entry);
+                }
+            }
+            let is_in_progress = item
+                .seen_history
+                .iter()
+                .any(|s| s.dropped.is_none() && s.progress.is_some_and(|p| (1..100).contains(&p)));
+            if preferences.general.collect_in_progress_imports && is_in_progress {
+                let collection_name = get_in_progress_collection_name(item.lot);
+                self.media_service
+                    .create_or_update_collection(
+                        user_id,
+                        CreateOrUpdateCollectionInput {
+                            name: collection_name.to_owned(),
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+                self.media_service
+                    .add_entity_to_collection(
+                        user_id,
+                        ChangeCollectionToEntityInput {
+                            collection_name: collection_name.to_owned(),
+                            entity_id: metadata.id,
+                            entity_lot: EntityLot::Media,
+                            import_report_id: Some(job_id),
+                            ..Default::default()
+                        },
+                    )
+                    .await
+                    .ok();
+            }
+            if item.is_favorite.unwrap_or_default() {
+                if let Err(e) = self
+                    .media_service
+                    .set_media_favorite(user_id, metadata.id, true)
+                    .await
+                {
+                    tracing::debug!("Could not mark media as favorite: {:?}", e);
+                }
+            }
+            if let (MetadataLot::Show, Some(next_episode)) = (item.lot, item.next_episode.clone()) {
+                if let Err(e) = self
+                    .media_service
+                    .set_media_next_episode(user_id, metadata.id, next_episode)
+                    .await
+                {
+                    tracing::debug!("Could not set next episode of media: {:?}", e);
+                }
+            }
+            if let Some(display_title) = item.display_title.clone() {
+                if let Err(e) = self
+                    .media_service
+                    .set_media_title_override(user_id, metadata.id, display_title)
+                    .await
+                {
+                    tracing::debug!("Could not set display title of media: {:?}", e);
+                }
+            }
             for review in item.reviews.iter() {
-                if review.review.is_none() && review.rating.is_none() {
+                let rating = get_imported_rating(review, zero_rating_is_unrated);
+                if review.review.is_none() && rating.is_none() {
                     tracing::debug!("Skipping review since it has no content");
                     continue;
                 }
-                let rating = match preferences.general.review_scale {
-                    UserReviewScale::OutOfFive => review.rating.map(|rating| rating / dec!(20)),
-                    UserReviewScale::OutOfHundred => review.rating,
-                };
+                let rating =
+                    rating.map(|r| convert_rating_to_scale(r, preferences.general.review_scale));
                 let text = review.review.clone().and_then(|r| r.text);
                 let spoiler = review.review.clone().map(|r| r.spoiler.unwrap_or(false));
                 let date = review.review.clone().map(|r| r.date);
-                match self
-                    .media_service
-                    .post_review(
-                        user_id,
-                        PostReviewInput {
-                            rating,
-                            text,
-                            spoiler,
-                            date: date.flatten(),
-                            metadata_id: Some(metadata.id),
-                            show_season_number: review.show_season_number,
-                            show_episode_number: review.show_episode_number,
-                            podcast_episode_number: review.podcast_episode_number,
-                            ..Default::default()
-                        },
-                    )
-                    .await
+                let visibility = review.review.clone().and_then(|r| r.visibility);
+                let review_input = PostReviewInput {
+                    rating,
+                    text,
+                    spoiler,
+                    date: date.flatten(),
+                    visibility,
+                    metadata_id: Some(metadata.id),
+                    show_season_number: review.show_season_number,
+                    show_episode_number: review.show_episode_number.or_else(|| {
+                        get_last_episode_of_season(specifics.as_ref(), review.show_season_number?)
+                    }),
+                    podcast_episode_number: review.podcast_episode_number,
+                    seen_id: review
+                        .seen_on
+                        .and_then(|d| seen_ids.get(&d.date_naive()).copied()),
+                    import_report_id: Some(job_id),
+                    ..Default::default()
+                };
+                let identity =
+                    ReviewIdentity::from_input(&review_input, preferences.general.review_scale);
+                if dedup_key != ImportDedupKey::SourceEventId
+                    && !existing_reviews.insert_imported(identity)
                 {
+                    tracing::debug!("Skipping review since it has already been imported");
+                    continue;
+                }
+                match self.media_service.post_review(user_id, review_input).await {
                     Ok(_) => {}
                     Err(e) => import.failed_items.push(ImportFailedItem {
                         lot: item.lot,
                         step: ImportFailStep::ReviewConversion,
                         identifier: item.source_id.to_owned(),
                         error: Some(e.message),
+                        code: ImportErrorCode::SaveError,
+                        source_row: None,
                     }),
                 };
             }
@@ -430,6 +2946,7 @@ impl ImporterService {
                         user_id,
                         CreateOrUpdateCollectionInput {
                             name: col.to_string(),
+                            visibility: input.collection_visibility,
                             ..Default::default()
                         },
                     )
@@ -441,6 +2958,15 @@ impl ImporterService {
                             collection_name: col.to_string(),
                             entity_id: metadata.id,
                             entity_lot: EntityLot::Media,
+                            rank: import
+                                .collection_ranks
+                                .get(&(col.to_owned(), item.source_id.to_owned()))
+                                .copied(),
+                            added_on: import
+                                .collection_added_on
+                                .get(&(col.to_owned(), item.source_id.to_owned()))
+                                .copied(),
+                            import_report_id: Some(job_id),
                         },
                     )
                     .await
@@ -449,57 +2975,1998 @@ impl ImporterService {
             tracing::debug!(
                 "Imported item: {idx}/{total}, lot: {lot}, history count: {hist}, review count: {rev}, collection count: {col}",
                 idx = idx + 1,
-                total = import.media.len(),
+                total = media.len(),
                 lot = item.lot,
                 hist = item.seen_history.len(),
                 rev = item.reviews.len(),
                 col = item.collections.len(),
             );
         }
-        self.media_service
-            .deploy_recalculate_summary_job(user_id)
-            .await
-            .ok();
-        tracing::debug!(
-            "Imported {total} media items from {source}",
-            total = import.media.len(),
-            source = db_import_job.source
-        );
-        let details = ImportResultResponse {
-            import: ImportDetails {
-                total: import.media.len(),
-            },
-            failed_items: import.failed_items,
-        };
-        self.finish_import_job(db_import_job, details).await?;
-        Ok(())
-    }
-
-    async fn start_import_job(
-        &self,
-        user_id: i32,
-        source: ImportSource,
-    ) -> Result<import_report::Model> {
-        let model = import_report::ActiveModel {
-            user_id: ActiveValue::Set(user_id),
-            source: ActiveValue::Set(source),
-            ..Default::default()
-        };
-        let model = model.insert(&self.media_service.db).await.unwrap();
-        tracing::trace!("Started import job with id = {id}", id = model.id);
-        Ok(model)
-    }
-
-    async fn finish_import_job(
-        &self,
-        job: import_report::Model,
-        details: ImportResultResponse,
-    ) -> Result<import_report::Model> {
-        let mut model: import_report::ActiveModel = job.into();
-        model.finished_on = ActiveValue::Set(Some(Utc::now()));
-        model.details = ActiveValue::Set(Some(details));
-        model.success = ActiveValue::Set(Some(true));
-        let model = model.update(&self.media_service.db).await.unwrap();
-        Ok(model)
+        let progress =
+            ImportProgress::new(media.len(), media.len(), None, media_started_on, Utc::now());
+        self.update_import_progress(job_id, progress).await;
+        for (lot, source, identifier) in import.metadata_groups.into_iter() {
+            if let Err(e) = self
+                .media_service
+                .associate_group_with_metadata(lot, source, identifier)
+                .await
+            {
+                tracing::debug!("Could not associate group: {:?}", e);
+            }
+        }
+        for item in import.metadata_groups_reviewed.iter() {
+            let mut db_group = None;
+            for _ in 0..2 {
+                db_group = MetadataGroup::find()
+                    .filter(metadata_group::Column::Identifier.eq(&item.identifier))
+                    .filter(metadata_group::Column::Lot.eq(item.lot))
+                    .filter(metadata_group::Column::Source.eq(item.source))
+                    .one(&self.media_service.db)
+                    .await?;
+                if db_group.is_some() {
+                    break;
+                }
+                if let Err(e) = self
+                    .media_service
+                    .associate_group_with_metadata(item.lot, item.source, item.identifier.clone())
+                    .await
+                {
+                    tracing::debug!("Could not get group: {:?}", e);
+                    break;
+                }
+            }
+            let Some(db_group) = db_group else {
+                tracing::debug!("Group with title = {} not found", item.title);
+                continue;
+            };
+            for review in item.reviews.iter() {
+                let review_input = PostReviewInput {
+                    metadata_group_id: Some(db_group.id),
+                    import_report_id: Some(job_id),
+                    ..get_entity_review_input(review, preferences.general.review_scale)
+                };
+                let identity =
+                    ReviewIdentity::from_input(&review_input, preferences.general.review_scale);
+                if dedup_key != ImportDedupKey::SourceEventId
+                    && !existing_reviews.insert_imported(identity)
+                {
+                    continue;
+                }
+                self.media_service
+                    .post_review(user_id, review_input)
+                    .await
+                    .ok();
+            }
+        }
+        for item in import.people.iter() {
+            let db_person = Person::find()
+                .filter(person::Column::Name.eq(&item.name))
+                .one(&self.media_service.db)
+                .await?;
+            let db_person = match db_person {
+                Some(p) => p,
+                None => {
+                    tracing::debug!("Person with name = {} not found", item.name);
+                    continue;
+                }
+            };
+            for review in item.reviews.iter() {
+                let review_input = PostReviewInput {
+                    person_id: Some(db_person.id),
+                    import_report_id: Some(job_id),
+                    ..get_entity_review_input(review, preferences.general.review_scale)
+                };
+                let identity =
+                    ReviewIdentity::from_input(&review_input, preferences.general.review_scale);
+                if dedup_key != ImportDedupKey::SourceEventId
+                    && !existing_reviews.insert_imported(identity)
+                {
+                    continue;
+                }
+                self.media_service
+                    .post_review(user_id, review_input)
+                    .await
+                    .ok();
+            }
+            for col in item.collections.iter() {
+                self.media_service
+                    .add_entity_to_collection(
+                        user_id,
+                        ChangeCollectionToEntityInput {
+                            collection_name: col.to_string(),
+                            entity_id: db_person.id,
+                            entity_lot: EntityLot::Person,
+                            import_report_id: Some(job_id),
+                            ..Default::default()
+                        },
+                    )
+                    .await
+                    .ok();
+            }
+        }
+        let mut warnings = import.warnings;
+        if !import.exercises.is_empty() || !import.completed_workouts.is_empty() {
+            let failed_workouts = self
+                .exercise_service
+                .restore_exercises_and_workouts(
+                    user_id,
+                    import.exercises,
+                    import.completed_workouts,
+                )
+                .await?;
+            warnings.extend(failed_workouts);
+        }
+        for measurement in import.measurements {
+            self.exercise_service
+                .create_user_measurement(user_id, measurement)
+                .await
+                .ok();
+        }
+        for goal in import.reading_goals {
+            self.media_service
+                .set_reading_goal(user_id, goal)
+                .await
+                .ok();
+        }
+        let keep_customized = input
+            .ryot
+            .as_ref()
+            .and_then(|r| r.keep_customized_preferences)
+            .unwrap_or_default();
+        if let Some(imported) = import.preferences {
+            if should_apply_preferences(&preferences, keep_customized) {
+                let mut user: user::ActiveModel =
+                    user_by_id(&self.media_service.db, user_id).await?.into();
+                user.preferences = ActiveValue::Set(imported);
+                user.update(&self.media_service.db).await?;
+            } else {
+                tracing::debug!("Keeping the preferences that were customized by the user");
+            }
+        }
+        let summary = if input.recalculate_summary_synchronously.unwrap_or_default() {
+            self.media_service
+                .calculate_user_summary(user_id, true)
+                .await
+                .map(|_| ())
+        } else {
+            self.media_service
+                .deploy_recalculate_summary_job(user_id)
+                .await
+        };
+        if let Err(e) = summary {
+            tracing::error!("Could not recalculate the summary: {:?}", e);
+            warnings.push(format!(
+                "The summary could not be recalculated: {}",
+                e.message
+            ));
+        }
+        tracing::debug!(
+            "Imported {total} media items from {source}",
+            total = media.len(),
+            source = input.source
+        );
+        let (failed_items, failed_items_overflow) = truncate_failed_items(
+            import.failed_items,
+            self.config.scheduler.import_max_failed_items,
+        );
+        let details = ImportResultResponse {
+            import: ImportDetails {
+                total,
+                skipped,
+                skipped_seen_history,
+            },
+            failed_items,
+            failed_items_overflow,
+            error: None,
+            warnings,
+            items,
+            items_overflow,
+            committed_items,
+            source_event_ids,
+            reverted_on: None,
+            retry: ImportRetry::new(input, retryable_items),
+            logs: ImportJobLogs::default(),
+            undo,
+        };
+        Ok(details)
+    }
+
+    /// Find the media of an imported item in the database, fetching its details
+    /// from the provider if it is not there yet. Requests that the provider
+    /// rejected for being too frequent are attempted again after a while.
+    async fn lookup_imported_media(
+        &self,
+        item: &ImportOrExportMediaItem,
+        item_timeout: StdDuration,
+    ) -> std::result::Result<IdObject, (Error, ImportErrorCode)> {
+        let limits = rate_limit::get_limits();
+        let mut attempt = 0;
+        loop {
+            let result = match timeout(item_timeout, self.commit_imported_media(item)).await {
+                Ok(data) => data.map_err(|e| {
+                    let code = ImportErrorCode::from_provider_message(&e.message);
+                    (e, code)
+                }),
+                Err(_) => Err((
+                    Error::new(format!(
+                        "Timed out after {} seconds",
+                        item_timeout.as_secs()
+                    )),
+                    ImportErrorCode::Timeout,
+                )),
+            };
+            match result {
+                Err((_, ImportErrorCode::ProviderRateLimited)) if attempt < limits.max_retries => {
+                    let wait = rate_limit::get_backoff(limits.backoff, attempt);
+                    tracing::debug!(
+                        "Provider rate limited {:?}, retrying in {:?}",
+                        item.source_id,
+                        wait
+                    );
+                    sleep(wait).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn commit_imported_media(&self, item: &ImportOrExportMediaItem) -> Result<IdObject> {
+        match item.internal_identifier.clone().unwrap() {
+            ImportOrExportItemIdentifier::NeedsDetails(i) => {
+                self.commit_provider_media(item.lot, item.source, &i).await
+            }
+            ImportOrExportItemIdentifier::NeedsDetailsFromAny(ids) => {
+                let mut error = Error::new("No identifiers to resolve the media from");
+                for id in ids {
+                    match self
+                        .commit_provider_media(item.lot, id.source, &id.identifier)
+                        .await
+                    {
+                        Ok(m) => return Ok(m),
+                        Err(e) => {
+                            tracing::debug!(
+                                "Could not resolve {:?} using {:?}: {:?}",
+                                item.source_id,
+                                id,
+                                e
+                            );
+                            error = e;
+                        }
+                    }
+                }
+                Err(error)
+            }
+            ImportOrExportItemIdentifier::AlreadyFilled(a) => {
+                self.media_service.commit_media_internal(*a).await
+            }
+        }
+    }
+
+    /// Commit the media with the given identifier on a provider. Requests to the
+    /// provider are rate limited, so the ones that are already in the database
+    /// are not delayed.
+    async fn commit_provider_media(
+        &self,
+        lot: MetadataLot,
+        source: MetadataSource,
+        identifier: &str,
+    ) -> Result<IdObject> {
+        if let Some(m) = self
+            .media_service
+            .media_exists_in_database(lot, source, identifier)
+            .await?
+        {
+            return Ok(m);
+        }
+        rate_limit::acquire(&source.to_string()).await;
+        self.media_service
+            .commit_media(lot, source, identifier)
+            .await
+    }
+
+    async fn update_import_progress(&self, job_id: i32, progress: ImportProgress) {
+        let model = import_report::ActiveModel {
+            id: ActiveValue::Unchanged(job_id),
+            progress: ActiveValue::Set(Some(progress)),
+            ..Default::default()
+        };
+        if let Err(e) = model.update(&self.media_service.db).await {
+            tracing::debug!("Could not update the progress of the import: {:?}", e);
+        }
+    }
+
+    async fn start_import_job(
+        &self,
+        user_id: i32,
+        source: ImportSource,
+    ) -> Result<import_report::Model> {
+        let model = import_report::ActiveModel {
+            user_id: ActiveValue::Set(user_id),
+            source: ActiveValue::Set(source),
+            ..Default::default()
+        };
+        let model = model.insert(&self.media_service.db).await.unwrap();
+        tracing::trace!("Started import job with id = {id}", id = model.id);
+        Ok(model)
+    }
+
+    async fn finish_import_job(
+        &self,
+        job: import_report::Model,
+        details: ImportResultResponse,
+    ) -> Result<import_report::Model> {
+        let logs = logs::finish_capturing(job.id);
+        let mut model: import_report::ActiveModel = job.into();
+        model.finished_on = ActiveValue::Set(Some(Utc::now()));
+        model.details = ActiveValue::Set(Some(ImportResultResponse { logs, ..details }));
+        model.success = ActiveValue::Set(Some(true));
+        model.payload = ActiveValue::Set(None);
+        model.running_since = ActiveValue::Set(None);
+        let model = model.update(&self.media_service.db).await.unwrap();
+        Ok(model)
+    }
+
+    async fn fail_import_job(
+        &self,
+        job: import_report::Model,
+        error: String,
+    ) -> Result<import_report::Model> {
+        let logs = logs::finish_capturing(job.id);
+        let mut model: import_report::ActiveModel = job.into();
+        model.finished_on = ActiveValue::Set(Some(Utc::now()));
+        model.details = ActiveValue::Set(Some(ImportResultResponse {
+            import: ImportDetails {
+                total: 0,
+                skipped: 0,
+                skipped_seen_history: 0,
+            },
+            failed_items: vec![],
+            failed_items_overflow: 0,
+            error: Some(error),
+            warnings: vec![],
+            items: vec![],
+            items_overflow: 0,
+            committed_items: vec![],
+            source_event_ids: vec![],
+            reverted_on: None,
+            retry: None,
+            logs,
+            undo: ImportUndo::default(),
+        }));
+        model.success = ActiveValue::Set(Some(false));
+        model.payload = ActiveValue::Set(None);
+        model.running_since = ActiveValue::Set(None);
+        let model = model.update(&self.media_service.db).await.unwrap();
+        Ok(model)
+    }
+}
+
+/// The media that an imported item is looked up with. Items with the same key
+/// are for the same media.
+fn get_lookup_key(item: &ImportOrExportMediaItem) -> Option<(MetadataLot, MetadataSource, String)> {
+    match item.internal_identifier.as_ref()? {
+        ImportOrExportItemIdentifier::NeedsDetails(i) => Some((item.lot, item.source, i.clone())),
+        ImportOrExportItemIdentifier::NeedsDetailsFromAny(ids) => ids
+            .first()
+            .map(|i| (item.lot, i.source, i.identifier.clone())),
+        ImportOrExportItemIdentifier::AlreadyFilled(d) => {
+            Some((d.lot, d.source, d.identifier.clone()))
+        }
+    }
+}
+
+/// Whether an import job that has not finished has stopped making progress, for
+/// eg: because it panicked or keeps failing to be resumed.
+fn is_import_job_stale(job: &import_report::Model, now: DateTimeUtc) -> bool {
+    let last_active_on = job
+        .progress
+        .as_ref()
+        .and_then(|p| p.updated_on)
+        .unwrap_or(job.started_on);
+    now - last_active_on > Duration::hours(24)
+}
+
+/// Get the first time after the given one at which a cron expression is due.
+fn get_next_scheduled_run(schedule: &str, after: DateTimeUtc) -> Option<DateTimeUtc> {
+    Schedule::from_str(schedule).ok()?.after(&after).next()
+}
+
+/// Get the time of the run of a scheduled import that follows the one that was
+/// due at `due_on`, leaving at least the minimum interval between them.
+fn get_next_scheduled_run_after(
+    schedule: &str,
+    due_on: DateTimeUtc,
+    now: DateTimeUtc,
+) -> Option<DateTimeUtc> {
+    // DEV: A second is taken off since the run at exactly the minimum interval
+    // is allowed.
+    let earliest = due_on + Duration::minutes(MIN_SCHEDULED_IMPORT_INTERVAL) - Duration::seconds(1);
+    get_next_scheduled_run(schedule, earliest.max(now))
+}
+
+/// Get the time before which the reports of finished imports are deleted, if they
+/// are deleted at all.
+fn get_report_retention_cutoff(retention_days: i64, now: DateTimeUtc) -> Option<DateTimeUtc> {
+    (retention_days > 0).then(|| now - Duration::days(retention_days))
+}
+
+/// Keep only the parts of the details of an old report that are still needed: the
+/// counts, the committed items and what is needed to revert it.
+fn prune_import_details(details: ImportResultResponse) -> ImportResultResponse {
+    ImportResultResponse {
+        import: details.import,
+        committed_items: details.committed_items,
+        source_event_ids: details.source_event_ids,
+        reverted_on: details.reverted_on,
+        undo: details.undo,
+        error: details.error,
+        failed_items: vec![],
+        failed_items_overflow: 0,
+        warnings: vec![],
+        items: vec![],
+        items_overflow: 0,
+        retry: None,
+        logs: ImportJobLogs::default(),
+    }
+}
+
+/// Whether the preferences from an export should replace the current ones of the
+/// user.
+fn should_apply_preferences(current: &UserPreferences, keep_customized: bool) -> bool {
+    !keep_customized || *current == UserPreferences::default()
+}
+
+fn check_import_job_owner(job: &import_report::Model, user_id: i32) -> Result<()> {
+    if job.user_id != user_id {
+        return Err(Error::new(format!(
+            "Import job with id = {id} was not deployed by user with id = {user_id}",
+            id = job.id
+        )));
+    }
+    Ok(())
+}
+
+/// Make sure that a deployed import job belongs to the user and has not been run
+/// already, for eg: if it was marked as failed while it was waiting to be run.
+fn check_import_job_can_start(job: &import_report::Model, user_id: i32) -> Result<()> {
+    check_import_job_owner(job, user_id)?;
+    if job.success.is_some() {
+        return Err(Error::new(format!(
+            "Import job with id = {id} has already finished",
+            id = job.id
+        )));
+    }
+    Ok(())
+}
+
+/// Identifies a workout of a user by the time it was started at and the exercises
+/// that were performed in it.
+type WorkoutIdentity = (DateTimeUtc, Vec<i32>);
+
+fn get_workout_identity(workout: &workout::Model) -> WorkoutIdentity {
+    let exercises = workout.information.exercises.iter().map(|e| e.id);
+    (workout.start_time, exercises.collect())
+}
+
+/// The identity of a completed seen history item, used to detect duplicates.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct SeenIdentity {
+    media: String,
+    date: Option<NaiveDate>,
+    show: Option<(i32, i32)>,
+    podcast_episode: Option<i32>,
+    part: Option<i32>,
+}
+
+impl SeenIdentity {
+    fn from_seen(media: String, seen: &seen::Model) -> Self {
+        let mut identity = Self {
+            media,
+            date: seen.finished_on,
+            show: None,
+            podcast_episode: None,
+            part: None,
+        };
+        match &seen.extra_information {
+            Some(SeenOrReviewOrCalendarEventExtraInformation::Show(s)) => {
+                identity.show = Some((s.season, s.episode))
+            }
+            Some(SeenOrReviewOrCalendarEventExtraInformation::Podcast(p)) => {
+                identity.podcast_episode = Some(p.episode)
+            }
+            Some(SeenOrReviewOrCalendarEventExtraInformation::Part(p)) => {
+                identity.part = Some(p.part)
+            }
+            _ => {}
+        }
+        identity
+    }
+
+    fn from_imported(
+        media: String,
+        date: Option<NaiveDate>,
+        seen: &ImportOrExportMediaItemSeen,
+    ) -> Self {
+        Self {
+            media,
+            date,
+            show: seen.show_season_number.zip(seen.show_episode_number),
+            podcast_episode: seen.podcast_episode_number,
+            part: seen.part_number,
+        }
+    }
+}
+
+/// The seen history items of the user, used to detect duplicates.
+#[derive(Debug, Default)]
+struct ExistingSeen {
+    items: HashSet<SeenIdentity>,
+    // DEV: The same items without their date, for lenient matching.
+    undated: HashSet<SeenIdentity>,
+    /// The media and progress of the items that are in progress.
+    in_progress: HashSet<(i32, i32)>,
+}
+
+impl ExistingSeen {
+    fn insert(&mut self, identity: SeenIdentity) {
+        self.undated.insert(SeenIdentity {
+            date: None,
+            ..identity.clone()
+        });
+        self.items.insert(identity);
+    }
+
+    /// Record an item that was added by the import. Items without a date are not
+    /// recorded, since a source lists media that was finished more than once
+    /// without dates as separate items, for eg: books that were read again.
+    fn insert_imported(&mut self, identity: SeenIdentity) {
+        if identity.date.is_some() {
+            self.insert(identity);
+        }
+    }
+
+    fn insert_in_progress(&mut self, metadata_id: i32, progress: i32) {
+        self.in_progress.insert((metadata_id, progress));
+    }
+
+    fn contains_in_progress(&self, metadata_id: i32, progress: i32) -> bool {
+        self.in_progress.contains(&(metadata_id, progress))
+    }
+
+    fn contains(&self, identity: &SeenIdentity, seen_match: ImportSeenMatch) -> bool {
+        if self.items.contains(identity) {
+            return true;
+        }
+        match (seen_match, identity.date) {
+            (ImportSeenMatch::Strict, _) => false,
+            (ImportSeenMatch::Lenient, None) => self.undated.contains(identity),
+            (ImportSeenMatch::Lenient, Some(_)) => self.items.contains(&SeenIdentity {
+                date: None,
+                ..identity.clone()
+            }),
+        }
+    }
+}
+
+/// The identity of a review, used to detect duplicates.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct ReviewIdentity {
+    metadata_id: Option<i32>,
+    metadata_group_id: Option<i32>,
+    person_id: Option<i32>,
+    show: Option<(i32, i32)>,
+    podcast_episode: Option<i32>,
+    date: Option<NaiveDate>,
+    text: Option<String>,
+    /// The rating out of 100, as it is stored.
+    rating: Option<Decimal>,
+}
+
+impl ReviewIdentity {
+    fn from_review(review: &review::Model) -> Self {
+        let mut identity = Self {
+            metadata_id: review.metadata_id,
+            metadata_group_id: review.metadata_group_id,
+            person_id: review.person_id,
+            show: None,
+            podcast_episode: None,
+            date: Some(review.posted_on.date_naive()),
+            text: review.text.clone(),
+            rating: review.rating,
+        };
+        match &review.extra_information {
+            Some(SeenOrReviewOrCalendarEventExtraInformation::Show(s)) => {
+                identity.show = Some((s.season, s.episode))
+            }
+            Some(SeenOrReviewOrCalendarEventExtraInformation::Podcast(p)) => {
+                identity.podcast_episode = Some(p.episode)
+            }
+            _ => {}
+        }
+        identity
+    }
+
+    fn from_input(input: &PostReviewInput, scale: UserReviewScale) -> Self {
+        Self {
+            metadata_id: input.metadata_id,
+            metadata_group_id: input.metadata_group_id,
+            person_id: input.person_id,
+            show: input.show_season_number.zip(input.show_episode_number),
+            podcast_episode: input.podcast_episode_number,
+            date: input.date.map(|d| d.date_naive()),
+            text: input.text.clone(),
+            rating: input.rating.map(|r| match scale {
+                UserReviewScale::OutOfFive => r * dec!(20),
+                UserReviewScale::OutOfHundred => r,
+            }),
+        }
+    }
+}
+
+/// The reviews of the user, used to detect duplicates.
+#[derive(Debug, Default)]
+struct ExistingReviews {
+    items: HashSet<ReviewIdentity>,
+    // DEV: The same reviews without their date. Reviews imported without a date
+    // are posted on the day of the import, so they match a review of any day.
+    undated: HashSet<ReviewIdentity>,
+}
+
+impl ExistingReviews {
+    fn insert(&mut self, identity: ReviewIdentity) {
+        self.undated.insert(ReviewIdentity {
+            date: None,
+            ..identity.clone()
+        });
+        self.items.insert(identity);
+    }
+
+    fn contains(&self, identity: &ReviewIdentity) -> bool {
+        match identity.date {
+            Some(_) => self.items.contains(identity),
+            None => self.undated.contains(identity),
+        }
+    }
+
+    /// Record a review that is being imported, returning whether it is not a
+    /// duplicate of an existing one.
+    fn insert_imported(&mut self, identity: ReviewIdentity) -> bool {
+        if self.contains(&identity) {
+            return false;
+        }
+        self.insert(identity);
+        true
+    }
+}
+
+/// Get the identity of a media according to the deduplication key.
+fn get_media_identity(dedup_key: ImportDedupKey, metadata: &metadata::Model) -> String {
+    match dedup_key {
+        ImportDedupKey::TitleAndYear => {
+            get_title_and_year_identity(metadata.lot, &metadata.title, metadata.publish_year)
+        }
+        _ => metadata.id.to_string(),
+    }
+}
+
+/// Identify a media by its title and publish year, ignoring case and punctuation.
+fn get_title_and_year_identity(lot: MetadataLot, title: &str, publish_year: Option<i32>) -> String {
+    let title = title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .join(" ");
+    format!("{lot:?}-{title}-{publish_year:?}")
+}
+
+/// Remove the media items which were part of an earlier import and return them
+/// along with the number of items that were removed.
+fn filter_previously_imported(
+    media: Vec<ImportOrExportMediaItem>,
+    previous: &HashSet<(MetadataLot, String)>,
+) -> (Vec<ImportOrExportMediaItem>, usize) {
+    let total = media.len();
+    let media = media
+        .into_iter()
+        .filter(|m| !previous.contains(&(m.lot, m.source_id.clone())))
+        .collect_vec();
+    let skipped = total - media.len();
+    (media, skipped)
+}
+
+/// Retain only the media items of the given types.
+fn filter_media_by_lots(
+    media: Vec<ImportOrExportMediaItem>,
+    lots: &[MetadataLot],
+) -> Vec<ImportOrExportMediaItem> {
+    media
+        .into_iter()
+        .filter(|m| lots.contains(&m.lot))
+        .collect()
+}
+
+/// Remove the workouts which already exist and return them along with the number
+/// of workouts that were removed. The identities of the remaining workouts are
+/// added to `existing` so that duplicates in the input are also removed.
+fn filter_duplicate_workouts(
+    workouts: Vec<UserWorkoutInput>,
+    existing: &mut HashSet<WorkoutIdentity>,
+) -> (Vec<UserWorkoutInput>, usize) {
+    let total = workouts.len();
+    let workouts = workouts
+        .into_iter()
+        .filter(|w| {
+            let exercises = w.exercises.iter().map(|e| e.exercise_id).collect();
+            existing.insert((w.start_time, exercises))
+        })
+        .collect_vec();
+    let skipped = total - workouts.len();
+    (workouts, skipped)
+}
+
+/// The collection that media of this lot is added to when it is imported with
+/// partial progress.
+fn get_in_progress_collection_name(lot: MetadataLot) -> &'static str {
+    match lot {
+        MetadataLot::Book | MetadataLot::Manga => "Currently Reading",
+        MetadataLot::AudioBook | MetadataLot::Podcast => "Currently Listening",
+        MetadataLot::VideoGame | MetadataLot::VisualNovel => "Currently Playing",
+        MetadataLot::Anime | MetadataLot::Movie | MetadataLot::Show => "Currently Watching",
+    }
+}
+
+/// A row of a CSV file along with its contents as they were in the file, which are
+/// kept in the report if the row can not be imported.
+struct CsvRow<T> {
+    raw: Option<String>,
+    record: csv::Result<T>,
+}
+
+/// Write a record of a CSV file back the way it would appear in the file.
+fn get_raw_csv_row(record: &csv::StringRecord) -> Option<String> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(record).ok()?;
+    let row = String::from_utf8(writer.into_inner().ok()?).ok()?;
+    Some(row.trim_end().to_owned())
+}
+
+/// Read the rows of a CSV file one at a time, so that large files do not have to
+/// be kept in memory.
+fn read_csv_rows<T: DeserializeOwned, R: Read>(
+    mut reader: csv::Reader<R>,
+) -> impl Iterator<Item = CsvRow<T>> {
+    let headers = reader.headers().ok().cloned();
+    reader.into_records().map(move |result| match result {
+        Ok(record) => CsvRow {
+            raw: get_raw_csv_row(&record),
+            record: record.deserialize(headers.as_ref()),
+        },
+        Err(e) => CsvRow {
+            raw: None,
+            record: Err(e),
+        },
+    })
+}
+
+/// The bytes that a UTF-8 byte order mark is encoded as.
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Get a reader for a CSV file which skips the byte order mark and, if `trim` is
+/// set, removes the whitespace around the headers and fields. Files exported on
+/// Windows often have these, which stops their headers from matching the
+/// expected ones.
+fn get_csv_reader<R: BufRead>(
+    mut data: R,
+    delimiter: u8,
+    trim: bool,
+) -> ImporterResult<csv::Reader<R>> {
+    let has_bom = data
+        .fill_buf()
+        .map_err(|e| ImporterError::parse("the CSV file", e))?
+        .starts_with(UTF8_BOM);
+    if has_bom {
+        data.consume(UTF8_BOM.len());
+    }
+    Ok(csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .trim(if trim {
+            csv::Trim::All
+        } else {
+            csv::Trim::None
+        })
+        .from_reader(data))
+}
+
+#[derive(Debug, Deserialize)]
+struct ReadingChallenge {
+    #[serde(rename = "Year")]
+    year: i32,
+    #[serde(rename = "Goal")]
+    goal: i32,
+}
+
+/// Get the reading goals from the CSV contents of the reading challenges, which
+/// the user writes using the goals they set in the source. Goodreads and
+/// StoryGraph do not export them, so the format is specific to Ryot: a `Year`
+/// and a `Goal` column with the number of books to read in that year.
+fn get_reading_goals(
+    challenges: Option<&str>,
+    trim_csv: bool,
+) -> ImporterResult<Vec<reading_goal::Model>> {
+    let Some(challenges) = challenges else {
+        return Ok(vec![]);
+    };
+    get_csv_reader(challenges.as_bytes(), b',', trim_csv)?
+        .into_deserialize::<ReadingChallenge>()
+        .map(|challenge| {
+            challenge
+                .map(|c| reading_goal::Model {
+                    user_id: 0,
+                    year: c.year,
+                    target: c.goal,
+                })
+                .map_err(|e| ImporterError::parse("the reading challenges", e))
+        })
+        .collect()
+}
+
+/// Get the date on which the episode that was seen aired, if it is known.
+fn get_episode_air_date(
+    specifics: &MediaSpecifics,
+    seen: &ImportOrExportMediaItemSeen,
+) -> Option<NaiveDate> {
+    match specifics {
+        MediaSpecifics::Show(s) => s
+            .get_episode(seen.show_season_number?, seen.show_episode_number?)
+            .and_then(|(_, e)| e.publish_date),
+        MediaSpecifics::Podcast(p) => p
+            .get_episode(seen.podcast_episode_number?)
+            .map(|e| e.publish_date),
+        _ => None,
+    }
+}
+
+/// Whether the seen item is for a whole season of a show rather than an episode.
+fn is_season_seen(seen: &ImportOrExportMediaItemSeen) -> bool {
+    seen.show_season_number.is_some() && seen.show_episode_number.is_none()
+}
+
+/// Replace the seen items for whole seasons of a show with one for each of their
+/// episodes. Also returns the seasons that the show does not have.
+fn expand_season_seen(
+    specifics: Option<&MediaSpecifics>,
+    seen_history: &[ImportOrExportMediaItemSeen],
+) -> (Vec<ImportOrExportMediaItemSeen>, Vec<i32>) {
+    let mut expanded = vec![];
+    let mut unknown_seasons = vec![];
+    for seen in seen_history {
+        let Some(season_number) = seen.show_season_number.filter(|_| is_season_seen(seen)) else {
+            expanded.push(seen.clone());
+            continue;
+        };
+        let season = match specifics {
+            Some(MediaSpecifics::Show(s)) => s
+                .seasons
+                .iter()
+                .find(|s| s.season_number == season_number && !s.episodes.is_empty()),
+            _ => None,
+        };
+        let Some(season) = season else {
+            unknown_seasons.push(season_number);
+            continue;
+        };
+        expanded.extend(season.episodes.iter().map(|e| ImportOrExportMediaItemSeen {
+            show_episode_number: Some(e.episode_number),
+            ..seen.clone()
+        }));
+    }
+    (expanded, unknown_seasons)
+}
+
+/// Reviews for a whole season of a show are attached to its last episode, since
+/// that is when the season was finished.
+fn get_last_episode_of_season(specifics: Option<&MediaSpecifics>, season: i32) -> Option<i32> {
+    match specifics {
+        Some(MediaSpecifics::Show(s)) => s
+            .seasons
+            .iter()
+            .find(|s| s.season_number == season)
+            .and_then(|s| s.episodes.iter().map(|e| e.episode_number).max()),
+        _ => None,
+    }
+}
+
+/// Compare the items of two imports, matching them by their type and identifier.
+fn diff_imported_items(before: Vec<ImportedItem>, after: Vec<ImportedItem>) -> ImportReportsDiff {
+    let mut before = before
+        .into_iter()
+        .map(|i| ((i.lot, i.identifier.clone()), i))
+        .collect::<HashMap<_, _>>();
+    let mut diff = ImportReportsDiff::default();
+    for item in after.into_iter() {
+        match before.remove(&(item.lot, item.identifier.clone())) {
+            None => diff.added.push(item),
+            Some(previous) => {
+                if previous.progress != item.progress || previous.rating != item.rating {
+                    diff.changed.push(ImportedItemChange {
+                        before: previous,
+                        after: item,
+                    });
+                }
+            }
+        }
+    }
+    diff.removed = before
+        .into_values()
+        .sorted_by(|a, b| a.identifier.cmp(&b.identifier))
+        .collect();
+    diff
+}
+
+/// The identifier to resolve an exported item with. The primary identifier is tried
+/// first, followed by the identifiers of the item on other providers.
+fn get_exported_item_identifier(item: &ImportOrExportMediaItem) -> ImportOrExportItemIdentifier {
+    let external = item.external_identifiers.clone().unwrap_or_default();
+    if external.is_empty() {
+        return ImportOrExportItemIdentifier::NeedsDetails(item.identifier.clone());
+    }
+    let mut ids = vec![MetadataExternalIdentifier {
+        source: item.source,
+        identifier: item.identifier.clone(),
+    }];
+    for id in external {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ImportOrExportItemIdentifier::NeedsDetailsFromAny(ids)
+}
+
+/// Get the percentage of the book that was read using its total number of pages.
+fn get_progress_from_pages_read(
+    specifics: &MediaSpecifics,
+    seen: &ImportOrExportMediaItemSeen,
+) -> Option<i32> {
+    let MediaSpecifics::Book(book) = specifics else {
+        return None;
+    };
+    let total = book.pages.filter(|p| *p > 0)?;
+    Some((seen.pages_read? * 100 / total).clamp(0, 100))
+}
+
+/// The rating of an imported review, ignoring ratings of zero if they mean that
+/// the item was not rated.
+fn get_imported_rating(
+    review: &ImportOrExportItemRating,
+    zero_rating_is_unrated: bool,
+) -> Option<Decimal> {
+    review
+        .rating
+        .filter(|r| !(zero_rating_is_unrated && r.is_zero()))
+}
+
+/// The review of an entity other than media, without the entity it is for.
+fn get_entity_review_input(
+    review: &ImportOrExportItemRating,
+    scale: UserReviewScale,
+) -> PostReviewInput {
+    PostReviewInput {
+        rating: review.rating.map(|r| convert_rating_to_scale(r, scale)),
+        text: review.review.clone().and_then(|r| r.text),
+        spoiler: review.review.clone().map(|r| r.spoiler.unwrap_or(false)),
+        date: review.review.clone().and_then(|r| r.date),
+        visibility: review.review.clone().and_then(|r| r.visibility),
+        ..Default::default()
+    }
+}
+
+/// Convert an imported rating (out of 100) to the review scale of the user.
+/// Ratings out of five are rounded to the nearest half-star, so sources that
+/// use half-stars keep their precision.
+pub fn convert_rating_to_scale(rating: Decimal, scale: UserReviewScale) -> Decimal {
+    match scale {
+        UserReviewScale::OutOfFive => {
+            (rating / dec!(10)).round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
+                / dec!(2)
+        }
+        UserReviewScale::OutOfHundred => rating,
+    }
+}
+
+/// Retain at most `max` failed items, picking them from each step in turn so
+/// that every kind of failure is represented. Returns the retained items (in
+/// their original order) and the number of items that were dropped.
+fn truncate_failed_items(
+    items: Vec<ImportFailedItem>,
+    max: usize,
+) -> (Vec<ImportFailedItem>, usize) {
+    if items.len() <= max {
+        return (items, 0);
+    }
+    let overflow = items.len() - max;
+    let mut groups: Vec<(ImportFailStep, Vec<usize>)> = vec![];
+    for (idx, item) in items.iter().enumerate() {
+        match groups.iter_mut().find(|(step, _)| *step == item.step) {
+            Some((_, group)) => group.push(idx),
+            None => groups.push((item.step, vec![idx])),
+        }
+    }
+    let mut kept = HashSet::new();
+    let mut round = 0;
+    while kept.len() < max {
+        for (_, group) in groups.iter() {
+            if kept.len() == max {
+                break;
+            }
+            if let Some(idx) = group.get(round) {
+                kept.insert(*idx);
+            }
+        }
+        round += 1;
+    }
+    let items = items
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, _)| kept.contains(idx))
+        .map(|(_, item)| item)
+        .collect();
+    (items, overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use apalis::sqlite::SqliteStorage;
+    use database::{Migrator, UserLot};
+    use schematic::ConfigLoader;
+    use sea_orm::Database;
+    use sea_orm_migration::MigratorTrait;
+    use sqlx::pool::PoolOptions;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{
+        miscellaneous::resolver::check_review_target,
+        models::{
+            media::{
+                BookSpecifics, PodcastEpisode, PodcastSpecifics, ShowEpisode, ShowSeason,
+                ShowSpecifics,
+            },
+            ImportOrExportRyotData,
+        },
+        utils::create_app_services,
+    };
+
+    /// Get an importer backed by a new SQLite database, along with the id of a user
+    /// in it. The database is deleted when the returned directory is dropped.
+    async fn get_test_importer() -> (Arc<ImporterService>, i32, TempDir) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("ryot.db");
+        let db = Database::connect(format!("sqlite:{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        // DEV: Every connection to an in-memory database gets its own, so the jobs
+        // need to use a single one.
+        let pool = PoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let storage = SqliteStorage::<ApplicationJob>::new(pool);
+        storage.setup().await.unwrap();
+        let config = ConfigLoader::<config::AppConfig>::new()
+            .load()
+            .unwrap()
+            .config;
+        let s3_client = aws_sdk_s3::Client::from_conf(aws_sdk_s3::Config::builder().build());
+        let services = create_app_services(
+            db.clone(),
+            s3_client,
+            Arc::new(config),
+            &storage,
+            chrono_tz::UTC,
+        )
+        .await;
+        let user = user::ActiveModel {
+            name: ActiveValue::Set("test".to_owned()),
+            password: ActiveValue::Set("test".to_owned()),
+            lot: ActiveValue::Set(UserLot::Admin),
+            preferences: ActiveValue::Set(UserPreferences::default()),
+            sink_integrations: ActiveValue::Set(vec![]),
+            notifications: ActiveValue::Set(vec![]),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        (services.importer_service, user.id, dir)
+    }
+
+    fn get_item(identifier: &str, progress: Option<i32>) -> ImportedItem {
+        ImportedItem {
+            lot: MetadataLot::Anime,
+            identifier: identifier.to_owned(),
+            progress,
+            rating: None,
+        }
+    }
+
+    fn get_media_item(source_id: &str, lot: MetadataLot) -> ImportOrExportMediaItem {
+        ImportOrExportMediaItem {
+            source_id: source_id.to_owned(),
+            lot,
+            source: MetadataSource::Tmdb,
+            identifier: "".to_owned(),
+            internal_identifier: None,
+            seen_history: vec![],
+            reviews: vec![],
+            collections: vec![],
+            external_identifiers: None,
+            is_favorite: None,
+            next_episode: None,
+            display_title: None,
+        }
+    }
+
+    #[test]
+    fn imports_are_diffed_by_identifier() {
+        let before = vec![
+            get_item("Cowboy Bebop", Some(100)),
+            get_item("Monster", Some(20)),
+            get_item("Trigun", None),
+        ];
+        let after = vec![
+            get_item("Cowboy Bebop", Some(100)),
+            get_item("Monster", Some(60)),
+            get_item("Mushishi", Some(10)),
+        ];
+        let diff = diff_imported_items(before, after);
+        assert_eq!(diff.added, vec![get_item("Mushishi", Some(10))]);
+        assert_eq!(diff.removed, vec![get_item("Trigun", None)]);
+        assert_eq!(
+            diff.changed,
+            vec![ImportedItemChange {
+                before: get_item("Monster", Some(20)),
+                after: get_item("Monster", Some(60)),
+            }]
+        );
+    }
+
+    #[test]
+    fn progress_is_calculated_from_pages_read() {
+        let specifics = MediaSpecifics::Book(BookSpecifics { pages: Some(400) });
+        let seen = |pages_read| ImportOrExportMediaItemSeen {
+            pages_read,
+            ..Default::default()
+        };
+        assert_eq!(
+            get_progress_from_pages_read(&specifics, &seen(Some(100))),
+            Some(25)
+        );
+        assert_eq!(
+            get_progress_from_pages_read(&specifics, &seen(Some(500))),
+            Some(100)
+        );
+        assert_eq!(get_progress_from_pages_read(&specifics, &seen(None)), None);
+        let unknown = MediaSpecifics::Book(BookSpecifics { pages: None });
+        assert_eq!(
+            get_progress_from_pages_read(&unknown, &seen(Some(100))),
+            None
+        );
+    }
+
+    #[test]
+    fn zero_rating_is_not_imported() {
+        let review = ImportOrExportItemRating {
+            rating: Some(dec!(0)),
+            ..Default::default()
+        };
+        assert_eq!(get_imported_rating(&review, true), None);
+        assert_eq!(get_imported_rating(&review, false), Some(dec!(0)));
+        let review = ImportOrExportItemRating {
+            rating: Some(dec!(80)),
+            ..Default::default()
+        };
+        assert_eq!(get_imported_rating(&review, true), Some(dec!(80)));
+    }
+
+    #[test]
+    fn exported_items_are_resolved_from_all_identifiers() {
+        let openlibrary = MetadataExternalIdentifier {
+            source: MetadataSource::Openlibrary,
+            identifier: "OL82563W".to_owned(),
+        };
+        let mut item = ImportOrExportMediaItem {
+            source_id: "Harry Potter and the Philosopher's Stone".to_owned(),
+            lot: MetadataLot::Book,
+            source: MetadataSource::Openlibrary,
+            identifier: "OL82563W".to_owned(),
+            internal_identifier: None,
+            seen_history: vec![],
+            reviews: vec![],
+            collections: vec![],
+            external_identifiers: None,
+            is_favorite: None,
+            next_episode: None,
+            display_title: None,
+        };
+        assert!(matches!(
+            get_exported_item_identifier(&item),
+            ImportOrExportItemIdentifier::NeedsDetails(i) if i == "OL82563W"
+        ));
+        let google_books = MetadataExternalIdentifier {
+            source: MetadataSource::GoogleBooks,
+            identifier: "wrOQLV6xB-wC".to_owned(),
+        };
+        item.external_identifiers = Some(vec![openlibrary.clone(), google_books.clone()]);
+        match get_exported_item_identifier(&item) {
+            ImportOrExportItemIdentifier::NeedsDetailsFromAny(ids) => {
+                assert_eq!(ids, vec![openlibrary, google_books]);
+            }
+            _ => panic!("expected multiple identifiers"),
+        }
+    }
+
+    #[test]
+    fn podcast_is_complete_when_every_episode_is_imported() {
+        let episode = |number| PodcastEpisode {
+            number,
+            id: number.to_string(),
+            runtime: None,
+            overview: None,
+            title: format!("Episode {number}"),
+            publish_date: NaiveDate::from_ymd_opt(2023, 1, number as u32).unwrap(),
+            thumbnail: None,
+        };
+        let specifics = PodcastSpecifics {
+            episodes: (1..=3).map(episode).collect(),
+            total_episodes: 3,
+        };
+        let seen_history = [3, 1, 2]
+            .into_iter()
+            .map(|e| ImportOrExportMediaItemSeen {
+                podcast_episode_number: Some(e),
+                ..Default::default()
+            })
+            .collect_vec();
+        let seen_episodes = |history: &[ImportOrExportMediaItemSeen]| {
+            history
+                .iter()
+                .filter_map(|s| s.podcast_episode_number)
+                .collect::<HashSet<_>>()
+        };
+        assert!(specifics.is_complete(&seen_episodes(&seen_history)));
+        assert!(!specifics.is_complete(&seen_episodes(&seen_history[..2])));
+        let partially_known = PodcastSpecifics {
+            total_episodes: 10,
+            ..specifics
+        };
+        assert!(!partially_known.is_complete(&seen_episodes(&seen_history)));
+    }
+
+    #[test]
+    fn truncated_failed_items_cover_every_step() {
+        let failed_item = |step, idx: usize| ImportFailedItem {
+            lot: MetadataLot::Book,
+            step,
+            identifier: idx.to_string(),
+            error: None,
+            code: ImportErrorCode::Unknown,
+            source_row: None,
+        };
+        let items = (0..10)
+            .map(|idx| failed_item(ImportFailStep::MediaDetailsFromProvider, idx))
+            .chain([
+                failed_item(ImportFailStep::ReviewConversion, 10),
+                failed_item(ImportFailStep::SeenHistoryConversion, 11),
+            ])
+            .collect_vec();
+        let (kept, overflow) = truncate_failed_items(items.clone(), 4);
+        assert_eq!(overflow, 8);
+        assert_eq!(
+            kept.iter().map(|i| i.identifier.as_str()).collect_vec(),
+            vec!["0", "1", "10", "11"]
+        );
+        let (kept, overflow) = truncate_failed_items(items.clone(), 20);
+        assert_eq!(overflow, 0);
+        assert_eq!(kept, items);
+    }
+
+    #[test]
+    fn only_temporary_importer_errors_are_transient() {
+        let network = ImporterError::network("the lists", "connection reset");
+        assert!(network.is_transient());
+        assert!(ImporterError::database("the exercises", "timed out").is_transient());
+        assert!(!ImporterError::parse("the export", "missing field").is_transient());
+        assert!(!ImporterError::auth("the user", StatusCode::Unauthorized).is_transient());
+        assert!(!ImporterError::provider("MAL", "invalid client id").is_transient());
+        assert_eq!(
+            network.to_string(),
+            "Could not fetch the lists: connection reset"
+        );
+    }
+
+    #[test]
+    fn reading_goals_are_read_from_challenges() {
+        let goals = get_reading_goals(Some("Year,Goal\n2022,40\n2023,50\n"), true).unwrap();
+        assert_eq!(
+            goals.iter().map(|g| (g.year, g.target)).collect_vec(),
+            vec![(2022, 40), (2023, 50)]
+        );
+        assert!(get_reading_goals(None, true).unwrap().is_empty());
+        assert!(get_reading_goals(Some("Year,Goal\n2023,many\n"), true).is_err());
+    }
+
+    #[test]
+    fn whole_seasons_are_expanded_into_their_episodes() {
+        let specifics = MediaSpecifics::Show(ShowSpecifics {
+            seasons: vec![ShowSeason {
+                season_number: 1,
+                episodes: (1..=3)
+                    .map(|episode_number| ShowEpisode {
+                        episode_number,
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }],
+        });
+        let ended_on = Some(Utc::now());
+        let seen = |season, episode| ImportOrExportMediaItemSeen {
+            ended_on,
+            show_season_number: Some(season),
+            show_episode_number: episode,
+            ..Default::default()
+        };
+        let (expanded, unknown) = expand_season_seen(
+            Some(&specifics),
+            &[seen(1, None), seen(1, Some(2)), seen(4, None)],
+        );
+        assert_eq!(
+            expanded
+                .iter()
+                .map(|s| (s.show_episode_number, s.ended_on))
+                .collect_vec(),
+            vec![
+                (Some(1), ended_on),
+                (Some(2), ended_on),
+                (Some(3), ended_on),
+                (Some(2), ended_on)
+            ]
+        );
+        assert_eq!(unknown, vec![4]);
+        assert_eq!(get_last_episode_of_season(Some(&specifics), 1), Some(3));
+        assert_eq!(get_last_episode_of_season(Some(&specifics), 2), None);
+    }
+
+    #[test]
+    fn provider_id_dedup_matches_the_same_episode_on_the_same_date() {
+        let date = NaiveDate::from_ymd_opt(2023, 6, 1);
+        let seen = |episode| ImportOrExportMediaItemSeen {
+            show_season_number: Some(1),
+            show_episode_number: Some(episode),
+            ..Default::default()
+        };
+        let existing = HashSet::from([SeenIdentity::from_imported("7".to_owned(), date, &seen(1))]);
+        assert!(existing.contains(&SeenIdentity::from_imported("7".to_owned(), date, &seen(1))));
+        assert!(!existing.contains(&SeenIdentity::from_imported("7".to_owned(), date, &seen(2))));
+        assert!(!existing.contains(&SeenIdentity::from_imported("8".to_owned(), date, &seen(1))));
+        assert!(!existing.contains(&SeenIdentity::from_imported(
+            "7".to_owned(),
+            NaiveDate::from_ymd_opt(2023, 6, 2),
+            &seen(1)
+        )));
+    }
+
+    #[test]
+    fn title_and_year_dedup_ignores_case_and_punctuation() {
+        let identity = get_title_and_year_identity(MetadataLot::Movie, "The Matrix", Some(1999));
+        assert_eq!(
+            identity,
+            get_title_and_year_identity(MetadataLot::Movie, "the matrix!", Some(1999))
+        );
+        assert_ne!(
+            identity,
+            get_title_and_year_identity(MetadataLot::Movie, "The Matrix", Some(2021))
+        );
+        assert_ne!(
+            identity,
+            get_title_and_year_identity(MetadataLot::Book, "The Matrix", Some(1999))
+        );
+    }
+
+    #[test]
+    fn source_event_id_dedup_skips_previously_imported_items() {
+        let previous = HashSet::from([(MetadataLot::Movie, "1".to_owned())]);
+        let (media, skipped) = filter_previously_imported(
+            vec![
+                get_media_item("1", MetadataLot::Movie),
+                get_media_item("1", MetadataLot::Show),
+                get_media_item("2", MetadataLot::Movie),
+            ],
+            &previous,
+        );
+        assert_eq!(skipped, 1);
+        assert_eq!(
+            media
+                .iter()
+                .map(|m| (m.lot, m.source_id.as_str()))
+                .collect_vec(),
+            vec![(MetadataLot::Show, "1"), (MetadataLot::Movie, "2")]
+        );
+    }
+
+    #[test]
+    fn ratings_out_of_five_keep_half_stars() {
+        let to_five = |r| convert_rating_to_scale(r, UserReviewScale::OutOfFive);
+        assert_eq!(to_five(dec!(10)), dec!(0.5));
+        assert_eq!(to_five(dec!(70)), dec!(3.5));
+        assert_eq!(to_five(dec!(90)), dec!(4.5));
+        assert_eq!(to_five(dec!(100)), dec!(5));
+    }
+
+    #[test]
+    fn ratings_out_of_five_are_rounded_to_nearest_half_star() {
+        let to_five = |r| convert_rating_to_scale(r, UserReviewScale::OutOfFive);
+        assert_eq!(to_five(dec!(62)), dec!(3));
+        assert_eq!(to_five(dec!(73)), dec!(3.5));
+        assert_eq!(to_five(dec!(75)), dec!(4));
+        assert_eq!(to_five(dec!(33.33)), dec!(1.5));
+        assert_eq!(
+            convert_rating_to_scale(dec!(73), UserReviewScale::OutOfHundred),
+            dec!(73)
+        );
+    }
+
+    #[test]
+    fn only_selected_lots_are_imported() {
+        let media = vec![
+            get_media_item("1", MetadataLot::Movie),
+            get_media_item("2", MetadataLot::Show),
+            get_media_item("3", MetadataLot::Movie),
+            get_media_item("4", MetadataLot::Show),
+        ];
+        let media = filter_media_by_lots(media, &[MetadataLot::Movie]);
+        assert_eq!(
+            media
+                .iter()
+                .map(|m| (m.lot, m.source_id.as_str()))
+                .collect_vec(),
+            vec![(MetadataLot::Movie, "1"), (MetadataLot::Movie, "3")]
+        );
+    }
+
+    #[test]
+    fn partial_ryot_export_is_read_without_preferences() {
+        let data: ImportOrExportRyotData =
+            serde_json::from_str(r#"{"workouts": [], "exercises": []}"#).unwrap();
+        assert!(data.media.is_empty());
+        assert!(data.collections.is_empty());
+        assert!(data.preferences.is_none());
+    }
+
+    #[test]
+    fn csv_is_cleaned_while_it_is_read() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Row {
+            #[serde(rename = "Date")]
+            date: String,
+            #[serde(rename = "Title")]
+            title: String,
+        }
+        let data = "\u{feff} Date ; Title \n2023-01-01 ; Dune \n2023-02-01;\"Dune; Part Two\"\n";
+        let read = |trim| {
+            get_csv_reader(data.as_bytes(), b';', trim)
+                .unwrap()
+                .into_deserialize::<Row>()
+                .collect::<Result<Vec<_>, _>>()
+        };
+        assert_eq!(
+            read(true).unwrap(),
+            vec![
+                Row {
+                    date: "2023-01-01".to_owned(),
+                    title: "Dune".to_owned(),
+                },
+                Row {
+                    date: "2023-02-01".to_owned(),
+                    title: "Dune; Part Two".to_owned(),
+                }
+            ]
+        );
+        let mut untrimmed = get_csv_reader(data.as_bytes(), b';', false).unwrap();
+        assert_eq!(untrimmed.headers().unwrap().get(0), Some(" Date "));
+        assert!(read(false).is_err());
+    }
+
+    #[test]
+    fn import_job_of_another_user_is_rejected() {
+        let job = import_report::Model {
+            id: 1,
+            user_id: 1,
+            source: ImportSource::Trakt,
+            started_on: Utc::now(),
+            finished_on: None,
+            details: None,
+            success: None,
+            progress: None,
+            payload: None,
+            running_since: None,
+        };
+        assert!(check_import_job_owner(&job, 1).is_ok());
+        assert!(check_import_job_owner(&job, 2).is_err());
+        assert!(check_import_job_can_start(&job, 1).is_ok());
+        assert!(check_import_job_can_start(&job, 2).is_err());
+        let finished = import_report::Model {
+            success: Some(false),
+            ..job.clone()
+        };
+        assert!(check_import_job_can_start(&finished, 1).is_err());
+    }
+
+    #[test]
+    fn deployed_import_job_carries_its_report() {
+        let input: DeployImportJobInput = serde_json::from_value(serde_json::json!({
+            "source": "Trakt",
+            "trakt": { "username": "test" },
+        }))
+        .unwrap();
+        let job = ApplicationJob::ImportFromExternalSource(1, 7, input);
+        let job = serde_json::to_string(&job).unwrap();
+        let job = serde_json::from_str::<ApplicationJob>(&job).unwrap();
+        match job {
+            ApplicationJob::ImportFromExternalSource(user_id, job_id, _) => {
+                assert_eq!((user_id, job_id), (1, 7))
+            }
+            _ => panic!("Unexpected job"),
+        }
+    }
+
+    #[test]
+    fn imported_group_review_has_a_single_target() {
+        let review = ImportOrExportItemRating {
+            rating: Some(dec!(80)),
+            ..Default::default()
+        };
+        let input = PostReviewInput {
+            metadata_group_id: Some(1),
+            ..get_entity_review_input(&review, UserReviewScale::OutOfFive)
+        };
+        assert_eq!(input.rating, Some(dec!(4)));
+        assert!(check_review_target(&input).is_ok());
+        let input = PostReviewInput {
+            metadata_id: Some(1),
+            ..input
+        };
+        assert!(check_review_target(&input).is_err());
+        assert!(check_review_target(&PostReviewInput::default()).is_err());
+    }
+
+    #[tokio::test]
+    async fn group_reviews_are_posted_when_importing() {
+        let (importer, user_id, _dir) = get_test_importer().await;
+        let db = &importer.media_service.db;
+        let group = metadata_group::ActiveModel {
+            parts: ActiveValue::Set(2),
+            identifier: ActiveValue::Set("1".to_owned()),
+            title: ActiveValue::Set("Cowboy Bebop".to_owned()),
+            images: ActiveValue::Set(vec![]),
+            lot: ActiveValue::Set(MetadataLot::Anime),
+            source: ActiveValue::Set(MetadataSource::Mal),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .unwrap();
+        let input: DeployImportJobInput =
+            serde_json::from_value(serde_json::json!({ "source": "Ryot" })).unwrap();
+        let get_import = || ImportResult {
+            metadata_groups_reviewed: vec![ImportOrExportMetadataGroupItem {
+                title: group.title.clone(),
+                lot: group.lot,
+                source: group.source,
+                identifier: group.identifier.clone(),
+                reviews: vec![ImportOrExportItemRating {
+                    rating: Some(dec!(80)),
+                    ..Default::default()
+                }],
+            }],
+            ..Default::default()
+        };
+        let job = importer
+            .start_import_job(user_id, ImportSource::Ryot)
+            .await
+            .unwrap();
+        importer
+            .import_media(user_id, job.id, &input, get_import())
+            .await
+            .unwrap();
+        let get_reviews = || {
+            Review::find()
+                .filter(review::Column::MetadataGroupId.eq(group.id))
+                .all(db)
+        };
+        let reviews = get_reviews().await.unwrap();
+        assert_eq!(reviews.len(), 1);
+        assert_eq!(reviews[0].user_id, user_id);
+        assert_eq!(reviews[0].rating, Some(dec!(80)));
+        assert_eq!(reviews[0].import_report_id, Some(job.id));
+        let job = importer
+            .start_import_job(user_id, ImportSource::Ryot)
+            .await
+            .unwrap();
+        importer
+            .import_media(user_id, job.id, &input, get_import())
+            .await
+            .unwrap();
+        assert_eq!(get_reviews().await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn customized_preferences_are_kept_if_requested() {
+        let default = UserPreferences::default();
+        let mut customized = UserPreferences::default();
+        customized.general.review_scale = UserReviewScale::OutOfFive;
+        assert!(should_apply_preferences(&default, true));
+        assert!(should_apply_preferences(&customized, false));
+        assert!(!should_apply_preferences(&customized, true));
+    }
+
+    #[test]
+    fn retryable_items_keep_their_identifiers() {
+        let mut movie = get_media_item("Heat", MetadataLot::Movie);
+        movie.internal_identifier =
+            Some(ImportOrExportItemIdentifier::NeedsDetails("949".to_owned()));
+        movie.collections = vec!["Watchlist".to_owned()];
+        let mut anime = get_media_item("Cowboy Bebop", MetadataLot::Anime);
+        anime.source = MetadataSource::Anilist;
+        anime.internal_identifier = Some(ImportOrExportItemIdentifier::NeedsDetailsFromAny(vec![
+            MetadataExternalIdentifier {
+                source: MetadataSource::Anilist,
+                identifier: "1".to_owned(),
+            },
+            MetadataExternalIdentifier {
+                source: MetadataSource::Mal,
+                identifier: "1".to_owned(),
+            },
+        ]));
+        let movie = ImportRetryableItem::new(&movie)
+            .and_then(ImportRetryableItem::into_item)
+            .unwrap();
+        assert_eq!(movie.collections, vec!["Watchlist".to_owned()]);
+        assert!(matches!(
+            movie.internal_identifier,
+            Some(ImportOrExportItemIdentifier::NeedsDetails(i)) if i == "949"
+        ));
+        let anime = ImportRetryableItem::new(&anime)
+            .and_then(ImportRetryableItem::into_item)
+            .unwrap();
+        assert!(matches!(
+            anime.internal_identifier,
+            Some(ImportOrExportItemIdentifier::NeedsDetailsFromAny(ids)) if ids.len() == 2
+        ));
+        let unresolved = get_media_item("Dune", MetadataLot::Movie);
+        assert!(ImportRetryableItem::new(&unresolved).is_none());
+    }
+
+    #[test]
+    fn finish_of_import_is_estimated_from_processed_items() {
+        let started_on = Utc::now();
+        let now = started_on + Duration::seconds(30);
+        let progress = ImportProgress::new(0, 10, None, started_on, now);
+        assert_eq!(progress.estimated_finish_on, None);
+        let progress = ImportProgress::new(3, 10, None, started_on, now);
+        assert_eq!(
+            progress.estimated_finish_on,
+            Some(now + Duration::seconds(70))
+        );
+        let progress = ImportProgress::new(10, 10, None, started_on, now);
+        assert_eq!(progress.estimated_finish_on, Some(now));
+    }
+
+    #[test]
+    fn stored_payload_does_not_keep_the_source_data() {
+        let input: DeployImportJobInput = serde_json::from_value(serde_json::json!({
+            "source": "Kitsu",
+            "kitsu": {"username": "spike"},
+            "watched_with": ["Jet"],
+        }))
+        .unwrap();
+        let items = ["Cowboy Bebop", "Trigun", "Mushishi"].map(|t| {
+            let mut item = get_media_item(t, MetadataLot::Anime);
+            item.internal_identifier =
+                Some(ImportOrExportItemIdentifier::NeedsDetails(t.to_owned()));
+            ImportRetryableItem::new(&item).unwrap()
+        });
+        let payload = ImportRetry::new(&input, items.to_vec()).unwrap();
+        assert_eq!(payload.input["kitsu"], serde_json::Value::Null);
+        assert_eq!(payload.input["watched_with"], serde_json::json!(["Jet"]));
+        let remaining = payload
+            .items
+            .into_iter()
+            .skip(1)
+            .filter_map(ImportRetryableItem::into_item)
+            .map(|i| i.source_id)
+            .collect_vec();
+        assert_eq!(remaining, vec!["Trigun", "Mushishi"]);
+        assert!(ImportRetry::new(&input, vec![]).is_none());
+    }
+
+    #[test]
+    fn lenient_seen_match_ignores_missing_dates() {
+        let seen = ImportOrExportMediaItemSeen::default();
+        let date = NaiveDate::from_ymd_opt(2023, 6, 1);
+        let identity = |date| SeenIdentity::from_imported("7".to_owned(), date, &seen);
+        let mut existing = ExistingSeen::default();
+        existing.insert(identity(date));
+        assert!(existing.contains(&identity(date), ImportSeenMatch::Strict));
+        assert!(!existing.contains(&identity(None), ImportSeenMatch::Strict));
+        assert!(existing.contains(&identity(None), ImportSeenMatch::Lenient));
+        let other_date = NaiveDate::from_ymd_opt(2023, 6, 2);
+        assert!(!existing.contains(&identity(other_date), ImportSeenMatch::Lenient));
+        existing.insert(identity(None));
+        assert!(existing.contains(&identity(other_date), ImportSeenMatch::Lenient));
+        assert!(!existing.contains(&identity(other_date), ImportSeenMatch::Strict));
+    }
+
+    #[test]
+    fn imported_reviews_match_existing_ones() {
+        let posted_on = DateTimeUtc::from_str("2023-06-01T10:00:00Z").unwrap();
+        let existing_review = review::Model {
+            id: 1,
+            posted_on,
+            rating: Some(dec!(80)),
+            text: Some("Great".to_owned()),
+            visibility: Visibility::Public,
+            spoiler: false,
+            user_id: 1,
+            metadata_id: Some(7),
+            person_id: None,
+            extra_information: None,
+            comments: vec![],
+            metadata_group_id: None,
+            collection_id: None,
+            seen_id: None,
+            import_report_id: None,
+        };
+        let mut existing = ExistingReviews::default();
+        existing.insert(ReviewIdentity::from_review(&existing_review));
+        let identity = |rating, date| {
+            let input = PostReviewInput {
+                rating: Some(rating),
+                text: Some("Great".to_owned()),
+                metadata_id: Some(7),
+                date,
+                ..Default::default()
+            };
+            ReviewIdentity::from_input(&input, UserReviewScale::OutOfFive)
+        };
+        assert!(!existing.insert_imported(identity(dec!(4), Some(posted_on))));
+        assert!(!existing.insert_imported(identity(dec!(4), None)));
+        assert!(existing.insert_imported(identity(dec!(3), Some(posted_on))));
+        assert!(!existing.insert_imported(identity(dec!(3), Some(posted_on))));
+    }
+
+    #[test]
+    fn in_progress_items_with_the_same_progress_are_duplicates() {
+        let mut existing = ExistingSeen::default();
+        existing.insert_in_progress(7, 40);
+        assert!(existing.contains_in_progress(7, 40));
+        assert!(!existing.contains_in_progress(7, 60));
+        assert!(!existing.contains_in_progress(8, 40));
+    }
+
+    #[test]
+    fn undated_items_of_the_same_import_are_not_duplicates() {
+        let seen = ImportOrExportMediaItemSeen::default();
+        let date = NaiveDate::from_ymd_opt(2023, 6, 1);
+        let identity = |date| SeenIdentity::from_imported("7".to_owned(), date, &seen);
+        let mut existing = ExistingSeen::default();
+        existing.insert_imported(identity(None));
+        assert!(!existing.contains(&identity(None), ImportSeenMatch::Strict));
+        assert!(!existing.contains(&identity(None), ImportSeenMatch::Lenient));
+        existing.insert_imported(identity(date));
+        assert!(existing.contains(&identity(date), ImportSeenMatch::Strict));
+    }
+
+    #[test]
+    fn provider_errors_are_categorized() {
+        let code = ImportErrorCode::from_provider_message;
+        assert_eq!(
+            code("Status 429: Too Many Requests"),
+            ImportErrorCode::ProviderRateLimited
+        );
+        assert_eq!(code("The media was Not Found"), ImportErrorCode::NotFound);
+        assert_eq!(code("Connection reset"), ImportErrorCode::ProviderError);
+    }
+
+    #[test]
+    fn csv_rows_keep_their_source() {
+        #[derive(Debug, Deserialize)]
+        struct Row {
+            #[serde(rename = "Year")]
+            _year: i32,
+        }
+        let data = "Title,Year\n\"Heat, Again\",1995\nAlien,soon\n";
+        let rows = read_csv_rows::<Row, _>(csv::Reader::from_reader(data.as_bytes())).collect_vec();
+        assert!(rows[0].record.is_ok());
+        assert_eq!(rows[0].raw.as_deref(), Some("\"Heat, Again\",1995"));
+        assert!(rows[1].record.is_err());
+        assert_eq!(rows[1].raw.as_deref(), Some("Alien,soon"));
+    }
+
+    #[test]
+    fn retryable_item_is_taken_by_its_identifier() {
+        let input: DeployImportJobInput = serde_json::from_value(serde_json::json!({
+            "source": "Kitsu",
+        }))
+        .unwrap();
+        let items = ["Cowboy Bebop", "Trigun"].map(|t| {
+            let mut item = get_media_item(t, MetadataLot::Anime);
+            item.internal_identifier =
+                Some(ImportOrExportItemIdentifier::NeedsDetails(t.to_owned()));
+            ImportRetryableItem::new(&item).unwrap()
+        });
+        let mut retry = ImportRetry::new(&input, items.to_vec()).unwrap();
+        assert!(retry.take_item("Mushishi").is_none());
+        let item = retry.take_item("Trigun").unwrap();
+        assert_eq!(item.lot, MetadataLot::Anime);
+        assert_eq!(retry.items, vec![items[0].clone()]);
+    }
+
+    #[test]
+    fn only_remote_imports_can_be_scheduled() {
+        let input = |value| serde_json::from_value::<DeployImportJobInput>(value).unwrap();
+        let rss = input(serde_json::json!({
+            "source": "Goodreads",
+            "goodreads": { "rss_url": "https://www.goodreads.com/review/list_rss/1" },
+        }));
+        assert!(rss.is_remote());
+        let csv = input(serde_json::json!({
+            "source": "Goodreads",
+            "goodreads": { "csv": "Title\nDune\n" },
+        }));
+        assert!(!csv.is_remote());
+        assert!(!input(serde_json::json!({ "source": "Kitsu" })).is_remote());
+    }
+
+    #[test]
+    fn scheduled_runs_follow_the_cron_expression() {
+        let after = DateTimeUtc::from_str("2023-11-17T10:30:00Z").unwrap();
+        let next = get_next_scheduled_run("0 0 */6 * * *", after);
+        assert_eq!(next, DateTimeUtc::from_str("2023-11-17T12:00:00Z").ok());
+        assert!(get_next_scheduled_run("every day", after).is_none());
+    }
+
+    #[test]
+    fn scheduled_runs_keep_the_minimum_interval() {
+        let due_on = DateTimeUtc::from_str("2023-11-17T10:00:00Z").unwrap();
+        let now = DateTimeUtc::from_str("2023-11-17T10:00:30Z").unwrap();
+        let next = |schedule| get_next_scheduled_run_after(schedule, due_on, now);
+        assert_eq!(
+            next("0 0 * * * *"),
+            DateTimeUtc::from_str("2023-11-17T11:00:00Z").ok()
+        );
+        assert_eq!(
+            next("* * * * * *"),
+            DateTimeUtc::from_str("2023-11-17T11:00:00Z").ok()
+        );
+        let late = DateTimeUtc::from_str("2023-11-17T13:30:00Z").unwrap();
+        assert_eq!(
+            get_next_scheduled_run_after("0 0 * * * *", due_on, late),
+            DateTimeUtc::from_str("2023-11-17T14:00:00Z").ok()
+        );
+    }
+
+    #[test]
+    fn import_jobs_without_recent_progress_are_stale() {
+        let now = DateTimeUtc::from_str("2023-11-18T00:00:00Z").unwrap();
+        let mut job = import_report::Model {
+            id: 1,
+            user_id: 1,
+            source: ImportSource::Trakt,
+            started_on: now - Duration::hours(30),
+            finished_on: None,
+            details: None,
+            success: None,
+            progress: None,
+            payload: None,
+            running_since: None,
+        };
+        assert!(is_import_job_stale(&job, now));
+        job.progress = Some(ImportProgress::new(
+            5,
+            10,
+            None,
+            job.started_on,
+            now - Duration::hours(1),
+        ));
+        assert!(!is_import_job_stale(&job, now));
+        job.progress = Some(ImportProgress::new(
+            5,
+            10,
+            None,
+            job.started_on,
+            now - Duration::hours(25),
+        ));
+        assert!(is_import_job_stale(&job, now));
+    }
+
+    #[test]
+    fn items_for_the_same_media_share_a_lookup() {
+        let item = |source_id: &str, identifier: &str| {
+            let mut item = get_media_item(source_id, MetadataLot::Movie);
+            item.internal_identifier = Some(ImportOrExportItemIdentifier::NeedsDetails(
+                identifier.to_owned(),
+            ));
+            item
+        };
+        let key = get_lookup_key(&item("Heat", "949"));
+        assert_eq!(
+            key,
+            Some((MetadataLot::Movie, MetadataSource::Tmdb, "949".to_owned()))
+        );
+        assert_eq!(key, get_lookup_key(&item("Heat (1995)", "949")));
+        assert_ne!(key, get_lookup_key(&item("Alien", "348")));
+        assert_eq!(
+            get_lookup_key(&get_media_item("Dune", MetadataLot::Movie)),
+            None
+        );
+    }
+
+    #[test]
+    fn only_uploads_of_the_user_can_be_imported() {
+        assert!(is_user_upload_key(1, "uploads/1/3f2a-export.json"));
+        assert!(is_user_upload_key(1, "exports/1/1700000000000.json"));
+        assert!(!is_user_upload_key(1, "uploads/12/3f2a-export.json"));
+        assert!(!is_user_upload_key(1, "backups/1/1700000000000.json"));
+        assert!(!is_user_upload_key(1, "exports/2/1700000000000.json"));
+        assert!(!is_user_upload_key(1, "uploads/1/../2/3f2a-export.json"));
+        assert!(!is_user_upload_key(1, "/etc/passwd"));
+    }
+
+    #[test]
+    fn only_old_reports_are_deleted() {
+        let now = DateTimeUtc::from_str("2023-11-18T00:00:00Z").unwrap();
+        assert_eq!(
+            get_report_retention_cutoff(30, now),
+            DateTimeUtc::from_str("2023-10-19T00:00:00Z").ok()
+        );
+        assert!(get_report_retention_cutoff(0, now).is_none());
+    }
+
+    #[test]
+    fn pruned_reports_keep_their_committed_items() {
+        let details = ImportResultResponse {
+            import: ImportDetails {
+                total: 2,
+                skipped: 0,
+                skipped_seen_history: 0,
+            },
+            failed_items: vec![],
+            failed_items_overflow: 1,
+            error: None,
+            warnings: vec!["The summary could not be recalculated".to_owned()],
+            items: vec![get_item("1", Some(100)), get_item("2", None)],
+            items_overflow: 0,
+            committed_items: vec![(MetadataLot::Movie, "1".to_owned())],
+            source_event_ids: vec![],
+            reverted_on: None,
+            retry: None,
+            logs: ImportJobLogs {
+                entries: vec![],
+                overflow: 3,
+            },
+            undo: ImportUndo::default(),
+        };
+        let pruned = prune_import_details(details.clone());
+        assert_eq!(pruned.import, details.import);
+        assert_eq!(pruned.committed_items, details.committed_items);
+        assert!(pruned.items.is_empty());
+        assert!(pruned.warnings.is_empty());
+        assert_eq!(pruned.failed_items_overflow, 0);
+        assert_eq!(pruned.logs, ImportJobLogs::default());
+        assert_eq!(prune_import_details(pruned.clone()), pruned);
     }
 }
@@ -1,21 +1,22 @@
-use async_graphql::Result;
 use chrono::NaiveDate;
-use csv::Reader;
 use database::{MetadataLot, MetadataSource};
 use rs_utils::convert_naive_to_utc;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use surf::Client;
 
 use crate::{
     importer::{
-        DeployMovaryImportInput, ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier,
-        ImportOrExportMediaItem, ImportResult,
+        get_csv_reader, get_source_json, read_csv_rows, DeployMovaryImportInput, ImportErrorCode,
+        ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier, ImportOrExportMediaItem,
+        ImportResult, ImporterError, ImporterResult,
     },
     miscellaneous::DefaultCollection,
     models::media::{
         ImportOrExportItemRating, ImportOrExportItemReview, ImportOrExportMediaItemSeen,
     },
+    utils::get_base_http_client,
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -38,29 +39,202 @@ struct Rating {
 struct History {
     #[serde(flatten)]
     common: Common,
-    watched_at: NaiveDate,
+    watched_at: Option<NaiveDate>,
     comment: Option<String>,
+    /// Where the movie was watched. Only present in newer exports.
+    #[serde(default)]
+    location: Option<String>,
 }
 
-pub async fn import(input: DeployMovaryImportInput) -> Result<ImportResult> {
-    let lot = MetadataLot::Movie;
-    let source = MetadataSource::Tmdb;
-    let mut media = vec![];
+/// The rows of the exported files, or the equivalent data from the API.
+#[derive(Debug, Default)]
+struct Records {
+    ratings: Vec<Rating>,
+    watchlist: Vec<Common>,
+    history: Vec<History>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiMovie {
+    #[serde(flatten)]
+    common: Common,
+    user_rating: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiEntry {
+    movie: ApiMovie,
+    watched_at: Option<NaiveDate>,
+    comment: Option<String>,
+    location: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiPage {
+    #[serde(alias = "history", alias = "watchlist")]
+    items: Vec<ApiEntry>,
+    current_page: i32,
+    max_page: i32,
+}
+
+// DEV: The maximum allowed by the API
+const PAGE_LIMIT: i32 = 100;
+
+pub async fn import(
+    input: DeployMovaryImportInput,
+    trim_csv: bool,
+) -> ImporterResult<ImportResult> {
     let mut failed_items = vec![];
-    let mut ratings_reader = Reader::from_reader(input.ratings.as_bytes());
-    for (idx, result) in ratings_reader.deserialize().enumerate() {
-        let record: Rating = match result {
-            Ok(r) => r,
-            Err(e) => {
-                failed_items.push(ImportFailedItem {
-                    lot,
-                    step: ImportFailStep::InputTransformation,
-                    identifier: idx.to_string(),
-                    error: Some(format!("Ratings file: {:#?}", e)),
+    let records = match input.api_url.as_ref() {
+        Some(api_url) => get_api_records(api_url, &input).await?,
+        None => get_csv_records(&input, trim_csv, &mut failed_items)?,
+    };
+    Ok(ImportResult {
+        collections: vec![],
+        media: convert_records(records),
+        failed_items,
+        workouts: vec![],
+        ..Default::default()
+    })
+}
+
+fn read_file<T: DeserializeOwned>(
+    data: Option<&String>,
+    context: &str,
+    trim_csv: bool,
+    failed_items: &mut Vec<ImportFailedItem>,
+) -> ImporterResult<Vec<T>> {
+    let Some(data) = data else {
+        return Ok(vec![]);
+    };
+    let reader = get_csv_reader(data.as_bytes(), b',', trim_csv)?;
+    let mut records = vec![];
+    for (idx, row) in read_csv_rows::<T, _>(reader).enumerate() {
+        match row.record {
+            Ok(r) => records.push(r),
+            Err(e) => failed_items.push(ImportFailedItem {
+                lot: MetadataLot::Movie,
+                step: ImportFailStep::InputTransformation,
+                identifier: idx.to_string(),
+                error: Some(ImporterError::parse(context, e).to_string()),
+                code: ImportErrorCode::ParseError,
+                source_row: row.raw,
+            }),
+        }
+    }
+    Ok(records)
+}
+
+fn get_csv_records(
+    input: &DeployMovaryImportInput,
+    trim_csv: bool,
+    failed_items: &mut Vec<ImportFailedItem>,
+) -> ImporterResult<Records> {
+    Ok(Records {
+        ratings: read_file(
+            input.ratings.as_ref(),
+            "the ratings file",
+            trim_csv,
+            failed_items,
+        )?,
+        watchlist: read_file(
+            input.watchlist.as_ref(),
+            "the watchlist file",
+            trim_csv,
+            failed_items,
+        )?,
+        history: read_file(
+            input.history.as_ref(),
+            "the history file",
+            trim_csv,
+            failed_items,
+        )?,
+    })
+}
+
+async fn get_api_pages(
+    client: &Client,
+    path: &str,
+    context: &str,
+) -> ImporterResult<Vec<ApiEntry>> {
+    let mut entries = vec![];
+    let mut page = 1;
+    loop {
+        let response: ApiPage = get_source_json(
+            client
+                .get(path)
+                .query(&serde_json::json!({ "page": page, "limit": PAGE_LIMIT }))
+                .map_err(|e| ImporterError::parse(context, e))?,
+            context,
+        )
+        .await?;
+        entries.extend(response.items);
+        if response.current_page >= response.max_page {
+            break;
+        }
+        page += 1;
+    }
+    Ok(entries)
+}
+
+async fn get_api_records(
+    api_url: &str,
+    input: &DeployMovaryImportInput,
+) -> ImporterResult<Records> {
+    let Some(username) = input.username.as_ref() else {
+        return Err(ImporterError::parse(
+            "the input",
+            "The username is required to import using the API",
+        ));
+    };
+    let mut headers = vec![];
+    if let Some(token) = input.api_token.as_ref() {
+        headers.push(("X-Movary-Token", token.to_owned()));
+    }
+    let client = get_base_http_client(
+        &format!("{}/api/users/{}/", api_url.trim_end_matches('/'), username),
+        headers,
+    );
+    let history = get_api_pages(&client, "history/movies", "the history").await?;
+    let watchlist = get_api_pages(&client, "watchlist/movies", "the watchlist").await?;
+    let mut records = Records::default();
+    for entry in history {
+        if let Some(user_rating) = entry.movie.user_rating {
+            let is_rated = records
+                .ratings
+                .iter()
+                .any(|r| r.common.tmdb_id == entry.movie.common.tmdb_id);
+            if !is_rated {
+                records.ratings.push(Rating {
+                    common: Common {
+                        title: entry.movie.common.title.clone(),
+                        tmdb_id: entry.movie.common.tmdb_id,
+                    },
+                    user_rating,
                 });
-                continue;
             }
-        };
+        }
+        records.history.push(History {
+            common: entry.movie.common,
+            watched_at: entry.watched_at,
+            comment: entry.comment,
+            location: entry.location,
+        });
+    }
+    records
+        .watchlist
+        .extend(watchlist.into_iter().map(|e| e.movie.common));
+    Ok(records)
+}
+
+fn convert_records(records: Records) -> Vec<ImportOrExportMediaItem> {
+    let lot = MetadataLot::Movie;
+    let source = MetadataSource::Tmdb;
+    let mut media = vec![];
+    for record in records.ratings {
         media.push(ImportOrExportMediaItem {
             source_id: record.common.title,
             lot,
@@ -76,22 +250,13 @@ pub async fn import(input: DeployMovaryImportInput) -> Result<ImportResult> {
                 ..Default::default()
             }],
             collections: vec![],
+            external_identifiers: None,
+            is_favorite: None,
+            next_episode: None,
+            display_title: None,
         })
     }
-    let mut watchlist_reader = Reader::from_reader(input.watchlist.as_bytes());
-    for (idx, result) in watchlist_reader.deserialize().enumerate() {
-        let record: Common = match result {
-            Ok(r) => r,
-            Err(e) => {
-                failed_items.push(ImportFailedItem {
-                    lot,
-                    step: ImportFailStep::InputTransformation,
-                    identifier: idx.to_string(),
-                    error: Some(format!("Watchlist file: {:#?}", e)),
-                });
-                continue;
-            }
-        };
+    for record in records.watchlist {
         media.push(ImportOrExportMediaItem {
             source_id: record.title,
             lot,
@@ -103,32 +268,25 @@ pub async fn import(input: DeployMovaryImportInput) -> Result<ImportResult> {
             seen_history: vec![],
             reviews: vec![],
             collections: vec![DefaultCollection::Watchlist.to_string()],
+            external_identifiers: None,
+            is_favorite: None,
+            next_episode: None,
+            display_title: None,
         })
     }
-    let mut history_reader = Reader::from_reader(input.history.as_bytes());
-    for (idx, result) in history_reader.deserialize().enumerate() {
-        let record: History = match result {
-            Ok(r) => r,
-            Err(e) => {
-                failed_items.push(ImportFailedItem {
-                    lot,
-                    step: ImportFailStep::InputTransformation,
-                    identifier: idx.to_string(),
-                    error: Some(format!("History file: {:#?}", e)),
-                });
-                continue;
-            }
-        };
-        let watched_at = Some(convert_naive_to_utc(record.watched_at));
+    for record in records.history {
+        let watched_at = record.watched_at.map(convert_naive_to_utc);
         let seen_item = ImportOrExportMediaItemSeen {
             started_on: None,
             ended_on: watched_at,
+            watch_platform: record.location.filter(|l| !l.is_empty()),
             ..Default::default()
         };
         let review = record.comment.map(|c| ImportOrExportItemReview {
             spoiler: Some(false),
             text: Some(c),
             date: watched_at,
+            visibility: None,
         });
         if let Some(media) = media
             .iter_mut()
@@ -164,13 +322,62 @@ pub async fn import(input: DeployMovaryImportInput) -> Result<ImportResult> {
                 seen_history: vec![seen_item],
                 reviews,
                 collections: vec![],
+                external_identifiers: None,
+                is_favorite: None,
+                next_episode: None,
+                display_title: None,
             })
         }
     }
-    Ok(ImportResult {
-        collections: vec![],
-        media,
-        failed_items,
-        workouts: vec![],
-    })
+    media
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_is_merged_into_rated_movies() {
+        let page: ApiPage = serde_json::from_str(
+            r#"{
+                "history": [
+                    {"movie": {"title": "Heat", "tmdbId": 949, "userRating": 9},
+                        "watchedAt": "2023-01-02", "comment": "Again"},
+                    {"movie": {"title": "Alien", "tmdbId": 348, "userRating": null},
+                        "watchedAt": null, "comment": null}
+                ],
+                "currentPage": 1,
+                "maxPage": 1
+            }"#,
+        )
+        .unwrap();
+        let records = Records {
+            ratings: vec![Rating {
+                common: Common {
+                    title: "Heat".to_owned(),
+                    tmdb_id: 949,
+                },
+                user_rating: dec!(9),
+            }],
+            watchlist: vec![],
+            history: page
+                .items
+                .into_iter()
+                .map(|e| History {
+                    common: e.movie.common,
+                    watched_at: e.watched_at,
+                    comment: e.comment,
+                    location: e.location,
+                })
+                .collect(),
+        };
+        let media = convert_records(records);
+        assert_eq!(media.len(), 2);
+        let heat = &media[0];
+        assert_eq!(heat.seen_history.len(), 1);
+        assert_eq!(heat.reviews.len(), 1);
+        assert_eq!(heat.reviews[0].rating, Some(dec!(90)));
+        assert!(heat.reviews[0].review.is_some());
+        assert!(media[1].seen_history[0].ended_on.is_none());
+    }
 }
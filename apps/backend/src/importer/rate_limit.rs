@@ -0,0 +1,171 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use surf::{
+    middleware::{Middleware, Next},
+    Client, Request, Response, StatusCode,
+};
+use tokio::time::sleep;
+
+/// How often requests can be made to a single provider during imports.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimits {
+    pub requests_per_second: u32,
+    /// The number of times a request that was rejected for being too frequent is
+    /// attempted again.
+    pub max_retries: u32,
+    /// The time to wait before the first retry. This doubles after every retry.
+    pub backoff: Duration,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 2,
+            max_retries: 5,
+            backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+fn configured_limits() -> &'static OnceLock<RateLimits> {
+    static LIMITS: OnceLock<RateLimits> = OnceLock::new();
+    &LIMITS
+}
+
+/// Set the limits that are used for all providers. Only the first call has an
+/// effect.
+pub fn configure(limits: RateLimits) {
+    configured_limits().set(limits).ok();
+}
+
+pub fn get_limits() -> RateLimits {
+    configured_limits().get().copied().unwrap_or_default()
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    // DEV: This goes below zero when requests are waiting for a token, so that
+    // they are spread out instead of all being let through at the next refill.
+    tokens: f64,
+    refilled_on: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: f64, now: Instant) -> Self {
+        Self {
+            tokens: capacity,
+            refilled_on: now,
+        }
+    }
+
+    /// Take a token from the bucket and return how long to wait before it can be
+    /// used. The bucket holds at most one second worth of tokens.
+    fn reserve(&mut self, per_second: f64, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.refilled_on);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * per_second).min(per_second);
+        self.refilled_on = self.refilled_on.max(now);
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / per_second)
+        }
+    }
+}
+
+fn buckets() -> &'static Mutex<HashMap<String, TokenBucket>> {
+    static BUCKETS: OnceLock<Mutex<HashMap<String, TokenBucket>>> = OnceLock::new();
+    BUCKETS.get_or_init(Default::default)
+}
+
+/// Wait until a request can be made to the provider. The limit is shared by all
+/// the imports that are running.
+pub async fn acquire(provider: &str) {
+    let per_second = f64::from(get_limits().requests_per_second.max(1));
+    let now = Instant::now();
+    let wait = buckets()
+        .lock()
+        .unwrap()
+        .entry(provider.to_owned())
+        .or_insert_with(|| TokenBucket::new(per_second, now))
+        .reserve(per_second, now);
+    if !wait.is_zero() {
+        sleep(wait).await;
+    }
+}
+
+/// The time to wait before retrying a request that was rate limited, where
+/// `attempt` is the number of retries made so far.
+pub fn get_backoff(base: Duration, attempt: u32) -> Duration {
+    base.saturating_mul(2_u32.saturating_pow(attempt))
+}
+
+/// Limits the rate of requests made to the host of the request, and retries the
+/// ones that are rejected with `429 Too Many Requests`. The `Retry-After` header
+/// of the response is respected if it is present.
+#[derive(Debug, Default)]
+pub struct SourceRateLimit;
+
+#[surf::utils::async_trait]
+impl Middleware for SourceRateLimit {
+    async fn handle(
+        &self,
+        mut req: Request,
+        client: Client,
+        next: Next<'_>,
+    ) -> surf::Result<Response> {
+        let limits = get_limits();
+        let host = req.url().host_str().unwrap_or_default().to_owned();
+        // DEV: Cloning a request does not clone its body, so it is kept aside to
+        // be set again on every attempt.
+        let body = req.take_body().into_bytes().await?;
+        let mut attempt = 0;
+        loop {
+            let mut attempt_req = req.clone();
+            if !body.is_empty() {
+                attempt_req.set_body(body.clone());
+            }
+            acquire(&host).await;
+            let rsp = next.run(attempt_req, client.clone()).await?;
+            if rsp.status() != StatusCode::TooManyRequests || attempt >= limits.max_retries {
+                return Ok(rsp);
+            }
+            let wait = rsp
+                .header("Retry-After")
+                .and_then(|h| h.last().as_str().parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or_else(|| get_backoff(limits.backoff, attempt));
+            tracing::debug!("Request to {host} was rate limited, retrying in {wait:?}");
+            sleep(wait).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_spreads_out_requests_after_a_burst() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, start);
+        assert_eq!(bucket.reserve(2.0, start), Duration::ZERO);
+        assert_eq!(bucket.reserve(2.0, start), Duration::ZERO);
+        assert_eq!(bucket.reserve(2.0, start), Duration::from_millis(500));
+        assert_eq!(bucket.reserve(2.0, start), Duration::from_secs(1));
+        let later = start + Duration::from_secs(5);
+        assert_eq!(bucket.reserve(2.0, later), Duration::ZERO);
+    }
+
+    #[test]
+    fn backoff_doubles_after_every_retry() {
+        let base = Duration::from_secs(2);
+        assert_eq!(get_backoff(base, 0), Duration::from_secs(2));
+        assert_eq!(get_backoff(base, 3), Duration::from_secs(16));
+    }
+}
@@ -0,0 +1,45 @@
+use crate::{
+    file_storage::FileStorageService,
+    importer::{
+        get_exported_item_identifier, read_uploaded_file, DeployRyotImportInput, ImportResult,
+        ImporterError, ImporterResult,
+    },
+    models::{media::CreateOrUpdateCollectionInput, ImportOrExportRyotData},
+};
+
+pub async fn import(
+    user_id: i32,
+    input: DeployRyotImportInput,
+    file_storage_service: &FileStorageService,
+) -> ImporterResult<ImportResult> {
+    let context = "the export file";
+    let export = read_uploaded_file(file_storage_service, user_id, &input.export_path).await?;
+    let mut data = serde_json::from_slice::<ImportOrExportRyotData>(&export)
+        .map_err(|e| ImporterError::parse(context, e))?;
+    data.media
+        .iter_mut()
+        .for_each(|m| m.internal_identifier = Some(get_exported_item_identifier(m)));
+    let collections = data
+        .collections
+        .into_iter()
+        .map(|c| CreateOrUpdateCollectionInput {
+            name: c.name,
+            description: c.description,
+            visibility: Some(c.visibility),
+            update_id: None,
+        })
+        .collect();
+    Ok(ImportResult {
+        collections,
+        media: data.media,
+        failed_items: vec![],
+        workouts: vec![],
+        people: data.people,
+        metadata_groups_reviewed: data.metadata_groups,
+        measurements: data.measurements,
+        completed_workouts: data.workouts,
+        exercises: data.exercises,
+        preferences: data.preferences,
+        ..Default::default()
+    })
+}
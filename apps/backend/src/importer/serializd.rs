@@ -0,0 +1,227 @@
+use database::{MetadataLot, MetadataSource};
+use rs_utils::{convert_naive_to_utc, convert_string_to_date};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    importer::{
+        get_csv_reader, read_csv_rows, DeploySerializdImportInput, ImportErrorCode, ImportFailStep,
+        ImportFailedItem, ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportResult,
+        ImporterError, ImporterResult,
+    },
+    miscellaneous::DefaultCollection,
+    models::media::{
+        ImportOrExportItemRating, ImportOrExportItemReview, ImportOrExportMediaItemSeen,
+    },
+};
+
+/// A row of the diary or the watchlist. The aliases allow files from other
+/// trackers of shows that use similar columns to be imported too.
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    #[serde(rename = "Show Name", alias = "Show", alias = "Title")]
+    name: String,
+    #[serde(rename = "TMDB ID", alias = "TMDB", alias = "tmdb_id")]
+    tmdb_id: Option<String>,
+    #[serde(rename = "Season", alias = "Season Number", default)]
+    season: Option<i32>,
+    #[serde(rename = "Episode", alias = "Episode Number", default)]
+    episode: Option<i32>,
+    #[serde(rename = "Date", alias = "Watched Date", default)]
+    date: Option<String>,
+    #[serde(rename = "Rating", default)]
+    rating: Option<Decimal>,
+    #[serde(rename = "Review", default)]
+    review: Option<String>,
+    #[serde(rename = "Contains Spoilers", alias = "Spoiler", default)]
+    spoiler: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ExportFile {
+    Diary,
+    Watchlist,
+}
+
+// DEV: Serializd rates out of 10, in steps of one
+fn convert_rating(rating: Decimal) -> Decimal {
+    rating.saturating_mul(dec!(10))
+}
+
+fn is_spoiler(value: &str) -> bool {
+    ["true", "yes", "1"].contains(&value.to_lowercase().as_str())
+}
+
+pub fn import(input: DeploySerializdImportInput, trim_csv: bool) -> ImporterResult<ImportResult> {
+    let lot = MetadataLot::Show;
+    let mut media: Vec<ImportOrExportMediaItem> = vec![];
+    let mut failed_items = vec![];
+    let files = [
+        (ExportFile::Diary, Some(input.diary)),
+        (ExportFile::Watchlist, input.watchlist),
+    ];
+    for (file, data) in files {
+        let Some(data) = data else {
+            continue;
+        };
+        let reader = get_csv_reader(data.as_bytes(), b',', trim_csv)?;
+        let context = match file {
+            ExportFile::Diary => "the diary file",
+            ExportFile::Watchlist => "the watchlist file",
+        };
+        for (idx, row) in read_csv_rows::<Entry, _>(reader).enumerate() {
+            let record = match row.record {
+                Ok(r) => r,
+                Err(e) => {
+                    failed_items.push(ImportFailedItem {
+                        lot,
+                        step: ImportFailStep::InputTransformation,
+                        identifier: idx.to_string(),
+                        error: Some(ImporterError::parse(context, e).to_string()),
+                        code: ImportErrorCode::ParseError,
+                        source_row: row.raw,
+                    });
+                    continue;
+                }
+            };
+            let mut fail = |error: &str, code: ImportErrorCode| {
+                failed_items.push(ImportFailedItem {
+                    lot,
+                    step: ImportFailStep::InputTransformation,
+                    identifier: record.name.clone(),
+                    error: Some(error.to_owned()),
+                    code,
+                    source_row: row.raw.clone(),
+                })
+            };
+            let Some(identifier) = record.tmdb_id.clone().filter(|i| !i.is_empty()) else {
+                fail(
+                    "The show does not have a TMDB ID",
+                    ImportErrorCode::MissingIdentifier,
+                );
+                continue;
+            };
+            let date = match record.date.as_deref().filter(|d| !d.is_empty()) {
+                Some(d) => match convert_string_to_date(d.get(..10).unwrap_or(d)) {
+                    Some(d) => Some(convert_naive_to_utc(d)),
+                    None => {
+                        fail(
+                            "The date is not in the YYYY-MM-DD format",
+                            ImportErrorCode::ParseError,
+                        );
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            let position = media.iter().position(|m| m.identifier == identifier);
+            let item = match position {
+                Some(p) => &mut media[p],
+                None => {
+                    media.push(ImportOrExportMediaItem {
+                        source_id: record.name.clone(),
+                        lot,
+                        source: MetadataSource::Tmdb,
+                        internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails(
+                            identifier.clone(),
+                        )),
+                        identifier,
+                        seen_history: vec![],
+                        reviews: vec![],
+                        collections: vec![],
+                        external_identifiers: None,
+                        is_favorite: None,
+                        next_episode: None,
+                        display_title: None,
+                    });
+                    media.last_mut().unwrap()
+                }
+            };
+            if let ExportFile::Watchlist = file {
+                let watchlist = DefaultCollection::Watchlist.to_string();
+                if !item.collections.contains(&watchlist) {
+                    item.collections.push(watchlist);
+                }
+                continue;
+            }
+            // DEV: A row without an episode is for the whole season, which is marked
+            // as seen episode by episode during the import. A row without a season
+            // is for the whole show and can only have a review.
+            if record.season.is_some() {
+                item.seen_history.push(ImportOrExportMediaItemSeen {
+                    ended_on: date,
+                    show_season_number: record.season,
+                    show_episode_number: record.episode,
+                    ..Default::default()
+                });
+            }
+            let text = record.review.filter(|r| !r.is_empty());
+            if record.rating.is_some() || text.is_some() {
+                item.reviews.push(ImportOrExportItemRating {
+                    rating: record.rating.map(convert_rating),
+                    review: text.map(|text| ImportOrExportItemReview {
+                        date,
+                        spoiler: record.spoiler.as_deref().map(is_spoiler),
+                        text: Some(text),
+                        visibility: None,
+                    }),
+                    show_season_number: record.season,
+                    show_episode_number: record.season.and(record.episode),
+                    seen_on: record.season.and(date),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+    Ok(ImportResult {
+        collections: vec![],
+        media,
+        failed_items,
+        workouts: vec![],
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seasons_and_episodes_are_kept_apart() {
+        let diary = "Show Name,TMDB ID,Season,Episode,Date,Rating,Review,Contains Spoilers\n\
+            Severance,95396,1,,2022-04-08,9,What a finale,Yes\n\
+            Severance,95396,2,1,2025-01-17,,,\n\
+            Severance,95396,,,,10,,\n\
+            Unknown Show,,1,,2023-01-01,,,\n";
+        let watchlist = "Show Name,TMDB ID\nDark,70523\n";
+        let import = import(
+            DeploySerializdImportInput {
+                diary: diary.to_owned(),
+                watchlist: Some(watchlist.to_owned()),
+            },
+            true,
+        )
+        .unwrap();
+        assert_eq!(import.media.len(), 2);
+        assert_eq!(import.failed_items.len(), 1);
+        let show = &import.media[0];
+        assert_eq!(
+            show.seen_history
+                .iter()
+                .map(|s| (s.show_season_number, s.show_episode_number))
+                .collect::<Vec<_>>(),
+            vec![(Some(1), None), (Some(2), Some(1))]
+        );
+        assert_eq!(show.reviews.len(), 2);
+        let season_review = &show.reviews[0];
+        assert_eq!(season_review.show_season_number, Some(1));
+        assert_eq!(season_review.show_episode_number, None);
+        assert_eq!(season_review.rating, Some(dec!(90)));
+        assert_eq!(season_review.review.as_ref().unwrap().spoiler, Some(true));
+        assert_eq!(show.reviews[1].show_season_number, None);
+        assert_eq!(show.reviews[1].rating, Some(dec!(100)));
+        assert_eq!(import.media[1].collections, vec!["Watchlist"]);
+        assert!(import.media[1].seen_history.is_empty());
+    }
+}
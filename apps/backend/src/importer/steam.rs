@@ -0,0 +1,182 @@
+use database::{MetadataLot, MetadataSource};
+use http_types::mime;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use surf::http::headers::ACCEPT;
+
+use crate::{
+    importer::{
+        get_source_json, DeploySteamImportInput, ImportErrorCode, ImportFailStep, ImportFailedItem,
+        ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportResult, ImporterError,
+        ImporterResult,
+    },
+    models::media::{ImportOrExportMediaItemSeen, SeenSession},
+    providers::igdb::IgdbService,
+    utils::get_base_http_client,
+};
+
+static URL: &str = "https://api.steampowered.com/";
+
+/// The collection that every game owned on Steam is added to.
+const OWNED_COLLECTION: &str = "Owned";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SteamResponse<T> {
+    response: T,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VanityUrl {
+    steamid: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OwnedGame {
+    appid: u32,
+    name: Option<String>,
+    /// The total time played in minutes.
+    #[serde(default)]
+    playtime_forever: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OwnedGames {
+    /// Missing if the game details of the profile are private.
+    games: Option<Vec<OwnedGame>>,
+}
+
+pub async fn import(
+    input: DeploySteamImportInput,
+    igdb_service: &IgdbService,
+) -> ImporterResult<ImportResult> {
+    let client = get_base_http_client(URL, vec![(ACCEPT, mime::JSON)]);
+    // DEV: A Steam ID is a 64 bit number, anything else is the custom name in the
+    // URL of the profile.
+    let steam_id = match input.steam_id.chars().all(|c| c.is_ascii_digit()) {
+        true => input.steam_id,
+        false => {
+            let vanity_url: SteamResponse<VanityUrl> = get_source_json(
+                client.get(format!(
+                    "ISteamUser/ResolveVanityURL/v1/?key={}&vanityurl={}",
+                    input.api_key, input.steam_id
+                )),
+                "the profile",
+            )
+            .await?;
+            vanity_url.response.steamid.ok_or_else(|| {
+                ImporterError::provider("the profile", "No profile has this custom URL")
+            })?
+        }
+    };
+    let owned: SteamResponse<OwnedGames> = get_source_json(
+        client.get(format!(
+            "IPlayerService/GetOwnedGames/v1/?key={}&steamid={}&include_appinfo=1&include_played_free_games=1",
+            input.api_key, steam_id
+        )),
+        "the owned games",
+    )
+    .await?;
+    let games = owned.response.games.ok_or_else(|| {
+        ImporterError::provider(
+            "the owned games",
+            "The game details of the profile are not public",
+        )
+    })?;
+    let app_ids = games.iter().map(|g| g.appid).collect_vec();
+    let igdb_ids = igdb_service
+        .ids_from_steam_app_ids(&app_ids)
+        .await
+        .map_err(|e| ImporterError::provider("IGDB", e))?;
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    for game in games {
+        let title = game.name.clone().unwrap_or_else(|| game.appid.to_string());
+        let Some(identifier) = igdb_ids.get(&game.appid) else {
+            failed_items.push(ImportFailedItem {
+                lot: MetadataLot::VideoGame,
+                step: ImportFailStep::InputTransformation,
+                identifier: title,
+                error: Some(format!(
+                    "No game on IGDB has the Steam app ID {}",
+                    game.appid
+                )),
+                code: ImportErrorCode::NotFound,
+                source_row: serde_json::to_string(&game).ok(),
+            });
+            continue;
+        };
+        media.push(convert_game(&game, title, identifier.to_owned()));
+    }
+    Ok(ImportResult {
+        collections: vec![],
+        media,
+        failed_items,
+        workouts: vec![],
+        ..Default::default()
+    })
+}
+
+/// Games that have been played are recorded as being in progress, with the time
+/// played as a session, since Steam does not know whether they were finished.
+fn convert_game(game: &OwnedGame, title: String, identifier: String) -> ImportOrExportMediaItem {
+    let seen_history = match game.playtime_forever {
+        0 => vec![],
+        minutes => vec![ImportOrExportMediaItemSeen {
+            progress: Some(0),
+            sessions: Some(vec![SeenSession {
+                started_on: None,
+                duration: minutes,
+                progress: None,
+            }]),
+            ..Default::default()
+        }],
+    };
+    ImportOrExportMediaItem {
+        source_id: title,
+        lot: MetadataLot::VideoGame,
+        source: MetadataSource::Igdb,
+        identifier: "".to_string(),
+        internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails(identifier)),
+        seen_history,
+        reviews: vec![],
+        collections: vec![OWNED_COLLECTION.to_owned()],
+        external_identifiers: None,
+        is_favorite: None,
+        next_episode: None,
+        display_title: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn played_games_are_in_progress() {
+        let owned: SteamResponse<OwnedGames> = serde_json::from_str(
+            r#"{"response": {"game_count": 2, "games": [
+                {"appid": 620, "name": "Portal 2", "playtime_forever": 754},
+                {"appid": 400, "name": "Portal", "playtime_forever": 0}
+            ]}}"#,
+        )
+        .unwrap();
+        let games = owned.response.games.unwrap();
+        let played = convert_game(&games[0], "Portal 2".to_owned(), "72".to_owned());
+        assert_eq!(played.collections, vec![OWNED_COLLECTION]);
+        assert_eq!(played.seen_history.len(), 1);
+        assert_eq!(played.seen_history[0].progress, Some(0));
+        assert_eq!(
+            played.seen_history[0].sessions.as_ref().unwrap()[0].duration,
+            754
+        );
+        let unplayed = convert_game(&games[1], "Portal".to_owned(), "71".to_owned());
+        assert!(unplayed.seen_history.is_empty());
+        assert_eq!(unplayed.collections, vec![OWNED_COLLECTION]);
+    }
+
+    #[test]
+    fn private_profiles_have_no_games() {
+        let owned: SteamResponse<OwnedGames> = serde_json::from_str(r#"{"response": {}}"#).unwrap();
+        assert!(owned.response.games.is_none());
+    }
+}
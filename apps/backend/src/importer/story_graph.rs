@@ -1,34 +1,43 @@
-use async_graphql::Result;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use convert_case::{Case, Casing};
-use csv::Reader;
 use database::{MetadataLot, MetadataSource};
-use itertools::Itertools;
-use rust_decimal::Decimal;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     importer::{
-        DeployStoryGraphImportInput, ImportFailStep, ImportFailedItem,
-        ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportResult,
+        get_csv_reader, get_reading_goals, read_csv_rows, DeployStoryGraphImportInput,
+        ImportErrorCode, ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier,
+        ImportOrExportMediaItem, ImportResult, ImporterError, ImporterResult,
     },
     models::media::{
         ImportOrExportItemRating, ImportOrExportItemReview, ImportOrExportMediaItemSeen,
+        ImportOrExportMediaItemSeenDropped,
     },
     providers::openlibrary::OpenlibraryService,
 };
 
 #[derive(Debug, Serialize, Deserialize)]
-#[serde(untagged)]
+#[serde(from = "String")]
 enum ReadStatus {
-    #[serde(rename = "to-read")]
     ToRead,
-    #[serde(rename = "currently-reading")]
     CurrentlyReading,
+    DidNotFinish,
     Other(String),
 }
 
+impl From<String> for ReadStatus {
+    fn from(status: String) -> Self {
+        match status.as_str() {
+            "to-read" => Self::ToRead,
+            "currently-reading" => Self::CurrentlyReading,
+            "did-not-finish" => Self::DidNotFinish,
+            _ => Self::Other(status),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct History {
     #[serde(rename = "Title")]
@@ -47,35 +56,61 @@ struct History {
     last_date_read: Option<String>,
     #[serde(rename = "Tags")]
     tags: Option<String>,
+    #[serde(rename = "Current Page", default)]
+    current_page: Option<i32>,
+    /// A percentage, which can be followed by a `%`.
+    #[serde(rename = "Progress", default)]
+    progress: Option<String>,
+    #[serde(rename = "DNF Reason", default)]
+    dnf_reason: Option<String>,
+}
+
+/// The progress of a book that is being read, which is never 100 since that would
+/// mark it as finished.
+fn get_progress(progress: Option<&str>) -> Option<i32> {
+    let progress = progress?.trim().trim_end_matches('%').trim();
+    let progress = progress.parse::<Decimal>().ok()?.round().to_i32()?;
+    Some(progress.clamp(0, 99))
+}
+
+/// The tags are separated by commas, with or without spaces after them.
+fn get_tags(tags: Option<&str>) -> Vec<String> {
+    tags.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_case(Case::Title))
+        .collect()
 }
 
 pub async fn import(
     input: DeployStoryGraphImportInput,
+    trim_csv: bool,
     openlibrary_service: &OpenlibraryService,
-) -> Result<ImportResult> {
+) -> ImporterResult<ImportResult> {
     let lot = MetadataLot::Book;
     let source = MetadataSource::Openlibrary;
+    let reading_goals = get_reading_goals(input.reading_challenges.as_deref(), trim_csv)?;
+    let export = get_csv_reader(input.export.as_bytes(), b',', trim_csv)?;
     let mut media = vec![];
     let mut failed_items = vec![];
-    let ratings_reader = Reader::from_reader(input.export.as_bytes())
-        .deserialize()
-        .collect_vec();
-    let total = ratings_reader.len();
-    for (idx, result) in ratings_reader.into_iter().enumerate() {
-        let record: History = match result {
+    for (idx, row) in read_csv_rows::<History, _>(export).enumerate() {
+        let record = match row.record {
             Ok(r) => r,
             Err(e) => {
                 failed_items.push(ImportFailedItem {
                     lot,
                     step: ImportFailStep::InputTransformation,
                     identifier: idx.to_string(),
-                    error: Some(e.to_string()),
+                    error: Some(ImporterError::parse("the export", e).to_string()),
+                    code: ImportErrorCode::ParseError,
+                    source_row: row.raw,
                 });
                 continue;
             }
         };
         tracing::debug!(
-            "Getting details for {title:?} ({idx}/{total})",
+            "Getting details for {title:?} (row {idx})",
             title = record.title
         );
         if let Some(isbn) = record.isbn {
@@ -88,23 +123,44 @@ pub async fn import(
                     };
                     record.read_count
                 ];
-                if let Some(w) = record.last_date_read {
+                let last_read_at = record.last_date_read.map(|w| {
                     let w = NaiveDate::parse_from_str(&w, "%Y/%m/%d").unwrap();
-                    let read_at = Some(DateTime::from_naive_utc_and_offset(
+                    DateTime::from_naive_utc_and_offset(
                         NaiveDateTime::new(w, NaiveTime::from_hms_opt(0, 0, 0).unwrap()),
                         Utc,
-                    ));
-                    seen_history.first_mut().unwrap().ended_on = read_at;
+                    )
+                });
+                if let (Some(read_at), Some(seen)) = (last_read_at, seen_history.first_mut()) {
+                    seen.ended_on = Some(read_at);
+                }
+                let progress = get_progress(record.progress.as_deref());
+                if matches!(record.read_status, ReadStatus::CurrentlyReading) {
+                    // DEV: Without a progress the book is recorded as just started
+                    seen_history.push(ImportOrExportMediaItemSeen {
+                        progress: progress.or(record.current_page.is_none().then_some(0)),
+                        pages_read: record.current_page,
+                        ..Default::default()
+                    });
+                }
+                if matches!(record.read_status, ReadStatus::DidNotFinish) {
+                    seen_history.push(ImportOrExportMediaItemSeen {
+                        progress,
+                        pages_read: record.current_page,
+                        dropped: Some(ImportOrExportMediaItemSeenDropped {
+                            dropped_on: last_read_at,
+                            reason: record.dnf_reason,
+                        }),
+                        ..Default::default()
+                    });
                 }
                 let mut collections = vec![];
                 collections.push(match record.read_status {
                     ReadStatus::ToRead => "Watchlist".to_owned(),
                     ReadStatus::CurrentlyReading => "In Progress".to_owned(),
+                    ReadStatus::DidNotFinish => "Did Not Finish".to_owned(),
                     ReadStatus::Other(s) => s.to_case(Case::Title),
                 });
-                if let Some(t) = record.tags {
-                    collections.extend(t.split(", ").map(|d| d.to_case(Case::Title)))
-                }
+                collections.extend(get_tags(record.tags.as_deref()));
                 media.push(ImportOrExportMediaItem {
                     source_id: record.title,
                     lot,
@@ -123,10 +179,15 @@ pub async fn import(
                             date: None,
                             spoiler: Some(false),
                             text: Some(r),
+                            visibility: None,
                         }),
                         ..Default::default()
                     }],
                     collections,
+                    external_identifiers: None,
+                    is_favorite: None,
+                    next_episode: None,
+                    display_title: None,
                 })
             } else {
                 failed_items.push(ImportFailedItem {
@@ -137,6 +198,8 @@ pub async fn import(
                         "Could not convert ISBN: {} to Openlibrary ID",
                         isbn
                     )),
+                    code: ImportErrorCode::NotFound,
+                    source_row: row.raw,
                 })
             }
         } else {
@@ -145,6 +208,8 @@ pub async fn import(
                 step: ImportFailStep::InputTransformation,
                 identifier: record.title,
                 error: Some("No ISBN found".to_owned()),
+                code: ImportErrorCode::MissingIdentifier,
+                source_row: row.raw,
             })
         }
     }
@@ -153,5 +218,30 @@ pub async fn import(
         media,
         failed_items,
         workouts: vec![],
+        reading_goals,
+        ..Default::default()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_of_books_being_read_is_parsed() {
+        assert_eq!(get_progress(Some("45%")), Some(45));
+        assert_eq!(get_progress(Some(" 12.6 ")), Some(13));
+        assert_eq!(get_progress(Some("100%")), Some(99));
+        assert_eq!(get_progress(Some("")), None);
+        assert_eq!(get_progress(None), None);
+    }
+
+    #[test]
+    fn tags_are_split_into_collections() {
+        assert_eq!(
+            get_tags(Some("sci-fi,book club, re-read ,")),
+            vec!["Sci Fi", "Book Club", "Re Read"]
+        );
+        assert!(get_tags(None).is_empty());
+    }
+}
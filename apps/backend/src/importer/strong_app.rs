@@ -1,26 +1,34 @@
-use std::{collections::HashMap, fs};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Read,
+};
 
-use async_graphql::Result;
 use chrono::{DateTime, Duration, NaiveDateTime, Utc};
-use csv::ReaderBuilder;
+use database::ExerciseLot;
 use itertools::Itertools;
 use regex::Regex;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
-use sea_orm::{DatabaseConnection, EntityTrait, QuerySelect};
+use sea_orm::DatabaseConnection;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    entities::{exercise, prelude::Exercise},
+    entities::user_measurement,
+    file_storage::FileStorageService,
+    fitness::{logic::find_exercise_ids_by_name, resolver::ExerciseService},
     models::fitness::{
-        EntityAssets, SetLot, UserExerciseInput, UserWorkoutInput, UserWorkoutSetRecord,
-        WorkoutSetStatistic,
+        EntityAssets, SetLot, UserExerciseInput, UserMeasurementStats, UserWorkoutInput,
+        UserWorkoutSetRecord, WorkoutSetStatistic,
     },
 };
 
-use super::{DeployStrongAppImportInput, ImportResult};
+use super::{
+    create_imported_exercise, get_csv_reader, DeployStrongAppImportInput, ImportResult,
+    ImporterError, ImporterResult, StrongAppExportExercise, StrongAppImportMapping,
+    StrongAppMappingCoverage, UploadedFile, WorkoutDurationHandling,
+};
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
 struct Entry {
     date: String,
@@ -29,8 +37,12 @@ struct Entry {
     reps: Option<usize>,
     distance: Option<Decimal>,
     seconds: Option<Decimal>,
+    #[serde(alias = "RPE")]
+    rpe: Option<Decimal>,
+    /// The position of the set in the exercise, or `W`, `D` or `F` for warm-up,
+    /// drop and failure sets.
     #[serde(alias = "Set Order")]
-    set_order: u8,
+    set_order: String,
     #[serde(alias = "Workout Duration")]
     workout_duration: String,
     #[serde(alias = "Workout Name")]
@@ -39,69 +51,479 @@ struct Entry {
     workout_notes: Option<String>,
     #[serde(alias = "Exercise Name")]
     exercise_name: String,
+    superset: Option<String>,
+    photos: Option<String>,
+    #[serde(alias = "Workout Photos")]
+    workout_photos: Option<String>,
+}
+
+/// Get the keys of the assets referenced by a column of comma separated photo
+/// filenames.
+fn get_asset_keys(photos: &Option<String>, prefix: &str) -> Vec<String> {
+    photos
+        .iter()
+        .flat_map(|p| p.split(','))
+        .map(|f| f.trim())
+        .filter(|f| !f.is_empty())
+        .map(|f| format!("{}{}", prefix, f))
+        .collect()
+}
+
+/// Drop the images which have not been uploaded to the file storage so that they
+/// do not show up as broken assets.
+async fn retain_uploaded_images(
+    assets: &mut EntityAssets,
+    file_storage_service: &FileStorageService,
+) {
+    let mut images = vec![];
+    for key in assets.images.drain(..) {
+        if file_storage_service.object_exists(key.clone()).await {
+            images.push(key);
+        } else {
+            tracing::warn!("Asset with key = {key} not found, skipping it");
+        }
+    }
+    assets.images = images;
+}
+
+/// Group the exercises of a workout into supersets using their labels. Labels
+/// sharing the same alphabetic prefix (eg: "A1" and "A2") belong to the same
+/// superset. Groups with a single exercise are ignored.
+fn get_supersets(labels: &[Option<String>]) -> Vec<Vec<u16>> {
+    let mut groups: Vec<(String, Vec<u16>)> = vec![];
+    for (idx, label) in labels.iter().enumerate() {
+        let group = match label {
+            Some(l) => l
+                .trim()
+                .chars()
+                .take_while(|c| c.is_alphabetic())
+                .collect::<String>()
+                .to_uppercase(),
+            None => continue,
+        };
+        if group.is_empty() {
+            continue;
+        }
+        let idx = idx.try_into().unwrap();
+        match groups.iter_mut().find(|(g, _)| *g == group) {
+            Some((_, members)) => members.push(idx),
+            None => groups.push((group, vec![idx])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(_, members)| members)
+        .filter(|members| members.len() > 1)
+        .collect()
+}
+
+async fn get_exercise_map(
+    db: &DatabaseConnection,
+    user_id: i32,
+) -> ImporterResult<HashMap<String, i32>> {
+    find_exercise_ids_by_name(db, user_id, None)
+        .await
+        .map_err(|e| ImporterError::database("the exercises", e))
+}
+
+/// Match the exercises in the export to the exercises visible to the user. The
+/// mapping is used first, then an exercise with the same name and finally the one
+/// with the most similar name. Exercises that can not be matched are left out.
+fn resolve_exercise_ids(
+    names: &[String],
+    mapping: &[StrongAppImportMapping],
+    map: &HashMap<String, i32>,
+) -> HashMap<String, i32> {
+    let exercises = map.keys().sorted().cloned().collect_vec();
+    names
+        .iter()
+        .filter_map(|name| {
+            let target = mapping
+                .iter()
+                .find(|m| m.source_name == *name)
+                .map(|m| m.target_name.clone())
+                .filter(|t| map.contains_key(t))
+                .or_else(|| map.contains_key(name).then(|| name.clone()))
+                .or_else(|| guess_exercise(name, &exercises))?;
+            Some((name.clone(), map[&target]))
+        })
+        .collect()
+}
+
+/// The statistics that the sets of an exercise in the export have.
+#[derive(Debug, Default, Clone, Copy)]
+struct LoggedStatistics {
+    weight: bool,
+    reps: bool,
+    distance: bool,
+    duration: bool,
+}
+
+impl LoggedStatistics {
+    fn get_lot(&self) -> ExerciseLot {
+        if self.distance && self.weight {
+            ExerciseLot::WeightAndDistance
+        } else if self.distance {
+            ExerciseLot::DistanceAndDuration
+        } else if self.duration && !self.reps && !self.weight {
+            ExerciseLot::Duration
+        } else {
+            ExerciseLot::RepsAndWeight
+        }
+    }
+}
+
+/// Guess the types of the exercises in the export from the statistics that their
+/// sets have.
+fn infer_exercise_lots<R: Read>(reader: csv::Reader<R>) -> HashMap<String, ExerciseLot> {
+    let is_positive = |d: Option<Decimal>| d.map_or(false, |d| d > dec!(0));
+    let mut statistics: HashMap<String, LoggedStatistics> = HashMap::new();
+    for entry in reader.into_deserialize::<Entry>().filter_map(|r| r.ok()) {
+        let logged = statistics
+            .entry(entry.exercise_name.trim().to_owned())
+            .or_default();
+        logged.weight |= is_positive(entry.weight);
+        logged.reps |= entry.reps.map_or(false, |r| r > 0);
+        logged.distance |= is_positive(entry.distance);
+        logged.duration |= is_positive(entry.seconds);
+    }
+    statistics
+        .into_iter()
+        .map(|(name, logged)| (name, logged.get_lot()))
+        .collect()
 }
 
 pub async fn import(
+    user_id: i32,
     input: DeployStrongAppImportInput,
+    trim_csv: bool,
+    db: &DatabaseConnection,
+    file_storage_service: &FileStorageService,
+    exercise_service: &ExerciseService,
+) -> ImporterResult<ImportResult> {
+    let max_workout_duration = input.max_workout_duration.unwrap_or(6);
+    if !(1..=48).contains(&max_workout_duration) {
+        return Err(ImporterError::parse(
+            "the input",
+            "the maximum workout duration must be between 1 and 48 hours",
+        ));
+    }
+    let map = get_exercise_map(db, user_id).await?;
+    let export = UploadedFile::find(file_storage_service, user_id, &input.export_path).await?;
+    // DEV: The export is read again for every pass instead of being kept in memory
+    let read_export = || get_csv_reader(export.open()?, b';', trim_csv);
+    let bodyweight_exercise_names = input.bodyweight_exercise_names.unwrap_or_default();
+    let names = get_exercise_names(read_export()?)?
+        .into_iter()
+        .filter(|n| !bodyweight_exercise_names.contains(n))
+        .collect_vec();
+    let mut exercise_ids = resolve_exercise_ids(&names, &input.mapping.unwrap_or_default(), &map);
+    let create_missing_exercises = input.create_missing_exercises.unwrap_or_default();
+    let lots = match create_missing_exercises && names.len() > exercise_ids.len() {
+        true => infer_exercise_lots(read_export()?),
+        false => HashMap::new(),
+    };
+    let mut warnings = vec![];
+    for name in names.iter().filter(|n| !exercise_ids.contains_key(*n)) {
+        if !create_missing_exercises {
+            warnings.push(format!(
+                "Exercise {name:?} could not be matched and its sets were not imported"
+            ));
+            continue;
+        }
+        let lot = lots
+            .get(name)
+            .copied()
+            .unwrap_or(ExerciseLot::RepsAndWeight);
+        match create_imported_exercise(exercise_service, user_id, name, lot).await {
+            Ok(id) => {
+                exercise_ids.insert(name.to_owned(), id);
+            }
+            Err(e) => warnings.push(format!(
+                "Exercise {name:?} could not be created and its sets were not imported: {}",
+                e.message
+            )),
+        }
+    }
+    let workouts = parse_workouts(
+        read_export()?,
+        &exercise_ids,
+        input.detect_supersets.unwrap_or(true),
+        input.assets_prefix.as_deref().unwrap_or_default(),
+        &bodyweight_exercise_names,
+    );
+    let (mut workouts, duration_warnings) = check_workout_durations(
+        workouts,
+        input.duration_handling.unwrap_or_default(),
+        Duration::hours(max_workout_duration),
+    );
+    warnings.extend(duration_warnings);
+    let measurements = parse_bodyweight_measurements(read_export()?, &bodyweight_exercise_names);
+    for workout in workouts.iter_mut() {
+        retain_uploaded_images(&mut workout.assets, file_storage_service).await;
+        for exercise in workout.exercises.iter_mut() {
+            retain_uploaded_images(&mut exercise.assets, file_storage_service).await;
+        }
+    }
+    Ok(ImportResult {
+        collections: vec![],
+        media: vec![],
+        failed_items: vec![],
+        workouts,
+        measurements,
+        warnings,
+        ..Default::default()
+    })
+}
+
+pub async fn inspect(
+    user_id: i32,
+    export_path: &str,
     db: &DatabaseConnection,
-) -> Result<ImportResult> {
-    let map = Exercise::find()
-        .select_only()
-        .column(exercise::Column::Name)
-        .column(exercise::Column::Id)
-        .into_tuple::<(String, i32)>()
-        .all(db)
+    file_storage_service: &FileStorageService,
+) -> ImporterResult<Vec<StrongAppExportExercise>> {
+    let exercises = get_exercise_map(db, user_id)
         .await?
         .into_iter()
-        .collect::<HashMap<_, _>>();
-    let file_string = fs::read_to_string(&input.export_path)?;
+        .sorted_by_key(|(_, id)| *id)
+        .map(|(name, _)| name)
+        .collect_vec();
+    let export = UploadedFile::find(file_storage_service, user_id, export_path).await?;
+    Ok(
+        get_exercise_names(get_csv_reader(export.open()?, b';', true)?)?
+            .into_iter()
+            .map(|source_name| StrongAppExportExercise {
+                target_name: guess_exercise(&source_name, &exercises),
+                source_name,
+            })
+            .collect(),
+    )
+}
+
+pub async fn preview_mapping(
+    user_id: i32,
+    input: &DeployStrongAppImportInput,
+    db: &DatabaseConnection,
+    file_storage_service: &FileStorageService,
+) -> ImporterResult<StrongAppMappingCoverage> {
+    let map = get_exercise_map(db, user_id).await?;
+    let export = UploadedFile::find(file_storage_service, user_id, &input.export_path).await?;
+    let read_export = || get_csv_reader(export.open()?, b';', true);
+    let exercise_ids = resolve_exercise_ids(
+        &get_exercise_names(read_export()?)?,
+        input.mapping.as_deref().unwrap_or_default(),
+        &map,
+    );
+    get_mapping_coverage(
+        read_export()?,
+        &exercise_ids,
+        input
+            .bodyweight_exercise_names
+            .as_deref()
+            .unwrap_or_default(),
+    )
+}
+
+/// Count the exercise rows of the export whose exercise was matched to an
+/// existing exercise. Bodyweight rows are not counted since they become
+/// measurements.
+fn get_mapping_coverage<R: Read>(
+    reader: csv::Reader<R>,
+    exercise_ids: &HashMap<String, i32>,
+    bodyweight_exercise_names: &[String],
+) -> ImporterResult<StrongAppMappingCoverage> {
+    let mut total_rows = 0;
+    let mut mapped_rows = 0;
+    let mut unmapped_names = vec![];
+    for entry in reader.into_deserialize::<Entry>() {
+        let entry = entry.map_err(|e| ImporterError::parse("the export file", e))?;
+        if is_bodyweight_entry(&entry, bodyweight_exercise_names) {
+            continue;
+        }
+        total_rows += 1;
+        let name = entry.exercise_name.trim();
+        if exercise_ids.contains_key(name) {
+            mapped_rows += 1;
+        } else if !unmapped_names.iter().any(|n| n == name) {
+            unmapped_names.push(name.to_owned());
+        }
+    }
+    let mapped_percentage = match total_rows {
+        0 => dec!(100),
+        _ => (Decimal::from(mapped_rows * 100) / Decimal::from(total_rows)).round_dp(2),
+    };
+    Ok(StrongAppMappingCoverage {
+        total_rows,
+        mapped_rows,
+        unmapped_rows: total_rows - mapped_rows,
+        mapped_percentage,
+        unmapped_names,
+    })
+}
+
+/// Get the distinct names of the exercises in the export, in the order in which
+/// they first appear.
+fn get_exercise_names<R: Read>(reader: csv::Reader<R>) -> ImporterResult<Vec<String>> {
+    let mut names = vec![];
+    for entry in reader.into_deserialize::<Entry>() {
+        let name = entry
+            .map_err(|e| ImporterError::parse("the export file", e))?
+            .exercise_name
+            .trim()
+            .to_owned();
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    Ok(names)
+}
+
+fn get_words(name: &str) -> HashSet<String> {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Find the exercise whose name has the same words as the name in the export.
+/// Strong names look like "Bench Press (Barbell)" whereas the exercises are named
+/// like "Barbell Bench Press".
+fn guess_exercise(source_name: &str, exercises: &[String]) -> Option<String> {
+    // DEV: Names that only share some words are often different exercises, for
+    // eg: "Bench Press (Dumbbell)" and "Barbell Bench Press", and a wrong guess
+    // would silently import the sets into the wrong exercise.
+    let source_words = get_words(source_name);
+    exercises
+        .iter()
+        .find(|e| get_words(e) == source_words)
+        .cloned()
+}
+
+fn is_bodyweight_entry(entry: &Entry, bodyweight_exercise_names: &[String]) -> bool {
+    bodyweight_exercise_names
+        .iter()
+        .any(|n| n == entry.exercise_name.trim())
+}
+
+/// Get the bodyweight logged using the rows of the bodyweight tracking exercises
+/// as measurements.
+fn parse_bodyweight_measurements<R: Read>(
+    reader: csv::Reader<R>,
+    bodyweight_exercise_names: &[String],
+) -> Vec<user_measurement::Model> {
+    reader
+        .into_deserialize::<Entry>()
+        .filter_map(|r| r.ok())
+        .filter(|e| is_bodyweight_entry(e, bodyweight_exercise_names))
+        .filter_map(|e| {
+            let weight = e.weight?;
+            let ndt = NaiveDateTime::parse_from_str(&e.date, "%Y-%m-%d %H:%M:%S").ok()?;
+            Some(user_measurement::Model {
+                timestamp: DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc),
+                user_id: 0,
+                name: None,
+                comment: e.notes,
+                stats: UserMeasurementStats {
+                    weight: Some(weight),
+                    ..Default::default()
+                },
+            })
+        })
+        .collect()
+}
+
+fn get_set_lot(set_order: &str) -> SetLot {
+    match set_order.trim() {
+        "W" => SetLot::WarmUp,
+        "D" => SetLot::Drop,
+        "F" => SetLot::Failure,
+        _ => SetLot::Normal,
+    }
+}
+
+/// Whether the next row of the export starts another exercise. The sets of an
+/// exercise are numbered from one, except for warm-up, drop and failure sets
+/// which are only labelled. Warm-up sets always come before the other sets.
+fn is_last_set_of_exercise(entry: &Entry, next_entry: &Entry) -> bool {
+    if next_entry.date != entry.date || next_entry.exercise_name != entry.exercise_name {
+        return true;
+    }
+    if get_set_lot(&next_entry.set_order) == SetLot::WarmUp {
+        return get_set_lot(&entry.set_order) != SetLot::WarmUp;
+    }
+    let get_order = |e: &Entry| e.set_order.trim().parse::<u8>().ok();
+    matches!(
+        (get_order(entry), get_order(next_entry)),
+        (Some(order), Some(next_order)) if next_order <= order
+    )
+}
+
+/// Build the workouts from the rows of the export. The rows are read one at a
+/// time and a workout is finished as soon as the next row belongs to another one.
+fn parse_workouts<R: Read>(
+    reader: csv::Reader<R>,
+    exercise_ids: &HashMap<String, i32>,
+    detect_supersets: bool,
+    assets_prefix: &str,
+    bodyweight_exercise_names: &[String],
+) -> Vec<UserWorkoutInput> {
     let mut workouts = vec![];
-    let mut entries_reader = ReaderBuilder::new()
-        .delimiter(b';')
-        .from_reader(file_string.as_bytes())
-        .deserialize::<Entry>()
+    let mut entries = reader
+        .into_deserialize::<Entry>()
         .map(|r| r.unwrap())
-        .collect_vec();
-    // DEV: without this, the last workout does not get appended
-    entries_reader.push(Entry {
-        date: "invalid".to_string(),
-        set_order: 0,
-        ..Default::default()
-    });
+        .filter(|e| !is_bodyweight_entry(e, bodyweight_exercise_names))
+        .peekable();
     let mut exercises = vec![];
+    let mut superset_labels = vec![];
     let mut sets = vec![];
     let mut notes = vec![];
-    for (entry, next_entry) in entries_reader.into_iter().tuple_windows() {
+    let mut images = vec![];
+    while let Some(entry) = entries.next() {
+        let (ends_exercise, ends_workout) = match entries.peek() {
+            Some(next_entry) => (
+                is_last_set_of_exercise(&entry, next_entry),
+                next_entry.date != entry.date,
+            ),
+            None => (true, true),
+        };
         sets.push(UserWorkoutSetRecord {
             statistic: WorkoutSetStatistic {
                 duration: entry.seconds.and_then(|r| r.checked_div(dec!(60))),
                 distance: entry.distance,
                 reps: entry.reps,
                 weight: entry.weight.map(|d| if d == dec!(0) { dec!(1) } else { d }),
+                rpe: entry.rpe,
             },
-            lot: SetLot::Normal,
+            lot: get_set_lot(&entry.set_order),
         });
         if let Some(n) = entry.notes {
             notes.push(n);
         }
-        if next_entry.set_order <= entry.set_order {
-            let target_exercise = input
-                .mapping
-                .iter()
-                .find(|m| m.source_name == entry.exercise_name.trim())
-                .unwrap();
-            let exercise_id = map.get(&target_exercise.target_name).unwrap().to_owned();
-            exercises.push(UserExerciseInput {
-                exercise_id,
-                sets,
-                notes,
-                rest_time: None,
-                assets: EntityAssets::default(),
-            });
+        for key in get_asset_keys(&entry.photos, assets_prefix) {
+            if !images.contains(&key) {
+                images.push(key);
+            }
+        }
+        if ends_exercise {
+            if let Some(exercise_id) = exercise_ids.get(entry.exercise_name.trim()) {
+                exercises.push(UserExerciseInput {
+                    exercise_id: *exercise_id,
+                    sets,
+                    notes,
+                    rest_time: None,
+                    assets: EntityAssets {
+                        images,
+                        videos: vec![],
+                    },
+                });
+                superset_labels.push(entry.superset.clone());
+            }
             sets = vec![];
             notes = vec![];
+            images = vec![];
         }
-        if next_entry.date != entry.date {
+        // DEV: the workout is left out if none of its exercises were matched
+        if ends_workout && !exercises.is_empty() {
             let ndt = NaiveDateTime::parse_from_str(&entry.date, "%Y-%m-%d %H:%M:%S")
                 .expect("Failed to parse input string");
             let ndt = DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc);
@@ -121,18 +543,350 @@ pub async fn import(
                 name: entry.workout_name,
                 comment: entry.workout_notes,
                 start_time: ndt,
-                end_time: ndt + workout_duration,
+                end_time: Some(ndt + workout_duration),
                 exercises,
-                supersets: vec![],
-                assets: EntityAssets::default(),
+                supersets: match detect_supersets {
+                    true => get_supersets(&superset_labels),
+                    false => vec![],
+                },
+                assets: EntityAssets {
+                    images: get_asset_keys(&entry.workout_photos, assets_prefix),
+                    videos: vec![],
+                },
             });
             exercises = vec![];
+            superset_labels = vec![];
         }
     }
-    Ok(ImportResult {
-        collections: vec![],
-        media: vec![],
-        failed_items: vec![],
-        workouts,
-    })
+    workouts
+}
+
+/// Handle the workouts that have no duration or are longer than `max_duration`.
+/// Returns the workouts that should be imported along with a warning for every
+/// workout that was changed or rejected.
+fn check_workout_durations(
+    workouts: Vec<UserWorkoutInput>,
+    handling: WorkoutDurationHandling,
+    max_duration: Duration,
+) -> (Vec<UserWorkoutInput>, Vec<String>) {
+    let mut warnings = vec![];
+    let mut checked = vec![];
+    for mut workout in workouts {
+        let Some(end_time) = workout.end_time else {
+            checked.push(workout);
+            continue;
+        };
+        let duration = end_time - workout.start_time;
+        if duration > Duration::zero() && duration <= max_duration {
+            checked.push(workout);
+            continue;
+        }
+        let description = format!(
+            "Workout {name:?} started on {start} lasted {minutes} minutes",
+            name = workout.name,
+            start = workout.start_time,
+            minutes = duration.num_minutes()
+        );
+        match handling {
+            WorkoutDurationHandling::Accept => {}
+            WorkoutDurationHandling::Reject => {
+                warnings.push(format!("{description} and was not imported"));
+                continue;
+            }
+            WorkoutDurationHandling::Clamp => {
+                let num_sets = workout
+                    .exercises
+                    .iter()
+                    .map(|e| e.sets.len())
+                    .sum::<usize>();
+                let clamped = if duration > max_duration {
+                    max_duration
+                } else {
+                    Duration::minutes(2 * i64::try_from(num_sets).unwrap_or(i64::MAX / 2))
+                        .min(max_duration)
+                };
+                workout.end_time = Some(workout.start_time + clamped);
+                warnings.push(format!(
+                    "{description} and was changed to {} minutes",
+                    clamped.num_minutes()
+                ));
+            }
+        }
+        checked.push(workout);
+    }
+    (checked, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::importer::filter_duplicate_workouts;
+
+    fn reader(csv: &str) -> csv::Reader<&[u8]> {
+        get_csv_reader(csv.as_bytes(), b';', true).unwrap()
+    }
+
+    #[test]
+    fn detects_labelled_supersets() {
+        let csv = "Date;Workout Name;Exercise Name;Set Order;Weight;Reps;Distance;Seconds;Notes;Workout Notes;Workout Duration;Superset
+2023-10-01 10:00:00;Upper;Bench Press;1;60;10;;;;;1h;A1
+2023-10-01 10:00:00;Upper;Bench Press;2;60;8;;;;;1h;A1
+2023-10-01 10:00:00;Upper;Bent Over Row;1;50;10;;;;;1h;A2
+2023-10-01 10:00:00;Upper;Bent Over Row;2;50;10;;;;;1h;A2
+2023-10-01 10:00:00;Upper;Plank;1;;;;60;;;1h;
+";
+        let map = HashMap::from([
+            ("Bench Press".to_owned(), 1),
+            ("Bent Over Row".to_owned(), 2),
+            ("Plank".to_owned(), 3),
+        ]);
+        let workouts = parse_workouts(reader(csv), &map, true, "", &[]);
+        assert_eq!(workouts.len(), 1);
+        assert_eq!(workouts[0].exercises.len(), 3);
+        assert_eq!(workouts[0].supersets, vec![vec![0, 1]]);
+        let workouts = parse_workouts(reader(csv), &map, false, "", &[]);
+        assert!(workouts[0].supersets.is_empty());
+    }
+
+    #[test]
+    fn maps_photos_to_assets() {
+        let csv = "Date;Workout Name;Exercise Name;Set Order;Weight;Reps;Distance;Seconds;Notes;Workout Notes;Workout Duration;Photos;Workout Photos
+2023-10-01 10:00:00;Legs;Squat;1;80;5;;;;;1h;front.jpg;progress.jpg
+2023-10-01 10:00:00;Legs;Squat;2;80;5;;;;;1h;front.jpg, side.jpg;progress.jpg
+";
+        let map = HashMap::from([("Squat".to_owned(), 1)]);
+        let workouts = parse_workouts(reader(csv), &map, true, "uploads/strong/", &[]);
+        assert_eq!(
+            workouts[0].assets.images,
+            vec!["uploads/strong/progress.jpg"]
+        );
+        assert_eq!(
+            workouts[0].exercises[0].assets.images,
+            vec!["uploads/strong/front.jpg", "uploads/strong/side.jpg"]
+        );
+    }
+
+    #[test]
+    fn skips_duplicate_workouts_on_reimport() {
+        let csv = "Date;Workout Name;Exercise Name;Set Order;Weight;Reps;Distance;Seconds;Notes;Workout Notes;Workout Duration
+2023-10-01 10:00:00;Legs;Squat;1;80;5;;;;;1h
+2023-10-01 10:00:00;Legs;Lunge;1;20;10;;;;;1h
+2023-10-03 10:00:00;Legs;Squat;1;85;5;;;;;1h
+";
+        let map = HashMap::from([("Squat".to_owned(), 1), ("Lunge".to_owned(), 2)]);
+        let mut existing = HashSet::new();
+        let workouts = parse_workouts(reader(csv), &map, true, "", &[]);
+        let (created, skipped) = filter_duplicate_workouts(workouts, &mut existing);
+        assert_eq!((created.len(), skipped), (2, 0));
+        let workouts = parse_workouts(reader(csv), &map, true, "", &[]);
+        let (created, skipped) = filter_duplicate_workouts(workouts, &mut existing);
+        assert_eq!((created.len(), skipped), (0, 2));
+    }
+
+    #[test]
+    fn ignores_single_exercise_groups() {
+        let labels = vec![
+            Some("A1".to_owned()),
+            None,
+            Some("b1".to_owned()),
+            Some("B2".to_owned()),
+            Some("C1".to_owned()),
+        ];
+        assert_eq!(get_supersets(&labels), vec![vec![2, 3]]);
+    }
+
+    #[test]
+    fn guesses_exercises_from_export() {
+        let csv = "Date;Workout Name;Exercise Name;Set Order;Weight;Reps;Distance;Seconds;Notes;Workout Notes;Workout Duration
+2023-10-01 10:00:00;Upper;Bench Press (Barbell);1;60;10;;;;;1h
+2023-10-01 10:00:00;Upper;Bench Press (Barbell);2;60;8;;;;;1h
+2023-10-01 10:00:00;Upper;Lat Pulldown (Cable);1;50;10;;;;;1h
+2023-10-01 10:00:00;Upper;Zercher Carry;1;;;;60;;;1h
+";
+        let exercises = [
+            "Barbell Bench Press - Medium Grip",
+            "Barbell Bench Press",
+            "Wide-Grip Lat Pulldown",
+            "Cable Lat Pulldown",
+        ]
+        .map(String::from);
+        let names = get_exercise_names(reader(csv)).unwrap();
+        assert_eq!(
+            names,
+            vec![
+                "Bench Press (Barbell)",
+                "Lat Pulldown (Cable)",
+                "Zercher Carry"
+            ]
+        );
+        let guesses = names
+            .iter()
+            .map(|n| guess_exercise(n, &exercises))
+            .collect_vec();
+        assert_eq!(
+            guesses,
+            vec![
+                Some("Barbell Bench Press".to_owned()),
+                Some("Cable Lat Pulldown".to_owned()),
+                None
+            ]
+        );
+        assert_eq!(guess_exercise("Bench Press (Dumbbell)", &exercises), None);
+    }
+
+    #[test]
+    fn imports_bodyweight_rows_as_measurements() {
+        let csv = "Date;Workout Name;Exercise Name;Set Order;Weight;Reps;Distance;Seconds;Notes;Workout Notes;Workout Duration
+2023-10-01 10:00:00;Legs;Bodyweight;1;72.5;;;;morning;;1h
+2023-10-01 10:00:00;Legs;Squat;1;80;5;;;;;1h
+2023-10-01 10:00:00;Legs;Squat;2;80;5;;;;;1h
+";
+        let map = HashMap::from([("Squat".to_owned(), 1)]);
+        let names = vec!["Bodyweight".to_owned()];
+        let workouts = parse_workouts(reader(csv), &map, true, "", &names);
+        assert_eq!(workouts.len(), 1);
+        assert_eq!(workouts[0].exercises.len(), 1);
+        assert_eq!(workouts[0].exercises[0].sets.len(), 2);
+        let measurements = parse_bodyweight_measurements(reader(csv), &names);
+        assert_eq!(measurements.len(), 1);
+        assert_eq!(measurements[0].stats.weight, Some(dec!(72.5)));
+        assert_eq!(measurements[0].comment, Some("morning".to_owned()));
+        assert!(parse_bodyweight_measurements(reader(csv), &[]).is_empty());
+    }
+
+    #[test]
+    fn reports_mapping_coverage_of_export() {
+        let csv = "Date;Workout Name;Exercise Name;Set Order;Weight;Reps;Distance;Seconds;Notes;Workout Notes;Workout Duration
+2023-10-01 10:00:00;Legs;Bodyweight;1;72.5;;;;;;1h
+2023-10-01 10:00:00;Legs;Squat;1;80;5;;;;;1h
+2023-10-01 10:00:00;Legs;Squat;2;80;5;;;;;1h
+2023-10-01 10:00:00;Legs;Lunge;1;20;10;;;;;1h
+2023-10-01 10:00:00;Legs;Calf Raise;1;40;12;;;;;1h
+2023-10-01 10:00:00;Legs;Calf Raise;2;40;12;;;;;1h
+2023-10-01 10:00:00;Legs;Leg Curl;1;30;12;;;;;1h
+";
+        let mapping = [
+            ("Squat", "Squat"),
+            ("Lunge", "Lunge"),
+            ("Leg Curl", "Missing"),
+        ]
+        .into_iter()
+        .map(|(source, target)| StrongAppImportMapping {
+            source_name: source.to_owned(),
+            target_name: target.to_owned(),
+        })
+        .collect_vec();
+        let map = HashMap::from([("Squat".to_owned(), 1), ("Lunge".to_owned(), 2)]);
+        let exercise_ids =
+            resolve_exercise_ids(&get_exercise_names(reader(csv)).unwrap(), &mapping, &map);
+        let names = vec!["Bodyweight".to_owned()];
+        let coverage = get_mapping_coverage(reader(csv), &exercise_ids, &names).unwrap();
+        assert_eq!(
+            coverage,
+            StrongAppMappingCoverage {
+                total_rows: 6,
+                mapped_rows: 3,
+                unmapped_rows: 3,
+                mapped_percentage: dec!(50),
+                unmapped_names: vec!["Calf Raise".to_owned(), "Leg Curl".to_owned()],
+            }
+        );
+    }
+
+    #[test]
+    fn clamps_implausible_workout_durations() {
+        let csv = "Date;Workout Name;Exercise Name;Set Order;Weight;Reps;Distance;Seconds;Notes;Workout Notes;Workout Duration
+2023-10-01 10:00:00;Legs;Squat;1;80;5;;;;;0m
+2023-10-01 10:00:00;Legs;Squat;2;80;5;;;;;0m
+2023-10-02 10:00:00;Legs;Squat;1;80;5;;;;;30h
+";
+        let map = HashMap::from([("Squat".to_owned(), 1)]);
+        let workouts = parse_workouts(reader(csv), &map, true, "", &[]);
+        let get_minutes = |w: &UserWorkoutInput| (w.end_time.unwrap() - w.start_time).num_minutes();
+        assert_eq!(
+            workouts.iter().map(get_minutes).collect_vec(),
+            vec![0, 30 * 60]
+        );
+        let max = Duration::hours(6);
+        let (clamped, warnings) =
+            check_workout_durations(workouts.clone(), WorkoutDurationHandling::Clamp, max);
+        assert_eq!(
+            clamped.iter().map(get_minutes).collect_vec(),
+            vec![4, 6 * 60]
+        );
+        assert_eq!(warnings.len(), 2);
+        let (rejected, warnings) =
+            check_workout_durations(workouts.clone(), WorkoutDurationHandling::Reject, max);
+        assert!(rejected.is_empty());
+        assert_eq!(warnings.len(), 2);
+        let (accepted, warnings) =
+            check_workout_durations(workouts, WorkoutDurationHandling::Accept, max);
+        assert_eq!(accepted.len(), 2);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn matches_exercises_without_a_mapping() {
+        let csv = "Date;Workout Name;Exercise Name;Set Order;Weight;Reps;Distance;Seconds;Notes;Workout Notes;Workout Duration
+2023-10-01 10:00:00;Upper;Bench Press (Barbell);1;60;10;;;;;1h
+2023-10-01 10:00:00;Upper;Pull Up;1;;10;;;;;1h
+2023-10-01 10:00:00;Upper;Dips;1;;10;;;;;1h
+2023-10-01 10:00:00;Upper;Zercher Carry;1;;;;60;;;1h
+";
+        let map = HashMap::from([
+            ("Barbell Bench Press".to_owned(), 1),
+            ("Pullups".to_owned(), 2),
+            ("Dips - Triceps".to_owned(), 3),
+        ]);
+        let mapping = vec![StrongAppImportMapping {
+            source_name: "Pull Up".to_owned(),
+            target_name: "Pullups".to_owned(),
+        }];
+        let names = get_exercise_names(reader(csv)).unwrap();
+        let exercise_ids = resolve_exercise_ids(&names, &mapping, &map);
+        assert_eq!(
+            exercise_ids,
+            HashMap::from([
+                ("Bench Press (Barbell)".to_owned(), 1),
+                ("Pull Up".to_owned(), 2),
+                ("Dips".to_owned(), 3),
+            ])
+        );
+        let workouts = parse_workouts(reader(csv), &exercise_ids, true, "", &[]);
+        assert_eq!(workouts[0].exercises.len(), 3);
+        let lots = infer_exercise_lots(reader(csv));
+        assert_eq!(lots["Zercher Carry"], ExerciseLot::Duration);
+        assert_eq!(lots["Dips"], ExerciseLot::RepsAndWeight);
+    }
+
+    #[test]
+    fn keeps_the_types_of_sets() {
+        let csv = "Date;Workout Name;Exercise Name;Set Order;Weight;Reps;Distance;Seconds;Notes;Workout Notes;Workout Duration;RPE
+2023-10-01 10:00:00;Legs;Squat;W;40;10;;;;;1h;
+2023-10-01 10:00:00;Legs;Squat;1;80;5;;;;;1h;7
+2023-10-01 10:00:00;Legs;Squat;F;80;3;;;;;1h;10
+2023-10-01 10:00:00;Legs;Squat;D;60;8;;;;;1h;
+2023-10-01 10:00:00;Legs;Squat;W;40;10;;;;;1h;
+2023-10-01 10:00:00;Legs;Squat;1;85;5;;;;;1h;
+2023-10-01 10:00:00;Legs;Lunge;1;20;10;;;;;1h;
+";
+        let map = HashMap::from([("Squat".to_owned(), 1), ("Lunge".to_owned(), 2)]);
+        let workouts = parse_workouts(reader(csv), &map, true, "", &[]);
+        let exercises = &workouts[0].exercises;
+        assert_eq!(exercises.len(), 3);
+        assert_eq!(
+            exercises[0].sets.iter().map(|s| s.lot).collect_vec(),
+            vec![
+                SetLot::WarmUp,
+                SetLot::Normal,
+                SetLot::Failure,
+                SetLot::Drop
+            ]
+        );
+        assert_eq!(exercises[0].sets[1].statistic.rpe, Some(dec!(7)));
+        assert_eq!(exercises[1].sets.len(), 2);
+        assert_eq!(exercises[2].exercise_id, 2);
+    }
 }
@@ -1,4 +1,5 @@
-use async_graphql::Result;
+use std::collections::HashMap;
+
 use convert_case::{Case, Casing};
 use database::{MetadataLot, MetadataSource};
 use http_types::mime;
@@ -7,12 +8,16 @@ use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use sea_orm::prelude::DateTimeUtc;
 use serde::{Deserialize, Serialize};
-use surf::http::headers::CONTENT_TYPE;
+use surf::{
+    http::headers::{AUTHORIZATION, CONTENT_TYPE},
+    Client,
+};
 
 use crate::{
     importer::{
-        DeployTraktImportInput, ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier,
-        ImportOrExportMediaItem, ImportResult,
+        get_source_json, send_source_request, DeployTraktImportInput, ImportErrorCode,
+        ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier, ImportOrExportMediaItem,
+        ImportResult, ImporterError, ImporterResult,
     },
     models::media::{
         CreateOrUpdateCollectionInput, ImportOrExportItemRating, ImportOrExportItemReview,
@@ -47,6 +52,9 @@ struct ListItemResponse {
     watched_at: Option<DateTimeUtc>,
     rated_at: Option<DateTimeUtc>,
     rating: Option<Decimal>,
+    rank: Option<i32>,
+    listed_at: Option<DateTimeUtc>,
+    hidden_at: Option<DateTimeUtc>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -58,32 +66,45 @@ struct ListResponse {
     items: Vec<ListItemResponse>,
 }
 
-pub async fn import(input: DeployTraktImportInput) -> Result<ImportResult> {
+pub async fn import(input: DeployTraktImportInput) -> ImporterResult<ImportResult> {
     let mut media_items = vec![];
     let mut failed_items = vec![];
 
-    let client = get_base_http_client(
-        &format!("{}/users/{}/", API_URL, input.username),
-        vec![
-            (CONTENT_TYPE, mime::JSON.to_string().as_str()),
-            ("trakt-api-key".into(), CLIENT_ID),
-            ("trakt-api-version".into(), API_VERSION),
-        ],
-    );
-    let mut rsp = client.get("lists").await.unwrap();
-    let mut lists: Vec<ListResponse> = rsp.body_json().await.unwrap();
+    let user = match (input.access_token.as_ref(), input.username) {
+        (Some(_), _) => "me".to_owned(),
+        (None, Some(username)) => username,
+        (None, None) => {
+            return Err(ImporterError::parse(
+                "the input",
+                "Either the username or an access token is required",
+            ))
+        }
+    };
+    let authorization = input.access_token.map(|t| format!("Bearer {t}"));
+    let mut headers = vec![
+        (CONTENT_TYPE, mime::JSON.to_string()),
+        ("trakt-api-key".into(), CLIENT_ID.to_owned()),
+        ("trakt-api-version".into(), API_VERSION.to_owned()),
+    ];
+    if let Some(authorization) = authorization.as_ref() {
+        headers.push((AUTHORIZATION, authorization.to_owned()));
+    }
+    let client = get_base_http_client(&format!("{}/users/", API_URL), headers);
+    let path = |p: &str| format!("{user}/{p}");
+    let mut lists: Vec<ListResponse> =
+        get_source_json(client.get(path("lists")), "the lists").await?;
 
     for list in lists.iter_mut() {
-        let mut rsp = client
-            .get(&format!("lists/{}/items", list.ids.trakt))
-            .await
-            .unwrap();
-        let items: Vec<ListItemResponse> = rsp.body_json().await.unwrap();
+        let items: Vec<ListItemResponse> = get_source_json(
+            client.get(path(&format!("lists/{}/items", list.ids.trakt))),
+            &format!("the items of list {:?}", list.name),
+        )
+        .await?;
         list.items = items;
     }
     for list in ["watchlist", "favorites"] {
-        let mut rsp = client.get(list).await.unwrap();
-        let items: Vec<ListItemResponse> = rsp.body_json().await.unwrap();
+        let items: Vec<ListItemResponse> =
+            get_source_json(client.get(path(list)), &format!("the {list}")).await?;
         lists.push(ListResponse {
             name: list.to_owned(),
             description: None,
@@ -95,11 +116,23 @@ pub async fn import(input: DeployTraktImportInput) -> Result<ImportResult> {
         });
     }
 
+    let mut collection_ranks = HashMap::new();
+    let mut collection_added_on = HashMap::new();
     for l in lists.iter() {
-        for i in l.items.iter() {
+        let collection_name = l.name.to_case(Case::Title);
+        for (idx, i) in l.items.iter().enumerate() {
             match process_item(i) {
                 Ok(mut d) => {
-                    d.collections.push(l.name.to_case(Case::Title));
+                    let rank = i.rank.unwrap_or_else(|| (idx + 1).try_into().unwrap());
+                    collection_ranks.insert((collection_name.clone(), d.source_id.clone()), rank);
+                    if let Some(listed_at) = i.listed_at {
+                        collection_added_on
+                            .insert((collection_name.clone(), d.source_id.clone()), listed_at);
+                    }
+                    d.collections.push(collection_name.clone());
+                    if l.name == "favorites" {
+                        d.is_favorite = Some(true);
+                    }
                     media_items.push(d)
                 }
                 Err(d) => failed_items.push(d),
@@ -107,7 +140,7 @@ pub async fn import(input: DeployTraktImportInput) -> Result<ImportResult> {
         }
     }
 
-    let all_collections = lists
+    let mut all_collections = lists
         .iter()
         .map(|l| CreateOrUpdateCollectionInput {
             name: l.name.to_case(Case::Title),
@@ -122,12 +155,16 @@ pub async fn import(input: DeployTraktImportInput) -> Result<ImportResult> {
         })
         .collect_vec();
 
-    for typ in ["movies", "shows"] {
-        let mut rsp = client.get(format!("ratings/{}", typ)).await.unwrap();
-        let ratings: Vec<ListItemResponse> = rsp.body_json().await.unwrap();
+    for typ in ["movies", "shows", "episodes"] {
+        let ratings: Vec<ListItemResponse> = get_source_json(
+            client.get(path(&format!("ratings/{}", typ))),
+            &format!("the ratings of {typ}"),
+        )
+        .await?;
         for item in ratings.iter() {
             match process_item(item) {
                 Ok(mut d) => {
+                    let episode = item.episode.as_ref();
                     d.reviews.push(ImportOrExportItemRating {
                         rating: item
                             .rating
@@ -137,11 +174,14 @@ pub async fn import(input: DeployTraktImportInput) -> Result<ImportResult> {
                             spoiler: Some(false),
                             text: Some("".to_owned()),
                             date: item.rated_at,
+                            visibility: None,
                         }),
+                        show_season_number: episode.and_then(|e| e.season),
+                        show_episode_number: episode.and_then(|e| e.number),
                         ..Default::default()
                     });
                     if let Some(a) = media_items.iter_mut().find(|i| i.source_id == d.source_id) {
-                        a.reviews = d.reviews;
+                        a.reviews.extend(d.reviews);
                     } else {
                         media_items.push(d)
                     }
@@ -151,30 +191,33 @@ pub async fn import(input: DeployTraktImportInput) -> Result<ImportResult> {
         }
     }
 
-    let mut histories = vec![];
-    let rsp = client
-        .head("history")
-        .query(&serde_json::json!({ "limit": 1000 }))
-        .unwrap()
-        .await
-        .unwrap();
-    let total_history = rsp
-        .header("x-pagination-page-count")
-        .expect("pagination to be present")
-        .last()
-        .as_str()
-        .parse::<usize>()
-        .unwrap();
-    for page in 1..total_history + 1 {
-        tracing::trace!("Fetching user history {page:?}/{total_history:?}");
-        let mut rsp = client
-            .get("history")
-            .query(&serde_json::json!({ "page": page, "limit": 1000 }))
-            .unwrap()
-            .await
-            .unwrap();
-        let history: Vec<ListItemResponse> = rsp.body_json().await.unwrap();
-        histories.extend(history);
+    let histories = get_all_pages(&client, &path("history"), "the history").await?;
+
+    // DEV: Only the user can see the items they hid, so these need to be
+    // fetched with an access token.
+    if authorization.is_some() {
+        let hidden = get_all_pages(&client, "hidden/progress_watched", "the hidden items").await?;
+        let collection_name = "Hidden".to_owned();
+        if !hidden.is_empty() {
+            all_collections.push(CreateOrUpdateCollectionInput {
+                name: collection_name.clone(),
+                description: Some("Items that were hidden from the progress on Trakt.".to_owned()),
+                ..Default::default()
+            });
+        }
+        for item in hidden.iter() {
+            match process_item(item) {
+                Ok(mut d) => {
+                    if let Some(hidden_at) = item.hidden_at {
+                        collection_added_on
+                            .insert((collection_name.clone(), d.source_id.clone()), hidden_at);
+                    }
+                    d.collections.push(collection_name.clone());
+                    media_items.push(d);
+                }
+                Err(d) => failed_items.push(d),
+            }
+        }
     }
 
     for item in histories.iter() {
@@ -197,6 +240,8 @@ pub async fn import(input: DeployTraktImportInput) -> Result<ImportResult> {
                             "Item is a show but does not have a season or episode number"
                                 .to_owned(),
                         ),
+                        code: ImportErrorCode::ParseError,
+                        source_row: serde_json::to_string(item).ok(),
                     });
                     continue;
                 }
@@ -220,9 +265,49 @@ pub async fn import(input: DeployTraktImportInput) -> Result<ImportResult> {
         media: media_items,
         failed_items,
         workouts: vec![],
+        collection_ranks,
+        collection_added_on,
+        ..Default::default()
     })
 }
 
+/// Get every page of a paginated endpoint.
+async fn get_all_pages(
+    client: &Client,
+    path: &str,
+    context: &str,
+) -> ImporterResult<Vec<ListItemResponse>> {
+    let rsp = send_source_request(
+        client
+            .head(path)
+            .query(&serde_json::json!({ "limit": 1000 }))
+            .unwrap(),
+        context,
+    )
+    .await?;
+    let total_pages = rsp
+        .header("x-pagination-page-count")
+        .ok_or_else(|| ImporterError::parse(context, "pagination is not present"))?
+        .last()
+        .as_str()
+        .parse::<usize>()
+        .map_err(|e| ImporterError::parse(context, e))?;
+    let mut items = vec![];
+    for page in 1..total_pages + 1 {
+        tracing::trace!("Fetching {context} {page:?}/{total_pages:?}");
+        let page: Vec<ListItemResponse> = get_source_json(
+            client
+                .get(path)
+                .query(&serde_json::json!({ "page": page, "limit": 1000 }))
+                .unwrap(),
+            context,
+        )
+        .await?;
+        items.extend(page);
+    }
+    Ok(items)
+}
+
 fn process_item(
     i: &ListItemResponse,
 ) -> std::result::Result<ImportOrExportMediaItem, ImportFailedItem> {
@@ -236,6 +321,8 @@ fn process_item(
             step: ImportFailStep::ItemDetailsFromSource,
             identifier: "".to_owned(),
             error: Some("Item is neither a movie or a show".to_owned()),
+            code: ImportErrorCode::Unsupported,
+            source_row: serde_json::to_string(i).ok(),
         });
     };
     match identifier {
@@ -248,12 +335,18 @@ fn process_item(
             seen_history: vec![],
             reviews: vec![],
             collections: vec![],
+            external_identifiers: None,
+            is_favorite: None,
+            next_episode: None,
+            display_title: None,
         }),
         None => Err(ImportFailedItem {
             lot: MetadataLot::Book,
             step: ImportFailStep::ItemDetailsFromSource,
             identifier: "".to_owned(),
             error: Some("Item does not have an associated TMDB id".to_owned()),
+            code: ImportErrorCode::MissingIdentifier,
+            source_row: serde_json::to_string(i).ok(),
         }),
     }
 }
@@ -41,19 +41,24 @@ use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::{fmt, layer::SubscriberExt};
 
 use crate::{
-    background::{media_jobs, perform_application_job, user_jobs, yank_integrations_data},
+    background::{
+        media_jobs, perform_application_job, scheduled_backups, scheduled_imports, user_jobs,
+        yank_integrations_data,
+    },
     entities::prelude::Exercise,
     graphql::get_schema,
+    importer::logs::ImportJobLogLayer,
     models::ExportAllResponse,
     routes::{
-        config_handler, graphql_handler, graphql_playground, integration_webhook, json_export,
-        static_handler, upload_file,
+        config_handler, csv_export, graphql_handler, graphql_playground, integration_webhook,
+        json_export, static_handler, upload_file,
     },
     utils::{create_app_services, BASE_DIR, VERSION},
 };
 
 mod background;
 mod entities;
+mod exporter;
 mod file_storage;
 mod fitness;
 mod graphql;
@@ -179,6 +184,14 @@ async fn main() -> Result<()> {
             .unwrap();
     }
 
+    if let Err(e) = app_services
+        .importer_service
+        .resume_interrupted_import_jobs()
+        .await
+    {
+        tracing::error!("Could not resume the interrupted import jobs: {:?}", e);
+    }
+
     if cfg!(debug_assertions) {
         use schematic::schema::{typescript::TypeScriptRenderer, SchemaGenerator};
 
@@ -228,11 +241,13 @@ async fn main() -> Result<()> {
         .route("/graphql", get(graphql_playground).post(graphql_handler))
         .nest("/webhooks", webhook_routes)
         .route("/export/:export_type", get(json_export))
+        .route("/export/csv/:file_name", get(csv_export))
         .route("/upload", post(upload_file))
         .fallback(static_handler)
         .layer(Extension(app_services.config.clone()))
         .layer(Extension(app_services.media_service.clone()))
         .layer(Extension(app_services.exercise_service.clone()))
+        .layer(Extension(app_services.exporter_service.clone()))
         .layer(Extension(schema))
         .layer(TowerTraceLayer::new_for_http())
         .layer(TowerCatchPanicLayer::new())
@@ -246,13 +261,17 @@ async fn main() -> Result<()> {
     let addr = SocketAddr::from(([0, 0, 0, 0, 0, 0, 0, 0], port));
     tracing::info!("Listening on: {}", addr);
 
+    let exporter_service_1 = app_services.exporter_service.clone();
+    let exporter_service_2 = app_services.exporter_service.clone();
     let importer_service_1 = app_services.importer_service.clone();
     let importer_service_2 = app_services.importer_service.clone();
+    let importer_service_3 = app_services.importer_service.clone();
     let media_service_1 = app_services.media_service.clone();
     let media_service_2 = app_services.media_service.clone();
     let media_service_3 = app_services.media_service.clone();
     let media_service_4 = app_services.media_service.clone();
     let exercise_service_1 = app_services.exercise_service.clone();
+    let exercise_service_2 = app_services.exercise_service.clone();
 
     let monitor = async {
         let mn = Monitor::new()
@@ -269,6 +288,7 @@ async fn main() -> Result<()> {
                     )
                     .layer(ApalisTraceLayer::new())
                     .layer(ApalisExtension(media_service_1.clone()))
+                    .layer(ApalisExtension(exercise_service_2.clone()))
                     .build_fn(user_jobs)
             })
             .register_with_count(1, move |c| {
@@ -297,6 +317,30 @@ async fn main() -> Result<()> {
                     .layer(ApalisExtension(media_service_3.clone()))
                     .build_fn(yank_integrations_data)
             })
+            .register_with_count(1, move |c| {
+                WorkerBuilder::new(format!("scheduled_imports-{c}"))
+                    .stream(
+                        // every minute
+                        CronStream::new(Schedule::from_str("0 * * * * *").unwrap())
+                            .timer(SleepTimer)
+                            .to_stream_with_timezone(tz),
+                    )
+                    .layer(ApalisTraceLayer::new())
+                    .layer(ApalisExtension(importer_service_3.clone()))
+                    .build_fn(scheduled_imports)
+            })
+            .register_with_count(1, move |c| {
+                WorkerBuilder::new(format!("scheduled_backups-{c}"))
+                    .stream(
+                        // every hour
+                        CronStream::new(Schedule::from_str("0 0 * * * *").unwrap())
+                            .timer(SleepTimer)
+                            .to_stream_with_timezone(tz),
+                    )
+                    .layer(ApalisTraceLayer::new())
+                    .layer(ApalisExtension(exporter_service_2.clone()))
+                    .build_fn(scheduled_backups)
+            })
             // application jobs
             .register_with_count(3, move |c| {
                 WorkerBuilder::new(format!("perform_application_job-{c}"))
@@ -308,6 +352,7 @@ async fn main() -> Result<()> {
                     .layer(ApalisExtension(importer_service_1.clone()))
                     .layer(ApalisExtension(media_service_4.clone()))
                     .layer(ApalisExtension(exercise_service_1.clone()))
+                    .layer(ApalisExtension(exporter_service_1.clone()))
                     .with_storage(perform_application_job_storage.clone())
                     .build_fn(perform_application_job)
             })
@@ -349,7 +394,8 @@ fn init_tracing() -> Result<WorkerGuard> {
                 fmt::Layer::default()
                     .with_writer(non_blocking)
                     .with_ansi(false),
-            ),
+            )
+            .with(ImportJobLogLayer),
     )
     .expect("Unable to set global tracing subscriber");
     Ok(guard)
@@ -10,7 +10,8 @@ use anyhow::anyhow;
 use apalis::{prelude::Storage as ApalisStorage, sqlite::SqliteStorage};
 use argon2::{Argon2, PasswordHash, PasswordVerifier};
 use async_graphql::{
-    Context, Enum, Error, InputObject, InputType, Object, OneofObject, Result, SimpleObject, Union,
+    Context, Enum, Error, InputObject, InputType, Json, Object, OneofObject, Result, SimpleObject,
+    Union,
 };
 use chrono::{Datelike, Days, Duration as ChronoDuration, NaiveDate, Utc};
 use cookie::{
@@ -45,7 +46,8 @@ use sea_orm::{
 };
 use sea_query::{
     Alias, Asterisk, Cond, Condition, Expr, Func, Keyword, MySqlQueryBuilder, NullOrdering,
-    PostgresQueryBuilder, Query, SelectStatement, SqliteQueryBuilder, UnionType, Values,
+    OnConflict, PostgresQueryBuilder, Query, SelectStatement, SqliteQueryBuilder, UnionType,
+    Values,
 };
 use semver::Version;
 use serde::{Deserialize, Serialize};
@@ -64,9 +66,10 @@ use crate::{
             CalendarEvent, Collection, CollectionToEntity, Exercise, Genre, Metadata,
             MetadataGroup, MetadataToGenre, MetadataToPartialMetadata, MetadataToPerson,
             PartialMetadata as PartialMetadataModel, PartialMetadataToMetadataGroup, Person,
-            PersonToPartialMetadata, Review, Seen, User, UserMeasurement, UserToEntity, Workout,
+            PersonToPartialMetadata, ReadingGoal, Review, Seen, User, UserMeasurement,
+            UserToEntity, Workout,
         },
-        review, seen,
+        reading_goal, review, seen,
         user::{
             self, UserWithOnlyIntegrationsAndNotifications, UserWithOnlyPreferences,
             UserWithOnlySummary,
@@ -80,18 +83,20 @@ use crate::{
     models::{
         media::{
             AnimeSpecifics, AudioBookSpecifics, BookSpecifics, ChangeCollectionToEntityInput,
-            CreateOrUpdateCollectionInput, GenreListItem, ImportOrExportItemRating,
-            ImportOrExportItemReview, ImportOrExportItemReviewComment, ImportOrExportMediaItem,
-            ImportOrExportMediaItemSeen, ImportOrExportPersonItem, MangaSpecifics,
-            MediaCreatorSearchItem, MediaDetails, MediaListItem, MediaSearchItem,
-            MediaSearchItemResponse, MediaSearchItemWithLot, MediaSpecifics, MetadataFreeCreator,
-            MetadataGroupListItem, MetadataImage, MetadataImageForMediaDetails, MetadataImageLot,
-            MetadataVideo, MetadataVideoSource, MovieSpecifics, PartialMetadataPerson,
-            PodcastSpecifics, PostReviewInput, ProgressUpdateError, ProgressUpdateErrorVariant,
-            ProgressUpdateInput, ProgressUpdateResultUnion, ReviewCommentUser,
-            SeenOrReviewOrCalendarEventExtraInformation, SeenPodcastExtraInformation,
-            SeenShowExtraInformation, ShowSpecifics, UserMediaReminder, UserSummary,
-            VideoGameSpecifics, VisualNovelSpecifics,
+            CreateOrUpdateCollectionInput, GenreListItem, ImportOrExportCollectionItem,
+            ImportOrExportItemRating, ImportOrExportItemReview, ImportOrExportItemReviewComment,
+            ImportOrExportMediaItem, ImportOrExportMediaItemSeen,
+            ImportOrExportMediaItemSeenDropped, ImportOrExportMetadataGroupItem,
+            ImportOrExportPersonItem, MangaSpecifics, MediaCreatorSearchItem, MediaDetails,
+            MediaListItem, MediaSearchItem, MediaSearchItemResponse, MediaSearchItemWithLot,
+            MediaSpecifics, MetadataExternalIdentifier, MetadataFreeCreator, MetadataGroupListItem,
+            MetadataImage, MetadataImageForMediaDetails, MetadataImageLot, MetadataVideo,
+            MetadataVideoSource, MovieSpecifics, PartialMetadataPerson, PodcastSpecifics,
+            PostReviewInput, ProgressUpdateError, ProgressUpdateErrorVariant, ProgressUpdateInput,
+            ProgressUpdateResultUnion, ReviewCommentUser,
+            SeenOrReviewOrCalendarEventExtraInformation, SeenPartExtraInformation,
+            SeenPodcastExtraInformation, SeenShowExtraInformation, ShowSpecifics,
+            UserMediaReminder, UserSummary, VideoGameSpecifics, VisualNovelSpecifics,
         },
         EntityLot, IdObject, SearchDetails, SearchInput, SearchResults, StoredUrl,
     },
@@ -116,14 +121,15 @@ use crate::{
     },
     users::{
         UserNotification, UserNotificationSetting, UserNotificationSettingKind, UserPreferences,
-        UserReviewScale, UserSinkIntegration, UserSinkIntegrationSetting,
+        UserReviewScale, UserSetVolumeFormula, UserSinkIntegration, UserSinkIntegrationSetting,
         UserSinkIntegrationSettingKind, UserUnitSystem, UserYankIntegration,
         UserYankIntegrationSetting, UserYankIntegrationSettingKind,
     },
     utils::{
-        add_entity_to_collection, associate_user_with_metadata, entity_in_collections,
-        get_ilike_query, get_stored_asset, get_user_and_metadata_association, partial_user_by_id,
-        user_by_id, user_id_from_token, AUTHOR, COOKIE_NAME, USER_AGENT_STR, VERSION,
+        add_entity_to_collection, admin_account_guard, associate_user_with_metadata,
+        entity_in_collections, get_ilike_query, get_stored_asset,
+        get_user_and_metadata_association, partial_user_by_id, user_by_id, user_id_from_token,
+        AUTHOR, COOKIE_NAME, USER_AGENT_STR, VERSION,
     },
 };
 
@@ -321,6 +327,7 @@ enum CollectionContentsSortBy {
     #[default]
     LastUpdatedOn,
     Date,
+    Rank,
 }
 
 #[derive(Debug, Serialize, Deserialize, InputObject, Clone, Default)]
@@ -365,6 +372,8 @@ struct ReviewItem {
     show_episode: Option<i32>,
     podcast_episode: Option<i32>,
     comments: Vec<ImportOrExportItemReviewComment>,
+    /// The seen history item this review is for, if any.
+    seen_id: Option<i32>,
 }
 
 #[derive(Debug, SimpleObject)]
@@ -537,6 +546,7 @@ enum MediaGeneralFilter {
     Completed,
     Unseen,
     ExplicitlyMonitored,
+    Favorite,
 }
 
 #[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
@@ -597,6 +607,7 @@ struct ProgressUpdateCache {
     show_season_number: Option<i32>,
     show_episode_number: Option<i32>,
     podcast_episode_number: Option<i32>,
+    part_number: Option<i32>,
 }
 
 #[derive(SimpleObject)]
@@ -623,8 +634,12 @@ struct UserMediaDetails {
     in_progress: Option<seen::Model>,
     /// The next episode of this media.
     next_episode: Option<UserMediaNextEpisode>,
+    /// The title to show instead of the one from the provider, if any.
+    title_override: Option<String>,
     /// Whether the user is monitoring this media.
     is_monitored: bool,
+    /// Whether the user has marked this media as a favorite.
+    is_favorite: bool,
     /// The reminder that the user has set for this media.
     reminder: Option<UserMediaReminder>,
     /// The number of users who have seen this media.
@@ -676,6 +691,15 @@ struct GraphqlCalendarEvent {
     podcast_episode_number: Option<i32>,
 }
 
+/// A reading goal along with the progress made towards it.
+#[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
+struct ReadingGoalProgress {
+    year: i32,
+    target: i32,
+    /// The number of distinct books that were finished in the year.
+    current: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize, SimpleObject, Clone, Default)]
 struct GroupedCalendarEvent {
     events: Vec<GraphqlCalendarEvent>,
@@ -997,6 +1021,13 @@ impl MiscellaneousQuery {
         let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
         service.people_list(input).await
     }
+
+    /// Get the reading goals of the user along with the progress made towards them.
+    async fn reading_goals(&self, gql_ctx: &Context<'_>) -> Result<Vec<ReadingGoalProgress>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.reading_goals(user_id).await
+    }
 }
 
 #[derive(Default)]
@@ -1051,6 +1082,19 @@ impl MiscellaneousMutation {
         service.remove_entity_from_collection(user_id, input).await
     }
 
+    /// Export the media in a collection along with the user's history and reviews
+    /// for them. The result can be imported using the "Media JSON" importer.
+    async fn export_collection(
+        &self,
+        gql_ctx: &Context<'_>,
+        collection_name: String,
+    ) -> Result<Json<Vec<ImportOrExportMediaItem>>> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        let items = service.export_collection(user_id, collection_name).await?;
+        Ok(Json(items))
+    }
+
     /// Delete a collection.
     async fn delete_collection(
         &self,
@@ -1284,6 +1328,31 @@ impl MiscellaneousMutation {
             .await
     }
 
+    /// Mark or unmark a media as a favorite of the user.
+    async fn set_media_favorite(
+        &self,
+        gql_ctx: &Context<'_>,
+        metadata_id: i32,
+        is_favorite: bool,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service
+            .set_media_favorite(user_id, metadata_id, is_favorite)
+            .await
+    }
+
+    /// Create or update the reading goal of the user for a year.
+    async fn set_reading_goal(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: reading_goal::Model,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.set_reading_goal(user_id, input).await
+    }
+
     /// Create or update a reminder on a media for a user.
     async fn create_media_reminder(
         &self,
@@ -1309,9 +1378,10 @@ impl MiscellaneousMutation {
         file_name: String,
     ) -> Result<PresignedPutUrlResponse> {
         let service = gql_ctx.data_unchecked::<Arc<MiscellaneousService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
         let (key, upload_url) = service
             .file_storage_service
-            .get_presigned_put_url(file_name)
+            .get_presigned_put_url(user_id, file_name)
             .await;
         Ok(PresignedPutUrlResponse { upload_url, key })
     }
@@ -1778,7 +1848,6 @@ impl MiscellaneousService {
                 None
             }
         });
-        let next_episode = next_episode.filter(|ne| ne.episode_number.is_some());
         let is_monitored = self.get_monitored_status(user_id, metadata_id).await?;
         let metadata_alias = Alias::new("m");
         let seen_alias = Alias::new("s");
@@ -1810,12 +1879,31 @@ impl MiscellaneousService {
             .map(|qr| qr.try_get_by_index::<i64>(1).unwrap())
             .unwrap();
         let seen_by: i32 = seen_by.try_into().unwrap();
-        let reminder = UserToEntity::find()
+        let association = UserToEntity::find()
             .filter(user_to_entity::Column::UserId.eq(user_id))
             .filter(user_to_entity::Column::MetadataId.eq(metadata_id))
             .one(&self.db)
-            .await?
-            .and_then(|n| n.metadata_reminder);
+            .await?;
+        let is_favorite = association
+            .as_ref()
+            .and_then(|n| n.metadata_favorite)
+            .unwrap_or_default();
+        let reminder = association
+            .as_ref()
+            .and_then(|n| n.metadata_reminder.clone());
+        let title_override = association
+            .as_ref()
+            .and_then(|n| n.metadata_title_override.clone());
+        // a pointer recorded by an import is cleared as soon as the user logs
+        // progress, so it takes precedence over the one derived from history
+        let next_episode = association
+            .and_then(|n| n.metadata_next_episode)
+            .map(|n| UserMediaNextEpisode {
+                season_number: Some(n.season),
+                episode_number: Some(n.episode),
+            })
+            .or(next_episode)
+            .filter(|ne| ne.episode_number.is_some());
 
         let average_rating = if reviews.is_empty() {
             None
@@ -1835,7 +1923,9 @@ impl MiscellaneousService {
             history,
             in_progress,
             next_episode,
+            title_override,
             is_monitored,
+            is_favorite,
             seen_by,
             reminder,
             average_rating,
@@ -1965,7 +2055,8 @@ impl MiscellaneousService {
                             }
                         };
                     }
-                    SeenOrReviewOrCalendarEventExtraInformation::Other => {}
+                    SeenOrReviewOrCalendarEventExtraInformation::Other
+                    | SeenOrReviewOrCalendarEventExtraInformation::Part(_) => {}
                 }
             }
             if image.is_none() {
@@ -2053,6 +2144,13 @@ impl MiscellaneousService {
                 },
                 |query, v| query.filter(user_to_entity::Column::MetadataMonitored.eq(v)),
             )
+            .apply_if(
+                match input.filter.as_ref().and_then(|f| f.general) {
+                    Some(MediaGeneralFilter::Favorite) => Some(true),
+                    _ => None,
+                },
+                |query, v| query.filter(user_to_entity::Column::MetadataFavorite.eq(v)),
+            )
             .into_tuple::<i32>()
             .all(&self.db)
             .await?;
@@ -2231,7 +2329,7 @@ impl MiscellaneousService {
                 };
                 match s {
                     MediaGeneralFilter::All => {}
-                    MediaGeneralFilter::ExplicitlyMonitored => {}
+                    MediaGeneralFilter::ExplicitlyMonitored | MediaGeneralFilter::Favorite => {}
                     MediaGeneralFilter::Rated => {
                         main_select = main_select
                             .and_where(
@@ -2393,12 +2491,14 @@ impl MiscellaneousService {
         input: ProgressUpdateInput,
         user_id: i32,
     ) -> Result<ProgressUpdateResultUnion> {
+        let import_report_id = input.import_report_id;
         let cache = ProgressUpdateCache {
             user_id,
             metadata_id: input.metadata_id,
             show_season_number: input.show_season_number,
             show_episode_number: input.show_episode_number,
             podcast_episode_number: input.podcast_episode_number,
+            part_number: input.part_number,
         };
 
         if self.seen_progress_cache.get(&cache).await.is_some() {
@@ -2470,6 +2570,21 @@ impl MiscellaneousService {
                 if progress == 100 {
                     last_seen.finished_on = ActiveValue::Set(Some(Utc::now().date_naive()));
                 }
+                if let Some(sessions) = input.sessions {
+                    let mut all_sessions = prev_seen[0].sessions.clone().unwrap_or_default();
+                    all_sessions.extend(sessions);
+                    last_seen.sessions = ActiveValue::Set(Some(all_sessions));
+                }
+                if let Some(watched_with) = input.watched_with {
+                    let mut all_watched_with =
+                        prev_seen[0].watched_with.clone().unwrap_or_default();
+                    all_watched_with.extend(watched_with);
+                    all_watched_with = all_watched_with.into_iter().unique().collect();
+                    last_seen.watched_with = ActiveValue::Set(Some(all_watched_with));
+                }
+                if let Some(watch_platform) = input.watch_platform {
+                    last_seen.watch_platform = ActiveValue::Set(Some(watch_platform));
+                }
                 last_seen.update(&self.db).await.unwrap()
             }
             ProgressUpdateAction::ChangeState => {
@@ -2545,7 +2660,22 @@ impl MiscellaneousService {
                             }));
                         }
                     }
-                    _ => None,
+                    _ => match input.part_number {
+                        Some(part) => {
+                            if part < 1 || input.total_parts.is_some_and(|t| part > t) {
+                                return Ok(ProgressUpdateResultUnion::Error(ProgressUpdateError {
+                                    error: ProgressUpdateErrorVariant::InvalidUpdate,
+                                }));
+                            }
+                            Some(SeenOrReviewOrCalendarEventExtraInformation::Part(
+                                SeenPartExtraInformation {
+                                    part,
+                                    total_parts: input.total_parts,
+                                },
+                            ))
+                        }
+                        None => None,
+                    },
                 };
                 let finished_on = if action == ProgressUpdateAction::JustStarted {
                     None
@@ -2566,7 +2696,11 @@ impl MiscellaneousService {
                     finished_on: ActiveValue::Set(finished_on),
                     last_updated_on: ActiveValue::Set(Utc::now()),
                     extra_information: ActiveValue::Set(extra_infomation),
+                    sessions: ActiveValue::Set(input.sessions),
+                    watched_with: ActiveValue::Set(input.watched_with),
+                    watch_platform: ActiveValue::Set(input.watch_platform),
                     state: ActiveValue::Set(SeenState::InProgress),
+                    import_report_id: ActiveValue::Set(import_report_id),
                     ..Default::default()
                 };
                 seen_insert.insert(&self.db).await.unwrap()
@@ -2584,10 +2718,28 @@ impl MiscellaneousService {
                 )
                 .await;
         }
-        self.after_media_seen_tasks(seen).await?;
+        self.after_media_seen_tasks(seen, import_report_id).await?;
         Ok(ProgressUpdateResultUnion::Ok(IdObject { id }))
     }
 
+    /// Mark a seen item as abandoned before it was finished.
+    pub async fn mark_seen_as_dropped(
+        &self,
+        seen_id: i32,
+        dropped_on: Option<DateTimeUtc>,
+        reason: Option<String>,
+    ) -> Result<()> {
+        let Some(seen) = Seen::find_by_id(seen_id).one(&self.db).await? else {
+            return Err(Error::new("The seen item does not exist".to_owned()));
+        };
+        let mut seen: seen::ActiveModel = seen.into();
+        seen.state = ActiveValue::Set(SeenState::Dropped);
+        seen.dropped_reason = ActiveValue::Set(reason);
+        seen.last_updated_on = ActiveValue::Set(dropped_on.unwrap_or_else(Utc::now));
+        seen.update(&self.db).await?;
+        Ok(())
+    }
+
     pub async fn deploy_bulk_progress_update(
         &self,
         user_id: i32,
@@ -3009,19 +3161,28 @@ impl MiscellaneousService {
     }
 
     async fn associate_genre_with_metadata(&self, name: String, metadata_id: i32) -> Result<()> {
-        let db_genre = if let Some(c) = Genre::find()
-            .filter(genre::Column::Name.eq(&name))
-            .one(&self.db)
-            .await
-            .unwrap()
-        {
+        let find_genre = || Genre::find().filter(genre::Column::Name.eq(&name));
+        let db_genre = if let Some(c) = find_genre().one(&self.db).await? {
             c
         } else {
+            // DEV: Media can be committed concurrently (for eg: during imports), so the
+            // genre might have been created since it was looked up.
             let c = genre::ActiveModel {
-                name: ActiveValue::Set(name),
+                name: ActiveValue::Set(name.clone()),
                 ..Default::default()
             };
-            c.insert(&self.db).await.unwrap()
+            Genre::insert(c)
+                .on_conflict(
+                    OnConflict::column(genre::Column::Name)
+                        .do_nothing()
+                        .to_owned(),
+                )
+                .exec_without_returning(&self.db)
+                .await?;
+            find_genre()
+                .one(&self.db)
+                .await?
+                .ok_or_else(|| Error::new(format!("Genre {name:?} could not be created")))?
         };
         let intermediate = metadata_to_genre::ActiveModel {
             metadata_id: ActiveValue::Set(metadata_id),
@@ -3032,6 +3193,7 @@ impl MiscellaneousService {
     }
 
     pub async fn commit_media_internal(&self, details: MediaDetails) -> Result<IdObject> {
+        let (lot, source, identifier) = (details.lot, details.source, details.identifier.clone());
         let mut images = vec![];
         images.extend(details.url_images.into_iter().map(|i| MetadataImage {
             url: StoredUrl::Url(i.image),
@@ -3065,7 +3227,33 @@ impl MiscellaneousService {
             }),
             ..Default::default()
         };
-        let metadata = metadata.insert(&self.db).await?;
+        // DEV: Media can be committed concurrently (for eg: during imports), so it
+        // might have been committed since it was looked up. Its associations are
+        // changed by whoever inserted it.
+        let inserted = Metadata::insert(metadata)
+            .on_conflict(
+                OnConflict::columns([
+                    metadata::Column::Identifier,
+                    metadata::Column::Source,
+                    metadata::Column::Lot,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .exec_without_returning(&self.db)
+            .await?;
+        let Some(metadata) = Metadata::find()
+            .filter(metadata::Column::Lot.eq(lot))
+            .filter(metadata::Column::Source.eq(source))
+            .filter(metadata::Column::Identifier.eq(&identifier))
+            .one(&self.db)
+            .await?
+        else {
+            return Err(Error::new("The metadata could not be created".to_owned()));
+        };
+        if inserted == 0 {
+            return Ok(IdObject { id: metadata.id });
+        }
 
         self.change_metadata_associations(
             metadata.id,
@@ -3438,7 +3626,35 @@ impl MiscellaneousService {
         .await)
     }
 
-    async fn get_media_provider(
+    pub async fn get_tmdb_movie_service(&self) -> Result<TmdbMovieService> {
+        Ok(TmdbMovieService::new(
+            &self.config.movies_and_shows.tmdb,
+            self.config.frontend.page_size,
+        )
+        .await)
+    }
+
+    pub async fn get_tmdb_show_service(&self) -> Result<TmdbShowService> {
+        Ok(TmdbShowService::new(
+            &self.config.movies_and_shows.tmdb,
+            self.config.frontend.page_size,
+        )
+        .await)
+    }
+
+    pub async fn get_igdb_service(&self) -> Result<IgdbService> {
+        Ok(IgdbService::new(&self.config.video_games, self.config.frontend.page_size).await)
+    }
+
+    pub async fn get_mal_anime_service(&self) -> Result<MalAnimeService> {
+        Ok(MalAnimeService::new(
+            &self.config.anime_and_manga.mal,
+            self.config.frontend.page_size,
+        )
+        .await)
+    }
+
+    pub async fn get_media_provider(
         &self,
         lot: MetadataLot,
         source: MetadataSource,
@@ -3472,20 +3688,8 @@ impl MiscellaneousService {
                     .await,
             ),
             MetadataSource::Tmdb => match lot {
-                MetadataLot::Show => Box::new(
-                    TmdbShowService::new(
-                        &self.config.movies_and_shows.tmdb,
-                        self.config.frontend.page_size,
-                    )
-                    .await,
-                ),
-                MetadataLot::Movie => Box::new(
-                    TmdbMovieService::new(
-                        &self.config.movies_and_shows.tmdb,
-                        self.config.frontend.page_size,
-                    )
-                    .await,
-                ),
+                MetadataLot::Show => Box::new(self.get_tmdb_show_service().await?),
+                MetadataLot::Movie => Box::new(self.get_tmdb_movie_service().await?),
                 _ => return err(),
             },
             MetadataSource::Anilist => match lot {
@@ -3506,13 +3710,7 @@ impl MiscellaneousService {
                 _ => return err(),
             },
             MetadataSource::Mal => match lot {
-                MetadataLot::Anime => Box::new(
-                    MalAnimeService::new(
-                        &self.config.anime_and_manga.mal,
-                        self.config.frontend.page_size,
-                    )
-                    .await,
-                ),
+                MetadataLot::Anime => Box::new(self.get_mal_anime_service().await?),
                 MetadataLot::Manga => Box::new(
                     MalMangaService::new(
                         &self.config.anime_and_manga.mal,
@@ -3619,6 +3817,34 @@ impl MiscellaneousService {
         }
     }
 
+    /// Record the identifiers of a media item on other providers so that it can be
+    /// resolved using any of them in the future.
+    pub async fn add_external_identifiers(
+        &self,
+        metadata_id: i32,
+        identifiers: Vec<MetadataExternalIdentifier>,
+    ) -> Result<()> {
+        let Some(meta) = Metadata::find_by_id(metadata_id).one(&self.db).await? else {
+            return Err(Error::new("The metadata does not exist".to_owned()));
+        };
+        let mut all_identifiers = meta.external_identifiers.clone().unwrap_or_default();
+        let before = all_identifiers.len();
+        for id in identifiers {
+            if (id.source == meta.source && id.identifier == meta.identifier)
+                || all_identifiers.contains(&id)
+            {
+                continue;
+            }
+            all_identifiers.push(id);
+        }
+        if all_identifiers.len() != before {
+            let mut meta: metadata::ActiveModel = meta.into();
+            meta.external_identifiers = ActiveValue::Set(Some(all_identifiers));
+            meta.update(&self.db).await?;
+        }
+        Ok(())
+    }
+
     async fn review_by_id(
         &self,
         review_id: i32,
@@ -3637,7 +3863,10 @@ impl MiscellaneousService {
                         SeenOrReviewOrCalendarEventExtraInformation::Podcast(d) => {
                             (None, None, Some(d.episode))
                         }
-                        SeenOrReviewOrCalendarEventExtraInformation::Other => (None, None, None),
+                        SeenOrReviewOrCalendarEventExtraInformation::Other
+                        | SeenOrReviewOrCalendarEventExtraInformation::Part(_) => {
+                            (None, None, None)
+                        }
                     },
                     None => (None, None, None),
                 };
@@ -3673,6 +3902,7 @@ impl MiscellaneousService {
                         name: user.name,
                     },
                     comments: r.comments,
+                    seen_id: r.seen_id,
                 })
             }
             None => Err(Error::new("Unable to find review".to_owned())),
@@ -3713,9 +3943,11 @@ impl MiscellaneousService {
         }
         let all_reviews = reviews
             .into_iter()
+            // DEV: Unlisted reviews can be viewed using their id, but are not listed
+            // to anyone other than their author.
             .filter(|r| match r.visibility {
-                Visibility::Private => r.posted_by.id == user_id,
-                _ => true,
+                Visibility::Public => true,
+                Visibility::Private | Visibility::Unlisted => r.posted_by.id == user_id,
             })
             .map(|r| ReviewItem {
                 text: r.text.map(|t| markdown_to_html(&t)),
@@ -3770,7 +4002,7 @@ impl MiscellaneousService {
             .await
             .unwrap()
             .unwrap();
-        if collection.visibility != Visibility::Public {
+        if collection.visibility == Visibility::Private {
             match user_id {
                 None => {
                     return Err(Error::new(
@@ -3845,6 +4077,7 @@ impl MiscellaneousService {
                         Expr::col((AliasedMetadata::Table, metadata::Column::PublishDate)).into(),
                         Expr::col((AliasedPerson::Table, person::Column::BirthDate)).into(),
                     ])),
+                    CollectionContentsSortBy::Rank => Expr::col(collection_to_entity::Column::Rank),
                 },
                 sort.order.into(),
             )
@@ -3972,6 +4205,7 @@ impl MiscellaneousService {
         if input.rating.is_none() && input.text.is_none() {
             return Err(Error::new("At-least one of rating or review is required."));
         }
+        check_review_target(&input)?;
 
         let preferences = partial_user_by_id::<UserWithOnlyPreferences>(&self.db, user_id)
             .await?
@@ -3992,6 +4226,7 @@ impl MiscellaneousService {
             collection_id: ActiveValue::Set(input.collection_id),
             extra_information: ActiveValue::Set(extra_information),
             comments: ActiveValue::Set(vec![]),
+            seen_id: ActiveValue::Set(input.seen_id),
             ..Default::default()
         };
         if let Some(s) = input.spoiler {
@@ -4003,6 +4238,9 @@ impl MiscellaneousService {
         if let Some(d) = input.date {
             review_obj.posted_on = ActiveValue::Set(d);
         }
+        if let Some(i) = input.import_report_id {
+            review_obj.import_report_id = ActiveValue::Set(Some(i));
+        }
         let insert = review_obj.save(&self.db).await.unwrap();
         Ok(IdObject {
             id: insert.id.unwrap(),
@@ -4131,6 +4369,14 @@ impl MiscellaneousService {
                 ));
             }
             si.delete(&self.db).await.ok();
+            Review::update_many()
+                .filter(review::Column::SeenId.eq(seen_id))
+                .set(review::ActiveModel {
+                    seen_id: ActiveValue::Set(None),
+                    ..Default::default()
+                })
+                .exec(&self.db)
+                .await?;
             if progress < 100 {
                 self.remove_entity_from_collection(
                     user_id,
@@ -4138,6 +4384,7 @@ impl MiscellaneousService {
                         collection_name: DefaultCollection::InProgress.to_string(),
                         entity_id: metadata_id,
                         entity_lot: EntityLot::Media,
+                        ..Default::default()
                     },
                 )
                 .await
@@ -4326,7 +4573,8 @@ impl MiscellaneousService {
                 MediaSpecifics::Show(item) => {
                     ls.unique_items.shows.insert(seen.metadata_id);
                     match seen.extra_information.to_owned().unwrap() {
-                        SeenOrReviewOrCalendarEventExtraInformation::Other => {
+                        SeenOrReviewOrCalendarEventExtraInformation::Other
+                        | SeenOrReviewOrCalendarEventExtraInformation::Part(_) => {
                             unreachable!()
                         }
                         SeenOrReviewOrCalendarEventExtraInformation::Podcast(_) => {
@@ -4352,7 +4600,8 @@ impl MiscellaneousService {
                 MediaSpecifics::Podcast(item) => {
                     ls.unique_items.podcasts.insert(seen.metadata_id);
                     match seen.extra_information.to_owned().unwrap() {
-                        SeenOrReviewOrCalendarEventExtraInformation::Other => {
+                        SeenOrReviewOrCalendarEventExtraInformation::Other
+                        | SeenOrReviewOrCalendarEventExtraInformation::Part(_) => {
                             unreachable!()
                         }
                         SeenOrReviewOrCalendarEventExtraInformation::Show(_) => {
@@ -4664,6 +4913,7 @@ impl MiscellaneousService {
                 collection_name: DefaultCollection::Custom.to_string(),
                 entity_id: media.id,
                 entity_lot: EntityLot::Media,
+                ..Default::default()
             },
         )
         .await?;
@@ -4872,6 +5122,28 @@ impl MiscellaneousService {
                                     preferences.fitness.exercises.unit_system =
                                         UserUnitSystem::from_str(&input.value).unwrap();
                                 }
+                                "volume_formula" => {
+                                    preferences.fitness.exercises.volume_formula =
+                                        UserSetVolumeFormula::from_str(&input.value).unwrap();
+                                }
+                                "plates.enabled" => {
+                                    preferences.fitness.exercises.plates.enabled =
+                                        value_bool.unwrap()
+                                }
+                                "plates.bar_weight" => {
+                                    preferences.fitness.exercises.plates.bar_weight =
+                                        Decimal::from_str(&input.value).unwrap();
+                                }
+                                "plates.available" => {
+                                    preferences.fitness.exercises.plates.available =
+                                        serde_json::from_str(&input.value).unwrap();
+                                }
+                                "rep_max_granularity" => {
+                                    preferences.fitness.exercises.rep_max_granularity =
+                                        Decimal::from_str(&input.value)
+                                            .ok()
+                                            .filter(|g| g.is_sign_positive() && !g.is_zero());
+                                }
                                 _ => return Err(err()),
                             },
                             _ => return Err(err()),
@@ -4975,6 +5247,9 @@ impl MiscellaneousService {
                         "display_nsfw" => {
                             preferences.general.display_nsfw = value_bool.unwrap();
                         }
+                        "collect_in_progress_imports" => {
+                            preferences.general.collect_in_progress_imports = value_bool.unwrap();
+                        }
                         "dashboard" => {
                             preferences.general.dashboard =
                                 serde_json::from_str(&input.value).unwrap();
@@ -5251,7 +5526,7 @@ impl MiscellaneousService {
         Ok(true)
     }
 
-    async fn media_exists_in_database(
+    pub async fn media_exists_in_database(
         &self,
         lot: MetadataLot,
         source: MetadataSource,
@@ -5391,11 +5666,7 @@ impl MiscellaneousService {
     }
 
     async fn admin_account_guard(&self, user_id: i32) -> Result<()> {
-        let main_user = user_by_id(&self.db, user_id).await?;
-        if main_user.lot != UserLot::Admin {
-            return Err(Error::new("Only admins can perform this operation."));
-        }
-        Ok(())
+        admin_account_guard(&self.db, user_id).await
     }
 
     async fn users_list(&self) -> Result<Vec<user::Model>> {
@@ -5508,7 +5779,13 @@ impl MiscellaneousService {
                 show_season_number: pu.show_season_number,
                 show_episode_number: pu.show_episode_number,
                 podcast_episode_number: pu.podcast_episode_number,
+                part_number: None,
+                total_parts: None,
+                sessions: None,
+                watched_with: None,
+                watch_platform: None,
                 change_state: None,
+                import_report_id: None,
             },
             user_id,
         )
@@ -5517,13 +5794,30 @@ impl MiscellaneousService {
         Ok(())
     }
 
-    pub async fn after_media_seen_tasks(&self, seen: seen::Model) -> Result<()> {
+    /// Keep the collections of the user in sync with a seen item which was just
+    /// created or updated, by the import with the given id if any.
+    pub async fn after_media_seen_tasks(
+        &self,
+        seen: seen::Model,
+        import_report_id: Option<i32>,
+    ) -> Result<()> {
+        UserToEntity::update_many()
+            .filter(user_to_entity::Column::UserId.eq(seen.user_id))
+            .filter(user_to_entity::Column::MetadataId.eq(seen.metadata_id))
+            .filter(user_to_entity::Column::MetadataNextEpisode.is_not_null())
+            .set(user_to_entity::ActiveModel {
+                metadata_next_episode: ActiveValue::Set(None),
+                ..Default::default()
+            })
+            .exec(&self.db)
+            .await?;
         self.remove_entity_from_collection(
             seen.user_id,
             ChangeCollectionToEntityInput {
                 collection_name: DefaultCollection::Watchlist.to_string(),
                 entity_id: seen.metadata_id,
                 entity_lot: EntityLot::Media,
+                ..Default::default()
             },
         )
         .await
@@ -5536,6 +5830,8 @@ impl MiscellaneousService {
                         collection_name: DefaultCollection::InProgress.to_string(),
                         entity_id: seen.metadata_id,
                         entity_lot: EntityLot::Media,
+                        import_report_id,
+                        ..Default::default()
                     },
                 )
                 .await
@@ -5548,6 +5844,8 @@ impl MiscellaneousService {
                         collection_name: DefaultCollection::InProgress.to_string(),
                         entity_id: seen.metadata_id,
                         entity_lot: EntityLot::Media,
+                        import_report_id,
+                        ..Default::default()
                     },
                 )
                 .await
@@ -5555,9 +5853,40 @@ impl MiscellaneousService {
             }
             SeenState::Completed => {
                 let metadata = self.generic_metadata(seen.metadata_id).await?;
-                if metadata.model.lot == MetadataLot::Podcast
-                    || metadata.model.lot == MetadataLot::Show
-                {
+                if let MediaSpecifics::Podcast(specifics) = &metadata.model.specifics {
+                    // A podcast is complete once every one of its episodes has been seen,
+                    // irrespective of the order in which they were seen.
+                    let seen_episodes = self
+                        .seen_history(seen.user_id, seen.metadata_id)
+                        .await?
+                        .into_iter()
+                        .filter(|h| h.state == SeenState::Completed)
+                        .filter_map(|h| h.podcast_information.map(|p| p.episode))
+                        .collect::<HashSet<_>>();
+                    let input = ChangeCollectionToEntityInput {
+                        collection_name: DefaultCollection::InProgress.to_string(),
+                        entity_id: seen.metadata_id,
+                        entity_lot: EntityLot::Media,
+                        import_report_id,
+                        ..Default::default()
+                    };
+                    if specifics.is_complete(&seen_episodes) {
+                        self.remove_entity_from_collection(seen.user_id, input)
+                            .await
+                            .ok();
+                    } else {
+                        self.add_entity_to_collection(seen.user_id, input)
+                            .await
+                            .ok();
+                        let is_monitored = self
+                            .get_monitored_status(seen.user_id, seen.metadata_id)
+                            .await?;
+                        if !is_monitored {
+                            self.toggle_media_monitor(seen.user_id, seen.metadata_id)
+                                .await?;
+                        }
+                    }
+                } else if metadata.model.lot == MetadataLot::Show {
                     // If the last `n` seen elements (`n` = number of episodes, excluding Specials)
                     // correspond to each episode exactly once, it means the show can be removed
                     // from the "In Progress" collection.
@@ -5572,11 +5901,6 @@ impl MiscellaneousService {
                                 })
                             })
                             .collect_vec(),
-                        MediaSpecifics::Podcast(p) => p
-                            .episodes
-                            .into_iter()
-                            .map(|e| format!("{}", e.number))
-                            .collect_vec(),
                         _ => unreachable!(),
                     };
                     let seen_history = self.seen_history(seen.user_id, seen.metadata_id).await?;
@@ -5588,8 +5912,6 @@ impl MiscellaneousService {
                         .map(|h| {
                             if let Some(s) = h.show_information {
                                 format!("{}-{}", s.season, s.episode)
-                            } else if let Some(p) = h.podcast_information {
-                                format!("{}", p.episode)
                             } else {
                                 String::new()
                             }
@@ -5606,6 +5928,8 @@ impl MiscellaneousService {
                                 collection_name: DefaultCollection::InProgress.to_string(),
                                 entity_id: seen.metadata_id,
                                 entity_lot: EntityLot::Media,
+                                import_report_id,
+                                ..Default::default()
                             },
                         )
                         .await
@@ -5617,6 +5941,8 @@ impl MiscellaneousService {
                                 collection_name: DefaultCollection::InProgress.to_string(),
                                 entity_id: seen.metadata_id,
                                 entity_lot: EntityLot::Media,
+                                import_report_id,
+                                ..Default::default()
                             },
                         )
                         .await
@@ -5629,6 +5955,38 @@ impl MiscellaneousService {
                                 .await?;
                         }
                     }
+                } else if let Some(SeenOrReviewOrCalendarEventExtraInformation::Part(
+                    SeenPartExtraInformation {
+                        total_parts: Some(total_parts),
+                        ..
+                    },
+                )) = seen.extra_information
+                {
+                    // Media made of multiple parts is complete once every part has been seen.
+                    let seen_parts = self
+                        .seen_history(seen.user_id, seen.metadata_id)
+                        .await?
+                        .into_iter()
+                        .filter(|h| h.state == SeenState::Completed)
+                        .filter_map(|h| h.part_information.map(|p| p.part))
+                        .collect::<HashSet<_>>();
+                    let is_complete = (1..=total_parts).all(|p| seen_parts.contains(&p));
+                    let input = ChangeCollectionToEntityInput {
+                        collection_name: DefaultCollection::InProgress.to_string(),
+                        entity_id: seen.metadata_id,
+                        entity_lot: EntityLot::Media,
+                        import_report_id,
+                        ..Default::default()
+                    };
+                    if is_complete {
+                        self.remove_entity_from_collection(seen.user_id, input)
+                            .await
+                            .ok();
+                    } else {
+                        self.add_entity_to_collection(seen.user_id, input)
+                            .await
+                            .ok();
+                    }
                 } else {
                     self.remove_entity_from_collection(
                         seen.user_id,
@@ -5636,6 +5994,8 @@ impl MiscellaneousService {
                             collection_name: DefaultCollection::InProgress.to_string(),
                             entity_id: seen.metadata_id,
                             entity_lot: EntityLot::Media,
+                            import_report_id,
+                            ..Default::default()
                         },
                     )
                     .await
@@ -5799,6 +6159,99 @@ impl MiscellaneousService {
         Ok(new_monitored_value)
     }
 
+    pub async fn set_media_favorite(
+        &self,
+        user_id: i32,
+        metadata_id: i32,
+        is_favorite: bool,
+    ) -> Result<bool> {
+        let metadata = associate_user_with_metadata(&user_id, &metadata_id, &self.db).await?;
+        let mut metadata: user_to_entity::ActiveModel = metadata.into();
+        metadata.metadata_favorite = ActiveValue::Set(Some(is_favorite));
+        metadata.save(&self.db).await?;
+        Ok(is_favorite)
+    }
+
+    pub async fn set_media_next_episode(
+        &self,
+        user_id: i32,
+        metadata_id: i32,
+        next_episode: SeenShowExtraInformation,
+    ) -> Result<bool> {
+        let metadata = associate_user_with_metadata(&user_id, &metadata_id, &self.db).await?;
+        let mut metadata: user_to_entity::ActiveModel = metadata.into();
+        metadata.metadata_next_episode = ActiveValue::Set(Some(next_episode));
+        metadata.save(&self.db).await?;
+        Ok(true)
+    }
+
+    pub async fn set_media_title_override(
+        &self,
+        user_id: i32,
+        metadata_id: i32,
+        title: String,
+    ) -> Result<bool> {
+        let Some(meta) = Metadata::find_by_id(metadata_id).one(&self.db).await? else {
+            return Err(Error::new("The metadata does not exist".to_owned()));
+        };
+        let title = Some(title.trim().to_owned()).filter(|t| !t.is_empty() && *t != meta.title);
+        let metadata = associate_user_with_metadata(&user_id, &metadata_id, &self.db).await?;
+        let mut metadata: user_to_entity::ActiveModel = metadata.into();
+        metadata.metadata_title_override = ActiveValue::Set(title);
+        metadata.save(&self.db).await?;
+        Ok(true)
+    }
+
+    async fn reading_goals(&self, user_id: i32) -> Result<Vec<ReadingGoalProgress>> {
+        let goals = ReadingGoal::find()
+            .filter(reading_goal::Column::UserId.eq(user_id))
+            .order_by_desc(reading_goal::Column::Year)
+            .all(&self.db)
+            .await?;
+        let mut progress = vec![];
+        for goal in goals {
+            let current = Seen::find()
+                .select_only()
+                .column(seen::Column::MetadataId)
+                .distinct()
+                .inner_join(Metadata)
+                .filter(seen::Column::UserId.eq(user_id))
+                .filter(seen::Column::State.eq(SeenState::Completed))
+                .filter(metadata::Column::Lot.eq(MetadataLot::Book))
+                .filter(seen::Column::FinishedOn.between(
+                    NaiveDate::from_ymd_opt(goal.year, 1, 1),
+                    NaiveDate::from_ymd_opt(goal.year, 12, 31),
+                ))
+                .count(&self.db)
+                .await?;
+            progress.push(ReadingGoalProgress {
+                year: goal.year,
+                target: goal.target,
+                current,
+            });
+        }
+        Ok(progress)
+    }
+
+    pub async fn set_reading_goal(&self, user_id: i32, input: reading_goal::Model) -> Result<bool> {
+        if input.target < 1 {
+            return Err(Error::new("The target must be at least one".to_owned()));
+        }
+        let existing = ReadingGoal::find_by_id((user_id, input.year))
+            .one(&self.db)
+            .await?;
+        let goal = reading_goal::ActiveModel {
+            user_id: ActiveValue::Set(user_id),
+            year: ActiveValue::Set(input.year),
+            target: ActiveValue::Set(input.target),
+        };
+        match existing {
+            Some(_) => goal.update(&self.db).await?,
+            None => goal.insert(&self.db).await?,
+        };
+        Ok(true)
+    }
+
     async fn get_monitored_status(
         &self,
         user_id: i32,
@@ -6209,10 +6662,40 @@ impl MiscellaneousService {
             .unwrap();
         let distinct_meta_ids = related_metadata
             .into_iter()
-            .map(|m| m.metadata_id)
+            .filter_map(|m| m.metadata_id)
             .collect_vec();
+        self.export_media_items(user_id, distinct_meta_ids).await
+    }
+
+    pub async fn export_collection(
+        &self,
+        user_id: i32,
+        collection_name: String,
+    ) -> Result<Vec<ImportOrExportMediaItem>> {
+        let collection = Collection::find()
+            .filter(collection::Column::Name.eq(collection_name))
+            .filter(collection::Column::UserId.eq(user_id))
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| Error::new("Collection does not exist".to_owned()))?;
+        let meta_ids: Vec<i32> = CollectionToEntity::find()
+            .select_only()
+            .column(collection_to_entity::Column::MetadataId)
+            .filter(collection_to_entity::Column::CollectionId.eq(collection.id))
+            .filter(collection_to_entity::Column::MetadataId.is_not_null())
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+        self.export_media_items(user_id, meta_ids).await
+    }
+
+    async fn export_media_items(
+        &self,
+        user_id: i32,
+        metadata_ids: Vec<i32>,
+    ) -> Result<Vec<ImportOrExportMediaItem>> {
         let metas = Metadata::find()
-            .filter(metadata::Column::Id.is_in(distinct_meta_ids))
+            .filter(metadata::Column::Id.is_in(metadata_ids))
             .order_by(metadata::Column::Id, Order::Asc)
             .all(&self.db)
             .await?;
@@ -6227,6 +6710,10 @@ impl MiscellaneousService {
                 .await
                 .unwrap();
             modify_seen_elements(&mut seen_history);
+            let seen_dates = seen_history
+                .iter()
+                .map(|s| (s.id, s.finished_on))
+                .collect::<HashMap<_, _>>();
             let seen_history = seen_history
                 .into_iter()
                 .map(|s| {
@@ -6235,6 +6722,10 @@ impl MiscellaneousService {
                         None => (None, None),
                     };
                     let podcast_episode_number = s.podcast_information.map(|d| d.episode);
+                    let (part_number, total_parts) = match s.part_information {
+                        Some(d) => (Some(d.part), d.total_parts),
+                        None => (None, None),
+                    };
                     ImportOrExportMediaItemSeen {
                         progress: Some(s.progress),
                         started_on: s.started_on.map(convert_naive_to_utc),
@@ -6242,6 +6733,18 @@ impl MiscellaneousService {
                         show_season_number,
                         show_episode_number,
                         podcast_episode_number,
+                        part_number,
+                        total_parts,
+                        sessions: s.sessions,
+                        pages_read: None,
+                        dropped: (s.state == SeenState::Dropped).then(|| {
+                            ImportOrExportMediaItemSeenDropped {
+                                dropped_on: Some(s.last_updated_on),
+                                reason: s.dropped_reason,
+                            }
+                        }),
+                        watched_with: s.watched_with,
+                        watch_platform: s.watch_platform,
                     }
                 })
                 .collect();
@@ -6253,9 +6756,13 @@ impl MiscellaneousService {
                 .unwrap();
             let mut reviews = vec![];
             for review in db_reviews {
-                let review_item = get_review_export_item(
+                let mut review_item = get_review_export_item(
                     self.review_by_id(review.id, user_id, false).await.unwrap(),
                 );
+                review_item.seen_on = review
+                    .seen_id
+                    .and_then(|id| seen_dates.get(&id).copied().flatten())
+                    .map(convert_naive_to_utc);
                 reviews.push(review_item);
             }
             let collections = entity_in_collections(&self.db, user_id, m.id, EntityLot::Media)
@@ -6263,6 +6770,12 @@ impl MiscellaneousService {
                 .into_iter()
                 .map(|c| c.name)
                 .collect();
+            let association = get_user_and_metadata_association(&user_id, &m.id, &self.db).await;
+            let is_favorite = association.as_ref().and_then(|a| a.metadata_favorite);
+            let next_episode = association
+                .as_ref()
+                .and_then(|a| a.metadata_next_episode.clone());
+            let display_title = association.and_then(|a| a.metadata_title_override);
             let exp = ImportOrExportMediaItem {
                 source_id: m.id.to_string(),
                 lot: m.lot,
@@ -6272,6 +6785,10 @@ impl MiscellaneousService {
                 seen_history,
                 reviews,
                 collections,
+                external_identifiers: m.external_identifiers,
+                is_favorite,
+                next_episode,
+                display_title,
             };
             resp.push(exp);
         }
@@ -6310,6 +6827,65 @@ impl MiscellaneousService {
         Ok(resp)
     }
 
+    pub async fn export_metadata_groups(
+        &self,
+        user_id: i32,
+    ) -> Result<Vec<ImportOrExportMetadataGroupItem>> {
+        let mut resp: Vec<ImportOrExportMetadataGroupItem> = vec![];
+        let all_reviews = Review::find()
+            .filter(review::Column::MetadataGroupId.is_not_null())
+            .filter(review::Column::UserId.eq(user_id))
+            .find_also_related(MetadataGroup)
+            .all(&self.db)
+            .await?;
+        for (review, group) in all_reviews {
+            let group = group.unwrap();
+            let review_item =
+                get_review_export_item(self.review_by_id(review.id, user_id, false).await.unwrap());
+            if let Some(entry) = resp
+                .iter_mut()
+                .find(|g| g.identifier == group.identifier && g.source == group.source)
+            {
+                entry.reviews.push(review_item);
+            } else {
+                resp.push(ImportOrExportMetadataGroupItem {
+                    title: group.title,
+                    lot: group.lot,
+                    source: group.source,
+                    identifier: group.identifier,
+                    reviews: vec![review_item],
+                });
+            }
+        }
+        Ok(resp)
+    }
+
+    pub async fn export_collections(
+        &self,
+        user_id: i32,
+    ) -> Result<Vec<ImportOrExportCollectionItem>> {
+        let collections = Collection::find()
+            .filter(collection::Column::UserId.eq(user_id))
+            .order_by_asc(collection::Column::Id)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|c| ImportOrExportCollectionItem {
+                name: c.name,
+                description: c.description,
+                visibility: c.visibility,
+            })
+            .collect();
+        Ok(collections)
+    }
+
+    pub async fn export_preferences(&self, user_id: i32) -> Result<UserPreferences> {
+        let preferences = partial_user_by_id::<UserWithOnlyPreferences>(&self.db, user_id)
+            .await?
+            .preferences;
+        Ok(preferences)
+    }
+
     async fn generate_auth_token(&self, user_id: i32) -> Result<String> {
         let auth_token = jwt::sign(
             user_id,
@@ -6392,7 +6968,8 @@ impl MiscellaneousService {
             )
             .unwrap();
             match info {
-                SeenOrReviewOrCalendarEventExtraInformation::Other => {
+                SeenOrReviewOrCalendarEventExtraInformation::Other
+                | SeenOrReviewOrCalendarEventExtraInformation::Part(_) => {
                     if cal_event.date != meta.publish_date.unwrap() {
                         need_to_delete = true;
                     }
@@ -6562,6 +7139,7 @@ impl MiscellaneousService {
                     })
                     .collect()
             });
+            let (identifier, source) = (provider_person.identifier.clone(), provider_person.source);
             let person = person::ActiveModel {
                 identifier: ActiveValue::Set(provider_person.identifier),
                 source: ActiveValue::Set(provider_person.source),
@@ -6576,7 +7154,22 @@ impl MiscellaneousService {
                 ..Default::default()
             };
             related_media.extend(provider_person.related);
-            person.insert(&self.db).await?
+            // DEV: The person might have been created by another job since it was
+            // looked up.
+            Person::insert(person)
+                .on_conflict(
+                    OnConflict::columns([person::Column::Identifier, person::Column::Source])
+                        .do_nothing()
+                        .to_owned(),
+                )
+                .exec_without_returning(&self.db)
+                .await?;
+            Person::find()
+                .filter(person::Column::Identifier.eq(identifier))
+                .filter(person::Column::Source.eq(source))
+                .one(&self.db)
+                .await?
+                .ok_or_else(|| Error::new("The person could not be created".to_owned()))?
         };
         let intermediate = metadata_to_person::ActiveModel {
             metadata_id: ActiveValue::Set(metadata_id),
@@ -6640,6 +7233,9 @@ fn modify_seen_elements(all_seen: &mut [seen::Model]) {
                 SeenOrReviewOrCalendarEventExtraInformation::Podcast(sea) => {
                     s.podcast_information = Some(sea.clone());
                 }
+                SeenOrReviewOrCalendarEventExtraInformation::Part(sea) => {
+                    s.part_information = Some(sea.clone());
+                }
             };
         }
     });
@@ -6651,6 +7247,7 @@ fn get_review_export_item(rev: ReviewItem) -> ImportOrExportItemRating {
             date: Some(rev.posted_on),
             spoiler: Some(rev.spoiler),
             text: rev.text,
+            visibility: Some(rev.visibility),
         }),
         rating: rev.rating,
         show_season_number: rev.show_season,
@@ -6660,5 +7257,22 @@ fn get_review_export_item(rev: ReviewItem) -> ImportOrExportItemRating {
             true => None,
             false => Some(rev.comments),
         },
+        seen_on: None,
     }
 }
+
+/// Make sure that a review is posted for exactly one entity.
+pub fn check_review_target(input: &PostReviewInput) -> Result<()> {
+    let targets = [
+        input.metadata_id,
+        input.person_id,
+        input.metadata_group_id,
+        input.collection_id,
+    ];
+    if targets.iter().flatten().count() != 1 {
+        return Err(Error::new(
+            "Exactly one of metadata, person, metadata group or collection is required.",
+        ));
+    }
+    Ok(())
+}
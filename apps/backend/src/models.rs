@@ -25,11 +25,14 @@ use serde_with::skip_serializing_none;
 
 use crate::{
     entities::{
-        exercise::ExerciseListItem, partial_metadata::PartialMetadataWithoutId, prelude::Workout,
+        exercise::{self, ExerciseListItem},
+        partial_metadata::PartialMetadataWithoutId,
+        prelude::Workout,
         user_measurement, workout,
     },
     file_storage::FileStorageService,
     traits::{DatabaseAssetsAsSingleUrl, DatabaseAssetsAsUrls},
+    users::{UserPreferences, UserSetVolumeFormula},
     utils::get_stored_asset,
 };
 
@@ -114,6 +117,36 @@ pub struct ExportAllResponse {
     pub workouts: Vec<workout::Model>,
 }
 
+/// Complete transfer of a user's data between two instances of Ryot.
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImportOrExportRyotData {
+    /// Data about user's media.
+    #[serde(default)]
+    pub media: Vec<media::ImportOrExportMediaItem>,
+    /// Data about user's people.
+    #[serde(default)]
+    pub people: Vec<media::ImportOrExportPersonItem>,
+    /// Data about the metadata groups reviewed by the user.
+    #[serde(default)]
+    pub metadata_groups: Vec<media::ImportOrExportMetadataGroupItem>,
+    /// Data about user's collections.
+    #[serde(default)]
+    pub collections: Vec<media::ImportOrExportCollectionItem>,
+    /// Data about user's measurements.
+    #[serde(default)]
+    pub measurements: Vec<user_measurement::Model>,
+    /// Data about user's workouts.
+    #[serde(default)]
+    pub workouts: Vec<workout::Model>,
+    /// Data about user's exercises along with their history and personal bests.
+    #[serde(default)]
+    pub exercises: Vec<fitness::ImportOrExportExerciseItem>,
+    /// The preferences of the user. Not present in partial exports, in which case
+    /// the preferences of the target instance are kept.
+    pub preferences: Option<UserPreferences>,
+}
+
 pub mod media {
     use super::*;
 
@@ -236,6 +269,19 @@ pub mod media {
         pub fn get_episode(&self, episode_number: i32) -> Option<&PodcastEpisode> {
             self.episodes.iter().find(|e| e.number == episode_number)
         }
+
+        /// Whether all the episodes of the podcast have been seen. It can not be
+        /// complete if some of its episodes are not known.
+        pub fn is_complete(&self, seen_episodes: &HashSet<i32>) -> bool {
+            let total = self.total_episodes.max(self.episodes.len() as i32);
+            total > 0
+                && self
+                    .episodes
+                    .iter()
+                    .filter(|e| seen_episodes.contains(&e.number))
+                    .count()
+                    >= total as usize
+        }
     }
 
     #[derive(
@@ -686,11 +732,18 @@ pub mod media {
         pub unique_items: UserSummaryUniqueItems,
     }
 
-    #[derive(Debug, InputObject)]
+    #[derive(Debug, InputObject, Default)]
     pub struct ChangeCollectionToEntityInput {
         pub collection_name: String,
         pub entity_id: i32,
         pub entity_lot: EntityLot,
+        /// The position of the entity in the collection.
+        pub rank: Option<i32>,
+        /// When the entity was added to the collection. Defaults to now.
+        pub added_on: Option<DateTimeUtc>,
+        /// The import which is adding the entity.
+        #[graphql(skip)]
+        pub import_report_id: Option<i32>,
     }
 
     #[derive(Debug, InputObject, Default)]
@@ -709,6 +762,11 @@ pub mod media {
         pub show_season_number: Option<i32>,
         pub show_episode_number: Option<i32>,
         pub podcast_episode_number: Option<i32>,
+        /// The seen history item this review is for. For eg: a specific rewatch.
+        pub seen_id: Option<i32>,
+        /// The import which is posting the review.
+        #[graphql(skip)]
+        pub import_report_id: Option<i32>,
     }
 
     #[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
@@ -719,7 +777,20 @@ pub mod media {
         pub show_season_number: Option<i32>,
         pub show_episode_number: Option<i32>,
         pub podcast_episode_number: Option<i32>,
+        /// For media made of multiple parts, the part which was seen.
+        pub part_number: Option<i32>,
+        /// For media made of multiple parts, the total number of parts.
+        pub total_parts: Option<i32>,
+        /// The sessions in which the media was consumed, if known.
+        pub sessions: Option<Vec<SeenSession>>,
+        /// The people the media was consumed with.
+        pub watched_with: Option<Vec<String>>,
+        /// Where the media was consumed.
+        pub watch_platform: Option<String>,
         pub change_state: Option<SeenState>,
+        /// The import which is recording the progress.
+        #[graphql(skip)]
+        pub import_report_id: Option<i32>,
     }
 
     #[derive(Enum, Clone, Debug, Copy, PartialEq, Eq)]
@@ -796,10 +867,22 @@ pub mod media {
     pub enum ImportOrExportItemIdentifier {
         // the identifier in case we need to fetch details
         NeedsDetails(String),
+        // multiple provider identifiers which are tried in order until one resolves
+        NeedsDetailsFromAny(Vec<MetadataExternalIdentifier>),
         // details are already filled and just need to be comitted to database
         AlreadyFilled(Box<MediaDetails>),
     }
 
+    /// Details about a media item that was abandoned before it was finished.
+    #[skip_serializing_none]
+    #[derive(Debug, Serialize, Deserialize, Clone, Default, Schematic)]
+    pub struct ImportOrExportMediaItemSeenDropped {
+        /// The timestamp when it was abandoned.
+        pub dropped_on: Option<DateTimeUtc>,
+        /// The reason why it was abandoned.
+        pub reason: Option<String>,
+    }
+
     /// A specific instance when an entity was seen.
     #[skip_serializing_none]
     #[derive(Debug, Serialize, Deserialize, Clone, Default, Schematic)]
@@ -812,10 +895,26 @@ pub mod media {
         pub ended_on: Option<DateTimeUtc>,
         /// If for a show, the season which was seen.
         pub show_season_number: Option<i32>,
-        /// If for a show, the episode which was seen.
+        /// If for a show, the episode which was seen. If missing, all the episodes
+        /// of the season are marked as seen.
         pub show_episode_number: Option<i32>,
         /// If for a podcast, the episode which was seen.
         pub podcast_episode_number: Option<i32>,
+        /// If for a media made of multiple parts, the part which was seen.
+        pub part_number: Option<i32>,
+        /// If for a media made of multiple parts, the total number of parts.
+        pub total_parts: Option<i32>,
+        /// The sessions in which the media was consumed.
+        pub sessions: Option<Vec<SeenSession>>,
+        /// If for a book, the number of pages that were read. Used to calculate the
+        /// progress when it is not provided and the total pages are known.
+        pub pages_read: Option<i32>,
+        /// If the media was abandoned before it was finished, details about it.
+        pub dropped: Option<ImportOrExportMediaItemSeenDropped>,
+        /// The people the media was consumed with, if it was not consumed alone.
+        pub watched_with: Option<Vec<String>>,
+        /// Where the media was consumed. For eg: a streaming service or a theater.
+        pub watch_platform: Option<String>,
     }
 
     /// Review data associated to a rating.
@@ -828,6 +927,8 @@ pub mod media {
         pub spoiler: Option<bool>,
         /// Actual text for the review.
         pub text: Option<String>,
+        /// Who can see the review. Defaults to everyone.
+        pub visibility: Option<Visibility>,
     }
 
     /// A rating given to an entity.
@@ -840,12 +941,16 @@ pub mod media {
         pub rating: Option<Decimal>,
         /// If for a show, the season for which this review was for.
         pub show_season_number: Option<i32>,
-        /// If for a show, the episode for which this review was for.
+        /// If for a show, the episode for which this review was for. If missing
+        /// while the season is present, it is for the last episode of the season.
         pub show_episode_number: Option<i32>,
         /// If for a podcast, the episode for which this review was for.
         pub podcast_episode_number: Option<i32>,
         /// The comments attached to this review.
         pub comments: Option<Vec<ImportOrExportItemReviewComment>>,
+        /// The date on which the seen history item this review is for was
+        /// completed. Used to rate each watch separately.
+        pub seen_on: Option<DateTimeUtc>,
     }
 
     /// Details about a specific media item that needs to be imported or exported.
@@ -870,6 +975,17 @@ pub mod media {
         pub reviews: Vec<ImportOrExportItemRating>,
         /// The collections this entity was added to.
         pub collections: Vec<String>,
+        /// The identifiers of this media on other providers. These are tried in order
+        /// if the media can not be found using the primary identifier.
+        pub external_identifiers: Option<Vec<MetadataExternalIdentifier>>,
+        /// Whether the user marked this media as a favorite.
+        pub is_favorite: Option<bool>,
+        /// The episode of the show the user should watch next, if the source
+        /// keeps track of it.
+        pub next_episode: Option<SeenShowExtraInformation>,
+        /// The title the user sees for this media in the source, if it can differ
+        /// from the one of the provider. For eg: the romaji title of an anime.
+        pub display_title: Option<String>,
     }
 
     /// Details about a specific creator item that needs to be exported.
@@ -884,6 +1000,35 @@ pub mod media {
         pub collections: Vec<String>,
     }
 
+    /// Details about a metadata group (for eg: a franchise) that was reviewed by the
+    /// user and needs to be imported or exported.
+    #[skip_serializing_none]
+    #[derive(Debug, Serialize, Deserialize, Clone, Schematic)]
+    pub struct ImportOrExportMetadataGroupItem {
+        /// The title of the group.
+        pub title: String,
+        /// The type of media in the group.
+        pub lot: MetadataLot,
+        /// The source of the group.
+        pub source: MetadataSource,
+        /// The provider identifier of the group.
+        pub identifier: String,
+        /// The review history for the user.
+        pub reviews: Vec<ImportOrExportItemRating>,
+    }
+
+    /// Details about a collection that needs to be imported or exported.
+    #[skip_serializing_none]
+    #[derive(Debug, Serialize, Deserialize, Clone)]
+    pub struct ImportOrExportCollectionItem {
+        /// The name of the collection.
+        pub name: String,
+        /// The description of the collection.
+        pub description: Option<String>,
+        /// The visibility of the collection.
+        pub visibility: Visibility,
+    }
+
     #[derive(Debug, Serialize, Deserialize, Clone, FromJsonQueryResult, Eq, PartialEq, Default)]
     #[serde(tag = "t", content = "d")]
     pub enum MediaSpecifics {
@@ -1048,7 +1193,38 @@ pub mod media {
         pub image: Option<String>,
     }
 
-    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, SimpleObject)]
+    /// An identifier of a media item on a provider.
+    #[derive(
+        Clone,
+        Debug,
+        PartialEq,
+        FromJsonQueryResult,
+        Eq,
+        Serialize,
+        Deserialize,
+        SimpleObject,
+        Default,
+        Hash,
+        Schematic,
+    )]
+    pub struct MetadataExternalIdentifier {
+        /// The provider of the identifier.
+        pub source: MetadataSource,
+        /// The identifier on the provider.
+        pub identifier: String,
+    }
+
+    #[derive(
+        Debug,
+        PartialEq,
+        Eq,
+        Serialize,
+        Deserialize,
+        Clone,
+        SimpleObject,
+        FromJsonQueryResult,
+        Schematic,
+    )]
     pub struct SeenShowExtraInformation {
         pub season: i32,
         pub episode: i32,
@@ -1059,10 +1235,40 @@ pub mod media {
         pub episode: i32,
     }
 
+    /// A single session in which (a part of) a media was consumed.
+    #[derive(
+        Debug,
+        PartialEq,
+        Eq,
+        Serialize,
+        Deserialize,
+        Clone,
+        SimpleObject,
+        InputObject,
+        FromJsonQueryResult,
+        Schematic,
+    )]
+    #[graphql(input_name = "SeenSessionInput")]
+    pub struct SeenSession {
+        /// The timestamp when the session started.
+        pub started_on: Option<DateTimeUtc>,
+        /// The duration of the session in minutes.
+        pub duration: i32,
+        /// The progress made during the session. For eg: the number of pages read.
+        pub progress: Option<i32>,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, SimpleObject)]
+    pub struct SeenPartExtraInformation {
+        pub part: i32,
+        pub total_parts: Option<i32>,
+    }
+
     #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, FromJsonQueryResult)]
     pub enum SeenOrReviewOrCalendarEventExtraInformation {
         Show(SeenShowExtraInformation),
         Podcast(SeenPodcastExtraInformation),
+        Part(SeenPartExtraInformation),
         #[serde(other)]
         Other,
     }
@@ -1141,6 +1347,7 @@ pub mod fitness {
     #[derive(
         Debug,
         Clone,
+        Default,
         Serialize,
         Deserialize,
         FromJsonQueryResult,
@@ -1241,6 +1448,8 @@ pub mod fitness {
         pub distance: Option<Decimal>,
         pub reps: Option<usize>,
         pub weight: Option<Decimal>,
+        /// The rate of perceived exertion, on a scale of 1 to 10.
+        pub rpe: Option<Decimal>,
     }
 
     /// The types of set (mostly characterized by exertion level).
@@ -1287,6 +1496,27 @@ pub mod fitness {
         Time,
         Pace,
         Reps,
+        LoadedDistance,
+    }
+
+    /// The improvement of a personal best over the previous best of the same type.
+    #[derive(
+        Clone,
+        Debug,
+        Deserialize,
+        Serialize,
+        FromJsonQueryResult,
+        Eq,
+        PartialEq,
+        SimpleObject,
+        Schematic,
+    )]
+    pub struct WorkoutSetPersonalBestImprovement {
+        pub lot: WorkoutSetPersonalBest,
+        /// The absolute increase over the previous best.
+        pub delta: Decimal,
+        /// The increase as a percentage of the previous best.
+        pub percentage: Option<Decimal>,
     }
 
     /// Details about the set performed.
@@ -1305,6 +1535,8 @@ pub mod fitness {
         pub statistic: WorkoutSetStatistic,
         pub lot: SetLot,
         pub personal_bests: Vec<WorkoutSetPersonalBest>,
+        #[serde(default)]
+        pub personal_best_improvements: Vec<WorkoutSetPersonalBestImprovement>,
     }
 
     impl WorkoutSetRecord {
@@ -1314,8 +1546,20 @@ pub mod fitness {
                 .checked_div(dec!(37.0) - Decimal::from_usize(self.statistic.reps?).unwrap())
         }
 
-        pub fn calculate_volume(&self) -> Option<Decimal> {
-            Some(self.statistic.weight? * Decimal::from_usize(self.statistic.reps?).unwrap())
+        pub fn calculate_volume(&self, formula: UserSetVolumeFormula) -> Option<Decimal> {
+            let volume =
+                self.statistic.weight? * Decimal::from_usize(self.statistic.reps?).unwrap();
+            match formula {
+                UserSetVolumeFormula::Simple => Some(volume),
+                UserSetVolumeFormula::RpeAdjusted => match self.statistic.rpe {
+                    Some(rpe) => Some(volume * rpe / dec!(10)),
+                    None => Some(volume),
+                },
+                UserSetVolumeFormula::WorkingSetsOnly => match self.lot {
+                    SetLot::WarmUp => None,
+                    _ => Some(volume),
+                },
+            }
         }
 
         pub fn calculate_pace(&self) -> Option<Decimal> {
@@ -1324,18 +1568,43 @@ pub mod fitness {
                 .checked_div(self.statistic.duration?)
         }
 
-        pub fn get_personal_best(&self, pb_type: &WorkoutSetPersonalBest) -> Option<Decimal> {
+        /// The distance covered multiplied by the weight that was carried.
+        pub fn calculate_loaded_distance(&self) -> Option<Decimal> {
+            Some(self.statistic.weight? * self.statistic.distance?)
+        }
+
+        pub fn get_personal_best(
+            &self,
+            pb_type: &WorkoutSetPersonalBest,
+            volume_formula: UserSetVolumeFormula,
+        ) -> Option<Decimal> {
             match pb_type {
                 WorkoutSetPersonalBest::Weight => self.statistic.weight,
                 WorkoutSetPersonalBest::Time => self.statistic.duration,
                 WorkoutSetPersonalBest::Reps => self.statistic.reps.and_then(Decimal::from_usize),
                 WorkoutSetPersonalBest::OneRm => self.calculate_one_rm(),
-                WorkoutSetPersonalBest::Volume => self.calculate_volume(),
+                WorkoutSetPersonalBest::Volume => self.calculate_volume(volume_formula),
                 WorkoutSetPersonalBest::Pace => self.calculate_pace(),
+                WorkoutSetPersonalBest::LoadedDistance => self.calculate_loaded_distance(),
             }
         }
     }
 
+    #[derive(Clone, Debug, Eq, PartialEq, SimpleObject)]
+    pub struct PlateCount {
+        pub weight: Decimal,
+        pub count: usize,
+    }
+
+    /// The plates that need to be loaded on each side of a bar to make up a weight.
+    #[derive(Clone, Debug, Eq, PartialEq, SimpleObject)]
+    pub struct PlateBreakdown {
+        pub bar_weight: Decimal,
+        pub per_side: Vec<PlateCount>,
+        /// The weight that can not be made up using the available plates.
+        pub remainder: Decimal,
+    }
+
     #[derive(
         Clone, Debug, Deserialize, Serialize, FromJsonQueryResult, Eq, PartialEq, SimpleObject,
     )]
@@ -1376,6 +1645,111 @@ pub mod fitness {
         pub history: Vec<UserToExerciseHistoryExtraInformation>,
         pub lifetime_stats: WorkoutTotalMeasurement,
         pub personal_bests: Vec<UserToExerciseBestSetExtraInformation>,
+        #[serde(default)]
+        pub goals: Vec<UserExerciseGoal>,
+        #[serde(default)]
+        pub progression: Option<UserExerciseProgression>,
+        /// The most reps performed at each load, ordered by weight. Only tracked
+        /// if enabled in the preferences of the user.
+        #[serde(default)]
+        pub rep_maxes: Vec<UserToExerciseRepMax>,
+    }
+
+    /// The most reps performed with a load. The weight is rounded down to the
+    /// granularity in the preferences of the user.
+    #[derive(
+        Clone, Debug, Deserialize, Serialize, FromJsonQueryResult, Eq, PartialEq, SimpleObject,
+    )]
+    pub struct UserToExerciseRepMax {
+        pub weight: Decimal,
+        pub reps: usize,
+        pub workout_id: String,
+        pub set_idx: usize,
+    }
+
+    /// The schemes that can be used to suggest the target of the next session.
+    #[derive(
+        Debug, Clone, Copy, Serialize, Deserialize, FromJsonQueryResult, Eq, PartialEq, Enum,
+    )]
+    pub enum ProgressionScheme {
+        /// Add weight after every session in which the target reps were reached.
+        Linear,
+        /// Add a rep every session until the top of the rep range is reached, then
+        /// add weight and start again from the bottom of the range.
+        DoubleProgression,
+    }
+
+    /// How an exercise should be progressed from one session to the next.
+    #[derive(
+        Clone,
+        Debug,
+        Deserialize,
+        Serialize,
+        FromJsonQueryResult,
+        Eq,
+        PartialEq,
+        SimpleObject,
+        InputObject,
+    )]
+    #[graphql(input_name = "UserExerciseProgressionInput")]
+    pub struct UserExerciseProgression {
+        pub scheme: ProgressionScheme,
+        /// The weight that is added once the reps have been reached.
+        pub weight_increment: Decimal,
+        /// The reps to reach for linear progression, or the bottom of the rep range
+        /// for double progression.
+        pub min_reps: usize,
+        /// The top of the rep range for double progression.
+        pub max_reps: Option<usize>,
+    }
+
+    /// The target suggested for the next session of an exercise.
+    #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq, SimpleObject)]
+    pub struct ExerciseProgressionSuggestion {
+        pub weight: Decimal,
+        pub reps: usize,
+        /// The change in weight compared to the best set of the last session.
+        pub weight_change: Decimal,
+        /// The change in reps compared to the best set of the last session.
+        pub reps_change: i64,
+    }
+
+    /// A target set by the user for an exercise. It is achieved when a set reaches
+    /// the target for the given type of personal best.
+    #[derive(
+        Clone,
+        Debug,
+        Deserialize,
+        Serialize,
+        FromJsonQueryResult,
+        Eq,
+        PartialEq,
+        SimpleObject,
+        InputObject,
+        Schematic,
+    )]
+    #[graphql(input_name = "UserExerciseGoalInput")]
+    pub struct UserExerciseGoal {
+        pub lot: WorkoutSetPersonalBest,
+        pub target: Decimal,
+    }
+
+    /// Details about an exercise performed by the user that needs to be imported
+    /// or exported.
+    #[skip_serializing_none]
+    #[derive(Debug, Serialize, Deserialize, Clone)]
+    pub struct ImportOrExportExerciseItem {
+        /// The name of the exercise.
+        pub name: String,
+        /// The number of times the exercise was performed.
+        pub num_times_interacted: i32,
+        /// The history, lifetime statistics and personal bests of the exercise.
+        pub extra_information: UserToExerciseExtraInformation,
+        /// The collections this entity was added to.
+        pub collections: Vec<String>,
+        /// The complete details of the exercise. Only present for custom exercises
+        /// since they might not exist in the target instance.
+        pub details: Option<exercise::Model>,
     }
 
     /// The assets that were uploaded for an entity.
@@ -1445,6 +1819,27 @@ pub mod fitness {
         pub assets: EntityAssets,
     }
 
+    /// The number of sets done for each movement pattern in a workout.
+    #[derive(
+        Clone,
+        Debug,
+        Deserialize,
+        Serialize,
+        FromJsonQueryResult,
+        Eq,
+        PartialEq,
+        SimpleObject,
+        Schematic,
+        Default,
+    )]
+    pub struct WorkoutBalance {
+        pub push: usize,
+        pub pull: usize,
+        pub legs: usize,
+        /// Set when the upper body work in the workout is heavily skewed.
+        pub warning: Option<String>,
+    }
+
     /// The summary about an exercise done in a workout.
     #[derive(
         Clone,
@@ -1462,6 +1857,9 @@ pub mod fitness {
         pub name: String,
         pub lot: ExerciseLot,
         pub best_set: WorkoutSetRecord,
+        /// The goals of the user for this exercise that were reached in the workout.
+        #[serde(default)]
+        pub goals_achieved: Vec<UserExerciseGoal>,
     }
 
     #[derive(
@@ -1478,6 +1876,8 @@ pub mod fitness {
     pub struct WorkoutSummary {
         pub total: WorkoutTotalMeasurement,
         pub exercises: Vec<WorkoutSummaryExercise>,
+        #[serde(default)]
+        pub balance: WorkoutBalance,
     }
 
     #[derive(
@@ -1500,13 +1900,35 @@ pub mod fitness {
         pub name: Option<String>,
     }
 
-    #[derive(Clone, Debug, Deserialize, Serialize, InputObject)]
+    #[derive(
+        Clone,
+        Debug,
+        Deserialize,
+        Serialize,
+        FromJsonQueryResult,
+        Eq,
+        PartialEq,
+        SimpleObject,
+        InputObject,
+    )]
+    #[graphql(name = "WorkoutDraftSetRecord", input_name = "UserWorkoutSetRecord")]
     pub struct UserWorkoutSetRecord {
         pub statistic: WorkoutSetStatistic,
         pub lot: SetLot,
     }
 
-    #[derive(Clone, Debug, Deserialize, Serialize, InputObject)]
+    #[derive(
+        Clone,
+        Debug,
+        Deserialize,
+        Serialize,
+        FromJsonQueryResult,
+        Eq,
+        PartialEq,
+        SimpleObject,
+        InputObject,
+    )]
+    #[graphql(name = "WorkoutDraftExercise", input_name = "UserExerciseInput")]
     pub struct UserExerciseInput {
         pub exercise_id: i32,
         pub sets: Vec<UserWorkoutSetRecord>,
@@ -1515,12 +1937,26 @@ pub mod fitness {
         pub assets: EntityAssets,
     }
 
-    #[derive(Clone, Debug, Deserialize, Serialize, InputObject)]
+    /// A workout logged by the user. When saved as a draft, it does not have an end
+    /// time and does not contribute to any statistics until it is finished.
+    #[derive(
+        Clone,
+        Debug,
+        Deserialize,
+        Serialize,
+        FromJsonQueryResult,
+        Eq,
+        PartialEq,
+        SimpleObject,
+        InputObject,
+    )]
+    #[graphql(name = "WorkoutDraft", input_name = "UserWorkoutInput")]
     pub struct UserWorkoutInput {
         pub name: String,
         pub comment: Option<String>,
         pub start_time: DateTimeUtc,
-        pub end_time: DateTimeUtc,
+        /// If not provided, the current time is used when the workout is committed.
+        pub end_time: Option<DateTimeUtc>,
         pub exercises: Vec<UserExerciseInput>,
         pub supersets: Vec<Vec<u16>>,
         pub assets: EntityAssets,
@@ -143,6 +143,43 @@ impl IgdbService {
             page_limit,
         }
     }
+
+    /// Get the IDs of games from their Steam app IDs. Games which are not known to
+    /// IGDB are left out.
+    pub async fn ids_from_steam_app_ids(&self, app_ids: &[u32]) -> Result<HashMap<u32, String>> {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct ExternalGame {
+            game: Option<i32>,
+            uid: String,
+        }
+        let client = get_client(&self.config).await;
+        let mut ids = HashMap::new();
+        // DEV: 500 is the maximum number of results that IGDB returns at once
+        for chunk in app_ids.chunks(500) {
+            let req_body = format!(
+                r#"
+fields game, uid;
+where category = 1 & uid = ({uids});
+limit 500;
+                "#,
+                uids = chunk.iter().map(|id| format!("\"{id}\"")).join(",")
+            );
+            let external_games: Vec<ExternalGame> = client
+                .post("external_games")
+                .body_string(req_body)
+                .await
+                .map_err(|e| anyhow!(e))?
+                .body_json()
+                .await
+                .map_err(|e| anyhow!(e))?;
+            for external_game in external_games {
+                if let (Ok(app_id), Some(game)) = (external_game.uid.parse(), external_game.game) {
+                    ids.insert(app_id, game.to_string());
+                }
+            }
+        }
+        Ok(ids)
+    }
 }
 
 #[async_trait]
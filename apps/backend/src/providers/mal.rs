@@ -1,3 +1,5 @@
+use std::collections::{HashSet, VecDeque};
+
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use database::{MetadataLot, MetadataSource};
@@ -10,19 +12,23 @@ use serde_json::json;
 use surf::Client;
 
 use crate::{
-    entities::partial_metadata::PartialMetadataWithoutId,
+    entities::{
+        metadata_group::MetadataGroupWithoutId, partial_metadata::PartialMetadataWithoutId,
+    },
     models::{
         media::{
             AnimeSpecifics, MangaSpecifics, MediaDetails, MediaSearchItem, MediaSpecifics,
-            MetadataImageForMediaDetails, MetadataImageLot,
+            MetadataImage, MetadataImageForMediaDetails, MetadataImageLot,
         },
-        NamedObject, SearchDetails, SearchResults,
+        NamedObject, SearchDetails, SearchResults, StoredUrl,
     },
     traits::{MediaProvider, MediaProviderLanguages},
     utils::get_base_http_client,
 };
 
 static URL: &str = "https://api.myanimelist.net/v2/";
+/// The maximum number of entries that will be fetched when building a franchise.
+const MAX_FRANCHISE_SIZE: usize = 50;
 
 #[derive(Debug, Clone)]
 pub struct MalService {
@@ -65,6 +71,18 @@ impl MalAnimeService {
             page_limit,
         }
     }
+
+    /// Get the identifier of the prequel (or parent story) of this anime, which
+    /// are followed to find the first entry of its franchise.
+    pub async fn franchise_prequel(&self, identifier: &str) -> Result<Option<String>> {
+        let node = franchise_node(&self.base.client, identifier).await?;
+        Ok(node
+            .related_anime
+            .unwrap_or_default()
+            .into_iter()
+            .find(|r| matches!(r.relation_type.as_deref(), Some("prequel" | "parent_story")))
+            .map(|r| r.node.id.to_string()))
+    }
 }
 
 #[async_trait]
@@ -74,6 +92,70 @@ impl MediaProvider for MalAnimeService {
         Ok(details)
     }
 
+    async fn group_details(
+        &self,
+        identifier: &str,
+    ) -> Result<(MetadataGroupWithoutId, Vec<PartialMetadataWithoutId>)> {
+        let root = franchise_node(&self.base.client, identifier).await?;
+        let mut visited = HashSet::from([identifier.to_owned()]);
+        let mut to_visit = VecDeque::new();
+        let mut parts = vec![];
+        let mut enqueue = |node: &ItemNode, to_visit: &mut VecDeque<String>| {
+            for rel in node.related_anime.iter().flatten() {
+                let id = rel.node.id.to_string();
+                if is_franchise_relation(rel) && visited.insert(id.clone()) {
+                    to_visit.push_back(id);
+                }
+            }
+        };
+        enqueue(&root, &mut to_visit);
+        while let Some(id) = to_visit.pop_front() {
+            if parts.len() + 1 >= MAX_FRANCHISE_SIZE {
+                break;
+            }
+            let Ok(node) = franchise_node(&self.base.client, &id).await else {
+                continue;
+            };
+            enqueue(&node, &mut to_visit);
+            parts.push(node);
+        }
+        parts.sort_by(|a, b| match (&a.start_date, &b.start_date) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => a.id.cmp(&b.id),
+        });
+        let image = root.main_picture.large.clone();
+        let title = root.title.clone();
+        parts.insert(0, root);
+        let parts = parts
+            .into_iter()
+            .map(|p| PartialMetadataWithoutId {
+                identifier: p.id.to_string(),
+                title: p.title,
+                image: Some(p.main_picture.large),
+                source: MetadataSource::Mal,
+                lot: MetadataLot::Anime,
+            })
+            .collect::<Vec<_>>();
+        Ok((
+            MetadataGroupWithoutId {
+                display_images: vec![],
+                parts: parts.len().try_into().unwrap(),
+                identifier: identifier.to_owned(),
+                title,
+                description: None,
+                images: vec![MetadataImage {
+                    url: StoredUrl::Url(image),
+                    lot: MetadataImageLot::Poster,
+                }],
+                lot: MetadataLot::Anime,
+                source: MetadataSource::Mal,
+            },
+            parts,
+        ))
+    }
+
     async fn search(
         &self,
         query: &str,
@@ -199,6 +281,28 @@ struct ItemNode {
 #[derive(Serialize, Deserialize, Debug)]
 struct ItemData {
     node: ItemNode,
+    relation_type: Option<String>,
+}
+
+/// Whether the relation makes the related anime part of the same franchise.
+fn is_franchise_relation(data: &ItemData) -> bool {
+    matches!(
+        data.relation_type.as_deref(),
+        Some("prequel" | "sequel" | "side_story" | "parent_story")
+    )
+}
+
+async fn franchise_node(client: &Client, id: &str) -> Result<ItemNode> {
+    let node: ItemNode = client
+        .get(format!("anime/{}", id))
+        .query(&json!({ "fields": "start_date,related_anime" }))
+        .unwrap()
+        .await
+        .map_err(|e| anyhow!(e))?
+        .body_json()
+        .await
+        .map_err(|e| anyhow!(e))?;
+    Ok(node)
 }
 
 async fn details(client: &Client, media_type: &str, id: &str) -> Result<MediaDetails> {
@@ -254,6 +254,53 @@ impl TmdbMovieService {
             },
         }
     }
+
+    /// The identifiers of the movies with the given title. Only movies released in
+    /// `year` are considered if it is provided. If no title matches exactly, only
+    /// the most relevant movie is returned.
+    pub async fn ids_from_title(&self, title: &str, year: Option<i32>) -> Result<Vec<String>> {
+        let mut query = json!({
+            "query": title.to_owned(),
+            "language": self.base.language,
+        });
+        if let Some(year) = year {
+            query["primary_release_year"] = json!(year);
+        }
+        let mut rsp = self
+            .client
+            .get("search/movie")
+            .query(&query)
+            .map_err(|e| anyhow!(e))?
+            .await
+            .map_err(|e| anyhow!(e))?;
+        check_response_status(&rsp)?;
+        let search: TmdbListResponse = rsp.body_json().await.map_err(|e| anyhow!(e))?;
+        let exact = search
+            .results
+            .iter()
+            .filter(|d| {
+                d.title
+                    .as_ref()
+                    .is_some_and(|t| t.to_lowercase() == title.to_lowercase())
+            })
+            .map(|d| d.id.to_string())
+            .collect::<Vec<_>>();
+        if !exact.is_empty() {
+            return Ok(exact);
+        }
+        Ok(search
+            .results
+            .first()
+            .map(|d| d.id.to_string())
+            .into_iter()
+            .collect())
+    }
+
+    /// The identifier of the movie with the given IMDb ID, if there is one.
+    pub async fn id_from_imdb_id(&self, imdb_id: &str) -> Result<Option<String>> {
+        let found = find_by_imdb_id(&self.client, imdb_id).await?;
+        Ok(found.movie_results.first().map(|d| d.id.to_string()))
+    }
 }
 
 #[async_trait]
@@ -544,6 +591,12 @@ impl TmdbShowService {
             },
         }
     }
+
+    /// The identifier of the show with the given IMDb ID, if there is one.
+    pub async fn id_from_imdb_id(&self, imdb_id: &str) -> Result<Option<String>> {
+        let found = find_by_imdb_id(&self.client, imdb_id).await?;
+        Ok(found.tv_results.first().map(|d| d.id.to_string()))
+    }
 }
 
 #[async_trait]
@@ -836,6 +889,37 @@ async fn get_client_config(url: &str, access_token: &str) -> Client {
     client
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct TmdbFindResponse {
+    movie_results: Vec<TmdbEntry>,
+    tv_results: Vec<TmdbEntry>,
+}
+
+async fn find_by_imdb_id(client: &Client, imdb_id: &str) -> Result<TmdbFindResponse> {
+    let mut rsp = client
+        .get(format!("find/{}", imdb_id))
+        .query(&json!({ "external_source": "imdb_id" }))
+        .map_err(|e| anyhow!(e))?
+        .await
+        .map_err(|e| anyhow!(e))?;
+    check_response_status(&rsp)?;
+    rsp.body_json().await.map_err(|e| anyhow!(e))
+}
+
+/// Fail with the status of the response if the request was not successful, so
+/// that errors like rate limiting are not mistaken for empty results.
+fn check_response_status(rsp: &surf::Response) -> Result<()> {
+    let status = rsp.status();
+    if !status.is_success() {
+        return Err(anyhow!(
+            "Status {}: {}",
+            status as u16,
+            status.canonical_reason()
+        ));
+    }
+    Ok(())
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct TmdbNonMediaEntity {
     id: i32,
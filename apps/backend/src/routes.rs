@@ -1,4 +1,7 @@
-use std::{fs::write, path::PathBuf, sync::Arc};
+use std::{
+    fs::{create_dir_all, write},
+    sync::Arc,
+};
 
 use anyhow::Result;
 use async_graphql::http::GraphiQLSource;
@@ -10,12 +13,20 @@ use axum::{
     response::{Html, IntoResponse, Response},
     Extension, Json,
 };
+use database::MetadataLot;
 use rust_embed::RustEmbed;
 use serde_json::json;
+use strum::IntoEnumIterator;
+use uuid::Uuid;
 
 use crate::{
-    fitness::resolver::ExerciseService, graphql::GraphqlSchema,
-    miscellaneous::resolver::MiscellaneousService, models::ExportAllResponse, utils::AuthContext,
+    exporter::{media_csv, strong_app, ExporterService},
+    fitness::resolver::ExerciseService,
+    graphql::GraphqlSchema,
+    importer::get_user_temporary_uploads_dir,
+    miscellaneous::resolver::MiscellaneousService,
+    models::{ExportAllResponse, ImportOrExportRyotData},
+    utils::AuthContext,
 };
 
 static INDEX_HTML: &str = "index.html";
@@ -88,19 +99,31 @@ pub async fn config_handler(
 }
 
 /// Upload a file to the temporary file system. Primarily to be used for uploading
-/// import files.
+/// import files, which can only be imported by the user who uploaded them.
 pub async fn upload_file(
+    ctx: AuthContext,
     mut files: Multipart,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = ctx.user_id.ok_or_else(|| {
+        (
+            StatusCode::FORBIDDEN,
+            Json(json!({"err": "User is not authenticated"})),
+        )
+    })?;
+    let tmp_dir = get_user_temporary_uploads_dir(user_id);
+    create_dir_all(&tmp_dir).unwrap();
     let mut res = vec![];
     while let Some(file) = files.next_field().await.unwrap() {
+        // DEV: The name is chosen by the client, so only its last component is
+        // kept, and it is prefixed to not replace an earlier upload.
         let name = file
             .file_name()
-            .map(String::from)
-            .unwrap_or_else(|| "file.png".to_string());
+            .and_then(|n| n.rsplit(['/', '\\']).next())
+            .filter(|n| !n.is_empty() && *n != ".." && *n != ".")
+            .unwrap_or("file.png")
+            .to_string();
         let data = file.bytes().await.unwrap();
-        let tmp_dir = PathBuf::new().join("tmp");
-        let path = tmp_dir.join(name);
+        let path = tmp_dir.join(format!("{}-{}", Uuid::new_v4(), name));
         write(&path, data).unwrap();
         res.push(path.canonicalize().unwrap());
     }
@@ -111,6 +134,7 @@ pub async fn json_export(
     Path(export_type): Path<String>,
     Extension(media_service): Extension<Arc<MiscellaneousService>>,
     Extension(exercise_service): Extension<Arc<ExerciseService>>,
+    Extension(exporter_service): Extension<Arc<ExporterService>>,
     ctx: AuthContext,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
     let user_id = ctx.user_id.ok_or_else(|| {
@@ -132,6 +156,21 @@ pub async fn json_export(
                 workouts
             })
         }
+        "ryot" => json!(exporter_service.export_ryot_data(user_id).await.unwrap()),
+        "exercises" => {
+            let workouts = exercise_service.export_workouts(user_id).await.unwrap();
+            let exercises = exercise_service.export_exercises(user_id).await.unwrap();
+            json!(ImportOrExportRyotData {
+                media: vec![],
+                people: vec![],
+                metadata_groups: vec![],
+                collections: vec![],
+                measurements: vec![],
+                workouts,
+                exercises,
+                preferences: None
+            })
+        }
         "media" => {
             json!(media_service.export_media(user_id).await.unwrap())
         }
@@ -152,6 +191,49 @@ pub async fn json_export(
     Ok(Json(resp))
 }
 
+/// Export the media of the user with a lot as a CSV file, for eg: `movies.csv`, or
+/// their workouts in the format of the Strong app.
+pub async fn csv_export(
+    Path(file_name): Path<String>,
+    Extension(exporter_service): Extension<Arc<ExporterService>>,
+    ctx: AuthContext,
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = ctx.user_id.ok_or_else(|| {
+        (
+            StatusCode::FORBIDDEN,
+            Json(json!({"err": "User is not authenticated"})),
+        )
+    })?;
+    let csv = match file_name.as_str() {
+        strong_app::FILE_NAME => exporter_service.export_workouts_csv(user_id).await,
+        _ => {
+            let lot = MetadataLot::iter()
+                .find(|l| media_csv::get_file_name(*l) == file_name)
+                .ok_or_else(|| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        Json(json!({"err": "There is no export with this name"})),
+                    )
+                })?;
+            exporter_service.export_media_csv(user_id, lot).await
+        }
+    }
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"err": e.message})),
+        )
+    })?;
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, "text/csv")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{file_name}\""),
+        )
+        .body(boxed(Full::from(csv)))
+        .unwrap())
+}
+
 pub async fn integration_webhook(
     Path((integration, user_hash_id)): Path<(String, String)>,
     Extension(media_service): Extension<Arc<MiscellaneousService>>,
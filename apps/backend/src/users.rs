@@ -1,5 +1,7 @@
 use async_graphql::{Enum, SimpleObject};
 use kinded::Kinded;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
 use sea_orm::{prelude::DateTimeUtc, FromJsonQueryResult};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
@@ -104,12 +106,76 @@ pub enum UserUnitSystem {
     Imperial,
 }
 
+/// How the volume of a set is calculated.
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Enum,
+    Clone,
+    Eq,
+    PartialEq,
+    FromJsonQueryResult,
+    Copy,
+    EnumString,
+    Default,
+)]
+#[strum(ascii_case_insensitive, serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum UserSetVolumeFormula {
+    /// The weight multiplied by the number of reps.
+    #[default]
+    Simple,
+    /// The simple volume scaled by the RPE (out of 10) of the set. Sets without an
+    /// RPE are counted fully.
+    RpeAdjusted,
+    /// The simple volume, but warm up sets are not counted.
+    WorkingSetsOnly,
+}
+
+/// How the weight of barbell sets is broken down into plates.
+#[derive(
+    Debug, Serialize, Deserialize, SimpleObject, Clone, Eq, PartialEq, FromJsonQueryResult,
+)]
+pub struct UserPlatesPreferences {
+    pub enabled: bool,
+    /// The weight of the empty bar (in kg).
+    pub bar_weight: Decimal,
+    /// The weights of the plates that are available (in kg).
+    pub available: Vec<Decimal>,
+}
+
+impl Default for UserPlatesPreferences {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bar_weight: dec!(20),
+            available: vec![
+                dec!(25),
+                dec!(20),
+                dec!(15),
+                dec!(10),
+                dec!(5),
+                dec!(2.5),
+                dec!(1.25),
+            ],
+        }
+    }
+}
+
 #[derive(
     Debug, Serialize, Deserialize, SimpleObject, Clone, Eq, PartialEq, FromJsonQueryResult,
 )]
 pub struct UserExercisePreferences {
     pub save_history: usize,
     pub unit_system: UserUnitSystem,
+    #[serde(default)]
+    pub volume_formula: UserSetVolumeFormula,
+    #[serde(default)]
+    pub plates: UserPlatesPreferences,
+    /// Track the most reps performed at each load, with weights rounded down to
+    /// a multiple of this value. For eg: `2.5`. Disabled if not set.
+    #[serde(default)]
+    pub rep_max_granularity: Option<Decimal>,
 }
 
 impl Default for UserExercisePreferences {
@@ -117,6 +183,9 @@ impl Default for UserExercisePreferences {
         Self {
             save_history: 15,
             unit_system: UserUnitSystem::Metric,
+            volume_formula: UserSetVolumeFormula::default(),
+            plates: UserPlatesPreferences::default(),
+            rep_max_granularity: None,
         }
     }
 }
@@ -281,6 +350,10 @@ pub struct UserGeneralPreferences {
     pub review_scale: UserReviewScale,
     pub display_nsfw: bool,
     pub dashboard: Vec<UserGeneralDashboardElement>,
+    /// Whether media imported with partial progress should be added to a
+    /// "Currently Reading" (or similar) collection depending on its lot.
+    #[serde(default)]
+    pub collect_in_progress_imports: bool,
 }
 
 impl Default for UserGeneralPreferences {
@@ -288,6 +361,7 @@ impl Default for UserGeneralPreferences {
         Self {
             review_scale: UserReviewScale::default(),
             display_nsfw: false,
+            collect_in_progress_imports: false,
             dashboard: vec![
                 UserGeneralDashboardElement {
                     section: DashboardElementLot::Upcoming,
@@ -10,6 +10,7 @@ use axum::{
 };
 use axum_extra::extract::cookie::CookieJar;
 use chrono::Utc;
+use database::UserLot;
 use http::header::AUTHORIZATION;
 use http_types::headers::HeaderName;
 use itertools::Itertools;
@@ -31,6 +32,7 @@ use crate::{
         prelude::{Collection, CollectionToEntity, User, UserToEntity},
         user, user_to_entity,
     },
+    exporter::ExporterService,
     file_storage::FileStorageService,
     fitness::resolver::ExerciseService,
     importer::ImporterService,
@@ -64,6 +66,7 @@ pub struct AppServices {
     pub importer_service: Arc<ImporterService>,
     pub file_storage_service: Arc<FileStorageService>,
     pub exercise_service: Arc<ExerciseService>,
+    pub exporter_service: Arc<ExporterService>,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -98,6 +101,14 @@ pub async fn create_app_services(
     let importer_service = Arc::new(ImporterService::new(
         media_service.clone(),
         exercise_service.clone(),
+        file_storage_service.clone(),
+        config.clone(),
+    ));
+    let exporter_service = Arc::new(ExporterService::new(
+        media_service.clone(),
+        exercise_service.clone(),
+        file_storage_service.clone(),
+        config.clone(),
     ));
     AppServices {
         config,
@@ -105,6 +116,7 @@ pub async fn create_app_services(
         importer_service,
         file_storage_service,
         exercise_service,
+        exporter_service,
     }
 }
 
@@ -260,13 +272,21 @@ pub async fn add_entity_to_collection(
         .await?
     {
         let mut to_update: collection_to_entity::ActiveModel = etc.into();
-        to_update.last_updated_on = ActiveValue::Set(Utc::now());
+        to_update.last_updated_on = ActiveValue::Set(input.added_on.unwrap_or_else(Utc::now));
+        if input.rank.is_some() {
+            to_update.rank = ActiveValue::Set(input.rank);
+        }
         Ok(to_update.update(db).await.is_ok())
     } else {
         let mut created_collection = collection_to_entity::ActiveModel {
             collection_id: ActiveValue::Set(collection.id),
+            rank: ActiveValue::Set(input.rank),
+            import_report_id: ActiveValue::Set(input.import_report_id),
             ..Default::default()
         };
+        if let Some(added_on) = input.added_on {
+            created_collection.last_updated_on = ActiveValue::Set(added_on);
+        }
         match input.entity_lot {
             EntityLot::Media => {
                 created_collection.metadata_id = ActiveValue::Set(Some(input.entity_id))
@@ -285,6 +305,14 @@ pub async fn add_entity_to_collection(
     }
 }
 
+pub async fn admin_account_guard(db: &DatabaseConnection, user_id: i32) -> Result<()> {
+    let main_user = user_by_id(db, user_id).await?;
+    if main_user.lot != UserLot::Admin {
+        return Err(Error::new("Only admins can perform this operation."));
+    }
+    Ok(())
+}
+
 pub async fn user_by_id(db: &DatabaseConnection, user_id: i32) -> Result<user::Model> {
     User::find_by_id(user_id)
         .one(db)
@@ -243,6 +243,7 @@ export const uploadFileToServiceAndGetPath = async (
 			resolve(data[0]);
 		});
 		xhr.open("POST", `${BASE_URL}/upload`, true);
+		xhr.withCredentials = true;
 		xhr.send(formData);
 	});
 	return data;
@@ -64,10 +64,27 @@ export interface DatabaseConfig {
 }
 
 export interface ExerciseConfig {
-
+	/**
+	 * Whether a custom exercise can have the same name as an existing exercise
+	 * that does not belong to the user. The exercise of the user is preferred
+	 * when looking up exercises by name. Not supported on SQLite.
+	 * @default true
+	 */
+	allowShadowingNames: boolean;
 }
 
 export interface FileStorageConfig {
+	/**
+	 * Back up the data of every user to the file storage every x hours, in the
+	 * format of the `ryot` export. Backups are disabled when set to 0.
+	 * @default 0
+	 */
+	backup_every: number;
+	/**
+	 * The number of backups of each user that are kept. Older ones are deleted.
+	 * @default 7
+	 */
+	backups_to_keep: number;
 	/**
 	 * The access key ID for the S3 compatible file storage. **Required*to
 	 * enable file storage.
@@ -168,6 +185,77 @@ export interface SchedulerConfig {
 	 * @default 'sqlite::memory:'
 	 */
 	database_url: string;
+	/**
+	 * The number of seconds after which fetching the details of a single item
+	 * during an import is abandoned. Can be overridden for each import.
+	 * @default 120
+	 */
+	import_item_timeout: number;
+	/**
+	 * The number of media items whose details are fetched from the providers at
+	 * the same time during an import.
+	 * @default 4
+	 */
+	import_lookup_concurrency: number;
+	/**
+	 * The maximum number of times an import job is attempted. Only failures that
+	 * are likely to be temporary (network or database outages) and happen before
+	 * anything has been imported are retried.
+	 * @default 3
+	 */
+	import_max_attempts: number;
+	/**
+	 * The maximum number of failed items that are retained in the report of
+	 * an import. The rest are only counted.
+	 * @default 500
+	 */
+	import_max_failed_items: number;
+	/**
+	 * The maximum number of log events of an import job that are retained in
+	 * its report. Only events at the configured log level are captured.
+	 * @default 1000
+	 */
+	import_max_log_entries: number;
+	/**
+	 * The maximum number of media items that are recorded in the report of an
+	 * import, to compare it with other imports. The least active items are the
+	 * ones that are dropped.
+	 * @default 10000
+	 */
+	import_max_recorded_items: number;
+	/**
+	 * The number of seconds to wait before retrying a request that a provider
+	 * rejected for being too frequent during an import. This doubles after every
+	 * retry.
+	 * @default 2
+	 */
+	import_rate_limit_backoff: number;
+	/**
+	 * The maximum number of times a request that a provider rejected for being
+	 * too frequent during an import is retried.
+	 * @default 5
+	 */
+	import_rate_limit_retries: number;
+	/**
+	 * The number of days after which the reports of finished imports are deleted
+	 * along with their details. Set to 0 to keep them forever. Successful imports
+	 * keep the items they committed, which imports deduplicated by
+	 * `SOURCE_EVENT_ID` skip.
+	 * @default 90
+	 */
+	import_report_retention_days: number;
+	/**
+	 * The maximum number of requests per second made to each import source or
+	 * metadata provider during imports. Shared by all the running imports.
+	 * @default 2
+	 */
+	import_requests_per_second: number;
+	/**
+	 * The number of seconds to wait before retrying a failed import job. This
+	 * doubles after every attempt.
+	 * @default 30
+	 */
+	import_retry_backoff: number;
 	/**
 	 * The number of jobs to process every 5 seconds when updating metadata in
 	 * the background.
@@ -42,8 +42,23 @@ export interface UserMeasurement {
 	timestamp: string;
 }
 
+export type MetadataSource = 'Anilist' | 'Audible' | 'Custom' | 'GoogleBooks' | 'Igdb' | 'Itunes' | 'Listennotes' | 'MangaUpdates' | 'Mal' | 'Openlibrary' | 'Tmdb' | 'Vndb';
+
+/** An identifier of a media item on a provider. */
+export interface MetadataExternalIdentifier {
+	/** The identifier on the provider. */
+	identifier: string;
+	/** The provider of the identifier. */
+	source: MetadataSource;
+}
+
 export type MetadataLot = 'AudioBook' | 'Anime' | 'Book' | 'Podcast' | 'Manga' | 'Movie' | 'Show' | 'VideoGame' | 'VisualNovel';
 
+export interface SeenShowExtraInformation {
+	episode: number;
+	season: number;
+}
+
 /** A user that has commented on a review. */
 export interface ReviewCommentUser {
 	id: number;
@@ -60,6 +75,8 @@ export interface ImportOrExportItemReviewComment {
 	user: ReviewCommentUser;
 }
 
+export type Visibility = 'Public' | 'Private' | 'Unlisted';
+
 /** Review data associated to a rating. */
 export interface ImportOrExportItemReview {
 	/** The date the review was posted. */
@@ -68,6 +85,8 @@ export interface ImportOrExportItemReview {
 	spoiler: boolean | null;
 	/** Actual text for the review. */
 	text: string | null;
+	/** Who can see the review. Defaults to everyone. */
+	visibility: Visibility | null;
 }
 
 /** A rating given to an entity. */
@@ -80,38 +99,99 @@ export interface ImportOrExportItemRating {
 	rating: string | null;
 	/** Data about the review. */
 	review: ImportOrExportItemReview | null;
-	/** If for a show, the episode for which this review was for. */
+	/**
+	 * The date on which the seen history item this review is for was
+	 * completed. Used to rate each watch separately.
+	 */
+	seenOn: string | null;
+	/**
+	 * If for a show, the episode for which this review was for. If missing
+	 * while the season is present, it is for the last episode of the season.
+	 */
 	showEpisodeNumber: number | null;
 	/** If for a show, the season for which this review was for. */
 	showSeasonNumber: number | null;
 }
 
+/** Details about a media item that was abandoned before it was finished. */
+export interface ImportOrExportMediaItemSeenDropped {
+	/** The timestamp when it was abandoned. */
+	droppedOn: string | null;
+	/** The reason why it was abandoned. */
+	reason: string | null;
+}
+
+/** A single session in which (a part of) a media was consumed. */
+export interface SeenSession {
+	/** The duration of the session in minutes. */
+	duration: number;
+	/** The progress made during the session. For eg: the number of pages read. */
+	progress: number | null;
+	/** The timestamp when the session started. */
+	startedOn: string | null;
+}
+
 /** A specific instance when an entity was seen. */
 export interface ImportOrExportMediaItemSeen {
+	/** If the media was abandoned before it was finished, details about it. */
+	dropped: ImportOrExportMediaItemSeenDropped | null;
 	/** The timestamp when finished watching. */
 	endedOn: string | null;
+	/**
+	 * If for a book, the number of pages that were read. Used to calculate the
+	 * progress when it is not provided and the total pages are known.
+	 */
+	pagesRead: number | null;
+	/** If for a media made of multiple parts, the part which was seen. */
+	partNumber: number | null;
 	/** If for a podcast, the episode which was seen. */
 	podcastEpisodeNumber: number | null;
 	/** The progress of media done. If none, it is considered as done. */
 	progress: number | null;
-	/** If for a show, the episode which was seen. */
+	/** The sessions in which the media was consumed. */
+	sessions: SeenSession[] | null;
+	/**
+	 * If for a show, the episode which was seen. If missing, all the episodes
+	 * of the season are marked as seen.
+	 */
 	showEpisodeNumber: number | null;
 	/** If for a show, the season which was seen. */
 	showSeasonNumber: number | null;
 	/** The timestamp when started watching. */
 	startedOn: string | null;
+	/** If for a media made of multiple parts, the total number of parts. */
+	totalParts: number | null;
+	/** Where the media was consumed. For eg: a streaming service or a theater. */
+	watchPlatform: string | null;
+	/** The people the media was consumed with, if it was not consumed alone. */
+	watchedWith: string[] | null;
 }
 
-export type MetadataSource = 'Anilist' | 'Audible' | 'Custom' | 'GoogleBooks' | 'Igdb' | 'Itunes' | 'Listennotes' | 'MangaUpdates' | 'Mal' | 'Openlibrary' | 'Tmdb' | 'Vndb';
-
 /** Details about a specific media item that needs to be imported or exported. */
 export interface ImportOrExportMediaItem {
 	/** The collections this entity was added to. */
 	collections: string[];
+	/**
+	 * The title the user sees for this media in the source, if it can differ
+	 * from the one of the provider. For eg: the romaji title of an anime.
+	 */
+	displayTitle: string | null;
+	/**
+	 * The identifiers of this media on other providers. These are tried in order
+	 * if the media can not be found using the primary identifier.
+	 */
+	externalIdentifiers: MetadataExternalIdentifier[] | null;
 	/** The provider identifier. For eg: TMDB-ID, Openlibrary ID and so on. */
 	identifier: string;
+	/** Whether the user marked this media as a favorite. */
+	isFavorite: boolean | null;
 	/** The type of media. */
 	lot: MetadataLot;
+	/**
+	 * The episode of the show the user should watch next, if the source
+	 * keeps track of it.
+	 */
+	nextEpisode: SeenShowExtraInformation | null;
 	/** The review history for the user. */
 	reviews: ImportOrExportItemRating[];
 	/** The seen history for the user. */
@@ -140,23 +220,35 @@ export interface EntityAssets {
 	videos: string[];
 }
 
-export type ExerciseLot = 'Duration' | 'DistanceAndDuration' | 'RepsAndWeight';
+export type ExerciseLot = 'Duration' | 'DistanceAndDuration' | 'RepsAndWeight' | 'WeightAndDistance';
 
 export type SetLot = 'Normal' | 'WarmUp' | 'Drop' | 'Failure';
 
-export type WorkoutSetPersonalBest = 'Weight' | 'OneRm' | 'Volume' | 'Time' | 'Pace' | 'Reps';
+export type WorkoutSetPersonalBest = 'Weight' | 'OneRm' | 'Volume' | 'Time' | 'Pace' | 'Reps' | 'LoadedDistance';
+
+/** The improvement of a personal best over the previous best of the same type. */
+export interface WorkoutSetPersonalBestImprovement {
+	/** The absolute increase over the previous best. */
+	delta: string;
+	lot: WorkoutSetPersonalBest;
+	/** The increase as a percentage of the previous best. */
+	percentage: string | null;
+}
 
 /** Details about the statistics of the set performed. */
 export interface WorkoutSetStatistic {
 	distance: string | null;
 	duration: string | null;
 	reps: number | null;
+	/** The rate of perceived exertion, on a scale of 1 to 10. */
+	rpe: string | null;
 	weight: string | null;
 }
 
 /** Details about the set performed. */
 export interface WorkoutSetRecord {
 	lot: SetLot;
+	personalBestImprovements: WorkoutSetPersonalBestImprovement[];
 	personalBests: WorkoutSetPersonalBest[];
 	statistic: WorkoutSetStatistic;
 }
@@ -194,15 +286,36 @@ export interface WorkoutInformation {
 	supersets: number[][];
 }
 
+/** The number of sets done for each movement pattern in a workout. */
+export interface WorkoutBalance {
+	legs: number;
+	pull: number;
+	push: number;
+	/** Set when the upper body work in the workout is heavily skewed. */
+	warning: string | null;
+}
+
+/**
+ * A target set by the user for an exercise. It is achieved when a set reaches
+ * the target for the given type of personal best.
+ */
+export interface UserExerciseGoal {
+	lot: WorkoutSetPersonalBest;
+	target: string;
+}
+
 /** The summary about an exercise done in a workout. */
 export interface WorkoutSummaryExercise {
 	bestSet: WorkoutSetRecord;
+	/** The goals of the user for this exercise that were reached in the workout. */
+	goalsAchieved: UserExerciseGoal[];
 	lot: ExerciseLot;
 	name: string;
 	numSets: number;
 }
 
 export interface WorkoutSummary {
+	balance: WorkoutBalance;
 	exercises: WorkoutSummaryExercise[];
 	total: WorkoutTotalMeasurement;
 }
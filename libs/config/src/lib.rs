@@ -113,7 +113,14 @@ pub struct DatabaseConfig {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Config, PartialEq, Eq)]
-pub struct ExerciseConfig {}
+#[config(rename_all = "snake_case", env_prefix = "EXERCISE_")]
+pub struct ExerciseConfig {
+    /// Whether a custom exercise can have the same name as an existing exercise
+    /// that does not belong to the user. The exercise of the user is preferred
+    /// when looking up exercises by name. Not supported on SQLite.
+    #[setting(default = true)]
+    pub allow_shadowing_names: bool,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, Config)]
 #[config(rename_all = "snake_case", env_prefix = "MEDIA_")]
@@ -225,6 +232,13 @@ impl IsFeatureEnabled for VisualNovelConfig {}
 #[derive(Debug, Serialize, Deserialize, Clone, Config)]
 #[config(rename_all = "snake_case", env_prefix = "FILE_STORAGE_")]
 pub struct FileStorageConfig {
+    /// Back up the data of every user to the file storage every x hours, in the
+    /// format of the `ryot` export. Backups are disabled when set to 0.
+    #[setting(default = 0)]
+    pub backup_every: i64,
+    /// The number of backups of each user that are kept. Older ones are deleted.
+    #[setting(default = 7)]
+    pub backups_to_keep: usize,
     /// The access key ID for the S3 compatible file storage. **Required** to
     /// enable file storage.
     pub s3_access_key_id: String,
@@ -296,6 +310,55 @@ pub struct SchedulerConfig {
     /// calculation.
     #[setting(default = 12)]
     pub user_cleanup_every: i32,
+    /// The maximum number of times an import job is attempted. Only failures that
+    /// are likely to be temporary (network or database outages) and happen before
+    /// anything has been imported are retried.
+    #[setting(default = 3)]
+    pub import_max_attempts: u32,
+    /// The number of seconds to wait before retrying a failed import job. This
+    /// doubles after every attempt.
+    #[setting(default = 30)]
+    pub import_retry_backoff: u64,
+    /// The number of seconds after which fetching the details of a single item
+    /// during an import is abandoned. Can be overridden for each import.
+    #[setting(default = 120)]
+    pub import_item_timeout: u64,
+    /// The maximum number of failed items that are retained in the report of
+    /// an import. The rest are only counted.
+    #[setting(default = 500)]
+    pub import_max_failed_items: usize,
+    /// The maximum number of log events of an import job that are retained in
+    /// its report. Only events at the configured log level are captured.
+    #[setting(default = 1000)]
+    pub import_max_log_entries: usize,
+    /// The maximum number of media items that are recorded in the report of an
+    /// import, to compare it with other imports. The least active items are the
+    /// ones that are dropped.
+    #[setting(default = 10000)]
+    pub import_max_recorded_items: usize,
+    /// The number of media items whose details are fetched from the providers at
+    /// the same time during an import.
+    #[setting(default = 4)]
+    pub import_lookup_concurrency: usize,
+    /// The maximum number of requests per second made to each import source or
+    /// metadata provider during imports. Shared by all the running imports.
+    #[setting(default = 2)]
+    pub import_requests_per_second: u32,
+    /// The maximum number of times a request that a provider rejected for being
+    /// too frequent during an import is retried.
+    #[setting(default = 5)]
+    pub import_rate_limit_retries: u32,
+    /// The number of seconds to wait before retrying a request that a provider
+    /// rejected for being too frequent during an import. This doubles after every
+    /// retry.
+    #[setting(default = 2)]
+    pub import_rate_limit_backoff: u64,
+    /// The number of days after which the reports of finished imports are deleted
+    /// along with their details. Set to 0 to keep them forever. Successful imports
+    /// keep the items they committed, which imports deduplicated by
+    /// `SOURCE_EVENT_ID` skip.
+    #[setting(default = 90)]
+    pub import_report_retention_days: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Config)]
@@ -131,6 +131,8 @@ pub enum Metadata {
     LastProcessedOnForCalendar,
     // those creators who can not be created as a `person` due to incomplete info
     FreeCreators,
+    // the identifiers of this media item on other providers
+    ExternalIdentifiers,
 }
 
 #[async_trait::async_trait]
@@ -32,6 +32,8 @@ pub enum User {
     SinkIntegrations,
     Notifications,
     Summary,
+    // the workout that the user has not finished logging yet
+    WorkoutDraft,
 }
 
 #[async_trait::async_trait]
@@ -38,6 +38,14 @@ pub enum Seen {
     // for the time being this stores the `season` and `episode` numbers
     ExtraInformation,
     NumTimesUpdated,
+    Sessions,
+    // why the media was abandoned, if it was dropped
+    DroppedReason,
+    WatchedWith,
+    // where the media was consumed, for eg: a streaming service or a theater
+    WatchPlatform,
+    // the import which created it
+    ImportReportId,
 }
 
 #[async_trait::async_trait]
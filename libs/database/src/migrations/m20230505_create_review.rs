@@ -1,4 +1,5 @@
 use async_graphql::Enum;
+use schematic::ConfigEnum;
 use sea_orm::{DeriveActiveEnum, EnumIter};
 use sea_orm_migration::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -28,14 +29,19 @@ pub static COLLECTION_TO_REVIEW_FOREIGN_KEY: &str = "review_to_collection_foreig
     Serialize,
     Enum,
     Default,
+    ConfigEnum,
 )]
 #[sea_orm(rs_type = "String", db_type = "String(None)")]
+#[config(rename_all = "PascalCase")]
 pub enum Visibility {
     #[default]
     #[sea_orm(string_value = "PU")]
     Public,
     #[sea_orm(string_value = "PR")]
     Private,
+    /// Can be viewed by anyone who has the link, but is not listed publicly.
+    #[sea_orm(string_value = "UN")]
+    Unlisted,
 }
 
 /// A review can be for either a creator or a media item.
@@ -56,6 +62,10 @@ pub enum Review {
     CollectionId,
     Spoiler,
     Comments,
+    // the seen history item this review is for, for eg: a specific rewatch
+    SeenId,
+    // the import which created it
+    ImportReportId,
 }
 
 #[async_trait::async_trait]
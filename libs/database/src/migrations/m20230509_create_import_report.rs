@@ -40,6 +40,32 @@ pub enum ImportSource {
     StoryGraph,
     #[sea_orm(string_value = "SA")]
     StrongApp,
+    #[sea_orm(string_value = "RY")]
+    Ryot,
+    #[sea_orm(string_value = "LE")]
+    Letterboxd,
+    #[sea_orm(string_value = "AN")]
+    Anilist,
+    #[sea_orm(string_value = "IM")]
+    Imdb,
+    #[sea_orm(string_value = "KI")]
+    Kitsu,
+    #[sea_orm(string_value = "AB")]
+    Audiobookshelf,
+    #[sea_orm(string_value = "GC")]
+    GenericCsv,
+    #[sea_orm(string_value = "BM")]
+    BodyMeasurements,
+    #[sea_orm(string_value = "AH")]
+    AppleHealth,
+    #[sea_orm(string_value = "SM")]
+    Steam,
+    #[sea_orm(string_value = "SZ")]
+    Serializd,
+    #[sea_orm(string_value = "KO")]
+    Komga,
+    #[sea_orm(string_value = "KV")]
+    Kavita,
 }
 
 #[derive(Iden)]
@@ -52,6 +78,9 @@ pub enum ImportReport {
     Source,
     Details,
     Success,
+    Progress,
+    Payload,
+    RunningSince,
 }
 
 #[async_trait::async_trait]
@@ -148,6 +148,8 @@ pub enum ExerciseLot {
     DistanceAndDuration,
     #[sea_orm(string_value = "RW")]
     RepsAndWeight,
+    #[sea_orm(string_value = "WD")]
+    WeightAndDistance,
 }
 
 #[derive(
@@ -175,6 +177,7 @@ pub enum Exercise {
     Identifier,
     Attributes,
     Source,
+    CreatedByUserId,
 }
 
 #[async_trait::async_trait]
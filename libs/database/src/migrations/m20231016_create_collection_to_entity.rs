@@ -25,6 +25,10 @@ pub enum CollectionToEntity {
     MetadataGroupId,
     PersonId,
     ExerciseId,
+    // the position of the entity in the collection
+    Rank,
+    // the import which created it
+    ImportReportId,
 }
 
 #[async_trait::async_trait]
@@ -28,6 +28,9 @@ pub enum UserToEntity {
     MetadataMonitored,
     MetadataReminder,
     ExerciseExtraInformation,
+    MetadataFavorite,
+    MetadataNextEpisode,
+    MetadataTitleOverride,
 }
 
 #[async_trait::async_trait]
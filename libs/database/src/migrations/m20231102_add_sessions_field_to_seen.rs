@@ -0,0 +1,27 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230419_create_seen::Seen;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if !manager.has_column("seen", "sessions").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Seen::Table)
+                        .add_column(ColumnDef::new(Seen::Sessions).json().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20231016_create_collection_to_entity::CollectionToEntity;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if !manager.has_column("collection_to_entity", "rank").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(CollectionToEntity::Table)
+                        .add_column(ColumnDef::new(CollectionToEntity::Rank).integer().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
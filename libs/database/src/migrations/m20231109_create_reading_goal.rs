@@ -0,0 +1,52 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230417_create_user::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+pub static READING_GOAL_PRIMARY_KEY: &str = "pk-reading_goal";
+
+#[derive(Iden)]
+pub enum ReadingGoal {
+    Table,
+    UserId,
+    Year,
+    // the number of books that the user wants to read in the year
+    Target,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ReadingGoal::Table)
+                    .col(ColumnDef::new(ReadingGoal::UserId).integer().not_null())
+                    .col(ColumnDef::new(ReadingGoal::Year).integer().not_null())
+                    .col(ColumnDef::new(ReadingGoal::Target).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .name(READING_GOAL_PRIMARY_KEY)
+                            .col(ReadingGoal::UserId)
+                            .col(ReadingGoal::Year),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-reading_goal-user_id")
+                            .from(ReadingGoal::Table, ReadingGoal::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
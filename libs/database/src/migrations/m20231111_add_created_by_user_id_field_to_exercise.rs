@@ -0,0 +1,47 @@
+use sea_orm::{ConnectionTrait, DatabaseBackend};
+use sea_orm_migration::prelude::*;
+
+use super::m20230622_create_exercise::Exercise;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if !manager.has_column("exercise", "created_by_user_id").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Exercise::Table)
+                        .add_column(ColumnDef::new(Exercise::CreatedByUserId).integer().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+        // DEV: Custom exercises can share their name with exercises of other users.
+        // SQLite can not drop the constraint without recreating the table, so names
+        // stay unique there.
+        let db = manager.get_connection();
+        match db.get_database_backend() {
+            DatabaseBackend::Postgres => {
+                db.execute_unprepared(
+                    "ALTER TABLE exercise DROP CONSTRAINT IF EXISTS exercise_name_key",
+                )
+                .await?;
+            }
+            DatabaseBackend::MySql => {
+                if manager.has_index("exercise", "name").await? {
+                    db.execute_unprepared("ALTER TABLE exercise DROP INDEX name")
+                        .await?;
+                }
+            }
+            DatabaseBackend::Sqlite => {}
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230505_create_review::Review;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if !manager.has_column("review", "seen_id").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Review::Table)
+                        .add_column(ColumnDef::new(Review::SeenId).integer().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
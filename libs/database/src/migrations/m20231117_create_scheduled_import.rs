@@ -0,0 +1,74 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230417_create_user::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+pub enum ScheduledImport {
+    Table,
+    Id,
+    UserId,
+    Source,
+    // the input that the import job is deployed with
+    Input,
+    // a cron expression with seconds, evaluated in UTC
+    Schedule,
+    CreatedOn,
+    LastRunOn,
+    NextRunOn,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScheduledImport::Table)
+                    .col(
+                        ColumnDef::new(ScheduledImport::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ScheduledImport::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(ScheduledImport::Source)
+                            .string_len(2)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ScheduledImport::Input).json().not_null())
+                    .col(ColumnDef::new(ScheduledImport::Schedule).text().not_null())
+                    .col(
+                        ColumnDef::new(ScheduledImport::CreatedOn)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(ScheduledImport::LastRunOn).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(ScheduledImport::NextRunOn)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-scheduled_import-user_id")
+                            .from(ScheduledImport::Table, ScheduledImport::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use sea_orm_migration::prelude::*;
+
+use super::{
+    m20230419_create_seen::Seen, m20230505_create_review::Review,
+    m20231016_create_collection_to_entity::CollectionToEntity,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if !manager.has_column("seen", "import_report_id").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Seen::Table)
+                        .add_column(ColumnDef::new(Seen::ImportReportId).integer().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+        if !manager.has_column("review", "import_report_id").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Review::Table)
+                        .add_column(ColumnDef::new(Review::ImportReportId).integer().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+        if !manager
+            .has_column("collection_to_entity", "import_report_id")
+            .await?
+        {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(CollectionToEntity::Table)
+                        .add_column(
+                            ColumnDef::new(CollectionToEntity::ImportReportId)
+                                .integer()
+                                .null(),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
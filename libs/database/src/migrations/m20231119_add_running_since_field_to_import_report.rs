@@ -0,0 +1,31 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230509_create_import_report::ImportReport;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if !manager.has_column("import_report", "running_since").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ImportReport::Table)
+                        .add_column(
+                            ColumnDef::new(ImportReport::RunningSince)
+                                .timestamp_with_time_zone()
+                                .null(),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
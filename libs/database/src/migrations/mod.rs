@@ -21,6 +21,24 @@ mod m20231017_create_user_to_entity;
 mod m20231024_add_metadata_group_id_field_to_review;
 mod m20231025_add_collection_id_field_to_review;
 mod m20231025_remove_duplicate_calendar_events;
+mod m20231102_add_sessions_field_to_seen;
+mod m20231103_add_rank_field_to_collection_to_entity;
+mod m20231104_add_external_identifiers_field_to_metadata;
+mod m20231105_add_workout_draft_field_to_user;
+mod m20231106_add_dropped_reason_field_to_seen;
+mod m20231107_add_metadata_favorite_field_to_user_to_entity;
+mod m20231108_add_watched_with_field_to_seen;
+mod m20231109_create_reading_goal;
+mod m20231110_add_watch_platform_field_to_seen;
+mod m20231111_add_created_by_user_id_field_to_exercise;
+mod m20231112_add_seen_id_field_to_review;
+mod m20231113_add_metadata_next_episode_field_to_user_to_entity;
+mod m20231114_add_metadata_title_override_field_to_user_to_entity;
+mod m20231115_add_progress_field_to_import_report;
+mod m20231116_add_payload_field_to_import_report;
+mod m20231117_create_scheduled_import;
+mod m20231118_add_import_report_id_field_to_seen_review_and_collection_to_entity;
+mod m20231119_add_running_since_field_to_import_report;
 
 pub use m20230410_create_metadata::{Metadata as AliasedMetadata, MetadataLot, MetadataSource};
 pub use m20230413_create_person::Person as AliasedPerson;
@@ -66,6 +84,26 @@ impl MigratorTrait for Migrator {
             Box::new(m20231024_add_metadata_group_id_field_to_review::Migration),
             Box::new(m20231025_add_collection_id_field_to_review::Migration),
             Box::new(m20231025_remove_duplicate_calendar_events::Migration),
+            Box::new(m20231102_add_sessions_field_to_seen::Migration),
+            Box::new(m20231103_add_rank_field_to_collection_to_entity::Migration),
+            Box::new(m20231104_add_external_identifiers_field_to_metadata::Migration),
+            Box::new(m20231105_add_workout_draft_field_to_user::Migration),
+            Box::new(m20231106_add_dropped_reason_field_to_seen::Migration),
+            Box::new(m20231107_add_metadata_favorite_field_to_user_to_entity::Migration),
+            Box::new(m20231108_add_watched_with_field_to_seen::Migration),
+            Box::new(m20231109_create_reading_goal::Migration),
+            Box::new(m20231110_add_watch_platform_field_to_seen::Migration),
+            Box::new(m20231111_add_created_by_user_id_field_to_exercise::Migration),
+            Box::new(m20231112_add_seen_id_field_to_review::Migration),
+            Box::new(m20231113_add_metadata_next_episode_field_to_user_to_entity::Migration),
+            Box::new(m20231114_add_metadata_title_override_field_to_user_to_entity::Migration),
+            Box::new(m20231115_add_progress_field_to_import_report::Migration),
+            Box::new(m20231116_add_payload_field_to_import_report::Migration),
+            Box::new(m20231117_create_scheduled_import::Migration),
+            Box::new(
+                m20231118_add_import_report_id_field_to_seen_review_and_collection_to_entity::Migration,
+            ),
+            Box::new(m20231119_add_running_since_field_to_import_report::Migration),
         ]
     }
 }